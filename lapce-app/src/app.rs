@@ -3,13 +3,14 @@
 use std::{
     io::{BufReader, Read, Write},
     ops::Range,
+    path::PathBuf,
     process::Stdio,
     rc::Rc,
     sync::{atomic::AtomicU64, Arc},
 };
 
 use anyhow::{anyhow, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use crossbeam_channel::Sender;
 use floem::{
     cosmic_text::{Style as FontStyle, Weight},
@@ -43,9 +44,10 @@
     meta,
 };
 use lapce_rpc::{
-    core::{CoreMessage, CoreNotification},
+    control::{ControlMessage, ControlRequest, ControlResponse},
+    core::CoreNotification,
     file::PathObject,
-    RpcMessage,
+    RpcError, RpcMessage,
 };
 use lsp_types::{CompletionItemKind, MessageType, ShowMessageParams};
 use notify::Watcher;
@@ -74,6 +76,7 @@
     },
     editor_tab::{EditorTabChild, EditorTabData},
     focus_text::focus_text,
+    hex_editor::hex_editor_view,
     id::{DiffEditorId, EditorId, EditorTabId, SplitId},
     keymap::keymap_view,
     keypress::keymap::KeyMap,
@@ -95,20 +98,25 @@
     update::ReleaseInfo,
     window::{TabsInfo, WindowData, WindowInfo},
     window_tab::{Focus, WindowTabData},
-    workspace::{LapceWorkspace, LapceWorkspaceType},
+    workspace::{is_workspace_file, LapceWorkspace, LapceWorkspaceType},
 };
 
 #[derive(Parser)]
 #[clap(name = "Lapce")]
 #[clap(version=meta::VERSION)]
+#[clap(args_conflicts_with_subcommands = true)]
 #[derive(Debug)]
 struct Cli {
     /// Launch new window even if Lapce is already running
     #[clap(short, long, action)]
     new: bool,
-    /// Don't return instantly when opened in a terminal
+    /// Wait for the opened files to be closed before returning,
+    /// e.g. to use Lapce as `GIT_EDITOR`
     #[clap(short, long, action)]
     wait: bool,
+    /// Set on the process relaunched to unblock the terminal
+    #[clap(long, action, hide = true)]
+    detached: bool,
 
     /// Paths to file(s) and/or folder(s) to open.
     /// When path is a file (that exists or not),
@@ -117,6 +125,45 @@ struct Cli {
     #[clap(value_parser = lapce_proxy::cli::parse_file_line_column)]
     #[clap(value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<PathObject>,
+
+    #[clap(subcommand)]
+    command: Option<CliCommand>,
+}
+
+#[derive(Subcommand, Debug)]
+enum CliCommand {
+    /// Control the running Lapce instance
+    #[clap(subcommand)]
+    Ctl(ControlCommand),
+}
+
+#[derive(Subcommand, Debug)]
+enum ControlCommand {
+    /// Open a file, accepting `path:line:column`
+    Open {
+        #[clap(value_parser = lapce_proxy::cli::parse_file_line_column)]
+        #[clap(value_hint = clap::ValueHint::FilePath)]
+        path: PathObject,
+    },
+    /// Run a workbench command by its keymap name, e.g. `open_settings`
+    Command { command: String },
+    /// Print the open documents and whether they have unsaved changes
+    Documents,
+    /// Print the diagnostics of a file, or of all files
+    Diagnostics {
+        #[clap(value_hint = clap::ValueHint::FilePath)]
+        path: Option<PathBuf>,
+    },
+    /// Insert text at the cursors of the active editor
+    Insert { text: String },
+}
+
+/// A message received on the local socket, with the way to answer it
+#[derive(Clone)]
+enum LocalSocketMessage {
+    /// The sender is set when the client waits for the opened files to be closed
+    Notification(CoreNotification, Option<Sender<()>>),
+    Request(ControlRequest, Sender<Result<ControlResponse, RpcError>>),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -143,13 +190,16 @@ pub struct AppData {
     /// The latest release information
     pub latest_release: RwSignal<Arc<Option<ReleaseInfo>>>,
     pub watcher: Arc<notify::RecommendedWatcher>,
+    /// Watches the config directory for the user's `languages.toml`, which
+    /// may only be created later on
+    pub languages_watcher: Option<Arc<notify::RecommendedWatcher>>,
     pub tracing_handle: Handle<Targets>,
     pub config: RwSignal<Arc<LapceConfig>>,
 }
 
 impl AppData {
     pub fn reload_config(&self) {
-        let config = LapceConfig::load(&LapceWorkspace::default(), &[]);
+        let config = LapceConfig::load(&LapceWorkspace::default(), &[], false);
         self.config.set(Arc::new(config));
         let windows = self.windows.get_untracked();
         for (_, window) in windows {
@@ -260,9 +310,11 @@ fn create_windows(
         let mut app = floem::Application::new();
 
         // Split user input into known existing directors and
-        // file paths that exist or not
-        let (dirs, files): (Vec<&PathObject>, Vec<&PathObject>) =
-            paths.iter().partition(|p| p.is_dir);
+        // file paths that exist or not. Workspace files are opened like
+        // directories.
+        let (dirs, files): (Vec<&PathObject>, Vec<&PathObject>) = paths
+            .iter()
+            .partition(|p| p.is_dir || is_workspace_file(&p.path));
 
         if !dirs.is_empty() {
             // There were directories specified, so we'll load those as windows
@@ -287,17 +339,33 @@ fn create_windows(
                 #[cfg(not(windows))]
                 let workspace_type = LapceWorkspaceType::Local;
 
+                let workspace = if dir.is_dir {
+                    LapceWorkspace {
+                        kind: workspace_type,
+                        path: Some(dir.path.to_owned()),
+                        multi_root: None,
+                        last_open: 0,
+                    }
+                } else {
+                    match LapceWorkspace::from_file(&dir.path) {
+                        Ok(workspace) => workspace,
+                        Err(err) => {
+                            error!(
+                                "failed to open workspace file {}: {err}",
+                                dir.path.display()
+                            );
+                            continue;
+                        }
+                    }
+                };
+
                 let info = WindowInfo {
                     size,
                     pos,
                     maximised: false,
                     tabs: TabsInfo {
                         active_tab: 0,
-                        workspaces: vec![LapceWorkspace {
-                            kind: workspace_type,
-                            path: Some(dir.path.to_owned()),
-                            last_open: 0,
-                        }],
+                        workspaces: vec![workspace],
                     },
                 };
 
@@ -561,6 +629,7 @@ fn editor_tab_header(
     let config = main_split.common.config;
     let internal_command = main_split.common.internal_command;
     let workbench_command = main_split.common.workbench_command;
+    let hex_editors = main_split.hex_editors;
     let editor_tab_id =
         editor_tab.with_untracked(|editor_tab| editor_tab.editor_tab_id);
 
@@ -597,7 +666,7 @@ fn editor_tab_header(
         let child_for_mouse_close = child.clone();
         let main_split = main_split.clone();
         let child_view = move || {
-            let info = child.view_info(editors, diff_editors, config);
+            let info = child.view_info(editors, diff_editors, hex_editors, config);
             let hovered = create_rw_signal(false);
 
             use crate::config::ui::TabCloseButton;
@@ -1003,6 +1072,7 @@ fn editor_tab_content(
     let workspace = common.workspace.clone();
     let editors = main_split.editors;
     let diff_editors = main_split.diff_editors;
+    let hex_editors = main_split.hex_editors;
     let config = common.config;
     let focus = common.focus;
     let items = move || {
@@ -1176,6 +1246,19 @@ fn editor_tab_content(
                 container_box(settings_view(plugin.installed, common))
             }
             EditorTabChild::Keymap(_) => container_box(keymap_view(common)),
+            EditorTabChild::HexEditor(hex_editor_id) => {
+                let hex_editor_data = hex_editors.with_untracked(|hex_editors| {
+                    hex_editors.get(&hex_editor_id).cloned()
+                });
+                if let Some(hex_editor_data) = hex_editor_data {
+                    container_box(hex_editor_view(
+                        main_split.clone(),
+                        hex_editor_data,
+                    ))
+                } else {
+                    container_box(text("emtpy hex editor"))
+                }
+            }
         };
         child.style(|s| s.size_full())
     };
@@ -2249,7 +2332,11 @@ fn palette_item(
         PaletteItemContent::Line { .. }
         | PaletteItemContent::Workspace { .. }
         | PaletteItemContent::SshHost { .. }
+        | PaletteItemContent::RemoteCommand { .. }
+        | PaletteItemContent::RemoteProxy { .. }
         | PaletteItemContent::Language { .. }
+        | PaletteItemContent::Encoding { .. }
+        | PaletteItemContent::LineEnding { .. }
         | PaletteItemContent::ColorTheme { .. }
         | PaletteItemContent::SCMReference { .. }
         | PaletteItemContent::TerminalProfile { .. }
@@ -2966,11 +3053,16 @@ fn window_tab(window_tab_data: Rc<WindowTabData>) -> impl View {
 }
 
 fn workspace_title(workspace: &LapceWorkspace) -> Option<String> {
-    let p = workspace.path.as_ref()?;
+    let p = match workspace.multi_root.as_ref() {
+        Some(multi_root) => multi_root.file.with_extension(""),
+        None => workspace.path.clone()?,
+    };
     let dir = p.file_name().unwrap_or(p.as_os_str()).to_string_lossy();
     Some(match &workspace.kind {
         LapceWorkspaceType::Local => format!("{dir}"),
         LapceWorkspaceType::RemoteSSH(ssh) => format!("{dir} [{ssh}]"),
+        LapceWorkspaceType::RemoteCommand(command) => format!("{dir} [{command}]"),
+        LapceWorkspaceType::RemoteProxy(host) => format!("{dir} [{host}]"),
         #[cfg(windows)]
         LapceWorkspaceType::RemoteWSL => format!("{dir} [wsl]"),
     })
@@ -3370,20 +3462,28 @@ pub fn launch() {
 
     let cli = Cli::parse();
 
+    if let Some(CliCommand::Ctl(command)) = cli.command {
+        if let Err(e) = run_control_command(command) {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if cli.wait {
+        if let Err(e) = open_and_wait(&cli.paths) {
+            eprintln!("Failed to open path(s): {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // small hack to unblock terminal if launched from it
     // launch it as a separate process that waits
-    if !cli.wait {
-        let mut args = std::env::args().collect::<Vec<_>>();
-        args.push("--wait".to_string());
-        let mut cmd = std::process::Command::new(&args[0]);
-        #[cfg(target_os = "windows")]
-        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-        if let Err(why) = cmd
-            .args(&args[1..])
-            .stderr(Stdio::null())
-            .stdout(Stdio::null())
-            .spawn()
-        {
+    if !cli.detached {
+        let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+        args.push("--detached".to_string());
+        if let Err(why) = spawn_detached(&args) {
             eprintln!("Failed to launch lapce: {why}");
         };
         return;
@@ -3391,7 +3491,7 @@ pub fn launch() {
 
     if !cli.new {
         if let Ok(socket) = get_socket() {
-            if let Err(e) = try_open_in_existing_process(socket, &cli.paths) {
+            if let Err(e) = try_open_in_existing_process(socket, &cli.paths, false) {
                 error!("failed to open path(s): {e}");
             };
             return;
@@ -3411,6 +3511,14 @@ pub fn launch() {
     let app_command = Listener::new_empty(scope);
 
     let (tx, rx) = crossbeam_channel::bounded(1);
+    let languages_watcher = LapceConfig::languages_file().and_then(|file| {
+        let dir = file.parent()?.to_path_buf();
+        let mut watcher =
+            notify::recommended_watcher(ConfigWatcher::for_file(tx.clone(), file))
+                .ok()?;
+        let _ = watcher.watch(&dir, notify::RecursiveMode::NonRecursive);
+        Some(Arc::new(watcher))
+    });
     let mut watcher = notify::recommended_watcher(ConfigWatcher::new(tx)).unwrap();
     if let Some(path) = LapceConfig::settings_file() {
         let _ = watcher.watch(&path, notify::RecursiveMode::Recursive);
@@ -3426,7 +3534,7 @@ pub fn launch() {
     }
 
     let windows = scope.create_rw_signal(im::HashMap::new());
-    let config = LapceConfig::load(&LapceWorkspace::default(), &[]);
+    let config = LapceConfig::load(&LapceWorkspace::default(), &[], false);
     let config = scope.create_rw_signal(Arc::new(config));
     let app_data = AppData {
         windows,
@@ -3434,6 +3542,7 @@ pub fn launch() {
         window_scale,
         app_terminated: scope.create_rw_signal(false),
         watcher: Arc::new(watcher),
+        languages_watcher,
         latest_release,
         app_command,
         tracing_handle: reload_handle,
@@ -3474,12 +3583,36 @@ pub fn launch() {
         let (tx, rx) = crossbeam_channel::bounded(1);
         let notification = create_signal_from_channel(rx);
         let app_data = app_data.clone();
-        create_effect(move |_| {
-            if let Some(CoreNotification::OpenPaths { paths }) = notification.get() {
+        create_effect(move |_| match notification.get() {
+            Some(LocalSocketMessage::Notification(
+                CoreNotification::OpenPaths { paths, .. },
+                closed,
+            )) => {
                 if let Some(window_tab) = app_data.active_window_tab() {
                     window_tab.open_paths(&paths);
+                    if let Some(closed) = closed {
+                        let files = paths
+                            .iter()
+                            .filter(|p| !p.is_dir)
+                            .map(|p| p.path.clone())
+                            .collect();
+                        window_tab.on_files_closed(files, move || {
+                            let _ = closed.send(());
+                        });
+                    }
                 }
             }
+            Some(LocalSocketMessage::Request(request, response)) => {
+                let result = match app_data.active_window_tab() {
+                    Some(window_tab) => window_tab.handle_control_request(request),
+                    None => Err(RpcError {
+                        code: 0,
+                        message: "no active window".to_string(),
+                    }),
+                };
+                let _ = response.send(result);
+            }
+            _ => {}
         });
         std::thread::spawn(move || {
             let _ = listen_local_socket(tx);
@@ -3551,35 +3684,131 @@ pub fn get_socket() -> Result<interprocess::local_socket::LocalSocketStream> {
     Ok(socket)
 }
 
+fn run_control_command(command: ControlCommand) -> Result<()> {
+    let request = match command {
+        ControlCommand::Open { path } => ControlRequest::OpenFile {
+            path: path.path,
+            line: path.linecol.map(|linecol| linecol.line),
+            column: path.linecol.map(|linecol| linecol.column),
+        },
+        ControlCommand::Command { command } => {
+            ControlRequest::RunCommand { command }
+        }
+        ControlCommand::Documents => ControlRequest::ListDocuments {},
+        ControlCommand::Diagnostics { path } => ControlRequest::GetDiagnostics {
+            path: path.map(|path| path.canonicalize().unwrap_or(path)),
+        },
+        ControlCommand::Insert { text } => ControlRequest::InsertText { text },
+    };
+
+    let socket = get_socket().map_err(|_| anyhow!("Lapce is not running"))?;
+    let mut reader = BufReader::new(socket);
+    let msg: ControlMessage = RpcMessage::Request(0, request);
+    lapce_rpc::stdio::write_msg(reader.get_mut(), msg)?;
+
+    let msg: ControlMessage = lapce_rpc::stdio::read_msg(&mut reader)?;
+    match msg {
+        RpcMessage::Response(
+            _,
+            ControlResponse::ListDocumentsResponse { documents },
+        ) => {
+            println!("{}", serde_json::to_string_pretty(&documents)?);
+        }
+        RpcMessage::Response(
+            _,
+            ControlResponse::GetDiagnosticsResponse { diagnostics },
+        ) => {
+            println!("{}", serde_json::to_string_pretty(&diagnostics)?);
+        }
+        RpcMessage::Response(..) => {}
+        RpcMessage::Error(_, e) => return Err(anyhow!(e.message)),
+        _ => return Err(anyhow!("unexpected response")),
+    }
+    Ok(())
+}
+
+fn spawn_detached(args: &[String]) -> std::io::Result<std::process::Child> {
+    let mut cmd = std::process::Command::new(std::env::current_exe()?);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    cmd.args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .stdout(Stdio::null())
+        .spawn()
+}
+
+/// Opens the paths in the running instance, starting one if needed,
+/// and blocks until the opened files are closed.
+fn open_and_wait(paths: &[PathObject]) -> Result<()> {
+    let socket = match get_socket() {
+        Ok(socket) => socket,
+        Err(_) => {
+            spawn_detached(&["--detached".to_string()])?;
+            let start = std::time::Instant::now();
+            loop {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                if let Ok(socket) = get_socket() {
+                    break socket;
+                }
+                if start.elapsed() > std::time::Duration::from_secs(10) {
+                    return Err(anyhow!("lapce didn't start in time"));
+                }
+            }
+        }
+    };
+    try_open_in_existing_process(socket, paths, true)
+}
+
 pub fn try_open_in_existing_process(
     mut socket: interprocess::local_socket::LocalSocketStream,
     paths: &[PathObject],
+    wait: bool,
 ) -> Result<()> {
-    let msg: CoreMessage = RpcMessage::Notification(CoreNotification::OpenPaths {
-        paths: paths.to_vec(),
-    });
+    let msg: ControlMessage =
+        RpcMessage::Notification(CoreNotification::OpenPaths {
+            paths: paths.to_vec(),
+            wait,
+        });
     lapce_rpc::stdio::write_msg(&mut socket, msg)?;
 
+    // The instance acks with "received", and when waiting it later sends
+    // "closed". The channel is dropped once there's nothing more to read.
     let (tx, rx) = crossbeam_channel::bounded(1);
     std::thread::spawn(move || {
+        let mut response = Vec::new();
         let mut buf = [0; 100];
-        let received = if let Ok(n) = socket.read(&mut buf) {
-            &buf[..n] == b"received"
-        } else {
-            false
-        };
-        tx.send(received)
+        let mut received = false;
+        loop {
+            match socket.read(&mut buf) {
+                Ok(n) if n > 0 => response.extend_from_slice(&buf[..n]),
+                _ => return,
+            }
+            if !received && response.starts_with(b"received") {
+                received = true;
+                let _ = tx.send(());
+                if !wait {
+                    return;
+                }
+            }
+            if response.ends_with(b"closed") {
+                return;
+            }
+        }
     });
 
-    let received = rx.recv_timeout(std::time::Duration::from_millis(500))?;
-    if !received {
-        return Err(anyhow!("didn't receive response"));
+    rx.recv_timeout(std::time::Duration::from_millis(500))
+        .map_err(|_| anyhow!("didn't receive response"))?;
+
+    if wait {
+        // returns once the files are closed or the instance has exited
+        let _ = rx.recv();
     }
 
     Ok(())
 }
 
-fn listen_local_socket(tx: Sender<CoreNotification>) -> Result<()> {
+fn listen_local_socket(tx: Sender<LocalSocketMessage>) -> Result<()> {
     let local_socket = Directory::local_socket()
         .ok_or_else(|| anyhow!("can't get local socket folder"))?;
     let _ = std::fs::remove_file(&local_socket);
@@ -3591,17 +3820,50 @@ fn listen_local_socket(tx: Sender<CoreNotification>) -> Result<()> {
         std::thread::spawn(move || -> Result<()> {
             let mut reader = BufReader::new(stream);
             loop {
-                let msg: CoreMessage = lapce_rpc::stdio::read_msg(&mut reader)?;
-
-                if let RpcMessage::Notification(msg) = msg {
-                    tx.send(msg)?;
-                } else {
-                    trace!("Unhandled message: {msg:?}");
+                let msg: ControlMessage = lapce_rpc::stdio::read_msg(&mut reader)?;
+
+                let mut closed = None;
+                match msg {
+                    RpcMessage::Request(id, request) => {
+                        let (response_tx, response_rx) =
+                            crossbeam_channel::bounded(1);
+                        tx.send(LocalSocketMessage::Request(request, response_tx))?;
+                        let msg: ControlMessage = match response_rx.recv()? {
+                            Ok(response) => RpcMessage::Response(id, response),
+                            Err(e) => RpcMessage::Error(id, e),
+                        };
+                        lapce_rpc::stdio::write_msg(reader.get_mut(), msg)?;
+                        continue;
+                    }
+                    RpcMessage::Notification(msg) => {
+                        if let CoreNotification::OpenPaths { wait: true, .. } = &msg
+                        {
+                            let (closed_tx, closed_rx) =
+                                crossbeam_channel::bounded(1);
+                            closed = Some(closed_rx);
+                            tx.send(LocalSocketMessage::Notification(
+                                msg,
+                                Some(closed_tx),
+                            ))?;
+                        } else {
+                            tx.send(LocalSocketMessage::Notification(msg, None))?;
+                        }
+                    }
+                    _ => {
+                        trace!("Unhandled message: {msg:?}");
+                    }
                 }
 
                 let stream_ref = reader.get_mut();
                 let _ = stream_ref.write_all(b"received");
                 let _ = stream_ref.flush();
+
+                if let Some(closed) = closed {
+                    // the sender is also dropped if there was no window to open in
+                    let _ = closed.recv();
+                    let _ = stream_ref.write_all(b"closed");
+                    let _ = stream_ref.flush();
+                }
             }
         });
     }
@@ -3659,6 +3921,13 @@ pub fn window_menu(
                 .entry(MenuItem::new("Open Folder").action(move || {
                     workbench_command.send(LapceWorkbenchCommand::OpenFolder);
                 }))
+                .entry(MenuItem::new("Open Workspace from File").action(move || {
+                    workbench_command.send(LapceWorkbenchCommand::OpenWorkspaceFile);
+                }))
+                .entry(MenuItem::new("Add Folder to Workspace").action(move || {
+                    workbench_command
+                        .send(LapceWorkbenchCommand::AddFolderToWorkspace);
+                }))
                 .separator()
                 .entry(MenuItem::new("Save").action(move || {
                     lapce_command.send(LapceCommand {
@@ -155,6 +155,14 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Close Folder")]
     CloseFolder,
 
+    #[strum(serialize = "open_workspace_file")]
+    #[strum(message = "Open Workspace from File")]
+    OpenWorkspaceFile,
+
+    #[strum(serialize = "add_folder_to_workspace")]
+    #[strum(message = "Add Folder to Workspace")]
+    AddFolderToWorkspace,
+
     #[strum(serialize = "open_file")]
     #[strum(message = "Open File")]
     OpenFile,
@@ -163,6 +171,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Reveal Active File in File Explorer")]
     RevealActiveFileInFileExplorer,
 
+    #[strum(serialize = "open_in_hex_editor")]
+    #[strum(message = "Open Current File in Hex Editor")]
+    OpenInHexEditor,
+
     #[strum(serialize = "change_color_theme")]
     #[strum(message = "Change Color Theme")]
     ChangeColorTheme,
@@ -275,6 +287,14 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Connect to SSH Host")]
     ConnectSshHost,
 
+    #[strum(serialize = "connect_remote_command")]
+    #[strum(message = "Connect to Remote with Command")]
+    ConnectRemoteCommand,
+
+    #[strum(serialize = "connect_remote_proxy")]
+    #[strum(message = "Connect to Remote Proxy")]
+    ConnectRemoteProxy,
+
     #[strum(serialize = "connect_wsl")]
     #[strum(message = "Connect to WSL")]
     ConnectWsl,
@@ -383,6 +403,14 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_search_focus")]
     ToggleSearchFocus,
 
+    #[strum(message = "Toggle Ports Focus")]
+    #[strum(serialize = "toggle_ports_focus")]
+    TogglePortsFocus,
+
+    #[strum(message = "Toggle Undo Tree Focus")]
+    #[strum(serialize = "toggle_undo_tree_focus")]
+    ToggleUndoTreeFocus,
+
     // Visual toggle commands
     #[strum(serialize = "toggle_terminal_visual")]
     ToggleTerminalVisual,
@@ -433,6 +461,40 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "source_control_discard_workspace_changes")]
     SourceControlDiscardWorkspaceChanges,
 
+    #[strum(serialize = "source_control_stage_target_file")]
+    SourceControlStageTargetFile,
+
+    #[strum(serialize = "source_control_unstage_target_file")]
+    SourceControlUnstageTargetFile,
+
+    #[strum(message = "Source Control: Stage All Changes")]
+    #[strum(serialize = "source_control_stage_all")]
+    SourceControlStageAll,
+
+    #[strum(message = "Source Control: Unstage All Changes")]
+    #[strum(serialize = "source_control_unstage_all")]
+    SourceControlUnstageAll,
+
+    #[strum(message = "Source Control: Stage Selected Lines")]
+    #[strum(serialize = "source_control_stage_selected_lines")]
+    SourceControlStageSelectedLines,
+
+    #[strum(message = "Source Control: Unstage Selected Lines")]
+    #[strum(serialize = "source_control_unstage_selected_lines")]
+    SourceControlUnstageSelectedLines,
+
+    #[strum(message = "Source Control: Revert Selected Lines")]
+    #[strum(serialize = "source_control_revert_selected_lines")]
+    SourceControlRevertSelectedLines,
+
+    #[strum(message = "Source Control: Show Log")]
+    #[strum(serialize = "source_control_show_log")]
+    SourceControlShowLog,
+
+    #[strum(message = "Source Control: Show Commit for Line")]
+    #[strum(serialize = "source_control_show_line_commit")]
+    SourceControlShowLineCommit,
+
     #[strum(serialize = "export_current_theme_settings")]
     #[strum(message = "Export current settings to a theme file")]
     ExportCurrentThemeSettings,
@@ -445,6 +507,18 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Change current file language")]
     ChangeFileLanguage,
 
+    #[strum(serialize = "reopen_with_encoding")]
+    #[strum(message = "Reopen current file with encoding")]
+    ReopenWithEncoding,
+
+    #[strum(serialize = "save_with_encoding")]
+    #[strum(message = "Save current file with encoding")]
+    SaveWithEncoding,
+
+    #[strum(serialize = "change_line_endings")]
+    #[strum(message = "Change line endings")]
+    ChangeLineEndings,
+
     #[strum(serialize = "next_editor_tab")]
     #[strum(message = "Next Editor Tab")]
     NextEditorTab,
@@ -516,6 +590,17 @@ pub enum InternalCommand {
     OpenFileChanges {
         path: PathBuf,
     },
+    /// Compare the file on disk with its unsaved document
+    CompareWithDisk {
+        path: PathBuf,
+    },
+    /// Show the commits that changed a file or directory
+    ShowFileHistory {
+        path: PathBuf,
+    },
+    RemoveWorkspaceFolder {
+        path: PathBuf,
+    },
     GoToLocation {
         location: EditorLocation,
     },
@@ -6,7 +6,10 @@
 
 use floem::peniko::Color;
 use itertools::Itertools;
-use lapce_core::directory::Directory;
+use lapce_core::{
+    directory::Directory,
+    language::{register_runtime_languages, LanguagesFile},
+};
 use lapce_proxy::plugin::wasi::find_all_volts;
 use lapce_rpc::plugin::VoltID;
 use lsp_types::{CompletionItemKind, SymbolKind};
@@ -19,7 +22,9 @@
     color::LapceColor,
     color_theme::{ColorThemeConfig, ThemeColor, ThemeColorPreference},
     core::CoreConfig,
+    debug::DebugConfig,
     editor::EditorConfig,
+    files::FilesConfig,
     icon::LapceIcons,
     icon_theme::IconThemeConfig,
     svg::SvgStore,
@@ -31,7 +36,9 @@
 pub mod color;
 pub mod color_theme;
 pub mod core;
+pub mod debug;
 pub mod editor;
+pub mod files;
 pub mod icon;
 pub mod icon_theme;
 pub mod svg;
@@ -44,6 +51,7 @@
 const DEFAULT_LIGHT_THEME: &str = include_str!("../../defaults/light-theme.toml");
 const DEFAULT_DARK_THEME: &str = include_str!("../../defaults/dark-theme.toml");
 const DEFAULT_ICON_THEME: &str = include_str!("../../defaults/icon-theme.toml");
+const LANGUAGES_FILE_NAME: &str = "languages.toml";
 
 static DEFAULT_CONFIG: Lazy<config::Config> = Lazy::new(LapceConfig::default_config);
 static DEFAULT_LAPCE_CONFIG: Lazy<LapceConfig> =
@@ -66,6 +74,10 @@ pub struct LapceConfig {
     pub ui: UIConfig,
     pub editor: EditorConfig,
     pub terminal: TerminalConfig,
+    #[serde(default)]
+    pub files: FilesConfig,
+    #[serde(default)]
+    pub debug: DebugConfig,
     pub color_theme: ColorThemeConfig,
     pub icon_theme: IconThemeConfig,
     #[serde(flatten)]
@@ -76,6 +88,10 @@ pub struct LapceConfig {
     pub default_icon_theme: IconThemeConfig,
     #[serde(skip)]
     pub color: ThemeColor,
+    /// The `.lapce` directory of the workspace, when its settings and keymaps
+    /// are applied on top of the user's
+    #[serde(skip)]
+    pub workspace_config_dir: Option<PathBuf>,
     #[serde(skip)]
     pub available_color_themes: HashMap<String, (String, config::Config)>,
     #[serde(skip)]
@@ -94,16 +110,30 @@ pub struct LapceConfig {
 }
 
 impl LapceConfig {
-    pub fn load(workspace: &LapceWorkspace, disabled_volts: &[VoltID]) -> Self {
-        let config = Self::merge_config(workspace, None, None);
+    /// Load the config, including the workspace's own settings if it's
+    /// `trusted`
+    pub fn load(
+        workspace: &LapceWorkspace,
+        disabled_volts: &[VoltID],
+        trusted: bool,
+    ) -> Self {
+        let workspace_config_dir = if trusted {
+            Self::workspace_config_dir(workspace)
+        } else {
+            None
+        };
+        let config = Self::merge_config(workspace_config_dir.as_deref(), None, None);
         let mut lapce_config: LapceConfig = config
             .try_deserialize()
             .unwrap_or_else(|_| DEFAULT_LAPCE_CONFIG.clone());
+        lapce_config.workspace_config_dir = workspace_config_dir;
 
         lapce_config.available_color_themes =
             Self::load_color_themes(disabled_volts);
         lapce_config.available_icon_themes = Self::load_icon_themes(disabled_volts);
-        lapce_config.resolve_theme(workspace);
+        lapce_config.resolve_theme();
+
+        Self::load_languages(disabled_volts);
 
         lapce_config.color_theme_list = lapce_config
             .available_color_themes
@@ -127,7 +157,7 @@ pub fn load(workspace: &LapceWorkspace, disabled_volts: &[VoltID]) -> Self {
     }
 
     fn merge_config(
-        workspace: &LapceWorkspace,
+        workspace_config_dir: Option<&Path>,
         color_theme_config: Option<config::Config>,
         icon_theme_config: Option<config::Config>,
     ) -> config::Config {
@@ -156,22 +186,13 @@ fn merge_config(
                 .unwrap_or_else(|_| config.clone());
         }
 
-        match workspace.kind {
-            LapceWorkspaceType::Local => {
-                if let Some(path) = workspace.path.as_ref() {
-                    let path = path.join("./.lapce/settings.toml");
-                    config = config::Config::builder()
-                        .add_source(config.clone())
-                        .add_source(
-                            config::File::from(path.as_path()).required(false),
-                        )
-                        .build()
-                        .unwrap_or_else(|_| config.clone());
-                }
-            }
-            LapceWorkspaceType::RemoteSSH(_) => {}
-            #[cfg(windows)]
-            LapceWorkspaceType::RemoteWSL => {}
+        if let Some(dir) = workspace_config_dir {
+            let path = dir.join("settings.toml");
+            config = config::Config::builder()
+                .add_source(config.clone())
+                .add_source(config::File::from(path.as_path()).required(false))
+                .build()
+                .unwrap_or_else(|_| config.clone());
         }
 
         config
@@ -201,7 +222,7 @@ fn default_lapce_config() -> LapceConfig {
         default_lapce_config
     }
 
-    fn resolve_theme(&mut self, workspace: &LapceWorkspace) {
+    fn resolve_theme(&mut self) {
         let mut default_lapce_config = DEFAULT_LAPCE_CONFIG.clone();
         if let Some((_, color_theme_config)) = self
             .available_color_themes
@@ -235,7 +256,7 @@ fn resolve_theme(&mut self, workspace: &LapceWorkspace) {
 
         if color_theme_config.is_some() || icon_theme_config.is_some() {
             if let Ok(new) = Self::merge_config(
-                workspace,
+                self.workspace_config_dir.as_deref(),
                 color_theme_config.cloned(),
                 icon_theme_config.cloned(),
             )
@@ -246,6 +267,7 @@ fn resolve_theme(&mut self, workspace: &LapceWorkspace) {
                 self.editor = new.editor;
                 self.terminal = new.terminal;
                 self.terminal.get_indexed_colors();
+                self.debug = new.debug;
 
                 self.color_theme = new.color_theme;
                 self.icon_theme = new.icon_theme;
@@ -283,16 +305,16 @@ fn load_color_themes(
 
     /// Set the active color theme.
     /// Note that this does not save the config.
-    pub fn set_color_theme(&mut self, workspace: &LapceWorkspace, theme: &str) {
+    pub fn set_color_theme(&mut self, theme: &str) {
         self.core.color_theme = theme.to_string();
-        self.resolve_theme(workspace);
+        self.resolve_theme();
     }
 
     /// Set the active icon theme.  
     /// Note that this does not save the config.
-    pub fn set_icon_theme(&mut self, workspace: &LapceWorkspace, theme: &str) {
+    pub fn set_icon_theme(&mut self, theme: &str) {
         self.core.icon_theme = theme.to_string();
-        self.resolve_theme(workspace);
+        self.resolve_theme();
     }
 
     pub fn set_modal(&mut self, _workspace: &LapceWorkspace, modal: bool) {
@@ -476,6 +498,38 @@ fn load_plugin_icon_themes(
         themes
     }
 
+    /// Register the languages declared in the `languages.toml` of the enabled
+    /// plugins and of the user, the user's taking precedence.
+    fn load_languages(disabled_volts: &[VoltID]) {
+        let mut files: Vec<PathBuf> = find_all_volts()
+            .into_iter()
+            .filter(|meta| !disabled_volts.contains(&meta.id()))
+            .filter_map(|meta| Some(meta.dir?.join(LANGUAGES_FILE_NAME)))
+            .collect();
+        files.extend(Self::languages_file());
+
+        let mut declarations = Vec::new();
+        for path in files {
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let Some(dir) = path.parent() else {
+                continue;
+            };
+            match toml::from_str::<LanguagesFile>(&content) {
+                Ok(file) => declarations.extend(
+                    file.language
+                        .into_iter()
+                        .map(|declaration| (dir.to_path_buf(), declaration)),
+                ),
+                Err(err) => {
+                    tracing::error!("Failed to parse {path:?}: {err}");
+                }
+            }
+        }
+        register_runtime_languages(declarations);
+    }
+
     fn load_icon_theme(
         path: &Path,
     ) -> Option<(String, (String, config::Config, PathBuf))> {
@@ -507,6 +561,11 @@ pub fn settings_file() -> Option<PathBuf> {
         Some(path)
     }
 
+    /// The user's language declarations, which aren't created unless needed
+    pub fn languages_file() -> Option<PathBuf> {
+        Some(Directory::config_directory()?.join(LANGUAGES_FILE_NAME))
+    }
+
     pub fn keymaps_file() -> Option<PathBuf> {
         let path = Directory::config_directory()?.join("keymaps.toml");
 
@@ -520,6 +579,25 @@ pub fn keymaps_file() -> Option<PathBuf> {
         Some(path)
     }
 
+    /// The directory of a local workspace's own `settings.toml` and
+    /// `keymaps.toml`
+    pub fn workspace_config_dir(workspace: &LapceWorkspace) -> Option<PathBuf> {
+        match workspace.kind {
+            LapceWorkspaceType::Local => {
+                workspace.path.as_ref().map(|path| path.join(".lapce"))
+            }
+            _ => None,
+        }
+    }
+
+    /// If the workspace has settings or keymaps of its own, which are only
+    /// applied once it's trusted
+    pub fn has_workspace_config(workspace: &LapceWorkspace) -> bool {
+        Self::workspace_config_dir(workspace).map_or(false, |dir| {
+            dir.join("settings.toml").exists() || dir.join("keymaps.toml").exists()
+        })
+    }
+
     pub fn ui_svg(&self, icon: &'static str) -> String {
         let svg = self.icon_theme.ui.get(icon).and_then(|path| {
             let path = self.icon_theme.path.join(path);
@@ -788,6 +866,13 @@ pub fn get_dropdown_info(&self, kind: &str, key: &str) -> Option<DropdownInfo> {
                     .sorted()
                     .collect(),
             }),
+            ("files", "eol") => Some(DropdownInfo {
+                active_index: self.files.eol as usize,
+                items: files::EolConfig::VARIANTS
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            }),
             ("terminal", "default-profile") => Some(DropdownInfo {
                 active_index: self
                     .terminal
@@ -808,8 +893,7 @@ pub fn get_dropdown_info(&self, kind: &str, key: &str) -> Option<DropdownInfo> {
         }
     }
 
-    fn get_file_table() -> Option<toml_edit::Document> {
-        let path = Self::settings_file()?;
+    fn get_file_table(path: &Path) -> Option<toml_edit::Document> {
         let content = std::fs::read_to_string(path).ok()?;
         let document: toml_edit::Document = content.parse().ok()?;
         Some(document)
@@ -822,7 +906,30 @@ pub fn update_file(
         key: &str,
         value: toml_edit::Value,
     ) -> Option<()> {
-        let mut main_table = Self::get_file_table().unwrap_or_default();
+        let path = Self::settings_file()?;
+        Self::update_settings_file(&path, parent, key, value)
+    }
+
+    /// Update the `settings.toml` in the workspace's `.lapce` directory,
+    /// creating it if needed
+    pub fn update_workspace_file(
+        workspace_config_dir: &Path,
+        parent: &str,
+        key: &str,
+        value: toml_edit::Value,
+    ) -> Option<()> {
+        std::fs::create_dir_all(workspace_config_dir).ok()?;
+        let path = workspace_config_dir.join("settings.toml");
+        Self::update_settings_file(&path, parent, key, value)
+    }
+
+    fn update_settings_file(
+        path: &Path,
+        parent: &str,
+        key: &str,
+        value: toml_edit::Value,
+    ) -> Option<()> {
+        let mut main_table = Self::get_file_table(path).unwrap_or_default();
 
         // Find the container table
         let mut table = main_table.as_table_mut();
@@ -840,7 +947,6 @@ pub fn update_file(
         table.insert(key, toml_edit::Item::Value(value));
 
         // Store
-        let path = Self::settings_file()?;
         std::fs::write(path, main_table.to_string().as_bytes()).ok()?;
 
         Some(())
@@ -120,6 +120,10 @@ impl LapceColor {
 
     pub const DEBUG_BREAKPOINT: &str = "debug.breakpoint";
     pub const DEBUG_BREAKPOINT_HOVER: &str = "debug.breakpoint.hover";
+    pub const DEBUG_CURRENT_LINE: &str = "debug.current_line";
+    pub const DEBUG_CURRENT_LINE_ARROW: &str = "debug.current_line.arrow";
+    pub const DEBUG_INLINE_VALUE_FOREGROUND: &str = "debug.inline_value.foreground";
+    pub const DEBUG_INLINE_VALUE_BACKGROUND: &str = "debug.inline_value.background";
 
     pub const PANEL_BACKGROUND: &str = "panel.background";
     pub const PANEL_FOREGROUND: &str = "panel.foreground";
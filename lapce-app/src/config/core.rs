@@ -14,4 +14,12 @@ pub struct CoreConfig {
         desc = "Enable customised titlebar and disable OS native one (Linux, BSD, Windows)"
     )]
     pub custom_titlebar: bool,
+    #[field_names(
+        desc = "Upload the proxy to remote hosts instead of downloading it there, for hosts without internet access"
+    )]
+    pub upload_remote_proxy: bool,
+    #[field_names(
+        desc = "Directory with the proxy binaries to upload, named lapce-proxy-<os>-<arch> like the release assets. When empty, the lapce-proxy next to Lapce is uploaded"
+    )]
+    pub remote_proxy_directory: String,
 }
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use lapce_rpc::dap_types::{DapServer, DapTransport};
+use serde::{Deserialize, Serialize};
+use structdesc::FieldNames;
+
+#[derive(FieldNames, Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct DebugConfig {
+    #[field_names(desc = "Debug adapters used in debug mode, keyed by name")]
+    #[serde(default)]
+    pub adapters: HashMap<String, DebugAdapterConfig>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DebugAdapterTransport {
+    #[default]
+    Stdio,
+    Tcp,
+}
+
+#[derive(FieldNames, Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct DebugAdapterConfig {
+    #[field_names(
+        desc = "Command to launch the debug adapter. It can be empty for a tcp adapter that is already running."
+    )]
+    #[serde(default)]
+    pub command: String,
+    #[field_names(
+        desc = "Arguments passed to the command. `${port}` is replaced with the tcp port."
+    )]
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[field_names(desc = "Languages this debug adapter is used for")]
+    #[serde(default)]
+    pub languages: Vec<String>,
+    #[field_names(desc = "How to talk to the debug adapter.\nOptions: stdio, tcp.")]
+    #[serde(default)]
+    pub transport: DebugAdapterTransport,
+    #[field_names(desc = "The host of a tcp debug adapter, defaults to 127.0.0.1")]
+    pub host: Option<String>,
+    #[field_names(
+        desc = "The port of a tcp debug adapter. If not set, a free port is picked."
+    )]
+    pub port: Option<u16>,
+}
+
+impl DebugAdapterConfig {
+    pub fn dap_server(&self) -> DapServer {
+        let transport = match self.transport {
+            DebugAdapterTransport::Stdio => DapTransport::Stdio,
+            DebugAdapterTransport::Tcp => DapTransport::Tcp {
+                host: self.host.clone().unwrap_or_else(|| "127.0.0.1".to_string()),
+                port: self.port,
+            },
+        };
+        DapServer {
+            program: self.command.clone(),
+            args: self.args.clone(),
+            cwd: None,
+            transport,
+        }
+    }
+}
+
+impl DebugConfig {
+    /// Find the debug adapter to use. An adapter explicitly named by the run
+    /// config wins, then one that's declared for the language, and at last the
+    /// only adapter if there's just one.
+    pub fn find_adapter(
+        &self,
+        name: Option<&str>,
+        language: Option<&str>,
+    ) -> Option<&DebugAdapterConfig> {
+        if let Some(name) = name {
+            return self
+                .adapters
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, adapter)| adapter);
+        }
+
+        if let Some(language) = language {
+            let adapter = self.adapters.values().find(|adapter| {
+                adapter
+                    .languages
+                    .iter()
+                    .any(|l| l.eq_ignore_ascii_case(language))
+            });
+            if adapter.is_some() {
+                return adapter;
+            }
+        }
+
+        if self.adapters.len() == 1 {
+            return self.adapters.values().next();
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(languages: &[&str]) -> DebugAdapterConfig {
+        DebugAdapterConfig {
+            command: "adapter".to_string(),
+            languages: languages.iter().map(|l| l.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_adapter() {
+        let mut config = DebugConfig::default();
+        config
+            .adapters
+            .insert("lldb".to_string(), adapter(&["rust", "c"]));
+        assert!(config.find_adapter(None, Some("python")).is_some());
+
+        config
+            .adapters
+            .insert("debugpy".to_string(), adapter(&["python"]));
+        let found = config.find_adapter(None, Some("Python")).unwrap();
+        assert_eq!(found.languages, vec!["python".to_string()]);
+        let found = config.find_adapter(Some("LLDB"), Some("python")).unwrap();
+        assert_eq!(found.languages, vec!["rust".to_string(), "c".to_string()]);
+        assert!(config.find_adapter(None, Some("go")).is_none());
+        assert!(config.find_adapter(Some("delve"), None).is_none());
+    }
+
+    #[test]
+    fn test_dap_server_transport() {
+        let mut config = adapter(&[]);
+        assert_eq!(config.dap_server().transport, DapTransport::Stdio);
+
+        config.transport = DebugAdapterTransport::Tcp;
+        config.port = Some(5678);
+        assert_eq!(
+            config.dap_server().transport,
+            DapTransport::Tcp {
+                host: "127.0.0.1".to_string(),
+                port: Some(5678),
+            }
+        );
+    }
+}
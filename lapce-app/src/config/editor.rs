@@ -1,3 +1,5 @@
+use std::{borrow::Cow, collections::HashMap};
+
 use serde::{Deserialize, Serialize};
 use structdesc::FieldNames;
 
@@ -135,6 +137,14 @@ pub struct EditorConfig {
     pub render_whitespace: String,
     #[field_names(desc = "Whether the editor show indent guide.")]
     pub show_indent_guide: bool,
+    #[field_names(
+        desc = "If the author and date of the commit that last changed each line are shown in the gutter"
+    )]
+    pub show_git_blame_gutter: bool,
+    #[field_names(
+        desc = "If the commit that last changed the current line is shown at the end of it"
+    )]
+    pub show_git_blame_inline: bool,
     #[field_names(
         desc = "Set the auto save delay (in milliseconds), Set to 0 to completely disable"
     )]
@@ -157,6 +167,52 @@ pub struct EditorConfig {
         desc = "Set the default number of visible lines above and below the diff block (-1 for infinite)"
     )]
     pub diff_context_lines: i32,
+
+    /// The settings in `[editor.lang.<name>]` tables, keyed by the name of
+    /// the language in lowercase
+    #[serde(default)]
+    #[field_names(skip)]
+    pub lang: HashMap<String, LanguageEditorConfig>,
+}
+
+/// Editor settings that can be set for a single language in
+/// `[editor.lang.<name>]`, overriding the ones in `[editor]`
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct LanguageEditorConfig {
+    pub tab_width: Option<usize>,
+    pub smart_tab: Option<bool>,
+    pub atomic_soft_tabs: Option<bool>,
+    pub format_on_save: Option<bool>,
+    pub format_on_autosave: Option<bool>,
+    pub show_indent_guide: Option<bool>,
+    pub auto_closing_matching_pairs: Option<bool>,
+}
+
+impl LanguageEditorConfig {
+    fn apply(&self, editor: &mut EditorConfig) {
+        if let Some(tab_width) = self.tab_width {
+            editor.tab_width = tab_width;
+        }
+        if let Some(smart_tab) = self.smart_tab {
+            editor.smart_tab = smart_tab;
+        }
+        if let Some(atomic_soft_tabs) = self.atomic_soft_tabs {
+            editor.atomic_soft_tabs = atomic_soft_tabs;
+        }
+        if let Some(format_on_save) = self.format_on_save {
+            editor.format_on_save = format_on_save;
+        }
+        if let Some(format_on_autosave) = self.format_on_autosave {
+            editor.format_on_autosave = format_on_autosave;
+        }
+        if let Some(show_indent_guide) = self.show_indent_guide {
+            editor.show_indent_guide = show_indent_guide;
+        }
+        if let Some(auto_closing_matching_pairs) = self.auto_closing_matching_pairs {
+            editor.auto_closing_matching_pairs = auto_closing_matching_pairs;
+        }
+    }
 }
 
 impl EditorConfig {
@@ -212,6 +268,26 @@ pub fn atomic_soft_tab_width(&self) -> Option<usize> {
         }
     }
 
+    /// The editor settings for a language that's known by any of `names`,
+    /// e.g. its syntax and its language id, with the overrides of the first
+    /// one that has a `[editor.lang.<name>]` table
+    pub fn for_language(&self, names: &[&str]) -> Cow<'_, EditorConfig> {
+        if self.lang.is_empty() {
+            return Cow::Borrowed(self);
+        }
+        match names
+            .iter()
+            .find_map(|name| self.lang.get(&name.to_lowercase()))
+        {
+            Some(lang) => {
+                let mut editor = self.clone();
+                lang.apply(&mut editor);
+                Cow::Owned(editor)
+            }
+            None => Cow::Borrowed(self),
+        }
+    }
+
     pub fn blink_interval(&self) -> u64 {
         if self.blink_interval == 0 {
             return 0;
@@ -219,3 +295,45 @@ pub fn blink_interval(&self) -> u64 {
         self.blink_interval.max(200)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_language() {
+        let mut editor = EditorConfig {
+            tab_width: 4,
+            smart_tab: true,
+            format_on_save: false,
+            ..Default::default()
+        };
+        editor.lang.insert(
+            "makefile".to_string(),
+            LanguageEditorConfig {
+                smart_tab: Some(false),
+                tab_width: Some(8),
+                ..Default::default()
+            },
+        );
+        editor.lang.insert(
+            "go".to_string(),
+            LanguageEditorConfig {
+                format_on_save: Some(true),
+                ..Default::default()
+            },
+        );
+
+        let go = editor.for_language(&["Go", "go"]);
+        assert!(go.format_on_save);
+        assert_eq!(go.tab_width, 4);
+
+        let make = editor.for_language(&["Plaintext", "makefile"]);
+        assert!(!make.smart_tab);
+        assert_eq!(make.tab_width, 8);
+
+        let rust = editor.for_language(&["Rust", "rust"]);
+        assert!(matches!(rust, Cow::Borrowed(_)));
+        assert!(rust.smart_tab);
+    }
+}
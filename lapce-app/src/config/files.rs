@@ -0,0 +1,68 @@
+use lapce_core::line_ending::LineEnding;
+use serde::{Deserialize, Serialize};
+use structdesc::FieldNames;
+
+#[derive(FieldNames, Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct FilesConfig {
+    #[field_names(
+        desc = "The line ending of new files, and of files without any line break yet. \"auto\" uses the platform's line ending"
+    )]
+    pub eol: EolConfig,
+    #[field_names(
+        desc = "Keep the unsaved changes of files and untitled documents when quitting, and restore them the next time the workspace is opened, instead of asking to save them"
+    )]
+    pub hot_exit: bool,
+    #[field_names(
+        desc = "Keep the undo history of a file when it's saved, so undoing can go back past the last time it was opened. Histories of files changed elsewhere since are discarded, and ones which weren't used for 30 days are removed"
+    )]
+    pub persistent_undo: bool,
+    #[field_names(
+        desc = "The size in megabytes an undo history can grow to before it isn't kept anymore"
+    )]
+    pub persistent_undo_max_size: usize,
+    #[field_names(
+        desc = "The size in megabytes from which files are opened in large file mode, without syntax highlighting, language servers or bracket matching. 0 turns it off"
+    )]
+    pub large_file_size: u64,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Deserialize,
+    Serialize,
+    Default,
+    PartialEq,
+    Eq,
+    strum_macros::EnumVariantNames,
+)]
+pub enum EolConfig {
+    #[default]
+    #[serde(rename = "auto")]
+    #[strum(serialize = "auto")]
+    Auto,
+    #[serde(rename = "lf")]
+    #[strum(serialize = "lf")]
+    Lf,
+    #[serde(rename = "crlf")]
+    #[strum(serialize = "crlf")]
+    CrLf,
+}
+
+impl FilesConfig {
+    /// The line ending new documents start out with
+    pub fn line_ending(&self) -> LineEnding {
+        match self.eol {
+            EolConfig::Auto => LineEnding::NATIVE,
+            EolConfig::Lf => LineEnding::Lf,
+            EolConfig::CrLf => LineEnding::CrLf,
+        }
+    }
+
+    /// The size in bytes from which files are opened in large file mode
+    pub fn large_file_threshold(&self) -> Option<u64> {
+        (self.large_file_size > 0).then(|| self.large_file_size * 1024 * 1024)
+    }
+}
@@ -49,6 +49,18 @@ impl LapceIcons {
     pub const SCM_DIFF_RENAMED: &str = "scm.diff.renamed";
     pub const SCM_CHANGE_ADD: &str = "scm.change.add";
     pub const SCM_CHANGE_REMOVE: &str = "scm.change.remove";
+    pub const SCM_LOG: &str = "scm.log";
+    pub const SCM_REFRESH: &str = "scm.refresh";
+
+    pub const PORTS: &str = "ports";
+    pub const PORTS_OPEN: &str = "ports.open";
+
+    pub const UNDO_TREE: &str = "undo_tree";
+    pub const UNDO_TREE_PREVIEW: &str = "undo_tree.preview";
+    pub const UNDO_TREE_RESTORE: &str = "undo_tree.restore";
+
+    pub const HEX_EDITOR: &str = "hex_editor";
+    pub const HEX_EDITOR_EDIT: &str = "hex_editor.edit";
 
     pub const FOLD: &str = "fold";
     pub const FOLD_UP: &str = "fold.up";
@@ -1,15 +1,26 @@
-use std::sync::{atomic::AtomicBool, Arc};
+use std::{
+    path::PathBuf,
+    sync::{atomic::AtomicBool, Arc},
+};
 
 use crossbeam_channel::Sender;
 
 pub struct ConfigWatcher {
     tx: Sender<()>,
     delay_handler: Arc<AtomicBool>,
+    /// Only report changes to this path, or to the paths in it if it's a
+    /// directory
+    path: Option<PathBuf>,
 }
 
 impl notify::EventHandler for ConfigWatcher {
     fn handle_event(&mut self, event: notify::Result<notify::Event>) {
         if let Ok(event) = event {
+            if let Some(filter) = self.path.as_ref() {
+                if !event.paths.iter().any(|path| path.starts_with(filter)) {
+                    return;
+                }
+            }
             match event.kind {
                 notify::EventKind::Create(_)
                 | notify::EventKind::Modify(_)
@@ -47,6 +58,27 @@ pub fn new(tx: Sender<()>) -> Self {
         Self {
             tx,
             delay_handler: Arc::new(AtomicBool::new(false)),
+            path: None,
+        }
+    }
+
+    /// A watcher that ignores changes outside of `dir`, e.g. when its parent
+    /// is watched to find out when `dir` is created
+    pub fn for_dir(tx: Sender<()>, dir: PathBuf) -> Self {
+        Self {
+            tx,
+            delay_handler: Arc::new(AtomicBool::new(false)),
+            path: Some(dir),
+        }
+    }
+
+    /// A watcher that ignores changes to anything but `file`, for watching
+    /// the directory of a file that may not exist yet
+    pub fn for_file(tx: Sender<()>, file: PathBuf) -> Self {
+        Self {
+            tx,
+            delay_handler: Arc::new(AtomicBool::new(false)),
+            path: Some(file),
         }
     }
 }
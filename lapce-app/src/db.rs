@@ -1,18 +1,25 @@
 use std::{
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
     rc::Rc,
     sync::Arc,
+    time::{Duration, SystemTime},
 };
 
 use anyhow::{anyhow, Result};
 use crossbeam_channel::{unbounded, Sender};
 use floem::peniko::kurbo::Vec2;
-use lapce_core::directory::Directory;
+use lapce_core::{
+    buffer::undo_history::UndoHistory, directory::Directory,
+    stable_hash::stable_hash,
+};
 use lapce_rpc::plugin::VoltID;
+use parking_lot::Mutex;
 
 use crate::{
     app::{AppData, AppInfo},
-    doc::DocInfo,
+    doc::{DocBackup, DocInfo},
+    id::WindowTabId,
     panel::{data::PanelOrder, kind::PanelKind, position::PanelPosition},
     window::{WindowData, WindowInfo},
     window_tab::WindowTabData,
@@ -27,12 +34,31 @@ pub enum SaveEvent {
     DisabledVolts(Vec<VoltID>),
     WorkspaceDisabledVolts(Arc<LapceWorkspace>, Vec<VoltID>),
     PanelOrder(PanelOrder),
+    Backups(LapceWorkspace, WindowTabId, DocBackups),
+    WindowTabClosed(LapceWorkspace, WindowTabId),
+    UndoHistory {
+        workspace: LapceWorkspace,
+        path: PathBuf,
+        history: Box<UndoHistory>,
+        max_size: usize,
+    },
 }
 
+/// How long an undo history is kept on disk without the file being saved
+const UNDO_HISTORY_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// The backups a workspace should have, by their keys. A key without a backup
+/// keeps the one that was written for it before.
+pub type DocBackups = Vec<(String, Option<DocBackup>)>;
+
 #[derive(Clone)]
 pub struct LapceDb {
     save_tx: Sender<SaveEvent>,
     sled_db: Option<sled::Db>,
+    /// The backups each window tab has, by the backups directory of its
+    /// workspace. Window tabs of the same workspace share the directory, so
+    /// a backup is only stale once none of them has it.
+    backup_keys: Arc<Mutex<HashMap<PathBuf, HashMap<WindowTabId, HashSet<String>>>>>,
 }
 
 impl LapceDb {
@@ -48,9 +74,14 @@ pub fn new() -> Result<Self> {
             .open()
             .ok();
 
-        let db = Self { save_tx, sled_db };
+        let db = Self {
+            save_tx,
+            sled_db,
+            backup_keys: Arc::new(Mutex::new(HashMap::new())),
+        };
         let local_db = db.clone();
         std::thread::spawn(move || -> Result<()> {
+            let _ = remove_old_undo_histories();
             loop {
                 let event = save_rx.recv()?;
                 match event {
@@ -76,6 +107,27 @@ pub fn new() -> Result<Self> {
                     SaveEvent::PanelOrder(order) => {
                         let _ = local_db.insert_panel_orders(&order);
                     }
+                    SaveEvent::Backups(workspace, window_tab_id, backups) => {
+                        let _ = local_db.insert_backups(
+                            &workspace,
+                            window_tab_id,
+                            &backups,
+                        );
+                    }
+                    SaveEvent::WindowTabClosed(workspace, window_tab_id) => {
+                        let _ = local_db
+                            .remove_window_tab_backups(&workspace, window_tab_id);
+                    }
+                    SaveEvent::UndoHistory {
+                        workspace,
+                        path,
+                        history,
+                        max_size,
+                    } => {
+                        let _ = local_db.insert_undo_history(
+                            &workspace, &path, &history, max_size,
+                        );
+                    }
                 }
             }
         });
@@ -145,6 +197,34 @@ pub fn get_workspace_disabled_volts(
         Ok(volts)
     }
 
+    /// If the workspace's own settings and keymaps in `.lapce` may be applied
+    pub fn is_workspace_trusted(&self, workspace: &LapceWorkspace) -> bool {
+        let path = if let Some(path) = workspace.path.as_ref() {
+            path
+        } else {
+            return false;
+        };
+        self.get_db()
+            .ok()
+            .and_then(|sled_db| {
+                sled_db
+                    .get(format!("trusted_workspace:{}", path.display()))
+                    .ok()?
+            })
+            .is_some()
+    }
+
+    pub fn trust_workspace(&self, workspace: &LapceWorkspace) -> Result<()> {
+        let path = workspace
+            .path
+            .as_ref()
+            .ok_or_else(|| anyhow!("workspace doesn't have a path"))?;
+        let sled_db = self.get_db()?;
+        sled_db.insert(format!("trusted_workspace:{}", path.display()), "true")?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
     pub fn recent_workspaces(&self) -> Result<Vec<LapceWorkspace>> {
         let sled_db = self.get_db()?;
         let workspaces = sled_db
@@ -180,7 +260,16 @@ fn insert_recent_workspace(&self, workspace: LapceWorkspace) -> Result<()> {
 
         let mut exits = false;
         for w in workspaces.iter_mut() {
-            if w.path == workspace.path && w.kind == workspace.kind {
+            // A multi-root workspace is its workspace file, whatever its
+            // folders are now
+            let same_roots = match (&w.multi_root, &workspace.multi_root) {
+                (Some(a), Some(b)) => a.file == b.file,
+                (None, None) => w.path == workspace.path,
+                _ => false,
+            };
+            if same_roots && w.kind == workspace.kind {
+                w.path = workspace.path.clone();
+                w.multi_root = workspace.multi_root.clone();
                 w.last_open = std::time::SystemTime::now()
                     .duration_since(std::time::UNIX_EPOCH)
                     .unwrap()
@@ -225,12 +314,28 @@ pub fn save_window_tab(&self, data: Rc<WindowTabData>) -> Result<()> {
         let workspace_info = data.workspace_info();
 
         self.save_tx
-            .send(SaveEvent::Workspace(workspace, workspace_info))?;
-        // self.insert_unsaved_buffer(main_split)?;
+            .send(SaveEvent::Workspace(workspace.clone(), workspace_info))?;
+        self.save_tx.send(SaveEvent::Backups(
+            workspace,
+            data.window_tab_id,
+            data.main_split.doc_backups(false),
+        ))?;
 
         Ok(())
     }
 
+    /// Save a window tab that's being closed. Its backups are written one last
+    /// time, and then no longer kept for it, so they're removed as soon as
+    /// another window tab of the workspace doesn't have them.
+    pub fn close_window_tab(&self, data: Rc<WindowTabData>) -> Result<()> {
+        let workspace = (*data.workspace).clone();
+        let window_tab_id = data.window_tab_id;
+        self.save_window_tab(data)?;
+        self.save_tx
+            .send(SaveEvent::WindowTabClosed(workspace, window_tab_id))?;
+        Ok(())
+    }
+
     fn insert_workspace(
         &self,
         workspace: &LapceWorkspace,
@@ -335,7 +440,13 @@ pub fn insert_window_tab(&self, data: Rc<WindowTabData>) -> Result<()> {
         let workspace_info = data.workspace_info();
 
         self.insert_workspace(&workspace, &workspace_info)?;
-        // self.insert_unsaved_buffer(main_split)?;
+        // This is what's left when quitting, so every backup is written again
+        // rather than trusting the save thread to have gotten to it
+        self.insert_backups(
+            &workspace,
+            data.window_tab_id,
+            &data.main_split.doc_backups(true),
+        )?;
 
         Ok(())
     }
@@ -401,4 +512,193 @@ pub fn get_doc_info(
         let info: DocInfo = serde_json::from_str(info)?;
         Ok(info)
     }
+
+    pub fn save_backups(
+        &self,
+        workspace: &LapceWorkspace,
+        window_tab_id: WindowTabId,
+        backups: DocBackups,
+    ) {
+        let _ = self.save_tx.send(SaveEvent::Backups(
+            workspace.clone(),
+            window_tab_id,
+            backups,
+        ));
+    }
+
+    /// Write the backups of the unsaved documents of a window tab, and remove
+    /// the ones of documents which were saved or closed since in every window
+    /// tab of the workspace
+    pub fn insert_backups(
+        &self,
+        workspace: &LapceWorkspace,
+        window_tab_id: WindowTabId,
+        backups: &DocBackups,
+    ) -> Result<()> {
+        let dir = Self::backups_directory(workspace)?;
+        let mut backup_keys = self.backup_keys.lock();
+        let window_tab_keys = backup_keys.entry(dir.clone()).or_default();
+        window_tab_keys.insert(
+            window_tab_id,
+            backups
+                .iter()
+                .map(|(key, _)| backup_file_name(key))
+                .collect(),
+        );
+        let keep: HashSet<&str> = window_tab_keys
+            .values()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        if keep.is_empty() {
+            if dir.exists() {
+                std::fs::remove_dir_all(&dir)?;
+            }
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&dir)?;
+        for (key, backup) in backups {
+            if let Some(backup) = backup {
+                let file_name = backup_file_name(key);
+                // Replaced in one go, so quitting halfway through leaves the
+                // previous backup
+                let tmp = dir.join(format!(".{file_name}.tmp"));
+                std::fs::write(&tmp, serde_json::to_string(backup)?)?;
+                std::fs::rename(&tmp, dir.join(&file_name))?;
+            }
+        }
+        for entry in std::fs::read_dir(&dir)?.flatten() {
+            if !keep.contains(entry.file_name().to_string_lossy().as_ref()) {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_window_tab_backups(
+        &self,
+        workspace: &LapceWorkspace,
+        window_tab_id: WindowTabId,
+    ) -> Result<()> {
+        let dir = Self::backups_directory(workspace)?;
+        let mut backup_keys = self.backup_keys.lock();
+        if let Some(window_tab_keys) = backup_keys.get_mut(&dir) {
+            window_tab_keys.remove(&window_tab_id);
+            if window_tab_keys.is_empty() {
+                backup_keys.remove(&dir);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_backup(
+        &self,
+        workspace: &LapceWorkspace,
+        key: &str,
+    ) -> Result<DocBackup> {
+        let path = Self::backups_directory(workspace)?.join(backup_file_name(key));
+        let backup = std::fs::read_to_string(path)?;
+        let backup: DocBackup = serde_json::from_str(&backup)?;
+        if backup.key != key {
+            return Err(anyhow!("backup is of another document"));
+        }
+        Ok(backup)
+    }
+
+    /// Keep the undo history of a file which was just saved, for when it's
+    /// opened again. Histories over `max_size` bytes aren't kept.
+    pub fn save_undo_history(
+        &self,
+        workspace: &LapceWorkspace,
+        path: PathBuf,
+        history: UndoHistory,
+        max_size: usize,
+    ) {
+        let _ = self.save_tx.send(SaveEvent::UndoHistory {
+            workspace: workspace.clone(),
+            path,
+            history: Box::new(history),
+            max_size,
+        });
+    }
+
+    fn insert_undo_history(
+        &self,
+        workspace: &LapceWorkspace,
+        path: &Path,
+        history: &UndoHistory,
+        max_size: usize,
+    ) -> Result<()> {
+        let file = Self::undo_history_file(workspace, path)?;
+        let history = serde_json::to_string(history)?;
+        if history.len() > max_size {
+            if file.exists() {
+                std::fs::remove_file(&file)?;
+            }
+            return Ok(());
+        }
+        let tmp = file.with_extension("tmp");
+        std::fs::write(&tmp, history)?;
+        std::fs::rename(&tmp, &file)?;
+        Ok(())
+    }
+
+    /// The undo history the file was last saved with. It still has to be
+    /// checked against the text the file has now.
+    pub fn get_undo_history(
+        &self,
+        workspace: &LapceWorkspace,
+        path: &Path,
+    ) -> Result<UndoHistory> {
+        let file = Self::undo_history_file(workspace, path)?;
+        let history = std::fs::read_to_string(file)?;
+        let history: UndoHistory = serde_json::from_str(&history)?;
+        Ok(history)
+    }
+
+    fn undo_history_file(
+        workspace: &LapceWorkspace,
+        path: &Path,
+    ) -> Result<PathBuf> {
+        let dir = Directory::undo_directory()
+            .ok_or_else(|| anyhow!("can't get undo directory"))?;
+        let key = format!("{}:{}", workspace, path.to_str().unwrap_or(""));
+        Ok(dir.join(format!("{}.json", hex_hash(&key))))
+    }
+
+    fn backups_directory(workspace: &LapceWorkspace) -> Result<PathBuf> {
+        let dir = Directory::backups_directory()
+            .ok_or_else(|| anyhow!("can't get backups directory"))?;
+        Ok(dir.join(hex_hash(&workspace.to_string())))
+    }
+}
+
+/// Remove the undo histories of files which haven't been saved for a while
+fn remove_old_undo_histories() -> Result<()> {
+    let dir = Directory::undo_directory()
+        .ok_or_else(|| anyhow!("can't get undo directory"))?;
+    let now = SystemTime::now();
+    for entry in std::fs::read_dir(dir)?.flatten() {
+        let modified = entry.metadata().and_then(|m| m.modified());
+        let old = modified
+            .map(|modified| {
+                now.duration_since(modified).unwrap_or_default()
+                    > UNDO_HISTORY_MAX_AGE
+            })
+            .unwrap_or(true);
+        if old {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+    Ok(())
+}
+
+fn backup_file_name(key: &str) -> String {
+    format!("{}.json", hex_hash(key))
+}
+
+/// The file name for a key, which has to be found again by later builds
+fn hex_hash(s: &str) -> String {
+    format!("{:016x}", stable_hash([s.as_bytes()]))
 }
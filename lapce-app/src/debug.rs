@@ -1,14 +1,16 @@
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::Display,
     path::{Path, PathBuf},
     time::Instant,
 };
 
 use floem::reactive::{RwSignal, Scope};
+use itertools::Itertools;
 use lapce_rpc::{
     dap_types::{
-        DapId, RunDebugConfig, SourceBreakpoint, StackFrame, Stopped, ThreadId,
+        self, DapId, RunDebugConfig, SourceBreakpoint, StackFrame, Stopped,
+        ThreadId, Variable,
     },
     terminal::TermId,
 };
@@ -16,6 +18,11 @@
 
 const DEFAULT_RUN_TOML: &str = include_str!("../../defaults/run.toml");
 
+/// How many lines above the execution line can show inline values
+const INLINE_VALUES_LINES: usize = 50;
+/// Values longer than this are cut when shown inline
+const INLINE_VALUE_MAX_LEN: usize = 50;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RunDebugMode {
     Run,
@@ -177,3 +184,97 @@ pub fn stopped(
         });
     }
 }
+
+/// The top stack frame of the thread the debugger stopped at. The editor
+/// highlights its line and shows the values of its variables inline.
+#[derive(Clone, PartialEq)]
+pub struct StoppedFrame {
+    pub dap_id: DapId,
+    pub path: PathBuf,
+    /// zero based line of the frame
+    pub line: usize,
+    /// (name, value) of the variables in the frame's scopes
+    pub variables: Vec<(String, String)>,
+}
+
+impl StoppedFrame {
+    pub fn new(
+        dap_id: DapId,
+        stopped: &Stopped,
+        stack_frames: &HashMap<ThreadId, Vec<StackFrame>>,
+        variables: &[(dap_types::Scope, Vec<Variable>)],
+    ) -> Option<Self> {
+        let frame = stack_frames
+            .get(&stopped.thread_id.unwrap_or_default())?
+            .first()?;
+        let path = frame.source.as_ref()?.path.clone()?;
+        let variables = variables
+            .iter()
+            .flat_map(|(_, variables)| variables.iter())
+            .map(|v| (v.name.clone(), v.value.clone()))
+            .collect();
+        Some(Self {
+            dap_id,
+            path,
+            line: frame.line.saturating_sub(1),
+            variables,
+        })
+    }
+
+    /// The inline values text for a line, which lists the frame's variables
+    /// that are mentioned in the line's content. Only the lines leading up to
+    /// the execution line get inline values.
+    pub fn inline_values(&self, line: usize, content: &str) -> Option<String> {
+        if line > self.line || line + INLINE_VALUES_LINES < self.line {
+            return None;
+        }
+
+        let words: HashSet<&str> = content
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .filter(|w| !w.is_empty())
+            .collect();
+        let values: Vec<String> = self
+            .variables
+            .iter()
+            .filter(|(name, _)| words.contains(name.as_str()))
+            .map(|(name, value)| {
+                let mut value = value.lines().join(" ");
+                if value.chars().count() > INLINE_VALUE_MAX_LEN {
+                    value = value.chars().take(INLINE_VALUE_MAX_LEN).collect();
+                    value.push('…');
+                }
+                format!("{name} = {value}")
+            })
+            .collect();
+        if values.is_empty() {
+            return None;
+        }
+        Some(values.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_rpc::dap_types::DapId;
+
+    use super::StoppedFrame;
+
+    #[test]
+    fn test_inline_values() {
+        let frame = StoppedFrame {
+            dap_id: DapId::next(),
+            path: "main.rs".into(),
+            line: 10,
+            variables: vec![
+                ("a".to_string(), "1".to_string()),
+                ("ab".to_string(), "\"x\ny\"".to_string()),
+            ],
+        };
+        assert_eq!(frame.inline_values(11, "let a = 1;"), None);
+        assert_eq!(frame.inline_values(10, "let b = 1;"), None);
+        assert_eq!(
+            frame.inline_values(10, "let a = ab.len();"),
+            Some("a = 1, ab = \"x y\"".to_string())
+        );
+    }
+}
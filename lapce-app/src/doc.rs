@@ -1,6 +1,7 @@
 use std::{
     cell::RefCell,
     collections::HashMap,
+    ops::Range,
     path::PathBuf,
     rc::Rc,
     sync::{atomic, Arc},
@@ -12,34 +13,41 @@
     action::exec_after,
     cosmic_text::{Attrs, AttrsList, FamilyOwned, TextLayout},
     ext_event::create_ext_action,
-    reactive::{RwSignal, Scope},
+    reactive::{use_context, RwSignal, Scope},
 };
 use itertools::Itertools;
 use lapce_core::{
     buffer::{
-        diff::{rope_diff, DiffLines},
-        rope_text::RopeText,
+        diff::{
+            diff_lines_selected, merge_diff_lines, rope_diff, rope_diff_delta,
+            DiffLines,
+        },
+        rope_text::{RopeText, RopeTextRef},
+        undo_history::text_hash,
         Buffer, InvalLines,
     },
     command::EditCommand,
     cursor::Cursor,
     editor::{EditType, Editor},
     language::LapceLanguage,
+    line_ending::LineEnding,
     register::{Clipboard, Register},
     selection::{InsertDrift, Selection},
     style::line_styles,
     syntax::{edit::SyntaxEdit, Syntax},
     word::WordCursor,
 };
+use lapce_proxy::buffer::language_id_from_path;
 use lapce_rpc::{
-    buffer::BufferId,
+    buffer::{BufferId, FileEncoding},
     plugin::PluginId,
-    proxy::ProxyResponse,
+    proxy::{ProxyResponse, SAVE_CONFLICT, SAVE_PERMISSION_DENIED},
+    source_control::BlameHunk,
     style::{LineStyle, LineStyles, Style},
 };
 use lapce_xi_rope::{
     spans::{Spans, SpansBuilder},
-    Interval, Rope, RopeDelta, Transformer,
+    DeltaBuilder, Interval, Rope, RopeDelta, Transformer,
 };
 use lsp_types::{
     CodeActionResponse, Diagnostic, DiagnosticSeverity, InlayHint, InlayHintLabel,
@@ -49,9 +57,12 @@
 
 use self::phantom_text::{PhantomText, PhantomTextKind, PhantomTextLine};
 use crate::{
-    config::{color::LapceColor, LapceConfig},
+    alert::AlertButton,
+    command::InternalCommand,
+    config::{color::LapceColor, editor::EditorConfig, LapceConfig},
+    db::LapceDb,
     editor::view_data::{LineExtraStyle, TextLayoutCache, TextLayoutLine},
-    find::{Find, FindProgress, FindResult},
+    find::{Find, FindProgress, FindResult, FindSearchString},
     history::DocumentHistory,
     window_tab::CommonData,
     workspace::LapceWorkspace,
@@ -59,6 +70,9 @@
 
 pub mod phantom_text;
 
+/// How many bytes of a large file are searched at a time
+const LARGE_FILE_FIND_CHUNK: usize = 4 * 1024 * 1024;
+
 pub struct SystemClipboard {
     ctx: ClipboardContext,
 }
@@ -143,6 +157,30 @@ pub fn path(&self) -> Option<&PathBuf> {
             DocContent::Scratch { .. } => None,
         }
     }
+
+    /// What a backup of the document's unsaved content is stored under.
+    /// Only files and scratch documents are backed up.
+    pub fn backup_key(&self) -> Option<String> {
+        match self {
+            DocContent::File { path, .. } => {
+                Some(format!("file:{}", path.display()))
+            }
+            DocContent::Scratch { name, .. } => Some(format!("scratch:{name}")),
+            DocContent::Local => None,
+            DocContent::History(_) => None,
+        }
+    }
+}
+
+/// How the file on disk relates to the document
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiskState {
+    /// The document was loaded from, or saved to, the file as it is now
+    InSync,
+    /// The file was changed on disk while the document had unsaved changes
+    Changed,
+    /// The file was deleted on disk
+    Deleted,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -153,6 +191,27 @@ pub struct DocInfo {
     pub cursor_offset: usize,
 }
 
+/// The unsaved content of a document, kept so it survives quitting the app
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DocBackup {
+    /// See [`DocContent::backup_key`]
+    pub key: String,
+    pub text: String,
+    /// The [`Document::disk_hash`] when the backup was taken, to tell whether
+    /// the file was changed on disk since
+    #[serde(default)]
+    pub disk_hash: Option<u64>,
+}
+
+/// The editor settings a document resolved for its language, which are only
+/// worked out again once the config, its language or its path changes
+struct EditorConfigCache {
+    config: Arc<LapceConfig>,
+    language: LapceLanguage,
+    path: Option<PathBuf>,
+    editor: Arc<EditorConfig>,
+}
+
 #[derive(Clone)]
 pub struct Preedit {
     pub text: String,
@@ -178,6 +237,15 @@ pub struct Document {
     pub loaded: RwSignal<bool>,
     pub buffer: RwSignal<Buffer>,
     pub syntax: RwSignal<Syntax>,
+    /// The encoding of the file, which it's saved in as well
+    pub encoding: RwSignal<FileEncoding>,
+    pub disk_state: RwSignal<DiskState>,
+    /// A [`text_hash`] of the file's content on disk, as of when the document
+    /// last loaded or saved it
+    pub disk_hash: RwSignal<Option<u64>>,
+    /// If the file is so large that syntax highlighting, language servers
+    /// and bracket matching are turned off for it
+    pub large_file: RwSignal<bool>,
     /// Semantic highlighting information (which is provided by the LSP)
     semantic_styles: RwSignal<Option<Spans<Style>>>,
     /// Inlay hints for the document
@@ -195,11 +263,14 @@ pub struct Document {
     /// Stores information about different versions of the document from source control.
     histories: RwSignal<im::HashMap<String, DocumentHistory>>,
     pub head_changes: RwSignal<im::Vector<DiffLines>>,
+    /// The git blame of the document, spanning the lines of each hunk
+    pub blame: RwSignal<Option<Spans<BlameHunk>>>,
     line_styles: Rc<RefCell<LineStyles>>,
     /// The text layouts for the document. This may be shared with other views.
     text_layouts: Rc<RefCell<TextLayoutCache>>,
     /// A cache for the sticky headers which maps a line to the lines it should show in the header.
     pub sticky_headers: Rc<RefCell<HashMap<usize, Option<Vec<usize>>>>>,
+    editor_config: Rc<RefCell<Option<EditorConfigCache>>>,
     pub find_result: FindResult,
     /// The diagnostics for the document
     pub diagnostics: DiagnosticData,
@@ -219,6 +290,10 @@ pub fn new(
             buffer_id: BufferId::next(),
             buffer: cx.create_rw_signal(Buffer::new("")),
             cache_rev: cx.create_rw_signal(0),
+            encoding: cx.create_rw_signal(FileEncoding::default()),
+            disk_state: cx.create_rw_signal(DiskState::InSync),
+            disk_hash: cx.create_rw_signal(None),
+            large_file: cx.create_rw_signal(false),
             syntax: cx.create_rw_signal(syntax),
             line_styles: Rc::new(RefCell::new(HashMap::new())),
             semantic_styles: cx.create_rw_signal(None),
@@ -233,8 +308,10 @@ pub fn new(
             loaded: cx.create_rw_signal(false),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
+            blame: cx.create_rw_signal(None),
             text_layouts: Rc::new(RefCell::new(TextLayoutCache::new())),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
+            editor_config: Rc::new(RefCell::new(None)),
             code_actions: cx.create_rw_signal(im::HashMap::new()),
             find_result: FindResult::new(cx),
             preedit: cx.create_rw_signal(None),
@@ -252,15 +329,26 @@ pub fn new_content(
         common: Rc<CommonData>,
     ) -> Self {
         let cx = cx.create_child();
+        let mut buffer = Buffer::new("");
+        if let DocContent::Scratch { .. } = content {
+            // New files start out with the configured line ending
+            buffer
+                .set_line_ending(common.config.get_untracked().files.line_ending());
+        }
         Self {
             scope: cx,
             buffer_id: BufferId::next(),
-            buffer: cx.create_rw_signal(Buffer::new("")),
+            buffer: cx.create_rw_signal(buffer),
             cache_rev: cx.create_rw_signal(0),
+            encoding: cx.create_rw_signal(FileEncoding::default()),
+            disk_state: cx.create_rw_signal(DiskState::InSync),
+            disk_hash: cx.create_rw_signal(None),
+            large_file: cx.create_rw_signal(false),
             content: cx.create_rw_signal(content),
             syntax: cx.create_rw_signal(Syntax::plaintext()),
             line_styles: Rc::new(RefCell::new(HashMap::new())),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
+            editor_config: Rc::new(RefCell::new(None)),
             semantic_styles: cx.create_rw_signal(None),
             inlay_hints: cx.create_rw_signal(None),
             diagnostics: DiagnosticData {
@@ -272,6 +360,7 @@ pub fn new_content(
             loaded: cx.create_rw_signal(true),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
+            blame: cx.create_rw_signal(None),
             text_layouts: Rc::new(RefCell::new(TextLayoutCache::new())),
             code_actions: cx.create_rw_signal(im::HashMap::new()),
             find_result: FindResult::new(cx),
@@ -296,10 +385,15 @@ pub fn new_hisotry(
             buffer_id: BufferId::next(),
             buffer: cx.create_rw_signal(Buffer::new("")),
             cache_rev: cx.create_rw_signal(0),
+            encoding: cx.create_rw_signal(FileEncoding::default()),
+            disk_state: cx.create_rw_signal(DiskState::InSync),
+            disk_hash: cx.create_rw_signal(None),
+            large_file: cx.create_rw_signal(false),
             content: cx.create_rw_signal(content),
             syntax: cx.create_rw_signal(syntax),
             line_styles: Rc::new(RefCell::new(HashMap::new())),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
+            editor_config: Rc::new(RefCell::new(None)),
             semantic_styles: cx.create_rw_signal(None),
             inlay_hints: cx.create_rw_signal(None),
             diagnostics: DiagnosticData {
@@ -311,6 +405,7 @@ pub fn new_hisotry(
             loaded: cx.create_rw_signal(true),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
+            blame: cx.create_rw_signal(None),
             text_layouts: Rc::new(RefCell::new(TextLayoutCache::new())),
             code_actions: cx.create_rw_signal(im::HashMap::new()),
             find_result: FindResult::new(cx),
@@ -343,16 +438,34 @@ pub fn loaded(&self) -> bool {
 
     //// Initialize the content with some text, this marks the document as loaded.
     pub fn init_content(&self, content: Rope) {
+        let line_ending = self.common.config.get_untracked().files.line_ending();
+        if self
+            .syntax
+            .with_untracked(|syntax| syntax.language == LapceLanguage::Plaintext)
+        {
+            let text = RopeTextRef::new(&content);
+            let first_line = text.slice_to_cow(0..text.line_end_offset(0, true));
+            if let Some(language) = LapceLanguage::from_shebang(&first_line) {
+                self.set_language(language);
+            }
+        }
+        self.disk_hash.set(Some(text_hash(&content)));
         self.syntax.with_untracked(|syntax| {
             self.buffer.update(|buffer| {
                 buffer.init_content(content);
                 buffer.detect_indent(syntax);
+                buffer.detect_line_ending(line_ending);
             });
         });
         self.loaded.set(true);
         self.on_update(None);
         self.init_diagnostics();
-        self.retrieve_head();
+        // Diffing a large file against its head after every edit would take
+        // far too long
+        if !self.large_file.get_untracked() {
+            self.retrieve_head();
+        }
+        self.retrieve_blame();
     }
 
     /// Reload the document's content, and is what you should typically use when you want to *set*
@@ -368,8 +481,83 @@ pub fn reload(&self, content: Rope, set_pristine: bool) {
     }
 
     pub fn handle_file_changed(&self, content: Rope) {
+        self.disk_hash.set(Some(text_hash(&content)));
         if self.is_pristine() {
             self.reload(content, true);
+            self.disk_state.set(DiskState::InSync);
+        } else {
+            // Kept until the document is saved, which then asks what to do
+            self.disk_state.set(DiskState::Changed);
+        }
+    }
+
+    pub fn handle_file_deleted(&self) {
+        self.disk_state.set(DiskState::Deleted);
+    }
+
+    /// Fetch the rest of a large file, which the proxy sends a chunk at a
+    /// time, after the `content` received so far. `f` gets the whole content
+    /// once it's all there, which is never the case if a chunk fails.
+    pub fn load_remaining_content(
+        &self,
+        path: PathBuf,
+        mut content: Rope,
+        f: impl FnOnce(Rope) + 'static,
+    ) {
+        let doc = self.clone();
+        let offset = content.len();
+        let local_path = path.clone();
+        let send = create_ext_action(self.scope, move |result| match result {
+            Ok(ProxyResponse::BufferChunkResponse {
+                content: chunk,
+                done,
+            }) => {
+                let len = content.len();
+                content.edit(len..len, chunk);
+                if done {
+                    f(content);
+                } else {
+                    doc.load_remaining_content(local_path, content, f);
+                }
+            }
+            Ok(_) => {}
+            Err(err) => {
+                doc.common
+                    .internal_command
+                    .send(InternalCommand::ShowAlert {
+                        title: "Failed to load the file".to_string(),
+                        msg: err.message,
+                        buttons: Vec::new(),
+                    });
+            }
+        });
+        self.common.proxy.buffer_chunk(path, offset, move |result| {
+            send(result);
+        });
+    }
+
+    /// Called with the content of the file once the document is opened again
+    /// in a new proxy, after reconnecting to a remote. The proxy is brought up
+    /// to date with the unsaved edits, while a document without any takes the
+    /// content the file has now.
+    pub fn reopened(&self, content: Rope) {
+        if !self.loaded() {
+            self.init_content(content);
+            return;
+        }
+
+        let path = match self.content.get_untracked() {
+            DocContent::File { path, .. } => path,
+            _ => return,
+        };
+        let (delta, rev) = self.buffer.with_untracked(|buffer| {
+            (rope_diff_delta(&content, buffer.text()), buffer.rev())
+        });
+        let changed = !delta.is_identity();
+        self.disk_hash.set(Some(text_hash(&content)));
+        self.common.proxy.sync_buffer(path, delta, rev);
+        if changed && self.is_pristine() {
+            self.reload(content, true);
         }
     }
 
@@ -377,7 +565,7 @@ pub fn do_insert(
         &self,
         cursor: &mut Cursor,
         s: &str,
-        config: &LapceConfig,
+        config: &Arc<LapceConfig>,
     ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
         if self.content.with_untracked(|c| c.read_only()) {
             return Vec::new();
@@ -392,7 +580,7 @@ pub fn do_insert(
                         buffer,
                         s,
                         syntax,
-                        config.editor.auto_closing_matching_pairs,
+                        self.editor_config(config).auto_closing_matching_pairs,
                     )
                 })
                 .unwrap()
@@ -406,6 +594,58 @@ pub fn do_insert(
         deltas
     }
 
+    /// Rewrite all the line breaks of the document to `line_ending`, which
+    /// new lines are inserted with from then on.
+    pub fn convert_line_ending(&self, line_ending: LineEnding) {
+        if self.content.with_untracked(|c| c.read_only()) {
+            return;
+        }
+        let delta = self
+            .buffer
+            .try_update(|buffer| buffer.convert_line_ending(line_ending))
+            .unwrap();
+        self.apply_deltas(&[delta]);
+    }
+
+    /// Revert the changes of the document against `index`, the content of the
+    /// file in the git index, that are in `lines`. It's an edit of the
+    /// document, so it can be undone like any other.
+    pub fn revert_lines(
+        &self,
+        index: &Rope,
+        changes: &[DiffLines],
+        lines: &Range<usize>,
+    ) {
+        let text = self.buffer.with_untracked(|buffer| buffer.text().clone());
+        let reverted = Rope::from(merge_diff_lines(index, &text, changes, |l| {
+            !diff_lines_selected(l, lines)
+        }));
+        let delta = rope_diff_delta(&text, &reverted);
+        if delta.is_identity() {
+            return;
+        }
+        let (iv, new_len) = delta.summary();
+        let new_text = reverted.slice_to_cow(iv.start..iv.start + new_len);
+        self.do_raw_edit(
+            &[(Selection::region(iv.start, iv.end), new_text.as_ref())],
+            EditType::Other,
+        );
+    }
+
+    /// Move the buffer around its undo tree, e.g. with
+    /// [`Buffer::go_to_undo_group`]
+    pub fn undo_tree_edit(
+        &self,
+        f: impl FnOnce(&mut Buffer) -> Option<(RopeDelta, InvalLines, SyntaxEdit)>,
+    ) {
+        if self.content.with_untracked(|c| c.read_only()) {
+            return;
+        }
+        if let Some(delta) = self.buffer.try_update(f).unwrap() {
+            self.apply_deltas(&[delta]);
+        }
+    }
+
     pub fn do_raw_edit(
         &self,
         edits: &[(impl AsRef<Selection>, &str)],
@@ -471,6 +711,7 @@ pub fn apply_deltas(&self, deltas: &[(RopeDelta, InvalLines, SyntaxEdit)]) {
         for (i, (delta, _, _)) in deltas.iter().enumerate() {
             self.update_styles(delta);
             self.update_inlay_hints(delta);
+            self.update_blame(delta);
             self.update_diagnostics(delta);
             self.update_completion_lens(delta);
             self.update_find_result(delta);
@@ -488,6 +729,39 @@ pub fn apply_deltas(&self, deltas: &[(RopeDelta, InvalLines, SyntaxEdit)]) {
         self.on_update(Some(edits));
     }
 
+    /// The editor settings of the document, with the `[editor.lang.<name>]`
+    /// overrides of its language applied. A language can be named by its
+    /// syntax, or by the language id that plugins know it by.
+    pub fn editor_config(&self, config: &Arc<LapceConfig>) -> Arc<EditorConfig> {
+        let language = self.syntax.with_untracked(|syntax| syntax.language);
+        let mut cache = self.editor_config.borrow_mut();
+        if let Some(cache) = cache.as_ref() {
+            if Arc::ptr_eq(&cache.config, config)
+                && cache.language == language
+                && self
+                    .content
+                    .with_untracked(|content| content.path() == cache.path.as_ref())
+            {
+                return cache.editor.clone();
+            }
+        }
+
+        let path = self
+            .content
+            .with_untracked(|content| content.path().cloned());
+        let language_id = path.as_deref().and_then(language_id_from_path);
+        let mut names = vec![language.name()];
+        names.extend(language_id);
+        let editor = Arc::new(config.editor.for_language(&names).into_owned());
+        *cache = Some(EditorConfigCache {
+            config: config.clone(),
+            language,
+            path,
+            editor: editor.clone(),
+        });
+        editor
+    }
+
     pub fn is_pristine(&self) -> bool {
         self.buffer.with_untracked(|b| b.is_pristine())
     }
@@ -563,7 +837,19 @@ fn update_inlay_hints(&self, delta: &RopeDelta) {
         });
     }
 
+    /// Update the blame so the hunks still cover the lines they were for after an edit.
+    fn update_blame(&self, delta: &RopeDelta) {
+        self.blame.update(|blame| {
+            if let Some(blame) = blame.as_mut() {
+                blame.apply_shape(delta);
+            }
+        });
+    }
+
     pub fn trigger_syntax_change(&self, edits: Option<SmallVec<[SyntaxEdit; 3]>>) {
+        if self.large_file.get_untracked() {
+            return;
+        }
         let (rev, text) =
             self.buffer.with_untracked(|b| (b.rev(), b.text().clone()));
 
@@ -650,7 +936,7 @@ pub fn line_style(&self, line: usize) -> Arc<Vec<LineStyle>> {
 
     /// Request semantic styles for the buffer from the LSP through the proxy.
     fn get_semantic_styles(&self) {
-        if !self.loaded() {
+        if !self.loaded() || self.large_file.get_untracked() {
             return;
         }
 
@@ -706,7 +992,7 @@ fn get_semantic_styles(&self) {
 
     /// Request inlay hints for the buffer from the LSP through the proxy.
     fn get_inlay_hints(&self) {
-        if !self.loaded() {
+        if !self.loaded() || self.large_file.get_untracked() {
             return;
         }
 
@@ -863,6 +1149,39 @@ pub fn line_phantom_text(&self, line: usize) -> PhantomTextLine {
 
         text.append(&mut diag_text);
 
+        // While the debugger is stopped in this document, show the values of the
+        // variables that the lines leading up to the execution line mention
+        let debug_value = self.common.stopped_frame.with_untracked(|frame| {
+            let frame = frame.as_ref()?;
+            if self
+                .content
+                .with_untracked(|c| c.path() != Some(&frame.path))
+            {
+                return None;
+            }
+            let line_content = self
+                .buffer
+                .with_untracked(|b| b.line_content(line).to_string());
+            let values = frame.inline_values(line, &line_content)?;
+            let col = line_content.trim_end_matches(['\r', '\n']).len();
+            Some(PhantomText {
+                kind: PhantomTextKind::DebugValue,
+                col,
+                text: format!("  {values}"),
+                fg: Some(
+                    *config.get_color(LapceColor::DEBUG_INLINE_VALUE_FOREGROUND),
+                ),
+                font_size: Some(config.editor.inlay_hint_font_size()),
+                bg: Some(
+                    *config.get_color(LapceColor::DEBUG_INLINE_VALUE_BACKGROUND),
+                ),
+                under_line: None,
+            })
+        });
+        if let Some(debug_value) = debug_value {
+            text.push(debug_value);
+        }
+
         let (completion_line, completion_col) = self.completion_pos.get_untracked();
         let completion_text = config
             .editor
@@ -1080,28 +1399,71 @@ pub fn update_find(&self) {
             .progress
             .set(FindProgress::InProgress(Selection::new()));
 
-        let find_result = self.find_result.clone();
-        let send = create_ext_action(self.scope, move |occurrences| {
-            find_result.occurrences.set(occurrences);
-            find_result.progress.set(FindProgress::Ready);
-        });
-
         let text = self.buffer.with_untracked(|b| b.text().clone());
+        self.find_from(text, search, 0, Selection::new());
+    }
+
+    /// Search the text from `start` on another thread. Large files are
+    /// searched a chunk at a time, so the matches show up while the rest of
+    /// the file is still being searched.
+    fn find_from(
+        &self,
+        text: Rope,
+        search: FindSearchString,
+        start: usize,
+        mut occurrences: Selection,
+    ) {
+        let end = if self.large_file.get_untracked() {
+            start.saturating_add(LARGE_FILE_FIND_CHUNK).min(text.len())
+        } else {
+            text.len()
+        };
+        let searched = if start == 0 {
+            Selection::new()
+        } else {
+            Selection::region(0, start)
+        };
+
+        let doc = self.clone();
+        let rev = self.rev();
+        let find_rev = self.find_result.find_rev.get_untracked();
+        let send =
+            create_ext_action(self.scope, move |(text, search, occurrences)| {
+                // The text or the search changed since, which started over
+                let find_result = &doc.find_result;
+                if doc.rev() != rev
+                    || find_result.find_rev.get_untracked() != find_rev
+                    || find_result.progress.get_untracked()
+                        != FindProgress::InProgress(searched)
+                {
+                    return;
+                }
+
+                find_result.occurrences.set(occurrences.clone());
+                if end < text.len() {
+                    find_result
+                        .progress
+                        .set(FindProgress::InProgress(Selection::region(0, end)));
+                    doc.find_from(text, search, end, occurrences);
+                } else {
+                    find_result.progress.set(FindProgress::Ready);
+                }
+            });
+
         let case_matching = self.common.find.case_matching.get_untracked();
         let whole_words = self.common.find.whole_words.get_untracked();
         rayon::spawn(move || {
-            let mut occurrences = Selection::new();
             Find::find(
                 &text,
                 &search,
-                0,
-                text.len(),
+                start,
+                end,
                 case_matching,
                 whole_words,
                 true,
                 &mut occurrences,
             );
-            send(occurrences);
+            send((text, search, occurrences));
         });
     }
 
@@ -1134,6 +1496,111 @@ pub fn sticky_headers(&self, line: usize) -> Option<Vec<usize>> {
         lines
     }
 
+    /// Retrieve the git blame of the buffer, if it's shown anywhere
+    pub fn retrieve_blame(&self) {
+        let path =
+            if let DocContent::File { path, .. } = self.content.get_untracked() {
+                path
+            } else {
+                return;
+            };
+        let config = self.common.config.get_untracked();
+        if !config.editor.show_git_blame_gutter
+            && !config.editor.show_git_blame_inline
+        {
+            return;
+        }
+
+        let rev = self.rev();
+        let doc = self.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            if let Ok(ProxyResponse::GitBlameResponse { hunks }) = result {
+                // The hunks are for an old version of the buffer
+                if doc.rev() != rev {
+                    return;
+                }
+                doc.set_blame(hunks);
+            }
+        });
+        self.common.proxy.git_blame(path, move |result| {
+            send(result);
+        });
+    }
+
+    fn set_blame(&self, hunks: Vec<BlameHunk>) {
+        let blame = self.buffer.with_untracked(|buffer| {
+            let mut builder = SpansBuilder::new(buffer.len());
+            for hunk in hunks {
+                let start = buffer.offset_of_line(hunk.start_line);
+                let end = buffer.offset_of_line(hunk.start_line + hunk.lines);
+                if start < end {
+                    builder.add_span(Interval::new(start, end), hunk);
+                }
+            }
+            builder.build()
+        });
+        self.blame.set(Some(blame));
+    }
+
+    /// The blame hunk the line is in
+    pub fn blame_of_line(&self, line: usize) -> Option<BlameHunk> {
+        let offset = self.buffer.with_untracked(|b| b.offset_of_line(line));
+        self.blame.with_untracked(|blame| {
+            blame.as_ref().and_then(|blame| {
+                blame
+                    .iter_chunks(offset..offset + 1)
+                    .next()
+                    .map(|(_, hunk)| hunk.clone())
+            })
+        })
+    }
+
+    /// Load the content of a document of an old version in the source control
+    pub fn retrieve_history(&self) {
+        let history =
+            if let DocContent::History(history) = self.content.get_untracked() {
+                history
+            } else {
+                return;
+            };
+        // The states of the undo tree are only in the document they're of
+        if history.version.starts_with("undo:") {
+            self.init_content(Rope::from(""));
+            return;
+        }
+
+        let doc = self.clone();
+        let send = create_ext_action(self.scope, move |result| match result {
+            Ok(ProxyResponse::BufferHeadResponse { content, .. }) => {
+                doc.init_content(Rope::from(content));
+            }
+            // The file doesn't exist in that version
+            Err(_) => {
+                doc.init_content(Rope::from(""));
+            }
+            _ => {}
+        });
+        if history.version == "head" {
+            self.common
+                .proxy
+                .get_buffer_head(history.path, move |result| {
+                    send(result);
+                });
+        } else if history.version == "disk" {
+            self.common.proxy.disk_content(history.path, move |result| {
+                send(result);
+            });
+        } else {
+            self.common.proxy.git_file_content(
+                history.path,
+                history.version,
+                move |result| {
+                    send(result);
+                },
+            );
+        }
+    }
+
     /// Retrieve the `head` version of the buffer
     pub fn retrieve_head(&self) {
         if let DocContent::File { path, .. } = self.content.get_untracked() {
@@ -1359,7 +1826,7 @@ fn new_text_layout(&self, line: usize, _font_size: usize) -> TextLayoutLine {
         }
 
         let mut text_layout = TextLayout::new();
-        text_layout.set_tab_width(config.editor.tab_width);
+        text_layout.set_tab_width(self.editor_config(&config).tab_width);
         text_layout.set_text(&line_content, attrs_list);
 
         // Keep track of background styling from phantom text, which is done separately
@@ -1543,32 +2010,272 @@ pub fn get_text_layout(
     }
 
     pub fn save(&self, after_action: impl Fn() + 'static) {
+        self.save_with_encoding(None, after_action);
+    }
+
+    /// Save the file, in the given encoding from now on, or else in the one
+    /// it has
+    pub fn save_with_encoding(
+        &self,
+        encoding: Option<FileEncoding>,
+        after_action: impl Fn() + 'static,
+    ) {
+        self.save_file(encoding, false, Rc::new(after_action));
+    }
+
+    /// Save the file, overwriting it even if it was changed on disk when
+    /// `force` is set
+    fn save_file(
+        &self,
+        encoding: Option<FileEncoding>,
+        force: bool,
+        after_action: Rc<dyn Fn()>,
+    ) {
         let content = self.content.get_untracked();
         if let DocContent::File { path, .. } = content {
             let rev = self.rev();
-            let buffer = self.buffer;
-            let send = create_ext_action(self.scope, move |result| {
-                if let Ok(ProxyResponse::SaveResponse {}) = result {
-                    let current_rev = buffer.with_untracked(|buffer| buffer.rev());
-                    if current_rev == rev {
-                        buffer.update(|buffer| {
-                            buffer.set_pristine();
+            let doc = self.clone();
+            let new_encoding = encoding.clone();
+            let save_path = path.clone();
+            let send = create_ext_action(self.scope, move |result| match result {
+                Ok(ProxyResponse::SaveResponse {}) => {
+                    if let Some(encoding) = new_encoding {
+                        doc.encoding.set(encoding);
+                    }
+                    doc.saved(rev, after_action);
+                }
+                Ok(_) => {}
+                Err(err) if err.code == SAVE_CONFLICT => {
+                    doc.save_conflict(save_path, new_encoding, after_action);
+                }
+                Err(err) => {
+                    let mut buttons = Vec::new();
+                    if err.code == SAVE_PERMISSION_DENIED {
+                        let internal_command = doc.common.internal_command;
+                        let doc = doc.clone();
+                        buttons.push(AlertButton {
+                            text: "Save as Root".to_string(),
+                            action: Rc::new(move || {
+                                internal_command.send(InternalCommand::HideAlert);
+                                doc.save_as_root(
+                                    rev,
+                                    save_path.clone(),
+                                    after_action.clone(),
+                                );
+                            }),
                         });
-                        after_action();
                     }
+                    doc.common
+                        .internal_command
+                        .send(InternalCommand::ShowAlert {
+                            title: "Failed to save the file".to_string(),
+                            msg: err.message,
+                            buttons,
+                        });
                 }
             });
 
-            self.common.proxy.save(rev, path, move |result| {
-                send(result);
+            self.common
+                .proxy
+                .save(rev, path, encoding, force, move |result| {
+                    send(result);
+                })
+        }
+    }
+
+    /// The save was refused as the file was changed on disk, so ask whether
+    /// to overwrite it, reload it, or compare the two first
+    fn save_conflict(
+        &self,
+        path: PathBuf,
+        encoding: Option<FileEncoding>,
+        after_action: Rc<dyn Fn()>,
+    ) {
+        self.disk_state.set(DiskState::Changed);
+        let internal_command = self.common.internal_command;
+        let overwrite = {
+            let doc = self.clone();
+            Rc::new(move || {
+                internal_command.send(InternalCommand::HideAlert);
+                doc.save_file(encoding.clone(), true, after_action.clone());
+            })
+        };
+        let reload = {
+            let doc = self.clone();
+            Rc::new(move || {
+                internal_command.send(InternalCommand::HideAlert);
+                doc.reopen_with_encoding(doc.encoding.get_untracked());
             })
+        };
+        let compare = {
+            let path = path.clone();
+            Rc::new(move || {
+                internal_command.send(InternalCommand::HideAlert);
+                internal_command
+                    .send(InternalCommand::CompareWithDisk { path: path.clone() });
+            })
+        };
+        internal_command.send(InternalCommand::ShowAlert {
+            title: format!(
+                "{} was changed on disk",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            msg: "Saving overwrites the changes made on disk.".to_string(),
+            buttons: vec![
+                AlertButton {
+                    text: "Overwrite".to_string(),
+                    action: overwrite,
+                },
+                AlertButton {
+                    text: "Reload and Lose Changes".to_string(),
+                    action: reload,
+                },
+                AlertButton {
+                    text: "Compare".to_string(),
+                    action: compare,
+                },
+            ],
+        });
+    }
+
+    /// Save the file as root, after a save failed because the file isn't
+    /// writable
+    fn save_as_root(&self, rev: u64, path: PathBuf, after_action: Rc<dyn Fn()>) {
+        let doc = self.clone();
+        let send = create_ext_action(self.scope, move |result| match result {
+            Ok(ProxyResponse::SaveResponse {}) => {
+                doc.saved(rev, after_action);
+            }
+            Ok(_) => {}
+            Err(err) => {
+                doc.common
+                    .internal_command
+                    .send(InternalCommand::ShowAlert {
+                        title: "Failed to save the file".to_string(),
+                        msg: err.message,
+                        buttons: Vec::new(),
+                    });
+            }
+        });
+        self.common.proxy.save_as_root(rev, path, move |result| {
+            send(result);
+        });
+    }
+
+    /// The file was saved at `rev`, which is pristine if nothing was edited
+    /// since
+    fn saved(&self, rev: u64, after_action: Rc<dyn Fn()>) {
+        self.disk_state.set(DiskState::InSync);
+        let current_rev = self.buffer.with_untracked(|buffer| buffer.rev());
+        if current_rev == rev {
+            self.buffer.update(|buffer| {
+                buffer.set_pristine();
+            });
+            let disk_hash = self.buffer.with_untracked(|b| text_hash(b.text()));
+            self.disk_hash.set(Some(disk_hash));
+            self.save_undo_history();
+            after_action();
+        }
+    }
+
+    /// Keep the undo history along with the text that was just saved
+    fn save_undo_history(&self) {
+        let config = self.common.config.get_untracked();
+        if !config.files.persistent_undo || self.large_file.get_untracked() {
+            return;
         }
+        let Some(path) = self.content.with_untracked(|c| c.path().cloned()) else {
+            return;
+        };
+        let history = self.buffer.with_untracked(|b| b.undo_history());
+        let db: Arc<LapceDb> = use_context().unwrap();
+        db.save_undo_history(
+            &self.common.workspace,
+            path,
+            history,
+            config.files.persistent_undo_max_size * 1024 * 1024,
+        );
+    }
+
+    /// Take over the undo history the file was last saved with, so undoing
+    /// can go back to before it was opened. A history of a file which was
+    /// changed elsewhere since is left alone.
+    pub fn restore_undo_history(&self) {
+        if !self.common.config.get_untracked().files.persistent_undo {
+            return;
+        }
+        let Some(path) = self.content.with_untracked(|c| c.path().cloned()) else {
+            return;
+        };
+        let db: Arc<LapceDb> = use_context().unwrap();
+        let Ok(history) = db.get_undo_history(&self.common.workspace, &path) else {
+            return;
+        };
+        let restored = self
+            .buffer
+            .try_update(|buffer| buffer.restore_undo_history(history))
+            .unwrap();
+        if !restored {
+            return;
+        }
+
+        // The proxy takes over the revision the history ends in
+        let (len, rev) = self.buffer.with_untracked(|b| (b.len(), b.rev()));
+        self.common
+            .proxy
+            .sync_buffer(path, DeltaBuilder::new(len).build(), rev);
+        self.on_update(None);
+    }
+
+    /// Read the file again in another encoding, which replaces the content
+    /// of the document, including any unsaved changes
+    pub fn reopen_with_encoding(&self, encoding: FileEncoding) {
+        let path = match self.content.get_untracked() {
+            DocContent::File { path, .. } => path,
+            _ => return,
+        };
+        let doc = self.clone();
+        let send = create_ext_action(self.scope, move |result| match result {
+            Ok(ProxyResponse::NewBufferResponse {
+                content, encoding, ..
+            }) => {
+                doc.encoding.set(encoding);
+                doc.content.update(|content| {
+                    if let DocContent::File { read_only, .. } = content {
+                        *read_only = false;
+                    }
+                });
+                let content = Rope::from(content);
+                doc.disk_hash.set(Some(text_hash(&content)));
+                doc.reload(content, true);
+                doc.disk_state.set(DiskState::InSync);
+            }
+            Ok(_) => {}
+            Err(err) => {
+                doc.common
+                    .internal_command
+                    .send(InternalCommand::ShowAlert {
+                        title: "Failed to reopen the file".to_string(),
+                        msg: err.message,
+                        buttons: Vec::new(),
+                    });
+            }
+        });
+        self.common
+            .proxy
+            .reopen_with_encoding(path, encoding, move |result| {
+                send(result);
+            });
     }
 
     /// Returns the offsets of the brackets enclosing the given offset.
     /// Uses a language aware algorithm if syntax support is available for the current language,
     /// else falls back to a language unaware algorithm.
     pub fn find_enclosing_brackets(&self, offset: usize) -> Option<(usize, usize)> {
+        // Searching for the brackets can go through the whole file
+        if self.large_file.get_untracked() {
+            return None;
+        }
         self.syntax
             .with_untracked(|syntax| {
                 (!syntax.text.is_empty()).then(|| syntax.find_enclosing_pair(offset))
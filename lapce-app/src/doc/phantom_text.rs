@@ -25,6 +25,8 @@ pub enum PhantomTextKind {
     Completion,
     /// Inlay hints supplied by an LSP/PSP (like type annotations)
     InlayHint,
+    /// Values of variables while stopped in the debugger
+    DebugValue,
     /// Error lens
     Diagnostic,
 }
@@ -1,5 +1,11 @@
 use std::{
-    cmp::Ordering, collections::HashMap, rc::Rc, str::FromStr, sync::Arc,
+    cmp::Ordering,
+    collections::HashMap,
+    ops::Range,
+    path::PathBuf,
+    rc::Rc,
+    str::FromStr,
+    sync::{atomic::AtomicU64, Arc},
     time::Duration,
 };
 
@@ -14,7 +20,11 @@
     reactive::{use_context, ReadSignal, RwSignal, Scope},
 };
 use lapce_core::{
-    buffer::{diff::DiffLines, rope_text::RopeText, InvalLines},
+    buffer::{
+        diff::{rope_diff, DiffLines},
+        rope_text::RopeText,
+        InvalLines,
+    },
     command::{EditCommand, FocusCommand, MotionModeCommand, MultiSelectionCommand},
     cursor::{Cursor, CursorMode},
     editor::EditType,
@@ -30,6 +40,7 @@
     Location, MarkedString, MarkupKind, TextEdit,
 };
 use serde::{Deserialize, Serialize};
+use tracing::error;
 
 use crate::{
     command::{
@@ -120,11 +131,11 @@ pub fn to_data(
                 EditorData::new_local(data.scope, editor_id, data.common)
             }
             DocContent::Scratch { name, .. } => {
-                let doc = data
+                let (doc, new_doc) = data
                     .scratch_docs
                     .try_update(|scratch_docs| {
                         if let Some(doc) = scratch_docs.get(name) {
-                            return doc.clone();
+                            return (doc.clone(), false);
                         }
                         let content = DocContent::Scratch {
                             id: BufferId::next(),
@@ -137,9 +148,12 @@ pub fn to_data(
                         );
                         let doc = Rc::new(doc);
                         scratch_docs.insert(name.to_string(), doc.clone());
-                        doc
+                        (doc, true)
                     })
                     .unwrap();
+                if new_doc {
+                    data.restore_backup(&doc);
+                }
 
                 EditorData::new(
                     data.scope,
@@ -324,7 +338,7 @@ fn run_edit_command(&self, cmd: &EditCommand) -> CommandExecuted {
         let smart_tab = self
             .common
             .config
-            .with_untracked(|config| config.editor.smart_tab);
+            .with_untracked(|config| doc.editor_config(config).smart_tab);
         let doc_before_edit =
             doc.buffer.with_untracked(|buffer| buffer.text().clone());
         let mut cursor = self.cursor.get_untracked();
@@ -846,7 +860,7 @@ fn inline_find(&self, direction: InlineFindDirection, c: &str) {
 
     fn go_to_definition(&self) {
         let doc = self.view.doc.get_untracked();
-        let path = match if doc.loaded() {
+        let path = match if doc.loaded() && !doc.large_file.get_untracked() {
             doc.content.with_untracked(|c| c.path().cloned())
         } else {
             None
@@ -1126,7 +1140,7 @@ fn update_completion(&self, display_if_empty_input: bool) {
         }
 
         let doc = self.view.doc.get_untracked();
-        let path = match if doc.loaded() {
+        let path = match if doc.loaded() && !doc.large_file.get_untracked() {
             doc.content.with_untracked(|c| c.path().cloned())
         } else {
             None
@@ -1431,6 +1445,18 @@ fn do_edit(
         self.apply_deltas(&[(delta, inval_lines, edits)]);
     }
 
+    /// Inserts the text at the cursors, replacing the selections in insert mode
+    pub fn insert_text(&self, text: &str) {
+        let selection = match self.cursor.get_untracked().mode {
+            CursorMode::Insert(selection) => selection,
+            CursorMode::Normal(offset) => Selection::caret(offset),
+            CursorMode::Visual { start, end, .. } => {
+                Selection::caret(start.min(end))
+            }
+        };
+        self.do_edit(&selection, &[(selection.clone(), text)]);
+    }
+
     pub fn do_text_edit(&self, edits: &[TextEdit]) {
         let (selection, edits) = self
             .view
@@ -1566,7 +1592,7 @@ pub fn go_to_position(
 
     pub fn get_code_actions(&self) {
         let doc = self.view.doc.get_untracked();
-        let path = match if doc.loaded() {
+        let path = match if doc.loaded() && !doc.large_file.get_untracked() {
             doc.content.with_untracked(|c| c.path().cloned())
         } else {
             None
@@ -1678,7 +1704,10 @@ pub fn save(
 
         let config = self.common.config.get_untracked();
         if let DocContent::File { path, .. } = content {
-            let format_on_save = allow_formatting && config.editor.format_on_save;
+            // Language servers aren't told about large files
+            let format_on_save = allow_formatting
+                && !doc.large_file.get_untracked()
+                && doc.editor_config(&config).format_on_save;
             if format_on_save {
                 let editor = self.clone();
                 let send = create_ext_action(self.scope, move |result| {
@@ -1832,7 +1861,7 @@ pub fn save_doc_position(&self) {
 
     fn rename(&self) {
         let doc = self.view.doc.get_untracked();
-        let path = match if doc.loaded() {
+        let path = match if doc.loaded() && !doc.large_file.get_untracked() {
             doc.content.with_untracked(|c| c.path().cloned())
         } else {
             None
@@ -2145,6 +2174,10 @@ fn right_click(&self, pointer_event: &PointerInputEvent) {
                 None,
                 Some(CommandKind::Focus(FocusCommand::Rename)),
                 None,
+                Some(CommandKind::Workbench(
+                    LapceWorkbenchCommand::SourceControlShowLineCommit,
+                )),
+                None,
                 Some(CommandKind::Edit(EditCommand::ClipboardCut)),
                 Some(CommandKind::Edit(EditCommand::ClipboardCopy)),
                 Some(CommandKind::Edit(EditCommand::ClipboardPaste)),
@@ -2186,6 +2219,10 @@ fn right_click(&self, pointer_event: &PointerInputEvent) {
 
     fn update_hover(&self, offset: usize) {
         let doc = self.view.doc.get_untracked();
+        // Large files aren't synced with language servers
+        if doc.large_file.get_untracked() {
+            return;
+        }
         let path = doc
             .content
             .with_untracked(|content| content.path().cloned());
@@ -2221,6 +2258,86 @@ pub fn reset(&self) {
             .update(|cursor| cursor.set_offset(0, false, false));
     }
 
+    /// Call `f` with the lines of the selection in this editor's file, for the
+    /// source control line operations, along with the content of the file in
+    /// the index and the changes of the document against it. Without a
+    /// selection, these are the lines of the change at the cursor. The line
+    /// operations work on the changes against the index rather than `HEAD`, so
+    /// that's what the change is looked up in.
+    pub fn selected_change_lines(
+        &self,
+        f: impl FnOnce(PathBuf, Range<usize>, Rope, Vec<DiffLines>) + 'static,
+    ) {
+        let doc = self.view.doc.get_untracked();
+        let path = match doc
+            .content
+            .with_untracked(|content| content.path().cloned())
+        {
+            Some(path) => path,
+            None => return,
+        };
+        let (start, end) = self
+            .cursor
+            .with_untracked(|cursor| (cursor.start_offset(), cursor.offset()));
+        let (start, end) = (start.min(end), start.max(end));
+        let (start_line, end_line) = doc.buffer.with_untracked(|buffer| {
+            (buffer.line_of_offset(start), buffer.line_of_offset(end))
+        });
+        let selection = (start != end).then_some(start_line..end_line + 1);
+        let (rev, text) = doc
+            .buffer
+            .with_untracked(|buffer| (buffer.rev(), buffer.text().clone()));
+
+        let send = {
+            let path = path.clone();
+            create_ext_action(self.scope, move |result| {
+                let content = match result {
+                    Ok(ProxyResponse::BufferHeadResponse { content, .. }) => content,
+                    Ok(_) => return,
+                    Err(err) => {
+                        error!("git: {}", err.message);
+                        return;
+                    }
+                };
+                // The lines no longer match if the document was edited since
+                if doc.rev() != rev {
+                    return;
+                }
+                let index = Rope::from(content);
+                let changes = rope_diff(
+                    index.clone(),
+                    text,
+                    0,
+                    Arc::new(AtomicU64::new(0)),
+                    None,
+                )
+                .unwrap_or_default();
+                let lines = match selection {
+                    Some(lines) => lines,
+                    None => match change_lines_at(&changes, start_line) {
+                        Some(lines) => lines,
+                        None => return,
+                    },
+                };
+                f(path, lines, index, changes);
+            })
+        };
+        self.common.proxy.git_index_content(path, move |result| {
+            send(result);
+        });
+    }
+
+    /// The line the debugger is stopped at, if it's in this editor's document
+    pub fn stopped_frame_line(&self) -> Option<usize> {
+        let doc = self.view.doc.get_untracked();
+        self.common.stopped_frame.with_untracked(|frame| {
+            let frame = frame.as_ref()?;
+            doc.content
+                .with_untracked(|c| c.path() == Some(&frame.path))
+                .then_some(frame.line)
+        })
+    }
+
     pub fn screen_lines(&self) -> ScreenLines {
         let viewport = self.viewport.get_untracked();
         let editor_view = self.view.kind;
@@ -2581,6 +2698,30 @@ fn receive_char(&self, c: &str) {
     }
 }
 
+/// The lines of the diff change at `line`, which is empty for removed lines
+fn change_lines_at(changes: &[DiffLines], line: usize) -> Option<Range<usize>> {
+    let mut right_line = 0;
+    for change in changes {
+        match change {
+            DiffLines::Left(_) => {
+                if right_line == line {
+                    return Some(line..line);
+                }
+            }
+            DiffLines::Both(info) => {
+                right_line = info.right.end;
+            }
+            DiffLines::Right(range) => {
+                if range.contains(&line) {
+                    return Some(range.clone());
+                }
+                right_line = range.end;
+            }
+        }
+    }
+    None
+}
+
 /// Checks if completion should be triggered if the received command
 /// is one that inserts whitespace or deletes whitespace
 fn show_completion(
@@ -12,8 +12,7 @@
     diff::{expand_diff_lines, rope_diff, DiffExpand, DiffLines},
     rope_text::RopeText,
 };
-use lapce_rpc::{buffer::BufferId, proxy::ProxyResponse};
-use lapce_xi_rope::Rope;
+use lapce_rpc::buffer::BufferId;
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -60,30 +59,11 @@ pub fn to_data(
                 DocContent::Local => {
                     Rc::new(Document::new_local(cx, common.clone()))
                 }
-                DocContent::History(history) => {
+                DocContent::History(_) => {
                     let doc =
                         Document::new_hisotry(cx, content.clone(), common.clone());
                     let doc = Rc::new(doc);
-
-                    {
-                        let doc = doc.clone();
-                        let send = create_ext_action(cx, move |result| {
-                            if let Ok(ProxyResponse::BufferHeadResponse {
-                                content,
-                                ..
-                            }) = result
-                            {
-                                doc.init_content(Rope::from(content));
-                            }
-                        });
-                        common.proxy.get_buffer_head(
-                            history.path.clone(),
-                            move |result| {
-                                send(result);
-                            },
-                        );
-                    }
-
+                    doc.retrieve_history();
                     doc
                 }
                 DocContent::Scratch { name, .. } => {
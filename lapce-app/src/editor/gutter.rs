@@ -4,7 +4,7 @@
     context::PaintCx,
     cosmic_text::{Attrs, AttrsList, FamilyOwned, TextLayout},
     id::Id,
-    peniko::kurbo::{Point, Rect, Size},
+    peniko::kurbo::{BezPath, Point, Rect, Size},
     view::{ChangeFlags, View},
     Renderer,
 };
@@ -13,10 +13,29 @@
 use crate::{
     config::{color::LapceColor, LapceConfig},
     doc::Document,
+    source_control::commit_date,
 };
 
 use super::{view::changes_colors, EditorData};
 
+/// The number of characters of the git blame column in the gutter
+const BLAME_GUTTER_CHARS: usize = 24;
+
+/// The width of the git blame column in the gutter, which is zero if it's not shown
+pub fn blame_gutter_width(config: &LapceConfig) -> f64 {
+    if !config.editor.show_git_blame_gutter {
+        return 0.0;
+    }
+    let family: Vec<FamilyOwned> =
+        FamilyOwned::parse_list(&config.editor.font_family).collect();
+    let attrs = Attrs::new()
+        .family(&family)
+        .font_size(config.editor.font_size() as f32);
+    let mut text_layout = TextLayout::new();
+    text_layout.set_text(&"0".repeat(BLAME_GUTTER_CHARS + 1), AttrsList::new(attrs));
+    text_layout.size().width
+}
+
 pub struct EditorGutterView {
     id: Id,
     editor: Rc<EditorData>,
@@ -73,6 +92,95 @@ fn paint_head_changes(
         }
     }
 
+    /// Paint the author and date of the commit at the first line of each blame hunk
+    fn paint_blame(
+        &self,
+        cx: &mut PaintCx,
+        doc: &Document,
+        viewport: Rect,
+        attrs_list: &AttrsList,
+        config: &LapceConfig,
+    ) {
+        if !config.editor.show_git_blame_gutter {
+            return;
+        }
+
+        let line_height = config.editor.line_height() as f64;
+        let screen_lines = self.editor.screen_lines();
+        let last_line = self.editor.view.last_line();
+        for line in &screen_lines.lines {
+            let line = *line;
+            if line > last_line {
+                break;
+            }
+
+            let hunk = match doc.blame_of_line(line) {
+                Some(hunk) => hunk,
+                None => continue,
+            };
+            // Only the first line of a hunk is labelled
+            let first_line = line == 0
+                || doc
+                    .blame_of_line(line - 1)
+                    .map(|prev| prev.start_line != hunk.start_line)
+                    .unwrap_or(true);
+            if !first_line {
+                continue;
+            }
+
+            let text = if hunk.is_committed() {
+                format!("{} {}", commit_date(hunk.time), hunk.author)
+            } else {
+                "Not Committed Yet".to_string()
+            };
+            let text: String = text.chars().take(BLAME_GUTTER_CHARS).collect();
+            let info = screen_lines.info.get(&line).unwrap();
+            let mut text_layout = TextLayout::new();
+            text_layout.set_text(&text, attrs_list.clone());
+            let height = text_layout.size().height;
+            cx.draw_text(
+                &text_layout,
+                Point::new(
+                    0.0,
+                    info.y as f64 + (line_height - height) / 2.0 - viewport.y0,
+                ),
+            );
+        }
+    }
+
+    /// Paint an arrow next to the line the debugger stopped at
+    fn paint_stopped_frame(
+        &self,
+        cx: &mut PaintCx,
+        viewport: Rect,
+        config: &LapceConfig,
+    ) {
+        let line = match self.editor.stopped_frame_line() {
+            Some(line) => line,
+            None => return,
+        };
+        let screen_lines = self.editor.screen_lines();
+        let info = match screen_lines.info.get(&line) {
+            Some(info) => info,
+            None => return,
+        };
+
+        let line_height = config.editor.line_height() as f64;
+        let size = (line_height / 2.0).min(10.0);
+        let x = self.width + 14.0;
+        let y = info.y as f64 - viewport.y0 + (line_height - size) / 2.0;
+        let mut path = BezPath::new();
+        path.move_to(Point::new(x, y));
+        path.line_to(Point::new(x + size, y + size / 2.0));
+        path.line_to(Point::new(x, y + size));
+        path.close_path();
+        cx.fill(
+            &path,
+            config.get_color(LapceColor::DEBUG_CURRENT_LINE_ARROW),
+            0.0,
+        );
+    }
+
     fn paint_sticky_headers(
         &self,
         cx: &mut PaintCx,
@@ -240,6 +348,14 @@ fn paint(&mut self, cx: &mut floem::context::PaintCx) {
             );
         }
 
+        self.paint_blame(
+            cx,
+            &self.editor.view.doc.get_untracked(),
+            viewport,
+            &attrs_list,
+            &config,
+        );
+        self.paint_stopped_frame(cx, viewport, &config);
         self.paint_head_changes(
             cx,
             self.editor.view.doc.get_untracked(),
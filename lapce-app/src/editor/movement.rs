@@ -101,7 +101,10 @@ pub fn move_offset(
                 offset,
                 mode,
                 count,
-                config.editor.atomic_soft_tab_width(),
+                view.doc
+                    .get_untracked()
+                    .editor_config(&config)
+                    .atomic_soft_tab_width(),
             );
 
             (new_offset, None)
@@ -112,7 +115,10 @@ pub fn move_offset(
                 offset,
                 mode,
                 count,
-                config.editor.atomic_soft_tab_width(),
+                view.doc
+                    .get_untracked()
+                    .editor_config(&config)
+                    .atomic_soft_tab_width(),
             );
 
             (new_offset, None)
@@ -29,7 +29,7 @@
 use lapce_xi_rope::find::CaseMatching;
 
 use super::{
-    gutter::editor_gutter_view,
+    gutter::{blame_gutter_width, editor_gutter_view},
     view_data::{EditorViewData, LineExtraStyle},
     EditorData,
 };
@@ -40,6 +40,7 @@
     doc::{DocContent, Document},
     keypress::KeyPressFocus,
     main_split::MainSplitData,
+    source_control::commit_date,
     text_input::text_input,
     window_tab::Focus,
     workspace::LapceWorkspace,
@@ -103,8 +104,10 @@ pub fn editor_view(
     });
 
     let hide_cursor = editor.common.hide_cursor;
+    let stopped_frame = editor.common.stopped_frame;
     create_effect(move |_| {
         hide_cursor.track();
+        stopped_frame.track();
         let occurrences = doc.with(|doc| doc.find_result.occurrences);
         occurrences.track();
         id.request_paint();
@@ -427,6 +430,86 @@ fn paint_cursor(
         }
     }
 
+    /// Highlight the line the debugger stopped at
+    fn paint_stopped_frame(
+        &self,
+        cx: &mut PaintCx,
+        viewport: Rect,
+        screen_lines: &ScreenLines,
+        config: &LapceConfig,
+    ) {
+        let line = match self.editor.stopped_frame_line() {
+            Some(line) => line,
+            None => return,
+        };
+        if let Some(info) = screen_lines.info.get(&line) {
+            let line_height = config.editor.line_height() as f64;
+            cx.fill(
+                &Rect::ZERO
+                    .with_size(Size::new(viewport.width(), line_height))
+                    .with_origin(Point::new(viewport.x0, info.y as f64)),
+                config.get_color(LapceColor::DEBUG_CURRENT_LINE),
+                0.0,
+            );
+        }
+    }
+
+    /// Paint the commit that last changed the current line at the end of it
+    fn paint_blame_inline(
+        &self,
+        cx: &mut PaintCx,
+        screen_lines: &ScreenLines,
+        config: &LapceConfig,
+    ) {
+        if !config.editor.show_git_blame_inline {
+            return;
+        }
+
+        let view = &self.editor.view;
+        let offset = self.editor.cursor.with_untracked(|c| c.offset());
+        let line = view.line_of_offset(offset);
+        let info = match screen_lines.info.get(&line) {
+            Some(info) => info,
+            None => return,
+        };
+        let doc = view.doc.get_untracked();
+        let hunk = match doc.blame_of_line(line) {
+            Some(hunk) => hunk,
+            None => return,
+        };
+        let text = if hunk.is_committed() {
+            format!(
+                "{}, {} \u{2022} {}",
+                hunk.author,
+                commit_date(hunk.time),
+                hunk.summary
+            )
+        } else {
+            "Not Committed Yet".to_string()
+        };
+
+        let line_height = config.editor.line_height() as f64;
+        let font_size = config.editor.font_size();
+        let family: Vec<FamilyOwned> =
+            FamilyOwned::parse_list(&config.editor.font_family).collect();
+        let attrs = Attrs::new()
+            .family(&family)
+            .color(*config.get_color(LapceColor::EDITOR_DIM))
+            .font_size(config.editor.inlay_hint_font_size() as f32);
+        let mut text_layout = TextLayout::new();
+        text_layout.set_text(&text, AttrsList::new(attrs));
+        let height = text_layout.size().height;
+
+        let line_width = view.get_text_layout(line, font_size).text.size().width;
+        cx.draw_text(
+            &text_layout,
+            Point::new(
+                line_width + font_size as f64 * 3.0,
+                info.y as f64 + (line_height - height) / 2.0,
+            ),
+        );
+    }
+
     fn paint_wave_line(
         &self,
         cx: &mut PaintCx,
@@ -524,6 +607,11 @@ fn paint_text(
         let font_size = config.editor.font_size();
 
         let indent_unit = view.indent_unit();
+        let show_indent_guide = view
+            .doc
+            .get_untracked()
+            .editor_config(&config)
+            .show_indent_guide;
         let family: Vec<FamilyOwned> =
             FamilyOwned::parse_list(&config.editor.font_family).collect();
         let attrs = Attrs::new()
@@ -595,7 +683,7 @@ fn paint_text(
                 }
             }
 
-            if config.editor.show_indent_guide {
+            if show_indent_guide {
                 let mut x = 0.0;
                 while x + 1.0 < text_layout.indent {
                     cx.stroke(
@@ -1153,10 +1241,12 @@ fn paint(&mut self, cx: &mut PaintCx) {
         let is_local = doc.content.with_untracked(|content| content.is_local());
 
         self.paint_cursor(cx, is_local, &screen_lines);
+        self.paint_stopped_frame(cx, viewport, &screen_lines, &config);
         self.paint_diff_sections(cx, viewport, &screen_lines, &config);
         self.paint_find(cx, &screen_lines);
         self.paint_bracket_highlights_scope_lines(cx, viewport, &screen_lines);
         self.paint_text(cx, viewport, &screen_lines);
+        self.paint_blame_inline(cx, &screen_lines, &config);
         self.paint_sticky_headers(cx, viewport);
         self.paint_scroll_bar(cx, viewport, is_local, config);
     }
@@ -1621,6 +1711,8 @@ fn editor_gutter(
     stack((
         stack((
             empty().style(move |s| s.width(padding_left)),
+            empty()
+                .style(move |s| s.width(blame_gutter_width(&config.get()) as f32)),
             label(move || {
                 let doc = doc.get();
                 doc.buffer.with(|b| b.last_line() + 1).to_string()
@@ -663,13 +663,14 @@ pub fn line_col_of_point(
         let max_col = self.line_end_col(line, mode != Mode::Normal);
         let mut col = col.min(max_col);
 
-        if config.editor.atomic_soft_tabs && config.editor.tab_width > 1 {
+        let editor_config = self.doc.get_untracked().editor_config(&config);
+        if editor_config.atomic_soft_tabs && editor_config.tab_width > 1 {
             col = snap_to_soft_tab_line_col(
                 &self.text(),
                 line,
                 col,
                 SnapDirection::Nearest,
-                config.editor.tab_width,
+                editor_config.tab_width,
             );
         }
 
@@ -750,6 +751,10 @@ pub fn find_matching_pair(&self, offset: usize) -> usize {
         // This needs the doc's syntax, but it isn't cheap to clone
         // so this has to be a method on view for now.
         self.doc.with_untracked(|doc| {
+            // Searching for the pair can go through the whole file
+            if doc.large_file.get_untracked() {
+                return offset;
+            }
             doc.syntax.with_untracked(|syntax| {
                 if syntax.layers.is_some() {
                     syntax.find_matching_pair(offset).unwrap_or(offset)
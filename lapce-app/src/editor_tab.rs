@@ -15,13 +15,17 @@
 
 use crate::{
     config::{color::LapceColor, icon::LapceIcons, LapceConfig},
-    doc::{DocContent, Document},
+    doc::{DiskState, DocContent, Document},
     editor::{
         diff::{DiffEditorData, DiffEditorInfo},
         location::EditorLocation,
         EditorData, EditorInfo,
     },
-    id::{DiffEditorId, EditorId, EditorTabId, KeymapId, SettingsId, SplitId},
+    hex_editor::HexEditorData,
+    id::{
+        DiffEditorId, EditorId, EditorTabId, HexEditorId, KeymapId, SettingsId,
+        SplitId,
+    },
     main_split::MainSplitData,
     window_tab::WindowTabData,
 };
@@ -32,6 +36,7 @@ pub enum EditorTabChildInfo {
     DiffEditor(DiffEditorInfo),
     Settings,
     Keymap,
+    HexEditor(PathBuf),
 }
 
 impl EditorTabChildInfo {
@@ -53,6 +58,20 @@ pub fn to_data(
                 EditorTabChild::Settings(SettingsId::next())
             }
             EditorTabChildInfo::Keymap => EditorTabChild::Keymap(KeymapId::next()),
+            EditorTabChildInfo::HexEditor(path) => {
+                let hex_editor_id = HexEditorId::next();
+                let hex_editor = HexEditorData::new(
+                    data.scope,
+                    hex_editor_id,
+                    editor_tab_id,
+                    path.clone(),
+                    data.common.clone(),
+                );
+                data.hex_editors.update(|hex_editors| {
+                    hex_editors.insert(hex_editor_id, hex_editor);
+                });
+                EditorTabChild::HexEditor(hex_editor_id)
+            }
         }
     }
 }
@@ -118,6 +137,9 @@ pub enum EditorTabChildSource {
     NewFileEditor,
     Settings,
     Keymap,
+    HexEditor {
+        path: PathBuf,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -126,6 +148,7 @@ pub enum EditorTabChild {
     DiffEditor(DiffEditorId),
     Settings(SettingsId),
     Keymap(KeymapId),
+    HexEditor(HexEditorId),
 }
 
 #[derive(PartialEq)]
@@ -144,6 +167,7 @@ pub fn id(&self) -> u64 {
             EditorTabChild::DiffEditor(id) => id.to_raw(),
             EditorTabChild::Settings(id) => id.to_raw(),
             EditorTabChild::Keymap(id) => id.to_raw(),
+            EditorTabChild::HexEditor(id) => id.to_raw(),
         }
     }
 
@@ -175,6 +199,16 @@ pub fn child_info(&self, data: &WindowTabData) -> EditorTabChildInfo {
             }
             EditorTabChild::Settings(_) => EditorTabChildInfo::Settings,
             EditorTabChild::Keymap(_) => EditorTabChildInfo::Keymap,
+            EditorTabChild::HexEditor(hex_editor_id) => {
+                let hex_editor_data = data
+                    .main_split
+                    .hex_editors
+                    .get_untracked()
+                    .get(hex_editor_id)
+                    .cloned()
+                    .unwrap();
+                EditorTabChildInfo::HexEditor(hex_editor_data.path)
+            }
         }
     }
 
@@ -182,6 +216,7 @@ pub fn view_info(
         &self,
         editors: RwSignal<im::HashMap<EditorId, Rc<EditorData>>>,
         diff_editors: RwSignal<im::HashMap<DiffEditorId, DiffEditorData>>,
+        hex_editors: RwSignal<im::HashMap<HexEditorId, HexEditorData>>,
         config: ReadSignal<Arc<LapceConfig>>,
     ) -> Memo<EditorTabChildViewInfo> {
         match self.clone() {
@@ -189,6 +224,7 @@ pub fn view_info(
                 let config = config.get();
                 let editor_data =
                     editors.with(|editors| editors.get(&editor_id).cloned());
+                let mut deleted = false;
                 let path = if let Some(editor_data) = editor_data {
                     let doc = editor_data.view.doc.get();
                     let (content, is_pristine, confirmed) = (
@@ -196,6 +232,7 @@ pub fn view_info(
                         doc.buffer.with(|b| b.is_pristine()),
                         editor_data.confirmed,
                     );
+                    deleted = doc.disk_state.get() == DiskState::Deleted;
                     match content {
                         DocContent::File { path, .. } => {
                             Some((path, confirmed, is_pristine))
@@ -212,14 +249,19 @@ pub fn view_info(
                 let (icon, color, path, confirmed, is_pristine) = match path {
                     Some((path, confirmed, is_pritine)) => {
                         let (svg, color) = config.file_svg(&path);
+                        let name = path
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_str()
+                            .unwrap_or_default();
                         (
                             svg,
                             color.cloned(),
-                            path.file_name()
-                                .unwrap_or_default()
-                                .to_str()
-                                .unwrap_or_default()
-                                .to_string(),
+                            if deleted {
+                                format!("{name} (Deleted)")
+                            } else {
+                                name.to_string()
+                            },
                             confirmed,
                             is_pritine,
                         )
@@ -312,6 +354,31 @@ pub fn view_info(
                     is_pristine: true,
                 }
             }),
+            EditorTabChild::HexEditor(hex_editor_id) => create_memo(move |_| {
+                let config = config.get();
+                let hex_editor_data = hex_editors
+                    .with(|hex_editors| hex_editors.get(&hex_editor_id).cloned());
+                let (name, is_pristine) = hex_editor_data
+                    .map(|hex_editor_data| {
+                        (
+                            hex_editor_data
+                                .path
+                                .file_name()
+                                .unwrap_or_default()
+                                .to_string_lossy()
+                                .to_string(),
+                            hex_editor_data.edited.with(|edited| edited.is_empty()),
+                        )
+                    })
+                    .unwrap_or_else(|| (String::new(), true));
+                EditorTabChildViewInfo {
+                    icon: config.ui_svg(LapceIcons::HEX_EDITOR),
+                    color: Some(*config.get_color(LapceColor::LAPCE_ICON_ACTIVE)),
+                    path: format!("{name} (Hex)"),
+                    confirmed: None,
+                    is_pristine,
+                }
+            }),
         }
     }
 }
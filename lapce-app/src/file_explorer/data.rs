@@ -5,43 +5,112 @@
 };
 
 use floem::{
+    action::show_context_menu,
     ext_event::create_ext_action,
+    menu::{Menu, MenuItem},
     reactive::{RwSignal, Scope},
 };
 use lapce_rpc::{file::FileNodeItem, proxy::ProxyResponse};
 
 use crate::{command::InternalCommand, window_tab::CommonData};
 
+fn root_node(path: PathBuf) -> FileNodeItem {
+    FileNodeItem {
+        path,
+        is_dir: true,
+        read: false,
+        open: false,
+        children: HashMap::new(),
+        children_open_count: 0,
+    }
+}
+
+/// The root that contains the path, which is the innermost one if roots
+/// are nested
+fn root_of<'a>(
+    roots: &'a mut [FileNodeItem],
+    path: &Path,
+) -> Option<&'a mut FileNodeItem> {
+    roots
+        .iter_mut()
+        .filter(|root| path.starts_with(&root.path))
+        .max_by_key(|root| root.path.components().count())
+}
+
 #[derive(Clone)]
 pub struct FileExplorerData {
     pub id: RwSignal<usize>,
-    pub root: RwSignal<FileNodeItem>,
+    /// The roots of the workspace, with the main one first. The other ones
+    /// are the folders of a multi-root workspace.
+    pub roots: RwSignal<Vec<FileNodeItem>>,
     pub common: Rc<CommonData>,
 }
 
 impl FileExplorerData {
     pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
-        let path = common.workspace.path.clone().unwrap_or_default();
-        let root = cx.create_rw_signal(FileNodeItem {
-            path: path.clone(),
-            is_dir: true,
-            read: false,
-            open: false,
-            children: HashMap::new(),
-            children_open_count: 0,
-        });
+        let mut paths = common.workspace.roots();
+        if paths.is_empty() {
+            paths.push(PathBuf::new());
+        }
         let data = Self {
             id: cx.create_rw_signal(0),
-            root,
+            roots: cx.create_rw_signal(Vec::new()),
             common,
         };
-        data.toggle_expand(&path);
+        data.set_roots(paths);
         data
     }
 
+    /// The paths of the roots, with the main one first
+    pub fn root_paths(&self) -> Vec<PathBuf> {
+        self.roots
+            .with_untracked(|roots| roots.iter().map(|r| r.path.clone()).collect())
+    }
+
+    /// Show the roots, keeping the nodes of the ones that were already shown
+    pub fn set_roots(&self, paths: Vec<PathBuf>) {
+        let mut old_roots = self.roots.get_untracked();
+        let mut new_paths = Vec::new();
+        let roots = paths
+            .into_iter()
+            .map(
+                |path| match old_roots.iter().position(|root| root.path == path) {
+                    Some(i) => old_roots.remove(i),
+                    None => {
+                        new_paths.push(path.clone());
+                        root_node(path)
+                    }
+                },
+            )
+            .collect();
+        self.id.update(|id| {
+            *id += 1;
+        });
+        self.roots.set(roots);
+        for path in new_paths {
+            self.toggle_expand(&path);
+        }
+    }
+
     pub fn reload(&self) {
-        let path = self.root.with_untracked(|root| root.path.clone());
-        self.read_dir(&path);
+        for path in self.root_paths() {
+            self.read_dir(&path);
+        }
+    }
+
+    fn get_node<T>(
+        &self,
+        path: &Path,
+        f: impl FnOnce(&FileNodeItem) -> T,
+    ) -> Option<T> {
+        self.roots.with_untracked(|roots| {
+            roots
+                .iter()
+                .filter(|root| path.starts_with(&root.path))
+                .max_by_key(|root| root.path.components().count())
+                .and_then(|root| root.get_file_node(path))
+                .map(f)
+        })
     }
 
     pub fn toggle_expand(&self, path: &Path) {
@@ -49,8 +118,9 @@ pub fn toggle_expand(&self, path: &Path) {
             *id += 1;
         });
         if let Some(read) = self
-            .root
-            .try_update(|root| {
+            .roots
+            .try_update(|roots| {
+                let root = root_of(roots, path)?;
                 let read = if let Some(node) = root.get_file_node_mut(path) {
                     if !node.is_dir {
                         return None;
@@ -74,7 +144,7 @@ pub fn toggle_expand(&self, path: &Path) {
     }
 
     pub fn read_dir(&self, path: &Path) {
-        let root = self.root;
+        let roots = self.roots;
         let id = self.id;
         let data = self.clone();
         let send = {
@@ -84,7 +154,11 @@ pub fn read_dir(&self, path: &Path) {
                     id.update(|id| {
                         *id += 1;
                     });
-                    root.update(|root| {
+                    roots.update(|roots| {
+                        let root = match root_of(roots, &path) {
+                            Some(root) => root,
+                            None => return,
+                        };
                         if let Some(node) = root.get_file_node_mut(&path) {
                             node.read = true;
                             let removed_paths: Vec<PathBuf> = node
@@ -121,10 +195,7 @@ pub fn read_dir(&self, path: &Path) {
     }
 
     pub fn click(&self, path: &Path) {
-        let is_dir = self
-            .root
-            .with_untracked(|root| root.get_file_node(path).map(|n| n.is_dir))
-            .unwrap_or(false);
+        let is_dir = self.get_node(path, |n| n.is_dir).unwrap_or(false);
         if is_dir {
             self.toggle_expand(path);
         } else {
@@ -137,10 +208,7 @@ pub fn click(&self, path: &Path) {
     }
 
     pub fn double_click(&self, path: &Path) -> bool {
-        let is_dir = self
-            .root
-            .with_untracked(|root| root.get_file_node(path).map(|n| n.is_dir))
-            .unwrap_or(false);
+        let is_dir = self.get_node(path, |n| n.is_dir).unwrap_or(false);
         if is_dir {
             false
         } else {
@@ -152,10 +220,7 @@ pub fn double_click(&self, path: &Path) -> bool {
     }
 
     pub fn middle_click(&self, path: &Path) -> bool {
-        let is_dir = self
-            .root
-            .with_untracked(|root| root.get_file_node(path).map(|n| n.is_dir))
-            .unwrap_or(false);
+        let is_dir = self.get_node(path, |n| n.is_dir).unwrap_or(false);
         if is_dir {
             false
         } else {
@@ -167,4 +232,30 @@ pub fn middle_click(&self, path: &Path) -> bool {
             true
         }
     }
+
+    pub fn secondary_click(&self, path: &Path) {
+        let internal_command = self.common.internal_command;
+        // The main root stays, as the workspace is opened on it
+        let is_folder = self
+            .roots
+            .with_untracked(|roots| roots.iter().skip(1).any(|r| r.path == path));
+        let path = path.to_path_buf();
+        let mut menu = Menu::new("").entry(MenuItem::new("View History").action({
+            let path = path.clone();
+            move || {
+                internal_command
+                    .send(InternalCommand::ShowFileHistory { path: path.clone() });
+            }
+        }));
+        if is_folder {
+            menu = menu.separator().entry(
+                MenuItem::new("Remove Folder from Workspace").action(move || {
+                    internal_command.send(InternalCommand::RemoveWorkspaceFolder {
+                        path: path.clone(),
+                    });
+                }),
+            );
+        }
+        show_context_menu(menu, None);
+    }
 }
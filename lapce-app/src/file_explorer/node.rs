@@ -2,13 +2,19 @@
 
 use lapce_rpc::file::{FileNodeItem, FileNodeViewData};
 
-pub struct FileNodeVirtualList(pub FileNodeItem);
+/// The nodes of the roots of the workspace. A single root isn't shown
+/// itself, only its children are, but the roots of a multi-root workspace
+/// are each shown with their children below them.
+pub struct FileNodeVirtualList(pub Vec<FileNodeItem>);
 
 impl VirtualListVector<FileNodeViewData> for FileNodeVirtualList {
     type ItemIterator = Box<dyn Iterator<Item = FileNodeViewData>>;
 
     fn total_len(&self) -> usize {
-        self.0.children_open_count
+        match self.0.as_slice() {
+            [root] => root.children_open_count,
+            roots => roots.iter().map(|root| root.children_open_count + 1).sum(),
+        }
     }
 
     fn slice(&mut self, range: std::ops::Range<usize>) -> Self::ItemIterator {
@@ -16,7 +22,11 @@ fn slice(&mut self, range: std::ops::Range<usize>) -> Self::ItemIterator {
         let max = range.end;
         let mut i = 0;
         let mut view_items = Vec::new();
-        for item in self.0.sorted_children() {
+        let items = match self.0.as_slice() {
+            [root] => root.sorted_children(),
+            roots => roots.iter().collect(),
+        };
+        for item in items {
             i = item.append_view_slice(&mut view_items, min, max, i + 1, 0);
             if i > max {
                 return Box::new(view_items.into_iter());
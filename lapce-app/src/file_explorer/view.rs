@@ -53,13 +53,13 @@ pub fn file_explorer_panel(
 }
 
 fn new_file_node_view(data: FileExplorerData) -> impl View {
-    let root = data.root;
+    let roots = data.roots;
     let ui_line_height = data.common.ui_line_height;
     let config = data.common.config;
     virtual_list(
         VirtualListDirection::Vertical,
         VirtualListItemSize::Fixed(Box::new(move || ui_line_height.get())),
-        move || FileNodeVirtualList(root.get()),
+        move || FileNodeVirtualList(roots.get()),
         move |node| (node.path.clone(), node.is_dir, node.open, node.level),
         move |node| {
             let level = node.level;
@@ -156,6 +156,8 @@ fn new_file_node_view(data: FileExplorerData) -> impl View {
                 if let Event::PointerDown(pointer_event) = event {
                     if pointer_event.button.is_auxiliary() {
                         aux_click_data.middle_click(&aux_click_path);
+                    } else if pointer_event.button.is_secondary() {
+                        aux_click_data.secondary_click(&aux_click_path);
                     }
                 }
                 true
@@ -167,6 +169,7 @@ fn new_file_node_view(data: FileExplorerData) -> impl View {
 
 fn open_editors_view(window_tab_data: Rc<WindowTabData>) -> impl View {
     let diff_editors = window_tab_data.main_split.diff_editors;
+    let hex_editors = window_tab_data.main_split.hex_editors;
     let editors = window_tab_data.main_split.editors;
     let editor_tabs = window_tab_data.main_split.editor_tabs;
     let config = window_tab_data.common.config;
@@ -179,7 +182,7 @@ fn open_editors_view(window_tab_data: Rc<WindowTabData>) -> impl View {
         let editor_tab_id =
             editor_tab.with_untracked(|editor_tab| editor_tab.editor_tab_id);
         let child_for_close = child.clone();
-        let info = child.view_info(editors, diff_editors, config);
+        let info = child.view_info(editors, diff_editors, hex_editors, config);
         let hovered = create_rw_signal(false);
 
         stack((
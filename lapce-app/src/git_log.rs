@@ -0,0 +1,467 @@
+use std::{path::PathBuf, rc::Rc, time::Duration};
+
+use floem::{
+    action::{exec_after, TimerToken},
+    ext_event::create_ext_action,
+    keyboard::ModifiersState,
+    reactive::{RwSignal, Scope},
+};
+use lapce_core::mode::Mode;
+use lapce_rpc::{
+    proxy::ProxyResponse,
+    source_control::{FileDiff, GitCommit, GitLogFilter},
+};
+
+use crate::{
+    command::{CommandExecuted, CommandKind},
+    editor::EditorData,
+    id::EditorId,
+    keypress::{condition::Condition, KeyPressFocus},
+    main_split::MainSplitData,
+    window_tab::CommonData,
+};
+
+/// The number of commits that are loaded at a time
+const PAGE_SIZE: usize = 200;
+
+/// How long typing in the filter has to pause before the log is loaded again
+const FILTER_DELAY: Duration = Duration::from_millis(300);
+
+/// How a row of the branch graph is drawn. A lane is a column of the graph.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct GraphRow {
+    /// The lane of the commit's node
+    pub column: usize,
+    /// Lines in the upper half of the row, from a lane at the top to a lane
+    /// at the height of the node
+    pub upper: Vec<(usize, usize)>,
+    /// Lines in the lower half of the row, from a lane at the height of the
+    /// node to a lane at the bottom
+    pub lower: Vec<(usize, usize)>,
+}
+
+impl GraphRow {
+    /// The number of lanes the row spans
+    pub fn width(&self) -> usize {
+        self.upper
+            .iter()
+            .chain(self.lower.iter())
+            .map(|(from, to)| *from.max(to))
+            .max()
+            .unwrap_or(0)
+            .max(self.column)
+            + 1
+    }
+}
+
+/// Lay out the branch graph of commits that are ordered with children before
+/// their parents.
+pub fn graph_rows(commits: &[GitCommit]) -> Vec<GraphRow> {
+    fn free_lane(lanes: &mut Vec<Option<&str>>) -> usize {
+        lanes.iter().position(|l| l.is_none()).unwrap_or_else(|| {
+            lanes.push(None);
+            lanes.len() - 1
+        })
+    }
+
+    // The commit each lane is waiting for
+    let mut lanes: Vec<Option<&str>> = Vec::new();
+
+    let mut rows = Vec::with_capacity(commits.len());
+    for commit in commits {
+        let id = commit.id.as_str();
+        let column = match lanes.iter().position(|l| *l == Some(id)) {
+            Some(column) => column,
+            None => free_lane(&mut lanes),
+        };
+
+        let mut upper = Vec::new();
+        for (i, lane) in lanes.iter_mut().enumerate() {
+            match lane {
+                Some(l) if *l == id => {
+                    upper.push((i, column));
+                    // The lane ends at the node
+                    *lane = None;
+                }
+                Some(_) => upper.push((i, i)),
+                None => {}
+            }
+        }
+
+        let mut lower = Vec::new();
+        let mut new_lanes = Vec::new();
+        for (i, parent) in commit.parents.iter().enumerate() {
+            let parent = parent.as_str();
+            if let Some(lane) = lanes.iter().position(|l| *l == Some(parent)) {
+                // Another child is already waiting for the parent
+                lower.push((column, lane));
+                continue;
+            }
+            // The first parent continues in the lane of the commit
+            let lane = if i == 0 {
+                column
+            } else {
+                free_lane(&mut lanes)
+            };
+            lanes[lane] = Some(parent);
+            lower.push((column, lane));
+            new_lanes.push(lane);
+        }
+        for (i, lane) in lanes.iter().enumerate() {
+            if lane.is_some() && !new_lanes.contains(&i) {
+                lower.push((i, i));
+            }
+        }
+
+        while lanes.last().map(|l| l.is_none()).unwrap_or(false) {
+            lanes.pop();
+        }
+
+        rows.push(GraphRow {
+            column,
+            upper,
+            lower,
+        });
+    }
+    rows
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GitLogInput {
+    Message,
+    Author,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct GitLogEntry {
+    pub commit: GitCommit,
+    pub row: GraphRow,
+}
+
+#[derive(Clone)]
+pub struct GitLogData {
+    pub entries: RwSignal<im::Vector<GitLogEntry>>,
+    /// The file or directory the log is limited to
+    pub path: RwSignal<Option<PathBuf>>,
+    pub message_editor: EditorData,
+    pub author_editor: EditorData,
+    pub active_input: RwSignal<GitLogInput>,
+    /// If there can be more commits after the loaded ones
+    pub has_more: RwSignal<bool>,
+    /// The commit that's opened, and the files it changed
+    pub opened: RwSignal<Option<(String, im::Vector<FileDiff>)>>,
+    /// The HEAD commit the log was loaded at, as a new commit changes it
+    pub head: RwSignal<String>,
+    /// Bumped on every reload, so that pages of an old filter are dropped
+    load_id: RwSignal<u64>,
+    commits: RwSignal<Vec<GitCommit>>,
+    main_split: MainSplitData,
+    pub common: Rc<CommonData>,
+}
+
+impl KeyPressFocus for GitLogData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::PanelFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &crate::command::LapceCommand,
+        count: Option<usize>,
+        mods: ModifiersState,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                self.active_editor().run_command(command, count, mods)
+            }
+            _ => CommandExecuted::No,
+        }
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.active_editor().receive_char(c);
+    }
+}
+
+impl GitLogData {
+    pub fn new(cx: Scope, main_split: MainSplitData) -> Self {
+        let common = main_split.common.clone();
+        let git_log = Self {
+            entries: cx.create_rw_signal(im::Vector::new()),
+            path: cx.create_rw_signal(None),
+            message_editor: EditorData::new_local(
+                cx,
+                EditorId::next(),
+                common.clone(),
+            ),
+            author_editor: EditorData::new_local(
+                cx,
+                EditorId::next(),
+                common.clone(),
+            ),
+            active_input: cx.create_rw_signal(GitLogInput::Message),
+            has_more: cx.create_rw_signal(false),
+            opened: cx.create_rw_signal(None),
+            head: cx.create_rw_signal(String::new()),
+            load_id: cx.create_rw_signal(0),
+            commits: cx.create_rw_signal(Vec::new()),
+            main_split,
+            common,
+        };
+
+        {
+            let git_log = git_log.clone();
+            let message = git_log.message_editor.view.doc.get_untracked().buffer;
+            let author = git_log.author_editor.view.doc.get_untracked().buffer;
+            let path = git_log.path;
+            let filter_timer = cx.create_rw_signal(TimerToken::INVALID);
+            cx.create_effect(move |_| {
+                message.track();
+                author.track();
+                path.track();
+                let git_log = git_log.clone();
+                let timer_token = exec_after(FILTER_DELAY, move |token| {
+                    if filter_timer.try_get_untracked() == Some(token) {
+                        git_log.reload();
+                    }
+                });
+                filter_timer.set(timer_token);
+            });
+        }
+
+        git_log
+    }
+
+    fn active_editor(&self) -> &EditorData {
+        match self.active_input.get_untracked() {
+            GitLogInput::Message => &self.message_editor,
+            GitLogInput::Author => &self.author_editor,
+        }
+    }
+
+    fn filter(&self) -> GitLogFilter {
+        let text = |editor: &EditorData| {
+            editor
+                .view
+                .doc
+                .get_untracked()
+                .buffer
+                .with_untracked(|buffer| buffer.to_string())
+                .trim()
+                .to_string()
+        };
+        GitLogFilter {
+            path: self.path.get_untracked(),
+            author: text(&self.author_editor),
+            message: text(&self.message_editor),
+        }
+    }
+
+    /// Load the log from the start, e.g. because the filter or the repository
+    /// changed
+    pub fn reload(&self) {
+        self.load_id.update(|id| *id += 1);
+        self.commits.set(Vec::new());
+        self.entries.set(im::Vector::new());
+        self.has_more.set(false);
+        self.load_page();
+    }
+
+    /// Load the commits after the ones that are loaded
+    pub fn load_more(&self) {
+        if self.has_more.get_untracked() {
+            self.has_more.set(false);
+            self.load_page();
+        }
+    }
+
+    fn load_page(&self) {
+        let load_id = self.load_id.get_untracked();
+        let skip = self.commits.with_untracked(|commits| commits.len());
+        let git_log = self.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            if git_log.load_id.get_untracked() != load_id {
+                return;
+            }
+            if let Ok(ProxyResponse::GitLogResponse { commits }) = result {
+                git_log.has_more.set(commits.len() == PAGE_SIZE);
+                git_log.commits.update(|c| c.extend(commits));
+                git_log.update_entries();
+            }
+        });
+        self.common
+            .proxy
+            .git_log(self.filter(), skip, PAGE_SIZE, move |result| {
+                send(result);
+            });
+    }
+
+    fn update_entries(&self) {
+        let filter = self.filter();
+        let filtered = filter.path.is_some()
+            || !filter.author.is_empty()
+            || !filter.message.is_empty();
+        let entries = self.commits.with_untracked(|commits| {
+            let rows = if filtered {
+                // The parents of the listed commits aren't listed themselves, so
+                // they're shown as a single line of history
+                let linear: Vec<GitCommit> = commits
+                    .iter()
+                    .enumerate()
+                    .map(|(i, commit)| GitCommit {
+                        parents: commits
+                            .get(i + 1)
+                            .map(|next| vec![next.id.clone()])
+                            .unwrap_or_default(),
+                        ..commit.clone()
+                    })
+                    .collect();
+                graph_rows(&linear)
+            } else {
+                graph_rows(commits)
+            };
+            commits
+                .iter()
+                .cloned()
+                .zip(rows)
+                .map(|(commit, row)| GitLogEntry { commit, row })
+                .collect()
+        });
+        self.entries.set(entries);
+    }
+
+    /// Show the files changed by the commit, or hide them if they're shown
+    pub fn toggle_commit(&self, commit: &str) {
+        if self
+            .opened
+            .with_untracked(|opened| opened.as_ref().map(|(id, _)| id.as_str()))
+            == Some(commit)
+        {
+            self.opened.set(None);
+            return;
+        }
+
+        let opened = self.opened;
+        let id = commit.to_string();
+        let send = create_ext_action(self.common.scope, move |result| {
+            if let Ok(ProxyResponse::GitCommitDiffsResponse { diffs }) = result {
+                opened.set(Some((id, diffs.into())));
+            }
+        });
+        self.common
+            .proxy
+            .git_commit_diffs(commit.to_string(), move |result| {
+                send(result);
+            });
+    }
+
+    /// Open the changes the commit made to a file in a diff editor
+    pub fn open_file_diff(&self, diff: FileDiff, commit: String) {
+        self.main_split.open_file_commit(diff, commit);
+    }
+
+    /// Show the log of the file or directory
+    pub fn show_path(&self, path: Option<PathBuf>) {
+        self.opened.set(None);
+        self.path.set(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(id: &str, parents: &[&str]) -> GitCommit {
+        GitCommit {
+            id: id.to_string(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            author: String::new(),
+            time: 0,
+            summary: String::new(),
+            refs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_graph_rows_linear() {
+        let rows = graph_rows(&[
+            commit("c", &["b"]),
+            commit("b", &["a"]),
+            commit("a", &[]),
+        ]);
+        assert_eq!(
+            rows,
+            vec![
+                GraphRow {
+                    column: 0,
+                    upper: vec![],
+                    lower: vec![(0, 0)],
+                },
+                GraphRow {
+                    column: 0,
+                    upper: vec![(0, 0)],
+                    lower: vec![(0, 0)],
+                },
+                GraphRow {
+                    column: 0,
+                    upper: vec![(0, 0)],
+                    lower: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_graph_rows_merge() {
+        let rows = graph_rows(&[
+            commit("merge", &["main", "topic"]),
+            commit("main", &["base"]),
+            commit("topic", &["base"]),
+            commit("base", &[]),
+        ]);
+        assert_eq!(
+            rows,
+            vec![
+                GraphRow {
+                    column: 0,
+                    upper: vec![],
+                    lower: vec![(0, 0), (0, 1)],
+                },
+                GraphRow {
+                    column: 0,
+                    upper: vec![(0, 0), (1, 1)],
+                    lower: vec![(0, 0), (1, 1)],
+                },
+                GraphRow {
+                    column: 1,
+                    upper: vec![(0, 0), (1, 1)],
+                    lower: vec![(1, 0), (0, 0)],
+                },
+                GraphRow {
+                    column: 0,
+                    upper: vec![(0, 0)],
+                    lower: vec![],
+                },
+            ]
+        );
+        assert_eq!(rows[0].width(), 2);
+        assert_eq!(rows[3].width(), 1);
+    }
+
+    #[test]
+    fn test_graph_rows_branches() {
+        // Two branch heads that aren't merged, loaded before their parents
+        let rows =
+            graph_rows(&[commit("feature", &["base"]), commit("main", &["base"])]);
+        assert_eq!(rows[0].column, 0);
+        assert_eq!(rows[1].column, 1);
+        assert_eq!(rows[1].upper, vec![(0, 0)]);
+        assert_eq!(rows[1].lower, vec![(1, 0), (0, 0)]);
+    }
+}
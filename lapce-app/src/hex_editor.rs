@@ -0,0 +1,945 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Range,
+    path::PathBuf,
+    rc::Rc,
+};
+
+use floem::{
+    event::EventListener,
+    ext_event::create_ext_action,
+    keyboard::ModifiersState,
+    peniko::kurbo::{Point, Rect, Size},
+    reactive::{create_memo, RwSignal, Scope},
+    view::View,
+    views::{
+        container, empty, label, list, scroll, stack, virtual_list, Decorators,
+        VirtualListDirection, VirtualListItemSize, VirtualListVector,
+    },
+};
+use lapce_core::{
+    command::{FocusCommand, MoveCommand},
+    mode::Mode,
+};
+use lapce_rpc::proxy::ProxyResponse;
+use lapce_xi_rope::Rope;
+
+use crate::{
+    app::clickable_icon,
+    command::{CommandExecuted, CommandKind, LapceWorkbenchCommand},
+    config::{color::LapceColor, icon::LapceIcons},
+    editor::EditorData,
+    id::{EditorId, EditorTabId, HexEditorId},
+    keypress::{condition::Condition, KeyPressFocus},
+    main_split::MainSplitData,
+    text_input::text_input,
+    window_tab::{CommonData, Focus},
+};
+
+/// The number of bytes shown on each row
+pub const HEX_ROW_BYTES: usize = 16;
+
+/// How much of the file is read at once, as the rows showing it come into
+/// view
+const HEX_PAGE_SIZE: usize = 64 * 1024;
+
+/// How many pages are kept around, the ones furthest from the last one read
+/// are dropped first
+const HEX_MAX_PAGES: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HexInputKind {
+    GoToOffset,
+    Search,
+}
+
+/// Parse a byte pattern to search for, either hex digits like `DE AD BE EF`
+/// or `0xdeadbeef`, or text in quotes like `"ELF"`
+pub fn parse_byte_pattern(input: &str) -> Option<Vec<u8>> {
+    let input = input.trim();
+    for quote in ['"', '\''] {
+        if input.len() >= 2 && input.starts_with(quote) && input.ends_with(quote) {
+            let text = &input[1..input.len() - 1];
+            return (!text.is_empty()).then(|| text.as_bytes().to_vec());
+        }
+    }
+
+    let digits: String = input
+        .split_whitespace()
+        .map(|part| part.trim_start_matches("0x").trim_start_matches("0X"))
+        .collect();
+    if digits.is_empty()
+        || digits.len() % 2 != 0
+        || !digits.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Parse an offset to go to, in hex with a `0x` prefix or in decimal
+pub fn parse_offset(input: &str) -> Option<usize> {
+    let input = input.trim();
+    if let Some(hex) = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+    {
+        usize::from_str_radix(hex, 16).ok()
+    } else {
+        input.parse().ok()
+    }
+}
+
+/// Group the changed bytes into runs of consecutive bytes, to be written to
+/// the file
+pub fn edited_runs(edited: &BTreeMap<usize, u8>) -> Vec<(u64, Vec<u8>)> {
+    let mut runs: Vec<(u64, Vec<u8>)> = Vec::new();
+    for (&offset, &byte) in edited {
+        match runs.last_mut() {
+            Some((start, run)) if *start as usize + run.len() == offset => {
+                run.push(byte);
+            }
+            _ => runs.push((offset as u64, vec![byte])),
+        }
+    }
+    runs
+}
+
+/// The byte at `offset` with the unsaved changes, if its page is loaded
+fn byte_at(
+    pages: &HashMap<usize, Rc<Vec<u8>>>,
+    edited: &BTreeMap<usize, u8>,
+    offset: usize,
+) -> Option<u8> {
+    edited.get(&offset).copied().or_else(|| {
+        pages
+            .get(&(offset / HEX_PAGE_SIZE))
+            .and_then(|page| page.get(offset % HEX_PAGE_SIZE).copied())
+    })
+}
+
+#[derive(Clone)]
+pub struct HexEditorData {
+    pub scope: Scope,
+    pub id: HexEditorId,
+    pub editor_tab_id: RwSignal<EditorTabId>,
+    pub path: PathBuf,
+    /// The pages of the file read so far, by their index
+    pub pages: RwSignal<HashMap<usize, Rc<Vec<u8>>>>,
+    /// The pages asked for that haven't come in yet
+    requested: RwSignal<HashSet<usize>>,
+    /// The size of the file, once the first page of it has been read
+    pub file_len: RwSignal<Option<u64>>,
+    pub error: RwSignal<Option<String>>,
+    /// The bytes changed since the file was last saved, by their offset
+    pub edited: RwSignal<BTreeMap<usize, u8>>,
+    pub editable: RwSignal<bool>,
+    pub cursor: RwSignal<usize>,
+    /// If the first of the two hex digits of the byte under the cursor was
+    /// typed already
+    pub half_typed: RwSignal<bool>,
+    /// The bytes found by the last search, or reached by going to an offset
+    pub selection: RwSignal<Option<Range<usize>>>,
+    /// Where the bytes of the last search pattern were found
+    pub matches: RwSignal<Vec<usize>>,
+    /// Whether the proxy is still searching the file, and which search it is,
+    /// so the results of an earlier one are ignored
+    pub searching: RwSignal<bool>,
+    search_id: RwSignal<u64>,
+    pub input: EditorData,
+    pub input_kind: RwSignal<Option<HexInputKind>>,
+    pub viewport: RwSignal<Rect>,
+    pub common: Rc<CommonData>,
+}
+
+impl KeyPressFocus for HexEditorData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        self.input_kind.get_untracked().is_some()
+            && matches!(
+                condition,
+                Condition::InputFocus | Condition::ListFocus | Condition::ModalFocus
+            )
+    }
+
+    fn run_command(
+        &self,
+        command: &crate::command::LapceCommand,
+        count: Option<usize>,
+        mods: ModifiersState,
+    ) -> CommandExecuted {
+        let input_open = self.input_kind.get_untracked().is_some();
+        match &command.kind {
+            CommandKind::Workbench(LapceWorkbenchCommand::PaletteLine) => {
+                self.open_input(HexInputKind::GoToOffset);
+                CommandExecuted::Yes
+            }
+            CommandKind::Focus(FocusCommand::Search) => {
+                self.open_input(HexInputKind::Search);
+                CommandExecuted::Yes
+            }
+            CommandKind::Focus(FocusCommand::Save) => {
+                self.save(|| {});
+                CommandExecuted::Yes
+            }
+            CommandKind::Focus(FocusCommand::ModalClose) if input_open => {
+                self.input_kind.set(None);
+                CommandExecuted::Yes
+            }
+            CommandKind::Focus(FocusCommand::ListSelect) if input_open => {
+                self.submit_input();
+                CommandExecuted::Yes
+            }
+            CommandKind::Focus(FocusCommand::ListNext) if input_open => {
+                self.next_match(true);
+                CommandExecuted::Yes
+            }
+            CommandKind::Focus(FocusCommand::ListPrevious) if input_open => {
+                self.next_match(false);
+                CommandExecuted::Yes
+            }
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_)
+                if input_open =>
+            {
+                self.input.run_command(command, count, mods)
+            }
+            CommandKind::Move(cmd) => {
+                self.move_cursor(cmd, count.unwrap_or(1));
+                CommandExecuted::Yes
+            }
+            CommandKind::Focus(FocusCommand::PageUp) => {
+                self.move_cursor_by(-(self.page_rows() as isize));
+                CommandExecuted::Yes
+            }
+            CommandKind::Focus(FocusCommand::PageDown) => {
+                self.move_cursor_by(self.page_rows() as isize);
+                CommandExecuted::Yes
+            }
+            _ => CommandExecuted::No,
+        }
+    }
+
+    fn receive_char(&self, c: &str) {
+        if self.input_kind.get_untracked().is_some() {
+            self.input.receive_char(c);
+        } else if self.editable.get_untracked() {
+            for c in c.chars() {
+                if let Some(digit) = c.to_digit(16) {
+                    self.type_digit(digit as u8);
+                }
+            }
+        }
+    }
+}
+
+impl HexEditorData {
+    pub fn new(
+        cx: Scope,
+        id: HexEditorId,
+        editor_tab_id: EditorTabId,
+        path: PathBuf,
+        common: Rc<CommonData>,
+    ) -> Self {
+        let cx = cx.create_child();
+        let data = Self {
+            scope: cx,
+            id,
+            editor_tab_id: cx.create_rw_signal(editor_tab_id),
+            path,
+            pages: cx.create_rw_signal(HashMap::new()),
+            requested: cx.create_rw_signal(HashSet::new()),
+            file_len: cx.create_rw_signal(None),
+            error: cx.create_rw_signal(None),
+            edited: cx.create_rw_signal(BTreeMap::new()),
+            editable: cx.create_rw_signal(false),
+            cursor: cx.create_rw_signal(0),
+            half_typed: cx.create_rw_signal(false),
+            selection: cx.create_rw_signal(None),
+            matches: cx.create_rw_signal(Vec::new()),
+            searching: cx.create_rw_signal(false),
+            search_id: cx.create_rw_signal(0),
+            input: EditorData::new_local(cx, EditorId::next(), common.clone()),
+            input_kind: cx.create_rw_signal(None),
+            viewport: cx.create_rw_signal(Rect::ZERO),
+            common,
+        };
+        data.load_visible_pages();
+        data
+    }
+
+    /// A hex editor for the same file in another editor tab, which keeps the
+    /// unsaved changes
+    pub fn copy(
+        &self,
+        cx: Scope,
+        id: HexEditorId,
+        editor_tab_id: EditorTabId,
+    ) -> Self {
+        let cx = cx.create_child();
+        let data = Self {
+            scope: cx,
+            id,
+            editor_tab_id: cx.create_rw_signal(editor_tab_id),
+            path: self.path.clone(),
+            pages: cx.create_rw_signal(self.pages.get_untracked()),
+            requested: cx.create_rw_signal(HashSet::new()),
+            file_len: cx.create_rw_signal(self.file_len.get_untracked()),
+            error: cx.create_rw_signal(None),
+            edited: cx.create_rw_signal(self.edited.get_untracked()),
+            editable: cx.create_rw_signal(self.editable.get_untracked()),
+            cursor: cx.create_rw_signal(self.cursor.get_untracked()),
+            half_typed: cx.create_rw_signal(false),
+            selection: cx.create_rw_signal(None),
+            matches: cx.create_rw_signal(Vec::new()),
+            searching: cx.create_rw_signal(false),
+            search_id: cx.create_rw_signal(0),
+            input: EditorData::new_local(cx, EditorId::next(), self.common.clone()),
+            input_kind: cx.create_rw_signal(None),
+            viewport: cx.create_rw_signal(Rect::ZERO),
+            common: self.common.clone(),
+        };
+        data.load_visible_pages();
+        data
+    }
+
+    /// Read the pages of the rows in view whenever they change, so only the
+    /// part of the file that's looked at is ever loaded
+    fn load_visible_pages(&self) {
+        let data = self.clone();
+        self.scope.create_effect(move |_| {
+            let viewport = data.viewport.get();
+            let file_len = data.file_len.get();
+            for page in data.visible_pages(viewport, file_len) {
+                let loaded = data.pages.with_untracked(|p| p.contains_key(&page));
+                let requested = data.requested.with_untracked(|r| r.contains(&page));
+                if !loaded && !requested {
+                    data.requested.update(|r| {
+                        r.insert(page);
+                    });
+                    data.load_page(page);
+                }
+            }
+        });
+    }
+
+    fn visible_pages(&self, viewport: Rect, file_len: Option<u64>) -> Range<usize> {
+        let line_height =
+            self.common.config.get_untracked().editor.line_height() as f64;
+        let first_row = (viewport.y0 / line_height).max(0.0) as usize;
+        let last_row = (viewport.y1 / line_height).ceil() as usize;
+        let start = first_row * HEX_ROW_BYTES;
+        let mut end = (last_row + 1) * HEX_ROW_BYTES;
+        if let Some(file_len) = file_len {
+            end = end.min(file_len as usize);
+        }
+        let first_page = start / HEX_PAGE_SIZE;
+        let last_page = end.saturating_sub(1) / HEX_PAGE_SIZE;
+        first_page..last_page.max(first_page) + 1
+    }
+
+    fn load_page(&self, page: usize) {
+        let data = self.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            data.requested.update(|r| {
+                r.remove(&page);
+            });
+            match result {
+                Ok(ProxyResponse::ReadFileBytesResponse { bytes, file_len }) => {
+                    if data.file_len.get_untracked() != Some(file_len) {
+                        data.file_len.set(Some(file_len));
+                    }
+                    data.pages.update(|pages| {
+                        pages.insert(page, Rc::new(bytes));
+                        while pages.len() > HEX_MAX_PAGES {
+                            let Some(furthest) = pages
+                                .keys()
+                                .copied()
+                                .max_by_key(|p| p.abs_diff(page))
+                            else {
+                                break;
+                            };
+                            pages.remove(&furthest);
+                        }
+                    });
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    data.error.set(Some(err.message));
+                }
+            }
+        });
+        self.common.proxy.read_file_bytes(
+            self.path.clone(),
+            (page * HEX_PAGE_SIZE) as u64,
+            HEX_PAGE_SIZE as u64,
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    /// The byte at `offset` with the unsaved changes, if it's loaded
+    pub fn byte(&self, offset: usize) -> Option<u8> {
+        self.pages
+            .with(|pages| self.edited.with(|edited| byte_at(pages, edited, offset)))
+    }
+
+    fn byte_untracked(&self, offset: usize) -> Option<u8> {
+        self.pages.with_untracked(|pages| {
+            self.edited
+                .with_untracked(|edited| byte_at(pages, edited, offset))
+        })
+    }
+
+    /// Write the changed bytes back to the file, in place
+    pub fn save(&self, after_save: impl FnOnce() + 'static) {
+        let edited = self.edited.get_untracked();
+        let changes = edited_runs(&edited);
+        if changes.is_empty() {
+            after_save();
+            return;
+        }
+
+        let data = self.clone();
+        let send = create_ext_action(self.scope, move |result| match result {
+            Ok(_) => {
+                // The loaded pages are what's on disk now
+                data.pages.update(|pages| {
+                    for (&offset, &byte) in &edited {
+                        if let Some(page) = pages.get_mut(&(offset / HEX_PAGE_SIZE))
+                        {
+                            if let Some(b) =
+                                Rc::make_mut(page).get_mut(offset % HEX_PAGE_SIZE)
+                            {
+                                *b = byte;
+                            }
+                        }
+                    }
+                });
+                data.edited.update(|current| {
+                    current.retain(|offset, byte| edited.get(offset) != Some(byte));
+                });
+                data.error.set(None);
+                after_save();
+            }
+            Err(err) => {
+                data.error.set(Some(err.message));
+            }
+        });
+        self.common.proxy.write_file_bytes(
+            self.path.clone(),
+            changes,
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    fn type_digit(&self, digit: u8) {
+        let offset = self.cursor.get_untracked();
+        let Some(byte) = self.byte_untracked(offset) else {
+            return;
+        };
+        let half_typed = self.half_typed.get_untracked();
+        let byte = if half_typed {
+            (byte & 0xF0) | digit
+        } else {
+            (digit << 4) | (byte & 0x0F)
+        };
+        self.edited.update(|edited| {
+            edited.insert(offset, byte);
+        });
+        if half_typed {
+            self.half_typed.set(false);
+            self.set_cursor(offset + 1);
+        } else {
+            self.half_typed.set(true);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.file_len.get_untracked().unwrap_or(0) as usize
+    }
+
+    fn set_cursor(&self, offset: usize) {
+        self.half_typed.set(false);
+        self.cursor.set(offset.min(self.len().saturating_sub(1)));
+    }
+
+    fn move_cursor_by(&self, rows: isize) {
+        let offset =
+            self.cursor.get_untracked() as isize + rows * HEX_ROW_BYTES as isize;
+        self.set_cursor(offset.max(0) as usize);
+    }
+
+    fn page_rows(&self) -> usize {
+        let line_height =
+            self.common.config.get_untracked().editor.line_height() as f64;
+        ((self.viewport.get_untracked().height() / line_height) as usize).max(1)
+    }
+
+    fn move_cursor(&self, cmd: &MoveCommand, count: usize) {
+        let cursor = self.cursor.get_untracked();
+        let row_start = cursor - cursor % HEX_ROW_BYTES;
+        match cmd {
+            MoveCommand::Left => self.set_cursor(cursor.saturating_sub(count)),
+            MoveCommand::Right => self.set_cursor(cursor + count),
+            MoveCommand::Up => self.move_cursor_by(-(count as isize)),
+            MoveCommand::Down => self.move_cursor_by(count as isize),
+            MoveCommand::LineStart | MoveCommand::LineStartNonBlank => {
+                self.set_cursor(row_start)
+            }
+            MoveCommand::LineEnd => self.set_cursor(row_start + HEX_ROW_BYTES - 1),
+            MoveCommand::DocumentStart => self.set_cursor(0),
+            MoveCommand::DocumentEnd => self.set_cursor(usize::MAX),
+            _ => {}
+        }
+    }
+
+    fn open_input(&self, kind: HexInputKind) {
+        if self.input_kind.get_untracked() != Some(kind) {
+            self.input
+                .view
+                .doc
+                .get_untracked()
+                .reload(Rope::from(""), true);
+        }
+        self.input_kind.set(Some(kind));
+    }
+
+    fn submit_input(&self) {
+        let input = self
+            .input
+            .view
+            .doc
+            .get_untracked()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string());
+        match self.input_kind.get_untracked() {
+            Some(HexInputKind::GoToOffset) => match parse_offset(&input) {
+                Some(offset) if offset < self.len() => {
+                    self.error.set(None);
+                    self.input_kind.set(None);
+                    self.set_cursor(offset);
+                    self.selection.set(Some(offset..offset + 1));
+                }
+                Some(_) => {
+                    self.error.set(Some(format!(
+                        "{} is past the end of the file",
+                        input.trim()
+                    )));
+                }
+                None => {
+                    self.error.set(Some(format!(
+                        "\"{}\" isn't an offset like 4096 or 0x1000",
+                        input.trim()
+                    )));
+                }
+            },
+            Some(HexInputKind::Search) => match parse_byte_pattern(&input) {
+                Some(pattern) => {
+                    self.error.set(None);
+                    self.search(pattern);
+                }
+                None => {
+                    self.error.set(Some(format!(
+                        "\"{}\" isn't hex bytes like DE AD BE EF or text in quotes",
+                        input.trim()
+                    )));
+                }
+            },
+            None => {}
+        }
+    }
+
+    /// Search the file, with the unsaved changes, in the proxy, which reads
+    /// through all of it
+    fn search(&self, pattern: Vec<u8>) {
+        let search_id = self.search_id.get_untracked() + 1;
+        self.search_id.set(search_id);
+        self.searching.set(true);
+        self.matches.set(Vec::new());
+        self.selection.set(None);
+
+        let changes = self.edited.with_untracked(edited_runs);
+        let data = self.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            if data.search_id.get_untracked() != search_id {
+                return;
+            }
+            data.searching.set(false);
+            match result {
+                Ok(ProxyResponse::SearchFileBytesResponse { matches }) => {
+                    if matches.is_empty() {
+                        data.error.set(Some("No matching bytes".to_string()));
+                    }
+                    data.matches
+                        .set(matches.into_iter().map(|m| m as usize).collect());
+                    data.next_match(true);
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    data.error.set(Some(err.message));
+                }
+            }
+        });
+        self.common.proxy.search_file_bytes(
+            self.path.clone(),
+            pattern,
+            changes,
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    /// Select the match after the cursor, or before it, wrapping around the
+    /// ends of the file
+    fn next_match(&self, forward: bool) {
+        let Some(pattern_len) = self.pattern_len() else {
+            return;
+        };
+        let cursor = self.cursor.get_untracked();
+        let on_match = self
+            .selection
+            .get_untracked()
+            .map_or(false, |selection| selection.start == cursor);
+        let next = self.matches.with_untracked(|matches| {
+            if forward {
+                matches
+                    .iter()
+                    .find(|&&m| m > cursor || (m == cursor && !on_match))
+                    .or_else(|| matches.first())
+                    .copied()
+            } else {
+                matches
+                    .iter()
+                    .rev()
+                    .find(|&&m| m < cursor)
+                    .or_else(|| matches.last())
+                    .copied()
+            }
+        });
+        if let Some(offset) = next {
+            self.set_cursor(offset);
+            self.selection.set(Some(offset..offset + pattern_len));
+        }
+    }
+
+    fn pattern_len(&self) -> Option<usize> {
+        let input = self
+            .input
+            .view
+            .doc
+            .get_untracked()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string());
+        parse_byte_pattern(&input).map(|pattern| pattern.len())
+    }
+}
+
+struct HexRows(usize);
+
+impl VirtualListVector<usize> for HexRows {
+    type ItemIterator = Range<usize>;
+
+    fn total_len(&self) -> usize {
+        self.0
+    }
+
+    fn slice(&mut self, range: Range<usize>) -> Self::ItemIterator {
+        range
+    }
+}
+
+pub fn hex_editor_view(
+    main_split: MainSplitData,
+    hex_editor: HexEditorData,
+) -> impl View {
+    let config = hex_editor.common.config;
+    let focus = hex_editor.common.focus;
+    let active_editor_tab = main_split.active_editor_tab;
+    let editor_tab_id = hex_editor.editor_tab_id;
+    let file_len = hex_editor.file_len;
+    let cursor = hex_editor.cursor;
+    let viewport = hex_editor.viewport;
+    let line_height = move || config.get().editor.line_height() as f64;
+    let rows = create_memo(move |_| {
+        let len = file_len.get().unwrap_or(0) as usize;
+        (len + HEX_ROW_BYTES - 1) / HEX_ROW_BYTES
+    });
+
+    stack((
+        hex_editor_header(hex_editor.clone(), active_editor_tab),
+        container(
+            scroll(
+                virtual_list(
+                    VirtualListDirection::Vertical,
+                    VirtualListItemSize::Fixed(Box::new(line_height)),
+                    move || HexRows(rows.get()),
+                    |row| *row,
+                    move |row| hex_row_view(hex_editor.clone(), row),
+                )
+                .style(|s| s.flex_col().min_width_full()),
+            )
+            .on_scroll(move |rect| {
+                viewport.set(rect);
+            })
+            .on_ensure_visible(move || {
+                let line_height = line_height();
+                Size::new(1.0, line_height)
+                    .to_rect()
+                    .with_origin(Point::new(
+                        0.0,
+                        (cursor.get() / HEX_ROW_BYTES) as f64 * line_height,
+                    ))
+            })
+            .style(|s| s.absolute().size_full()),
+        )
+        .style(move |s| {
+            let config = config.get();
+            s.size_full()
+                .font_family(config.editor.font_family.clone())
+                .font_size(config.editor.font_size() as f32)
+        }),
+    ))
+    .on_event(EventListener::PointerDown, move |_| {
+        focus.set(Focus::Workbench);
+        active_editor_tab.set(Some(editor_tab_id.get_untracked()));
+        false
+    })
+    .style(move |s| {
+        s.size_full()
+            .flex_col()
+            .background(*config.get().get_color(LapceColor::EDITOR_BACKGROUND))
+    })
+}
+
+fn hex_editor_header(
+    hex_editor: HexEditorData,
+    active_editor_tab: RwSignal<Option<EditorTabId>>,
+) -> impl View {
+    let config = hex_editor.common.config;
+    let focus = hex_editor.common.focus;
+    let editor_tab_id = hex_editor.editor_tab_id;
+    let input_kind = hex_editor.input_kind;
+    let error = hex_editor.error;
+    let editable = hex_editor.editable;
+    let file_len = hex_editor.file_len;
+    let searching = hex_editor.searching;
+    let doc = hex_editor.input.view.doc;
+    let is_empty = create_memo(move |_| {
+        let doc = doc.get();
+        doc.buffer.with(|b| b.len() == 0)
+    });
+    let is_focused = move || {
+        focus.get() == Focus::Workbench
+            && active_editor_tab.get() == Some(editor_tab_id.get())
+            && input_kind.with(|kind| kind.is_some())
+    };
+    let status = move || match file_len.get() {
+        Some(len) if searching.get() => format!("{len} bytes, searching"),
+        Some(len) => format!("{len} bytes"),
+        None => "Loading".to_string(),
+    };
+
+    stack((
+        label(status).style(move |s| {
+            s.margin_right(10.0)
+                .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+        }),
+        container(
+            stack((
+                text_input(hex_editor.input.clone(), is_focused)
+                    .style(|s| s.width_pct(100.0)),
+                label(move || {
+                    match input_kind.get() {
+                        Some(HexInputKind::GoToOffset) => {
+                            "Go to offset, e.g. 4096 or 0x1000"
+                        }
+                        _ => "Search for bytes, e.g. DE AD BE EF or \"text\"",
+                    }
+                    .to_string()
+                })
+                .style(move |s| {
+                    s.absolute()
+                        .padding_left(6.0)
+                        .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                        .apply_if(!is_empty.get(), |s| s.hide())
+                }),
+            ))
+            .style(move |s| {
+                s.width_pct(100.0)
+                    .items_center()
+                    .border(1.0)
+                    .border_radius(6.0)
+                    .border_color(*config.get().get_color(LapceColor::LAPCE_BORDER))
+            }),
+        )
+        .style(move |s| {
+            s.flex_basis(0.0)
+                .flex_grow(1.0)
+                .max_width(400.0)
+                .apply_if(input_kind.with(|kind| kind.is_none()), |s| s.hide())
+        }),
+        label(move || error.get().unwrap_or_default()).style(move |s| {
+            s.margin_left(10.0)
+                .min_width(0.0)
+                .text_ellipsis()
+                .color(*config.get().get_color(LapceColor::LAPCE_ERROR))
+                .apply_if(error.with(|e| e.is_none()), |s| s.hide())
+        }),
+        empty().style(|s| s.flex_grow(1.0)),
+        clickable_icon(
+            || LapceIcons::HEX_EDITOR_EDIT,
+            move || {
+                editable.update(|editable| *editable = !*editable);
+            },
+            move || editable.get(),
+            || false,
+            config,
+        ),
+    ))
+    .style(move |s| {
+        s.width_pct(100.0)
+            .items_center()
+            .padding_horiz(10.0)
+            .padding_vert(6.0)
+            .border_bottom(1.0)
+            .border_color(*config.get().get_color(LapceColor::LAPCE_BORDER))
+    })
+}
+
+fn hex_row_view(hex_editor: HexEditorData, row: usize) -> impl View {
+    let config = hex_editor.common.config;
+    let pages = hex_editor.pages;
+    let edited = hex_editor.edited;
+    let start = row * HEX_ROW_BYTES;
+    let line_height = move || config.get().editor.line_height() as f32;
+
+    stack((
+        label(move || format!("{start:08X}")).style(move |s| {
+            s.margin_right(16.0)
+                .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+        }),
+        list(
+            move || 0..HEX_ROW_BYTES,
+            |i| *i,
+            move |i| hex_byte_view(hex_editor.clone(), start + i),
+        )
+        .style(|s| s.margin_right(16.0)),
+        label(move || {
+            pages.with(|pages| {
+                edited.with(|edited| {
+                    (start..start + HEX_ROW_BYTES)
+                        .map(|offset| match byte_at(pages, edited, offset) {
+                            Some(b) if b.is_ascii_graphic() || b == b' ' => {
+                                b as char
+                            }
+                            Some(_) => '.',
+                            None => ' ',
+                        })
+                        .collect::<String>()
+                })
+            })
+        }),
+    ))
+    .style(move |s| {
+        s.items_center()
+            .padding_horiz(10.0)
+            .height(line_height())
+            .color(*config.get().get_color(LapceColor::EDITOR_FOREGROUND))
+    })
+}
+
+fn hex_byte_view(hex_editor: HexEditorData, offset: usize) -> impl View {
+    let config = hex_editor.common.config;
+    let file_len = hex_editor.file_len;
+    let cursor = hex_editor.cursor;
+    let half_typed = hex_editor.half_typed;
+    let selection = hex_editor.selection;
+    let edited = hex_editor.edited;
+
+    label(move || match hex_editor.byte(offset) {
+        Some(byte) => format!("{byte:02X}"),
+        None => "  ".to_string(),
+    })
+    .on_click(move |_| {
+        if file_len
+            .get_untracked()
+            .map_or(false, |len| (offset as u64) < len)
+        {
+            half_typed.set(false);
+            cursor.set(offset);
+        }
+        true
+    })
+    .style(move |s| {
+        let config = config.get();
+        let is_selected = selection.with(|selection| {
+            selection.as_ref().map_or(false, |r| r.contains(&offset))
+        });
+        s.padding_horiz(3.0)
+            .apply_if(offset % HEX_ROW_BYTES == HEX_ROW_BYTES / 2, |s| {
+                s.margin_left(6.0)
+            })
+            .apply_if(edited.with(|edited| edited.contains_key(&offset)), |s| {
+                s.color(*config.get_color(LapceColor::SOURCE_CONTROL_MODIFIED))
+            })
+            .apply_if(is_selected, |s| {
+                s.background(*config.get_color(LapceColor::EDITOR_SELECTION))
+            })
+            .apply_if(cursor.get() == offset, |s| {
+                s.border(1.0)
+                    .border_radius(2.0)
+                    .border_color(*config.get_color(LapceColor::EDITOR_CARET))
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_byte_pattern() {
+        assert_eq!(
+            parse_byte_pattern("DE AD be ef"),
+            Some(vec![0xDE, 0xAD, 0xBE, 0xEF])
+        );
+        assert_eq!(parse_byte_pattern("0x7f454c46"), Some(b"\x7FELF".to_vec()));
+        assert_eq!(parse_byte_pattern("\"ELF\""), Some(b"ELF".to_vec()));
+        assert_eq!(parse_byte_pattern("'a b'"), Some(b"a b".to_vec()));
+        assert_eq!(parse_byte_pattern("ABC"), None);
+        assert_eq!(parse_byte_pattern("+f"), None);
+        assert_eq!(parse_byte_pattern("\"\""), None);
+        assert_eq!(parse_byte_pattern(""), None);
+    }
+
+    #[test]
+    fn test_parse_offset() {
+        assert_eq!(parse_offset("4096"), Some(4096));
+        assert_eq!(parse_offset(" 0x1000 "), Some(4096));
+        assert_eq!(parse_offset("0XfF"), Some(255));
+        assert_eq!(parse_offset("ff"), None);
+        assert_eq!(parse_offset("-1"), None);
+    }
+
+    #[test]
+    fn test_edited_runs() {
+        let edited = BTreeMap::from([(1, 0xA), (2, 0xB), (4, 0xC)]);
+        assert_eq!(
+            edited_runs(&edited),
+            vec![(1, vec![0xA, 0xB]), (4, vec![0xC])]
+        );
+        assert!(edited_runs(&BTreeMap::new()).is_empty());
+    }
+}
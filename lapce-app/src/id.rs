@@ -22,4 +22,5 @@ pub fn to_raw(self) -> u64 {
 pub type KeymapId = Id;
 pub type EditorId = Id;
 pub type DiffEditorId = Id;
+pub type HexEditorId = Id;
 pub type TerminalTabId = Id;
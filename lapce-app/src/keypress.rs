@@ -440,6 +440,15 @@ fn get_keymaps(
             }
         }
 
+        if let Some(dir) = config.workspace_config_dir.as_ref() {
+            let path = dir.join("keymaps.toml");
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                if let Err(err) = loader.load_from_str(&content, is_modal) {
+                    error!("Failed to load from {path:?}: {err}");
+                }
+            }
+        }
+
         Ok(loader.finalize())
     }
 
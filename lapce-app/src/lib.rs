@@ -13,7 +13,9 @@
 pub mod file_explorer;
 pub mod find;
 pub mod focus_text;
+pub mod git_log;
 pub mod global_search;
+pub mod hex_editor;
 pub mod history;
 pub mod hover;
 pub mod id;
@@ -25,6 +27,7 @@
 pub mod palette;
 pub mod panel;
 pub mod plugin;
+pub mod port_forward;
 pub mod proxy;
 pub mod rename;
 pub mod settings;
@@ -35,6 +38,7 @@
 pub mod text_area;
 pub mod text_input;
 pub mod title;
+pub mod undo_tree;
 pub mod update;
 pub mod wave;
 pub mod web_link;
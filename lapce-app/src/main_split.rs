@@ -1,23 +1,31 @@
 use std::{
-    collections::HashMap,
+    cell::RefCell,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
     rc::Rc,
+    sync::Arc,
+    time::Duration,
 };
 
 use floem::{
-    action::save_as,
+    action::{exec_after, save_as},
     ext_event::create_ext_action,
     file::{FileDialogOptions, FileInfo},
     keyboard::ModifiersState,
     peniko::kurbo::{Point, Rect, Vec2},
-    reactive::{Memo, RwSignal, Scope},
+    reactive::{use_context, Memo, RwSignal, Scope},
 };
 use itertools::Itertools;
 use lapce_core::{
     buffer::rope_text::RopeText, command::FocusCommand, cursor::Cursor,
     selection::Selection, syntax::Syntax,
 };
-use lapce_rpc::{buffer::BufferId, plugin::PluginId, proxy::ProxyResponse};
+use lapce_rpc::{
+    buffer::BufferId,
+    plugin::PluginId,
+    proxy::ProxyResponse,
+    source_control::{BlameHunk, FileDiff},
+};
 use lapce_xi_rope::Rope;
 use lsp_types::{
     CodeAction, CodeActionOrCommand, DiagnosticSeverity, DocumentChangeOperation,
@@ -28,7 +36,11 @@
 use crate::{
     alert::AlertButton,
     command::InternalCommand,
-    doc::{DiagnosticData, DocContent, DocHistory, Document, EditorDiagnostic},
+    db::{DocBackups, LapceDb},
+    doc::{
+        DiagnosticData, DocBackup, DocContent, DocHistory, Document,
+        EditorDiagnostic,
+    },
     editor::{
         diff::DiffEditorData,
         location::{EditorLocation, EditorPosition},
@@ -37,11 +49,18 @@
     editor_tab::{
         EditorTabChild, EditorTabChildSource, EditorTabData, EditorTabInfo,
     },
-    id::{DiffEditorId, EditorId, EditorTabId, KeymapId, SettingsId, SplitId},
+    hex_editor::HexEditorData,
+    id::{
+        DiffEditorId, EditorId, EditorTabId, HexEditorId, KeymapId, SettingsId,
+        SplitId,
+    },
     keypress::{EventRef, KeyPressData},
     window_tab::{CommonData, Focus, WindowTabData},
 };
 
+/// How often the backups of unsaved documents are brought up to date
+const BACKUP_INTERVAL: Duration = Duration::from_secs(5);
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SplitDirection {
     Vertical,
@@ -225,6 +244,7 @@ pub struct MainSplitData {
     pub editor_tabs: RwSignal<im::HashMap<EditorTabId, RwSignal<EditorTabData>>>,
     pub editors: RwSignal<im::HashMap<EditorId, Rc<EditorData>>>,
     pub diff_editors: RwSignal<im::HashMap<DiffEditorId, DiffEditorData>>,
+    pub hex_editors: RwSignal<im::HashMap<HexEditorId, HexEditorData>>,
     pub docs: RwSignal<im::HashMap<PathBuf, Rc<Document>>>,
     pub scratch_docs: RwSignal<im::HashMap<String, Rc<Document>>>,
     pub diagnostics: RwSignal<im::HashMap<PathBuf, DiagnosticData>>,
@@ -234,6 +254,9 @@ pub struct MainSplitData {
     pub locations: RwSignal<im::Vector<EditorLocation>>,
     pub current_location: RwSignal<usize>,
     pub width: RwSignal<f64>,
+    /// The revision each document had when its backup was last written, by
+    /// [`DocContent::backup_key`]
+    backup_revs: Rc<RefCell<HashMap<String, u64>>>,
     pub common: Rc<CommonData>,
 }
 
@@ -247,6 +270,7 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
         let editors = cx.create_rw_signal(im::HashMap::new());
         let diff_editors: RwSignal<im::HashMap<DiffEditorId, DiffEditorData>> =
             cx.create_rw_signal(im::HashMap::new());
+        let hex_editors = cx.create_rw_signal(im::HashMap::new());
         let docs: RwSignal<im::HashMap<PathBuf, Rc<Document>>> =
             cx.create_rw_signal(im::HashMap::new());
         let scratch_docs = cx.create_rw_signal(im::HashMap::new());
@@ -295,7 +319,7 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
             });
         }
 
-        Self {
+        let main_split = Self {
             scope: cx,
             root_split: SplitId::next(),
             splits,
@@ -303,6 +327,7 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
             editor_tabs,
             editors,
             diff_editors,
+            hex_editors,
             docs,
             scratch_docs,
             active_editor,
@@ -312,8 +337,137 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
             locations,
             current_location,
             width: cx.create_rw_signal(0.0),
+            backup_revs: Rc::new(RefCell::new(HashMap::new())),
             common,
+        };
+        main_split.backup_docs_periodically();
+        main_split
+    }
+
+    /// The backups of the unsaved documents which are open in an editor, for
+    /// hot exit. Unless `all` is set, documents which didn't change since
+    /// their last backup keep it.
+    pub fn doc_backups(&self, all: bool) -> DocBackups {
+        let mut backup_revs = self.backup_revs.borrow_mut();
+        if !self.common.config.get_untracked().files.hot_exit {
+            backup_revs.clear();
+            return Vec::new();
+        }
+
+        let mut keys = HashSet::new();
+        let mut backups = Vec::new();
+        for (_, editor) in self.editors.get_untracked() {
+            let doc = editor.view.doc.get_untracked();
+            // Copying a large file every few seconds would grind everything
+            // to a halt
+            if !doc.loaded() || doc.is_pristine() || doc.large_file.get_untracked() {
+                continue;
+            }
+            let Some(key) = doc.content.with_untracked(|c| c.backup_key()) else {
+                continue;
+            };
+            if !keys.insert(key.clone()) {
+                continue;
+            }
+
+            let rev = doc.rev();
+            if !all && backup_revs.get(&key) == Some(&rev) {
+                backups.push((key, None));
+                continue;
+            }
+            backup_revs.insert(key.clone(), rev);
+            let text = doc.buffer.with_untracked(|b| b.to_string());
+            let disk_hash = doc.disk_hash.get_untracked();
+            backups.push((
+                key.clone(),
+                Some(DocBackup {
+                    key,
+                    text,
+                    disk_hash,
+                }),
+            ));
+        }
+        backup_revs.retain(|key, _| keys.contains(key));
+        backups
+    }
+
+    fn backup_docs_periodically(&self) {
+        let main_split = self.clone();
+        exec_after(BACKUP_INTERVAL, move |_| {
+            // The window tab was closed
+            if main_split.editors.try_with_untracked(|_| ()).is_none() {
+                return;
+            }
+            let db: Arc<LapceDb> = use_context().unwrap();
+            db.save_backups(
+                &main_split.common.workspace,
+                main_split.common.window_tab_id,
+                main_split.doc_backups(false),
+            );
+            main_split.backup_docs_periodically();
+        });
+    }
+
+    /// Bring back the unsaved content a newly loaded document had when Lapce
+    /// was last quit
+    pub fn restore_backup(&self, doc: &Document) {
+        if !self.common.config.get_untracked().files.hot_exit {
+            return;
+        }
+        let Some(key) = doc.content.with_untracked(|c| c.backup_key()) else {
+            return;
+        };
+        let db: Arc<LapceDb> = use_context().unwrap();
+        let Ok(backup) = db.get_backup(&self.common.workspace, &key) else {
+            return;
+        };
+        if doc.buffer.with_untracked(|b| b.to_string()) == backup.text {
+            return;
+        }
+        if backup.disk_hash.is_none()
+            || backup.disk_hash == doc.disk_hash.get_untracked()
+        {
+            doc.reload(Rope::from(backup.text), false);
+            return;
         }
+
+        // The file was changed on disk after the backup was taken, so the
+        // backup replacing it would lose those changes on the next save
+        let name = doc.content.with_untracked(|content| match content {
+            DocContent::File { path, .. } => path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            _ => String::new(),
+        });
+        let internal_command = self.common.internal_command;
+        let restore = {
+            let doc = doc.clone();
+            let text = backup.text;
+            Rc::new(move || {
+                internal_command.send(InternalCommand::HideAlert);
+                doc.reload(Rope::from(text.as_str()), false);
+            })
+        };
+        internal_command.send(InternalCommand::ShowAlert {
+            title: format!("{name} was changed on disk since Lapce was closed"),
+            msg: "Restoring the unsaved changes from then replaces the changes \
+                  made on disk."
+                .to_string(),
+            buttons: vec![
+                AlertButton {
+                    text: "Restore Unsaved Changes".to_string(),
+                    action: restore,
+                },
+                AlertButton {
+                    text: "Keep the File".to_string(),
+                    action: Rc::new(move || {
+                        internal_command.send(InternalCommand::HideAlert);
+                    }),
+                },
+            ],
+        });
     }
 
     pub fn key_down<'a>(
@@ -353,6 +507,12 @@ pub fn key_down<'a>(
             }
             EditorTabChild::Settings(_) => None,
             EditorTabChild::Keymap(_) => None,
+            EditorTabChild::HexEditor(hex_editor_id) => {
+                let hex_editor = self.hex_editors.with_untracked(|hex_editors| {
+                    hex_editors.get(&hex_editor_id).cloned()
+                })?;
+                Some(keypress.key_down(event, &hex_editor))
+            }
         }
     }
 
@@ -451,34 +611,123 @@ pub fn get_doc(&self, path: PathBuf) -> (Rc<Document>, bool) {
             {
                 let doc = doc.clone();
                 let local_doc = doc.clone();
+                let main_split = self.clone();
+                let local_path = path.clone();
                 let send = create_ext_action(cx, move |result| {
                     if let Ok(ProxyResponse::NewBufferResponse {
                         content,
                         read_only,
+                        encoding,
+                        large_file,
+                        binary,
+                        partial,
                     }) = result
                     {
-                        local_doc.init_content(Rope::from(content));
-                        if read_only {
-                            local_doc.content.update(|content| {
-                                if let DocContent::File { read_only, .. } = content {
-                                    *read_only = true;
-                                }
-                            });
+                        if binary {
+                            main_split.reopen_in_hex_editor(&local_path);
+                            return;
+                        }
+                        local_doc.encoding.set(encoding);
+                        if large_file {
+                            local_doc.large_file.set(true);
+                            local_doc.syntax.set(Syntax::plaintext());
+                        }
+                        let doc = local_doc.clone();
+                        let init = move |content: Rope| {
+                            doc.init_content(content);
+                            doc.restore_undo_history();
+                            main_split.restore_backup(&doc);
+                            if read_only {
+                                doc.content.update(|content| {
+                                    if let DocContent::File { read_only, .. } =
+                                        content
+                                    {
+                                        *read_only = true;
+                                    }
+                                });
+                            }
+                        };
+                        if partial {
+                            local_doc.load_remaining_content(
+                                local_path,
+                                Rope::from(content),
+                                init,
+                            );
+                        } else {
+                            init(Rope::from(content));
                         }
                     }
                 });
 
-                self.common
-                    .proxy
-                    .new_buffer(doc.buffer_id, path, move |result| {
+                self.common.proxy.new_buffer(
+                    doc.buffer_id,
+                    path,
+                    None,
+                    self.common
+                        .config
+                        .get_untracked()
+                        .files
+                        .large_file_threshold(),
+                    move |result| {
                         send(result);
-                    });
+                    },
+                );
             }
 
             (doc, true)
         }
     }
 
+    /// Opens the documents again in the proxy, which has lost them when the
+    /// connection to the remote was dropped
+    pub fn reopen_docs(&self) {
+        let docs = self.docs.get_untracked();
+        for (path, doc) in docs {
+            // The encoding may have been picked by hand
+            let encoding = doc.encoding.get_untracked();
+            let local_doc = doc.clone();
+            let local_path = path.clone();
+            let send = create_ext_action(self.scope, move |result| {
+                if let Ok(ProxyResponse::NewBufferResponse {
+                    content,
+                    partial,
+                    ..
+                }) = result
+                {
+                    if partial {
+                        let doc = local_doc.clone();
+                        local_doc.load_remaining_content(
+                            local_path,
+                            Rope::from(content),
+                            move |content| doc.reopened(content),
+                        );
+                    } else {
+                        local_doc.reopened(Rope::from(content));
+                    }
+                }
+            });
+            // Kept in large file mode even if the setting was changed since
+            let large_file_threshold = if doc.large_file.get_untracked() {
+                Some(0)
+            } else {
+                self.common
+                    .config
+                    .get_untracked()
+                    .files
+                    .large_file_threshold()
+            };
+            self.common.proxy.new_buffer(
+                doc.buffer_id,
+                path,
+                Some(encoding),
+                large_file_threshold,
+                move |result| {
+                    send(result);
+                },
+            );
+        }
+    }
+
     pub fn go_to_location(
         &self,
         location: EditorLocation,
@@ -516,19 +765,133 @@ pub fn open_file_changes(&self, path: PathBuf) {
             self.common.clone(),
         );
         let left = Rc::new(left);
+        left.retrieve_history();
+
+        self.get_editor_tab_child(
+            EditorTabChildSource::DiffEditor { left, right },
+            false,
+            false,
+        );
+    }
+
+    /// Compare the file as it is on disk, on the left, with its document
+    pub fn compare_with_disk(&self, path: PathBuf) {
+        let (right, _) = self.get_doc(path.clone());
+        let left = Document::new_hisotry(
+            self.scope,
+            DocContent::History(DocHistory {
+                path,
+                version: "disk".to_string(),
+            }),
+            self.common.clone(),
+        );
+        let left = Rc::new(left);
+        left.retrieve_history();
+
+        self.get_editor_tab_child(
+            EditorTabChildSource::DiffEditor { left, right },
+            false,
+            false,
+        );
+    }
+
+    /// Compare the document with its text in the state after the undo group
+    pub fn preview_undo_group(&self, right: Rc<Document>, group: usize) {
+        let text = if let Some(text) = right
+            .buffer
+            .with_untracked(|buffer| buffer.text_at_undo_group(group))
+        {
+            text
+        } else {
+            return;
+        };
+        let path = match right.content.get_untracked() {
+            DocContent::File { path, .. } => path,
+            DocContent::Scratch { name, .. } => PathBuf::from(name),
+            DocContent::Local | DocContent::History(_) => return,
+        };
+        let left = Document::new_hisotry(
+            self.scope,
+            DocContent::History(DocHistory {
+                path,
+                version: format!("undo:{group}"),
+            }),
+            self.common.clone(),
+        );
+        let left = Rc::new(left);
+        left.init_content(text);
+
+        self.get_editor_tab_child(
+            EditorTabChildSource::DiffEditor { left, right },
+            false,
+            false,
+        );
+    }
+
+    /// Open the changes to the file made by the commit that last changed the line
+    pub fn open_line_commit(&self, doc: Rc<Document>, line: usize) {
+        let path = if let DocContent::File { path, .. } = doc.content.get_untracked()
+        {
+            path
+        } else {
+            return;
+        };
+
+        if let Some(hunk) = doc.blame_of_line(line) {
+            self.open_blame_commit(path, &hunk);
+            return;
+        }
 
+        // The blame isn't shown, so it has to be retrieved first
+        let main_split = self.clone();
         let send = {
-            let left = left.clone();
+            let path = path.clone();
             create_ext_action(self.scope, move |result| {
-                if let Ok(ProxyResponse::BufferHeadResponse { content, .. }) = result
-                {
-                    left.init_content(Rope::from(content));
+                if let Ok(ProxyResponse::GitBlameResponse { hunks }) = result {
+                    if let Some(hunk) = hunks.iter().find(|hunk| {
+                        hunk.start_line <= line
+                            && line < hunk.start_line + hunk.lines
+                    }) {
+                        main_split.open_blame_commit(path, hunk);
+                    }
                 }
             })
         };
-        self.common.proxy.get_buffer_head(path, move |result| {
+        self.common.proxy.git_blame(path, move |result| {
             send(result);
         });
+    }
+
+    /// Open the changes to the file made by the commit of the blame hunk
+    fn open_blame_commit(&self, path: PathBuf, hunk: &BlameHunk) {
+        if hunk.is_committed() {
+            self.open_file_commit(FileDiff::Modified(path), hunk.commit_id.clone());
+        } else {
+            self.open_file_changes(path);
+        }
+    }
+
+    /// Open the changes a commit made to a file, comparing it with the file in
+    /// the commit's first parent
+    pub fn open_file_commit(&self, diff: FileDiff, commit: String) {
+        let (old_path, new_path) = match diff {
+            FileDiff::Modified(path)
+            | FileDiff::Added(path)
+            | FileDiff::Deleted(path) => (path.clone(), path),
+            FileDiff::Renamed(new, old) => (old, new),
+        };
+
+        let history = |path: PathBuf, version: String| {
+            let doc = Document::new_hisotry(
+                self.scope,
+                DocContent::History(DocHistory { path, version }),
+                self.common.clone(),
+            );
+            doc.retrieve_history();
+            Rc::new(doc)
+        };
+        let left = history(old_path, format!("{commit}^"));
+        let right = history(new_path, commit);
 
         self.get_editor_tab_child(
             EditorTabChildSource::DiffEditor { left, right },
@@ -579,6 +942,7 @@ fn get_editor_tab_child(
 
         let editors = self.editors.get_untracked();
         let diff_editors = self.diff_editors.get_untracked();
+        let hex_editors = self.hex_editors.get_untracked();
 
         let active_editor_tab = if let Some(editor_tab) = active_editor_tab {
             editor_tab
@@ -628,6 +992,12 @@ fn get_editor_tab_child(
                     })
                     .unwrap_or(false)
             };
+        let is_same_hex_editor = |hex_editor_id: &HexEditorId, path: &PathBuf| {
+            hex_editors
+                .get(hex_editor_id)
+                .map(|hex_editor| &hex_editor.path == path)
+                .unwrap_or(false)
+        };
 
         let selected = if !config.editor.show_tab {
             active_editor_tab.with_untracked(|editor_tab| {
@@ -690,6 +1060,25 @@ fn get_editor_tab_child(
                         }
                         EditorTabChild::Settings(_) => true,
                         EditorTabChild::Keymap(_) => true,
+                        EditorTabChild::HexEditor(hex_editor_id) => {
+                            let same_path =
+                                if let EditorTabChildSource::HexEditor { path } =
+                                    &source
+                                {
+                                    is_same_hex_editor(hex_editor_id, path)
+                                } else {
+                                    false
+                                };
+                            same_path
+                                || hex_editors
+                                    .get(hex_editor_id)
+                                    .map(|hex_editor| {
+                                        hex_editor.edited.with_untracked(|edited| {
+                                            edited.is_empty()
+                                        })
+                                    })
+                                    .unwrap_or(false)
+                        }
                     };
 
                     if can_be_selected {
@@ -804,6 +1193,34 @@ fn get_editor_tab_child(
                         })
                     }
                 }
+                EditorTabChildSource::HexEditor { path } => {
+                    if let Some(index) =
+                        active_editor_tab.with_untracked(|editor_tab| {
+                            editor_tab.children.iter().position(|(_, _, child)| {
+                                if let EditorTabChild::HexEditor(hex_editor_id) =
+                                    child
+                                {
+                                    is_same_hex_editor(hex_editor_id, path)
+                                } else {
+                                    false
+                                }
+                            })
+                        })
+                    {
+                        Some(index)
+                    } else if ignore_unconfirmed {
+                        None
+                    } else {
+                        active_editor_tab.with_untracked(|editor_tab| {
+                            editor_tab
+                                .get_unconfirmed_editor_tab_child(
+                                    &editors,
+                                    &diff_editors,
+                                )
+                                .map(|(i, _)| i)
+                        })
+                    }
+                }
             }
         };
 
@@ -862,6 +1279,20 @@ fn get_editor_tab_child(
                 EditorTabChildSource::Keymap => {
                     EditorTabChild::Keymap(KeymapId::next())
                 }
+                EditorTabChildSource::HexEditor { path } => {
+                    let hex_editor_id = HexEditorId::next();
+                    let hex_editor = HexEditorData::new(
+                        self.scope,
+                        hex_editor_id,
+                        editor_tab_id,
+                        path.clone(),
+                        self.common.clone(),
+                    );
+                    self.hex_editors.update(|hex_editors| {
+                        hex_editors.insert(hex_editor_id, hex_editor);
+                    });
+                    EditorTabChild::HexEditor(hex_editor_id)
+                }
                 EditorTabChildSource::DiffEditor { left, right } => {
                     let diff_editor_id = DiffEditorId::next();
                     let diff_editor = DiffEditorData::new(
@@ -893,6 +1324,7 @@ fn get_editor_tab_child(
                         EditorTabChild::DiffEditor(_) => {}
                         EditorTabChild::Settings(_) => {}
                         EditorTabChild::Keymap(_) => {}
+                        EditorTabChild::HexEditor(_) => {}
                     }
                     (editor_tab_id, current_child.clone())
                 });
@@ -933,6 +1365,10 @@ fn get_editor_tab_child(
                 (EditorTabChild::Settings(_), EditorTabChildSource::Settings) => {
                     true
                 }
+                (
+                    EditorTabChild::HexEditor(hex_editor_id),
+                    EditorTabChildSource::HexEditor { path },
+                ) => is_same_hex_editor(hex_editor_id, path),
                 _ => false,
             };
             if is_same {
@@ -957,6 +1393,11 @@ fn get_editor_tab_child(
                 }
                 EditorTabChild::Settings(_) => {}
                 EditorTabChild::Keymap(_) => {}
+                EditorTabChild::HexEditor(hex_editor_id) => {
+                    self.hex_editors.update(|hex_editors| {
+                        hex_editors.remove(hex_editor_id);
+                    });
+                }
             }
 
             // Now loading the new child
@@ -1011,6 +1452,18 @@ fn get_editor_tab_child(
                                 .position(|(_, _, child)| {
                                     matches!(child, EditorTabChild::Keymap(_))
                                 }),
+                            EditorTabChildSource::HexEditor { path } => editor_tab
+                                .children
+                                .iter()
+                                .position(|(_, _, child)| {
+                                    if let EditorTabChild::HexEditor(hex_editor_id) =
+                                        child
+                                    {
+                                        is_same_hex_editor(hex_editor_id, path)
+                                    } else {
+                                        false
+                                    }
+                                }),
                             EditorTabChildSource::NewFileEditor => None,
                         })
                     {
@@ -1320,6 +1773,18 @@ fn split_editor_tab(
                 EditorTabChild::Settings(SettingsId::next())
             }
             EditorTabChild::Keymap(_) => EditorTabChild::Keymap(KeymapId::next()),
+            EditorTabChild::HexEditor(hex_editor_id) => {
+                let new_hex_editor_id = HexEditorId::next();
+                let hex_editor = self
+                    .hex_editors
+                    .get_untracked()
+                    .get(hex_editor_id)?
+                    .copy(cx, new_hex_editor_id, editor_tab_id);
+                self.hex_editors.update(|hex_editors| {
+                    hex_editors.insert(new_hex_editor_id, hex_editor);
+                });
+                EditorTabChild::HexEditor(new_hex_editor_id)
+            }
         };
 
         let editor_tab = {
@@ -1663,6 +2128,8 @@ fn editor_tab_child_close_warning(
             EditorTabChild::DiffEditor(_) => None,
             EditorTabChild::Settings(_) => None,
             EditorTabChild::Keymap(_) => None,
+            // Asked about separately, as there's no document to save
+            EditorTabChild::HexEditor(_) => None,
         }
     }
 
@@ -1703,6 +2170,20 @@ pub fn editor_tab_child_close(
         force: bool,
     ) -> Option<()> {
         if !force {
+            if let EditorTabChild::HexEditor(hex_editor_id) = &child {
+                let hex_editor = self.hex_editors.with_untracked(|hex_editors| {
+                    hex_editors.get(hex_editor_id).cloned()
+                });
+                if let Some(hex_editor) = hex_editor.filter(|hex_editor| {
+                    hex_editor
+                        .edited
+                        .with_untracked(|edited| !edited.is_empty())
+                }) {
+                    self.hex_editor_close_warning(editor_tab_id, child, hex_editor);
+                    return Some(());
+                }
+            }
+
             if let Some((name, doc, editor)) =
                 self.editor_tab_child_close_warning(&child)
             {
@@ -1840,6 +2321,11 @@ pub fn editor_tab_child_close(
             }
             EditorTabChild::Settings(_) => {}
             EditorTabChild::Keymap(_) => {}
+            EditorTabChild::HexEditor(hex_editor_id) => {
+                self.hex_editors.update(|hex_editors| {
+                    hex_editors.remove(&hex_editor_id);
+                });
+            }
         }
 
         if editor_tab_children_len == 0 {
@@ -1849,6 +2335,55 @@ pub fn editor_tab_child_close(
         Some(())
     }
 
+    fn hex_editor_close_warning(
+        &self,
+        editor_tab_id: EditorTabId,
+        child: EditorTabChild,
+        hex_editor: HexEditorData,
+    ) {
+        let internal_command = self.common.internal_command;
+        let name = hex_editor
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let save_action = {
+            let main_split = self.clone();
+            let child = child.clone();
+            Rc::new(move || {
+                internal_command.send(InternalCommand::HideAlert);
+                let main_split = main_split.clone();
+                let child = child.clone();
+                hex_editor.save(move || {
+                    main_split.editor_tab_child_close(editor_tab_id, child, false);
+                });
+            })
+        };
+        let main_split = self.clone();
+        internal_command.send(InternalCommand::ShowAlert {
+            title: format!("Do you want to save the changes you made to {name}?"),
+            msg: "Your changes will be lost if you don't save them.".to_string(),
+            buttons: vec![
+                AlertButton {
+                    text: "Save".to_string(),
+                    action: save_action,
+                },
+                AlertButton {
+                    text: "Don't Save".to_string(),
+                    action: Rc::new(move || {
+                        internal_command.send(InternalCommand::HideAlert);
+                        main_split.editor_tab_child_close(
+                            editor_tab_id,
+                            child.clone(),
+                            true,
+                        );
+                    }),
+                },
+            ],
+        });
+    }
+
     pub fn editor_tab_update_layout(
         &self,
         editor_tab_id: &EditorTabId,
@@ -2024,6 +2559,12 @@ pub fn open_file_changed(&self, path: &Path, content: &str) {
         doc.handle_file_changed(Rope::from(content));
     }
 
+    pub fn open_file_deleted(&self, path: &Path) {
+        if let Some(doc) = self.docs.with_untracked(|docs| docs.get(path).cloned()) {
+            doc.handle_file_deleted();
+        }
+    }
+
     pub fn set_find_pattern(&self, pattern: Option<String>) {
         if let Some(pattern) = pattern {
             self.find_editor
@@ -2052,6 +2593,66 @@ pub fn open_keymap(&self) {
         self.get_editor_tab_child(EditorTabChildSource::Keymap, false, false);
     }
 
+    pub fn open_hex_editor(&self, path: PathBuf) {
+        self.get_editor_tab_child(
+            EditorTabChildSource::HexEditor { path },
+            false,
+            false,
+        );
+    }
+
+    /// Files which turned out not to be text are shown as bytes instead, in
+    /// place of the editors that were opened for them
+    fn reopen_in_hex_editor(&self, path: &Path) {
+        let editors = self.editors.get_untracked();
+        for (_, editor_tab) in self.editor_tabs.get_untracked() {
+            let (editor_tab_id, children) =
+                editor_tab.with_untracked(|editor_tab| {
+                    (editor_tab.editor_tab_id, editor_tab.children.clone())
+                });
+            for (i, (_, _, child)) in children.into_iter().enumerate() {
+                let EditorTabChild::Editor(editor_id) = child else {
+                    continue;
+                };
+                let is_path = editors.get(&editor_id).map_or(false, |editor| {
+                    editor.view.doc.with_untracked(|doc| {
+                        doc.content.with_untracked(|content| {
+                            content.path().map(|p| p.as_path()) == Some(path)
+                        })
+                    })
+                });
+                if !is_path {
+                    continue;
+                }
+
+                self.remove_editor(&editor_id);
+                let hex_editor_id = HexEditorId::next();
+                let hex_editor = HexEditorData::new(
+                    self.scope,
+                    hex_editor_id,
+                    editor_tab_id,
+                    path.to_path_buf(),
+                    self.common.clone(),
+                );
+                self.hex_editors.update(|hex_editors| {
+                    hex_editors.insert(hex_editor_id, hex_editor);
+                });
+                editor_tab.update(|editor_tab| {
+                    editor_tab.children[i] = (
+                        editor_tab.scope.create_rw_signal(i),
+                        editor_tab.scope.create_rw_signal(Rect::ZERO),
+                        EditorTabChild::HexEditor(hex_editor_id),
+                    );
+                });
+            }
+        }
+        // Opening the file again checks it anew, in case it's text by then
+        self.docs.update(|docs| {
+            docs.remove(path);
+        });
+        self.common.proxy.close_buffer(path.to_path_buf());
+    }
+
     pub fn new_file(&self) {
         self.get_editor_tab_child(EditorTabChildSource::NewFileEditor, false, false);
     }
@@ -2280,6 +2881,12 @@ fn editor_tab_child_set_parent(
             }
             EditorTabChild::Settings(_) => {}
             EditorTabChild::Keymap(_) => {}
+            EditorTabChild::HexEditor(hex_editor_id) => {
+                let hex_editor = self.hex_editors.with_untracked(|hex_editors| {
+                    hex_editors.get(hex_editor_id).cloned()
+                })?;
+                hex_editor.editor_tab_id.set(editor_tab_id);
+            }
         }
         Some(())
     }
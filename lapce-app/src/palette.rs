@@ -20,9 +20,10 @@
 use itertools::Itertools;
 use lapce_core::{
     buffer::rope_text::RopeText, command::FocusCommand, language::LapceLanguage,
-    mode::Mode, movement::Movement, selection::Selection, syntax::Syntax,
+    line_ending::LineEnding, mode::Mode, movement::Movement, selection::Selection,
+    syntax::Syntax,
 };
-use lapce_rpc::proxy::ProxyResponse;
+use lapce_rpc::{buffer::FileEncoding, proxy::ProxyResponse};
 use lapce_xi_rope::Rope;
 use lsp_types::DocumentSymbolResponse;
 use nucleo::Utf32Str;
@@ -49,7 +50,7 @@
     proxy::path_from_url,
     source_control::SourceControlData,
     window_tab::{CommonData, Focus},
-    workspace::{LapceWorkspace, LapceWorkspaceType, SshHost},
+    workspace::{LapceWorkspace, LapceWorkspaceType, ProxyHost, SshHost},
 };
 
 pub mod item;
@@ -324,6 +325,26 @@ pub fn run(&self, kind: PaletteKind) {
             .update(|cursor| cursor.set_insert(Selection::caret(symbol.len())));
     }
 
+    /// Ask for the token of the remote proxy the workspace is on, which isn't
+    /// kept when the workspace is stored, to connect to it again
+    pub fn ask_proxy_token(&self) {
+        let LapceWorkspaceType::RemoteProxy(host) = &self.workspace.kind else {
+            return;
+        };
+        if !host.token.is_empty() {
+            return;
+        }
+        self.run(PaletteKind::RemoteProxy);
+        self.input_editor
+            .view
+            .doc
+            .get_untracked()
+            .reload(Rope::from(format!("@{}", host.address)), true);
+        self.input_editor
+            .cursor
+            .update(|cursor| cursor.set_insert(Selection::caret(0)));
+    }
+
     /// Execute the internal behavior of the palette for the given kind. This ignores updating and
     /// focusing the palette input.
     fn run_inner(&self, kind: PaletteKind) {
@@ -358,6 +379,12 @@ fn run_inner(&self, kind: PaletteKind) {
             PaletteKind::SshHost => {
                 self.get_ssh_hosts();
             }
+            PaletteKind::RemoteCommand => {
+                self.get_remote_commands();
+            }
+            PaletteKind::RemoteProxy => {
+                self.get_remote_proxies();
+            }
             PaletteKind::RunAndDebug => {
                 self.get_run_configs();
             }
@@ -370,6 +397,15 @@ fn run_inner(&self, kind: PaletteKind) {
             PaletteKind::Language => {
                 self.get_languages();
             }
+            PaletteKind::ReopenWithEncoding => {
+                self.get_encodings(false);
+            }
+            PaletteKind::SaveWithEncoding => {
+                self.get_encodings(true);
+            }
+            PaletteKind::LineEnding => {
+                self.get_line_endings();
+            }
             PaletteKind::SCMReferences => {
                 self.get_scm_references();
             }
@@ -544,12 +580,21 @@ fn get_workspaces(&self) {
         let items = workspaces
             .into_iter()
             .filter_map(|w| {
-                let text = w.path.as_ref()?.to_str()?.to_string();
+                let text = match w.multi_root.as_ref() {
+                    Some(multi_root) => multi_root.file.to_str()?.to_string(),
+                    None => w.path.as_ref()?.to_str()?.to_string(),
+                };
                 let filter_text = match &w.kind {
                     LapceWorkspaceType::Local => text,
                     LapceWorkspaceType::RemoteSSH(ssh) => {
                         format!("[{ssh}] {text}")
                     }
+                    LapceWorkspaceType::RemoteCommand(command) => {
+                        format!("[{command}] {text}")
+                    }
+                    LapceWorkspaceType::RemoteProxy(host) => {
+                        format!("[{host}] {text}")
+                    }
                     #[cfg(windows)]
                     LapceWorkspaceType::RemoteWSL => {
                         format!("[wsl] {text}")
@@ -736,6 +781,54 @@ fn get_ssh_hosts(&self) {
         self.items.set(items);
     }
 
+    fn get_remote_commands(&self) {
+        let db: Arc<LapceDb> = use_context().unwrap();
+        let workspaces = db.recent_workspaces().unwrap_or_default();
+        let mut commands = Vec::new();
+        for workspace in workspaces.iter() {
+            if let LapceWorkspaceType::RemoteCommand(command) = &workspace.kind {
+                if !commands.contains(command) {
+                    commands.push(command.clone());
+                }
+            }
+        }
+
+        let items = commands
+            .into_iter()
+            .map(|command| PaletteItem {
+                filter_text: command.clone(),
+                content: PaletteItemContent::RemoteCommand { command },
+                score: 0,
+                indices: vec![],
+            })
+            .collect();
+        self.items.set(items);
+    }
+
+    fn get_remote_proxies(&self) {
+        let db: Arc<LapceDb> = use_context().unwrap();
+        let workspaces = db.recent_workspaces().unwrap_or_default();
+        let mut hosts = Vec::new();
+        for workspace in workspaces.iter() {
+            if let LapceWorkspaceType::RemoteProxy(host) = &workspace.kind {
+                if !hosts.contains(host) {
+                    hosts.push(host.clone());
+                }
+            }
+        }
+
+        let items = hosts
+            .into_iter()
+            .map(|host| PaletteItem {
+                filter_text: host.to_string(),
+                content: PaletteItemContent::RemoteProxy { host },
+                score: 0,
+                indices: vec![],
+            })
+            .collect();
+        self.items.set(items);
+    }
+
     fn get_run_configs(&self) {
         let configs = run_configs(self.common.workspace.path.as_deref());
         if configs.is_none() {
@@ -848,14 +941,51 @@ fn get_languages(&self) {
             .collect();
         if let Some(editor) = self.main_split.active_editor.get_untracked() {
             let doc = editor.view.doc.get_untracked();
-            let language = doc.syntax.with_untracked(|syntax| {
-                syntax.language.get_message().unwrap_or("")
-            });
+            let language = doc
+                .syntax
+                .with_untracked(|syntax| syntax.language.display_name());
             self.preselect_matching(&items, language);
         }
         self.items.set(items);
     }
 
+    /// Initialize the palette with the encodings a file can be reopened, or saved, in.
+    fn get_encodings(&self, save: bool) {
+        let items = FileEncoding::all()
+            .into_iter()
+            .map(|encoding| PaletteItem {
+                filter_text: encoding.to_string(),
+                content: PaletteItemContent::Encoding { encoding, save },
+                score: 0,
+                indices: Vec::new(),
+            })
+            .collect();
+        if let Some(editor) = self.main_split.active_editor.get_untracked() {
+            let doc = editor.view.doc.get_untracked();
+            let encoding = doc.encoding.get_untracked().to_string();
+            self.preselect_matching(&items, &encoding);
+        }
+        self.items.set(items);
+    }
+
+    fn get_line_endings(&self) {
+        let items = LineEnding::all()
+            .into_iter()
+            .map(|line_ending| PaletteItem {
+                content: PaletteItemContent::LineEnding { line_ending },
+                filter_text: line_ending.name().to_string(),
+                score: 0,
+                indices: Vec::new(),
+            })
+            .collect();
+        if let Some(editor) = self.main_split.active_editor.get_untracked() {
+            let doc = editor.view.doc.get_untracked();
+            let line_ending = doc.buffer.with_untracked(|b| b.line_ending());
+            self.preselect_matching(&items, line_ending.name());
+        }
+        self.items.set(items);
+    }
+
     fn get_scm_references(&self) {
         let branches = self.source_control.branches.get_untracked();
         let tags = self.source_control.tags.get_untracked();
@@ -1003,6 +1133,33 @@ fn select(&self) {
                             workspace: LapceWorkspace {
                                 kind: LapceWorkspaceType::RemoteSSH(host.clone()),
                                 path: None,
+                                multi_root: None,
+                                last_open: 0,
+                            },
+                        });
+                }
+                PaletteItemContent::RemoteCommand { command } => {
+                    self.common
+                        .window_command
+                        .send(WindowCommand::SetWorkspace {
+                            workspace: LapceWorkspace {
+                                kind: LapceWorkspaceType::RemoteCommand(
+                                    command.clone(),
+                                ),
+                                path: None,
+                                multi_root: None,
+                                last_open: 0,
+                            },
+                        });
+                }
+                PaletteItemContent::RemoteProxy { host } => {
+                    self.common
+                        .window_command
+                        .send(WindowCommand::SetWorkspace {
+                            workspace: LapceWorkspace {
+                                kind: LapceWorkspaceType::RemoteProxy(host.clone()),
+                                path: None,
+                                multi_root: None,
                                 last_open: 0,
                             },
                         });
@@ -1085,6 +1242,30 @@ fn select(&self) {
                     }
                     doc.trigger_syntax_change(None);
                 }
+                PaletteItemContent::Encoding { encoding, save } => {
+                    let editor = self.main_split.active_editor.get_untracked();
+                    let doc = match editor {
+                        Some(editor) => editor.view.doc.get_untracked(),
+                        None => {
+                            return;
+                        }
+                    };
+                    if *save {
+                        doc.save_with_encoding(Some(encoding.clone()), || {});
+                    } else {
+                        doc.reopen_with_encoding(encoding.clone());
+                    }
+                }
+                PaletteItemContent::LineEnding { line_ending } => {
+                    let editor = self.main_split.active_editor.get_untracked();
+                    let doc = match editor {
+                        Some(editor) => editor.view.doc.get_untracked(),
+                        None => {
+                            return;
+                        }
+                    };
+                    doc.convert_line_ending(*line_ending);
+                }
                 PaletteItemContent::SCMReference { name } => {
                     self.common
                         .lapce_command
@@ -1111,9 +1292,63 @@ fn select(&self) {
                     workspace: LapceWorkspace {
                         kind: LapceWorkspaceType::RemoteSSH(ssh),
                         path: None,
+                        multi_root: None,
                         last_open: 0,
                     },
                 });
+        } else if self.kind.get_untracked() == PaletteKind::RemoteCommand {
+            let command = self.input.with_untracked(|input| input.input.clone());
+            let command = command.trim();
+            if !command.is_empty() {
+                self.common
+                    .window_command
+                    .send(WindowCommand::SetWorkspace {
+                        workspace: LapceWorkspace {
+                            kind: LapceWorkspaceType::RemoteCommand(
+                                command.to_string(),
+                            ),
+                            path: None,
+                            multi_root: None,
+                            last_open: 0,
+                        },
+                    });
+            }
+        } else if self.kind.get_untracked() == PaletteKind::RemoteProxy {
+            let input = self.input.with_untracked(|input| input.input.clone());
+            match ProxyHost::from_string(&input) {
+                Ok(host) => {
+                    // Reconnecting to the proxy of the workspace keeps its
+                    // folders
+                    let workspace = match &self.workspace.kind {
+                        LapceWorkspaceType::RemoteProxy(current)
+                            if current.address == host.address =>
+                        {
+                            LapceWorkspace {
+                                kind: LapceWorkspaceType::RemoteProxy(host),
+                                ..(*self.workspace).clone()
+                            }
+                        }
+                        _ => LapceWorkspace {
+                            kind: LapceWorkspaceType::RemoteProxy(host),
+                            path: None,
+                            multi_root: None,
+                            last_open: 0,
+                        },
+                    };
+                    self.common
+                        .window_command
+                        .send(WindowCommand::SetWorkspace { workspace });
+                }
+                Err(e) => {
+                    self.common
+                        .internal_command
+                        .send(InternalCommand::ShowAlert {
+                            title: "Can't connect to the remote proxy".to_string(),
+                            msg: e,
+                            buttons: Vec::new(),
+                        });
+                }
+            }
         }
     }
 
@@ -1162,7 +1397,11 @@ fn preview(&self) {
                 PaletteItemContent::Workspace { .. } => {}
                 PaletteItemContent::RunAndDebug { .. } => {}
                 PaletteItemContent::SshHost { .. } => {}
+                PaletteItemContent::RemoteCommand { .. } => {}
+                PaletteItemContent::RemoteProxy { .. } => {}
                 PaletteItemContent::Language { .. } => {}
+                PaletteItemContent::Encoding { .. } => {}
+                PaletteItemContent::LineEnding { .. } => {}
                 PaletteItemContent::Reference { location, .. } => {
                     self.has_preview.set(true);
                     let (doc, new_doc) =
@@ -1228,6 +1467,8 @@ fn preview(&self) {
                         name: name.clone(),
                         save: false,
                     }),
+                PaletteItemContent::Encoding { .. } => {}
+                PaletteItemContent::LineEnding { .. } => {}
                 PaletteItemContent::SCMReference { .. } => {}
                 PaletteItemContent::TerminalProfile { .. } => {}
             }
@@ -1,13 +1,14 @@
 use std::path::PathBuf;
 
-use lapce_rpc::dap_types::RunDebugConfig;
+use lapce_core::line_ending::LineEnding;
+use lapce_rpc::{buffer::FileEncoding, dap_types::RunDebugConfig};
 use lsp_types::{Range, SymbolKind};
 
 use crate::{
     command::{LapceCommand, LapceWorkbenchCommand},
     debug::RunDebugMode,
     editor::location::EditorLocation,
-    workspace::{LapceWorkspace, SshHost},
+    workspace::{LapceWorkspace, ProxyHost, SshHost},
 };
 
 #[derive(Clone, Debug, PartialEq)]
@@ -56,6 +57,12 @@ pub enum PaletteItemContent {
     SshHost {
         host: SshHost,
     },
+    RemoteCommand {
+        command: String,
+    },
+    RemoteProxy {
+        host: ProxyHost,
+    },
     RunAndDebug {
         mode: RunDebugMode,
         config: RunDebugConfig,
@@ -69,6 +76,14 @@ pub enum PaletteItemContent {
     Language {
         name: String,
     },
+    Encoding {
+        encoding: FileEncoding,
+        /// If the file is saved in the encoding, rather than reopened
+        save: bool,
+    },
+    LineEnding {
+        line_ending: LineEnding,
+    },
     SCMReference {
         name: String,
     },
@@ -13,10 +13,15 @@ pub enum PaletteKind {
     DocumentSymbol,
     WorkspaceSymbol,
     SshHost,
+    RemoteCommand,
+    RemoteProxy,
     RunAndDebug,
     ColorTheme,
     IconTheme,
     Language,
+    ReopenWithEncoding,
+    SaveWithEncoding,
+    LineEnding,
     SCMReferences,
     TerminalProfile,
 }
@@ -36,10 +41,15 @@ pub fn symbol(&self) -> &'static str {
             PaletteKind::File
             | PaletteKind::Reference
             | PaletteKind::SshHost
+            | PaletteKind::RemoteCommand
+            | PaletteKind::RemoteProxy
             | PaletteKind::RunAndDebug
             | PaletteKind::ColorTheme
             | PaletteKind::IconTheme
             | PaletteKind::Language
+            | PaletteKind::ReopenWithEncoding
+            | PaletteKind::SaveWithEncoding
+            | PaletteKind::LineEnding
             | PaletteKind::SCMReferences => "",
         }
     }
@@ -74,12 +84,27 @@ pub fn command(self) -> Option<LapceWorkbenchCommand> {
             PaletteKind::File => Some(LapceWorkbenchCommand::Palette),
             PaletteKind::Reference => None, // InternalCommand::PaletteReferences
             PaletteKind::SshHost => Some(LapceWorkbenchCommand::ConnectSshHost),
+            PaletteKind::RemoteCommand => {
+                Some(LapceWorkbenchCommand::ConnectRemoteCommand)
+            }
+            PaletteKind::RemoteProxy => {
+                Some(LapceWorkbenchCommand::ConnectRemoteProxy)
+            }
             PaletteKind::RunAndDebug => {
                 Some(LapceWorkbenchCommand::PaletteRunAndDebug)
             }
             PaletteKind::ColorTheme => Some(LapceWorkbenchCommand::ChangeColorTheme),
             PaletteKind::IconTheme => Some(LapceWorkbenchCommand::ChangeIconTheme),
             PaletteKind::Language => Some(LapceWorkbenchCommand::ChangeFileLanguage),
+            PaletteKind::ReopenWithEncoding => {
+                Some(LapceWorkbenchCommand::ReopenWithEncoding)
+            }
+            PaletteKind::SaveWithEncoding => {
+                Some(LapceWorkbenchCommand::SaveWithEncoding)
+            }
+            PaletteKind::LineEnding => {
+                Some(LapceWorkbenchCommand::ChangeLineEndings)
+            }
             PaletteKind::SCMReferences => {
                 Some(LapceWorkbenchCommand::PaletteSCMReferences)
             }
@@ -103,10 +128,15 @@ pub fn get_input<'a>(&self, input: &'a str) -> &'a str {
             PaletteKind::File
             | PaletteKind::Reference
             | PaletteKind::SshHost
+            | PaletteKind::RemoteCommand
+            | PaletteKind::RemoteProxy
             | PaletteKind::RunAndDebug
             | PaletteKind::ColorTheme
             | PaletteKind::IconTheme
             | PaletteKind::Language
+            | PaletteKind::ReopenWithEncoding
+            | PaletteKind::SaveWithEncoding
+            | PaletteKind::LineEnding
             | PaletteKind::SCMReferences => input,
             PaletteKind::PaletteHelp
             | PaletteKind::Command
@@ -0,0 +1,419 @@
+use std::{rc::Rc, sync::Arc};
+
+use floem::{
+    event::EventListener,
+    id::Id,
+    peniko::kurbo::{Circle, Line, Point, Rect, Size},
+    reactive::{create_memo, ReadSignal},
+    style::CursorStyle,
+    view::{ChangeFlags, View},
+    views::{container, label, list, scroll, stack, svg, Decorators},
+};
+use lapce_core::buffer::rope_text::RopeText;
+use lapce_rpc::source_control::FileDiff;
+
+use super::{kind::PanelKind, position::PanelPosition};
+use crate::{
+    app::clickable_icon,
+    config::{color::LapceColor, icon::LapceIcons, LapceConfig},
+    editor::EditorData,
+    git_log::{GitLogData, GitLogEntry, GitLogInput, GraphRow},
+    source_control::commit_date,
+    text_input::text_input,
+    window_tab::{Focus, WindowTabData},
+};
+
+/// The width of a lane of the branch graph
+const LANE_WIDTH: f64 = 14.0;
+
+/// The colors the lanes of the branch graph cycle through
+const LANE_COLORS: [&str; 6] = [
+    LapceColor::TERMINAL_BLUE,
+    LapceColor::TERMINAL_GREEN,
+    LapceColor::TERMINAL_YELLOW,
+    LapceColor::TERMINAL_MAGENTA,
+    LapceColor::TERMINAL_CYAN,
+    LapceColor::TERMINAL_RED,
+];
+
+pub fn git_log_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let git_log = window_tab_data.git_log.clone();
+    let config = git_log.common.config;
+    let focus = git_log.common.focus;
+    let path = git_log.path;
+    let workspace = git_log.common.workspace.clone();
+
+    stack((
+        stack((
+            filter_input(git_log.clone(), GitLogInput::Message, "Message"),
+            filter_input(git_log.clone(), GitLogInput::Author, "Author"),
+            stack((
+                label(move || {
+                    let path = path.get().unwrap_or_default();
+                    let path = workspace
+                        .path
+                        .as_ref()
+                        .and_then(|w| path.strip_prefix(w).ok())
+                        .unwrap_or(path.as_path());
+                    path.to_string_lossy().to_string()
+                })
+                .style(|s| s.margin_right(6.0)),
+                clickable_icon(
+                    || LapceIcons::CLOSE,
+                    move || {
+                        path.set(None);
+                    },
+                    || false,
+                    || false,
+                    config,
+                ),
+            ))
+            .style(move |s| {
+                s.items_center()
+                    .padding_left(10.0)
+                    .apply_if(path.with(|p| p.is_none()), |s| s.hide())
+            }),
+            {
+                let git_log = git_log.clone();
+                clickable_icon(
+                    || LapceIcons::SCM_REFRESH,
+                    move || {
+                        git_log.reload();
+                    },
+                    || false,
+                    || false,
+                    config,
+                )
+                .style(|s| s.margin_left(6.0))
+            },
+        ))
+        .on_event(EventListener::PointerDown, move |_| {
+            focus.set(Focus::Panel(PanelKind::GitLog));
+            false
+        })
+        .style(|s| s.width_pct(100.0).padding(10.0).items_center()),
+        commit_list(git_log),
+    ))
+    .style(|s| s.absolute().size_pct(100.0, 100.0).flex_col())
+}
+
+fn filter_input(
+    git_log: GitLogData,
+    input: GitLogInput,
+    placeholder: &'static str,
+) -> impl View {
+    let config = git_log.common.config;
+    let focus = git_log.common.focus;
+    let active_input = git_log.active_input;
+    let editor: EditorData = match input {
+        GitLogInput::Message => git_log.message_editor.clone(),
+        GitLogInput::Author => git_log.author_editor.clone(),
+    };
+    let doc = editor.view.doc;
+    let is_empty = create_memo(move |_| {
+        let doc = doc.get();
+        doc.buffer.with(|b| b.len() == 0)
+    });
+    let is_focused = move || {
+        focus.get() == Focus::Panel(PanelKind::GitLog) && active_input.get() == input
+    };
+
+    stack((
+        text_input(editor, is_focused).style(|s| s.width_pct(100.0)),
+        label(move || placeholder.to_string()).style(move |s| {
+            s.absolute()
+                .padding_left(6.0)
+                .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                .apply_if(!is_empty.get(), |s| s.hide())
+        }),
+    ))
+    .on_event(EventListener::PointerDown, move |_| {
+        active_input.set(input);
+        false
+    })
+    .style(move |s| {
+        s.flex_basis(0.0)
+            .flex_grow(1.0)
+            .margin_right(6.0)
+            .items_center()
+            .border(1.0)
+            .border_radius(6.0)
+            .border_color(*config.get().get_color(LapceColor::LAPCE_BORDER))
+    })
+}
+
+fn commit_list(git_log: GitLogData) -> impl View {
+    let config = git_log.common.config;
+    let entries = git_log.entries;
+    let has_more = git_log.has_more;
+
+    container({
+        scroll({
+            stack((
+                list(move || entries.get(), |entry| entry.commit.id.clone(), {
+                    let git_log = git_log.clone();
+                    move |entry| commit_view(git_log.clone(), entry)
+                })
+                .style(|s| s.flex_col().width_pct(100.0)),
+                {
+                    let git_log = git_log.clone();
+                    label(|| "Load More".to_string())
+                        .on_click(move |_| {
+                            git_log.load_more();
+                            true
+                        })
+                        .style(move |s| {
+                            s.padding_horiz(10.0)
+                                .color(
+                                    *config.get().get_color(LapceColor::EDITOR_LINK),
+                                )
+                                .apply_if(!has_more.get(), |s| s.hide())
+                        })
+                        .hover_style(|s| s.cursor(CursorStyle::Pointer))
+                },
+            ))
+            .style(|s| s.flex_col().width_pct(100.0).line_height(1.6))
+        })
+        .style(|s| s.absolute().size_pct(100.0, 100.0))
+    })
+    .style(|s| s.size_pct(100.0, 100.0))
+}
+
+fn commit_view(git_log: GitLogData, entry: GitLogEntry) -> impl View {
+    let config = git_log.common.config;
+    let ui_line_height = git_log.common.ui_line_height;
+    let opened = git_log.opened;
+    let commit = entry.commit;
+    let id = commit.id.clone();
+    let graph_width = entry.row.width() as f64 * LANE_WIDTH;
+
+    let files = {
+        let id = id.clone();
+        move || {
+            opened.with(|opened| {
+                opened
+                    .as_ref()
+                    .filter(|(opened, _)| opened == &id)
+                    .map(|(_, diffs)| diffs.clone())
+                    .unwrap_or_default()
+            })
+        }
+    };
+    let file_view = {
+        let git_log = git_log.clone();
+        let id = id.clone();
+        move |diff: FileDiff| commit_file_view(git_log.clone(), diff, id.clone())
+    };
+
+    let refs = commit.refs.join(", ");
+    let has_refs = !refs.is_empty();
+    let summary = commit.summary.clone();
+    let author = commit.author.clone();
+    let date = commit_date(commit.time);
+    let short_id: String = commit.id.chars().take(8).collect();
+
+    stack((
+        stack((
+            git_graph_view(entry.row, config).style(move |s| {
+                s.width(graph_width as f32)
+                    .min_width(graph_width as f32)
+                    .height(ui_line_height.get() as f32)
+            }),
+            label(move || refs.clone()).style(move |s| {
+                let config = config.get();
+                s.margin_left(6.0)
+                    .padding_horiz(4.0)
+                    .border_radius(4.0)
+                    .color(*config.get_color(LapceColor::PANEL_CURRENT_FOREGROUND))
+                    .background(
+                        *config.get_color(LapceColor::PANEL_CURRENT_BACKGROUND),
+                    )
+                    .apply_if(!has_refs, |s| s.hide())
+            }),
+            label(move || summary.clone()).style(|s| {
+                s.margin_left(6.0)
+                    .flex_grow(1.0)
+                    .flex_basis(0.0)
+                    .min_width(0.0)
+            }),
+            label(move || format!("{author}  {date}  {short_id}")).style(move |s| {
+                s.margin_left(10.0)
+                    .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+            }),
+        ))
+        .on_click({
+            let id = id.clone();
+            move |_| {
+                git_log.toggle_commit(&id);
+                true
+            }
+        })
+        .style(|s| s.width_pct(100.0).items_center().padding_horiz(10.0))
+        .hover_style(move |s| {
+            s.cursor(CursorStyle::Pointer).background(
+                *config.get().get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+            )
+        }),
+        list(files, |diff| diff.clone(), file_view)
+            .style(|s| s.flex_col().width_pct(100.0)),
+    ))
+    .style(|s| s.flex_col().width_pct(100.0))
+}
+
+fn commit_file_view(
+    git_log: GitLogData,
+    diff: FileDiff,
+    commit: String,
+) -> impl View {
+    let config = git_log.common.config;
+    let workspace = git_log.common.workspace.clone();
+    let path = diff.path().clone();
+    let path = workspace
+        .path
+        .as_ref()
+        .and_then(|w| path.strip_prefix(w).ok())
+        .unwrap_or(path.as_path())
+        .to_string_lossy()
+        .to_string();
+    let icon = match &diff {
+        FileDiff::Modified(_) => LapceIcons::SCM_DIFF_MODIFIED,
+        FileDiff::Added(_) => LapceIcons::SCM_DIFF_ADDED,
+        FileDiff::Deleted(_) => LapceIcons::SCM_DIFF_REMOVED,
+        FileDiff::Renamed(_, _) => LapceIcons::SCM_DIFF_RENAMED,
+    };
+    let color = match &diff {
+        FileDiff::Modified(_) | FileDiff::Renamed(_, _) => {
+            LapceColor::SOURCE_CONTROL_MODIFIED
+        }
+        FileDiff::Added(_) => LapceColor::SOURCE_CONTROL_ADDED,
+        FileDiff::Deleted(_) => LapceColor::SOURCE_CONTROL_REMOVED,
+    };
+
+    stack((
+        svg(move || config.get().ui_svg(icon)).style(move |s| {
+            let config = config.get();
+            let size = config.ui.icon_size() as f32;
+            s.min_width(size)
+                .size(size, size)
+                .margin_right(6.0)
+                .color(*config.get_color(color))
+        }),
+        label(move || path.clone()),
+    ))
+    .on_click(move |_| {
+        git_log.open_file_diff(diff.clone(), commit.clone());
+        true
+    })
+    .style(|s| s.width_pct(100.0).items_center().padding_left(40.0))
+    .hover_style(move |s| {
+        s.cursor(CursorStyle::Pointer).background(
+            *config.get().get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+        )
+    })
+}
+
+/// Paints a row of the branch graph
+pub struct GitGraphView {
+    id: Id,
+    row: GraphRow,
+    config: ReadSignal<Arc<LapceConfig>>,
+    size: Size,
+}
+
+fn git_graph_view(
+    row: GraphRow,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> GitGraphView {
+    GitGraphView {
+        id: Id::next(),
+        row,
+        config,
+        size: Size::ZERO,
+    }
+}
+
+impl View for GitGraphView {
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn child(&self, _id: Id) -> Option<&dyn View> {
+        None
+    }
+
+    fn child_mut(&mut self, _id: Id) -> Option<&mut dyn View> {
+        None
+    }
+
+    fn children(&self) -> Vec<&dyn View> {
+        Vec::new()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn View> {
+        Vec::new()
+    }
+
+    fn update(
+        &mut self,
+        _cx: &mut floem::context::UpdateCx,
+        _state: Box<dyn std::any::Any>,
+    ) -> ChangeFlags {
+        ChangeFlags::default()
+    }
+
+    fn layout(
+        &mut self,
+        cx: &mut floem::context::LayoutCx,
+    ) -> floem::taffy::prelude::Node {
+        cx.layout_node(self.id, false, |_| Vec::new())
+    }
+
+    fn compute_layout(&mut self, cx: &mut floem::context::LayoutCx) -> Option<Rect> {
+        if let Some(layout) = cx.get_layout(self.id) {
+            self.size =
+                Size::new(layout.size.width as f64, layout.size.height as f64);
+        }
+        None
+    }
+
+    fn event(
+        &mut self,
+        _cx: &mut floem::context::EventCx,
+        _id_path: Option<&[Id]>,
+        _event: floem::event::Event,
+    ) -> bool {
+        false
+    }
+
+    fn paint(&mut self, cx: &mut floem::context::PaintCx) {
+        let config = self.config.get_untracked();
+        let color =
+            |lane: usize| config.get_color(LANE_COLORS[lane % LANE_COLORS.len()]);
+        let x = |lane: usize| lane as f64 * LANE_WIDTH + LANE_WIDTH / 2.0;
+        let height = self.size.height;
+        let middle = height / 2.0;
+
+        for (from, to) in &self.row.upper {
+            cx.stroke(
+                &Line::new(Point::new(x(*from), 0.0), Point::new(x(*to), middle)),
+                color(*from),
+                1.5,
+            );
+        }
+        for (from, to) in &self.row.lower {
+            cx.stroke(
+                &Line::new(Point::new(x(*from), middle), Point::new(x(*to), height)),
+                color(*to),
+                1.5,
+            );
+        }
+        cx.fill(
+            &Circle::new(Point::new(x(self.row.column), middle), 4.0),
+            color(self.row.column),
+            0.0,
+        );
+    }
+}
@@ -15,6 +15,9 @@ pub enum PanelKind {
     Search,
     Problem,
     Debug,
+    GitLog,
+    Ports,
+    UndoTree,
 }
 
 impl PanelKind {
@@ -27,6 +30,9 @@ pub fn svg_name(&self) -> &'static str {
             PanelKind::Search => LapceIcons::SEARCH,
             PanelKind::Problem => LapceIcons::PROBLEM,
             PanelKind::Debug => LapceIcons::DEBUG,
+            PanelKind::GitLog => LapceIcons::SCM_LOG,
+            PanelKind::Ports => LapceIcons::PORTS,
+            PanelKind::UndoTree => LapceIcons::UNDO_TREE,
         }
     }
 
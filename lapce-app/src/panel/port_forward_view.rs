@@ -0,0 +1,159 @@
+use std::rc::Rc;
+
+use floem::{
+    event::EventListener,
+    reactive::create_memo,
+    view::View,
+    views::{container, label, list, scroll, stack, Decorators},
+};
+
+use super::{kind::PanelKind, position::PanelPosition};
+use crate::{
+    app::clickable_icon,
+    config::{color::LapceColor, icon::LapceIcons},
+    port_forward::{ForwardedPort, PortForwardData},
+    text_input::text_input,
+    window_tab::{Focus, WindowTabData},
+};
+
+pub fn port_forward_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let port_forward = window_tab_data.port_forward.clone();
+    let config = port_forward.common.config;
+    let focus = port_forward.common.focus;
+    let error = port_forward.error;
+    let is_available = port_forward.is_available();
+
+    stack((
+        label(|| "Port forwarding is only available in SSH workspaces".to_string())
+            .style(move |s| {
+                s.padding(10.0)
+                    .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                    .apply_if(is_available, |s| s.hide())
+            }),
+        stack((
+            port_input(port_forward.clone()),
+            label(move || error.get().unwrap_or_default()).style(move |s| {
+                s.padding_horiz(10.0)
+                    .color(*config.get().get_color(LapceColor::LAPCE_ERROR))
+                    .apply_if(error.with(|e| e.is_none()), |s| s.hide())
+            }),
+            port_list(port_forward),
+        ))
+        .on_event(EventListener::PointerDown, move |_| {
+            focus.set(Focus::Panel(PanelKind::Ports));
+            false
+        })
+        .style(move |s| {
+            s.size_pct(100.0, 100.0)
+                .flex_col()
+                .apply_if(!is_available, |s| s.hide())
+        }),
+    ))
+    .style(|s| s.absolute().size_pct(100.0, 100.0).flex_col())
+}
+
+fn port_input(port_forward: PortForwardData) -> impl View {
+    let config = port_forward.common.config;
+    let focus = port_forward.common.focus;
+    let doc = port_forward.editor.view.doc;
+    let is_empty = create_memo(move |_| {
+        let doc = doc.get();
+        doc.buffer.with(|b| b.len() == 0)
+    });
+    let is_focused = move || focus.get() == Focus::Panel(PanelKind::Ports);
+
+    container(
+        stack((
+            text_input(port_forward.editor.clone(), is_focused)
+                .style(|s| s.width_pct(100.0)),
+            label(|| "Forward a port, e.g. 3000 or 8080:3000".to_string()).style(
+                move |s| {
+                    s.absolute()
+                        .padding_left(6.0)
+                        .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                        .apply_if(!is_empty.get(), |s| s.hide())
+                },
+            ),
+        ))
+        .style(move |s| {
+            s.width_pct(100.0)
+                .items_center()
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(*config.get().get_color(LapceColor::LAPCE_BORDER))
+        }),
+    )
+    .style(|s| s.width_pct(100.0).padding(10.0))
+}
+
+fn port_list(port_forward: PortForwardData) -> impl View {
+    let ports = port_forward.ports;
+
+    container({
+        scroll({
+            list(
+                move || ports.get(),
+                |port| port.clone(),
+                move |port| port_view(port_forward.clone(), port),
+            )
+            .style(|s| s.flex_col().width_pct(100.0).line_height(1.6))
+        })
+        .style(|s| s.absolute().size_pct(100.0, 100.0))
+    })
+    .style(|s| s.size_pct(100.0, 100.0))
+}
+
+fn port_view(port_forward: PortForwardData, port: ForwardedPort) -> impl View {
+    let config = port_forward.common.config;
+    let local = port.local;
+    let remote = port.remote;
+    let detected = port.detected;
+
+    stack((
+        stack((
+            label(move || format!("localhost:{local}"))
+                .style(|s| s.margin_right(6.0)),
+            label(move || format!("→ {remote}")).style(move |s| {
+                s.margin_right(6.0).apply_if(local == remote, |s| s.hide())
+            }),
+            label(|| "detected".to_string()).style(move |s| {
+                s.color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                    .apply_if(!detected, |s| s.hide())
+            }),
+        ))
+        .style(|s| s.flex_grow(1.0).flex_basis(0.0).min_width(0.0)),
+        stack((
+            {
+                let port_forward = port_forward.clone();
+                let port = port.clone();
+                clickable_icon(
+                    || LapceIcons::PORTS_OPEN,
+                    move || {
+                        port_forward.open_in_browser(&port);
+                    },
+                    || false,
+                    || false,
+                    config,
+                )
+                .style(|s| s.margin_right(4.0))
+            },
+            clickable_icon(
+                || LapceIcons::CLOSE,
+                move || {
+                    port_forward.remove(&port);
+                },
+                || false,
+                || false,
+                config,
+            ),
+        ))
+        .style(|s| s.margin_left(10.0)),
+    ))
+    .style(|s| s.width_pct(100.0).items_center().padding_horiz(10.0))
+    .hover_style(move |s| {
+        s.background(*config.get().get_color(LapceColor::PANEL_HOVERED_BACKGROUND))
+    })
+}
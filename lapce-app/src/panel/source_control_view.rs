@@ -18,7 +18,6 @@
     command::{CommandKind, InternalCommand, LapceCommand, LapceWorkbenchCommand},
     config::{color::LapceColor, icon::LapceIcons},
     editor::view::{cursor_caret, editor_view, CursorRender},
-    settings::checkbox,
     source_control::SourceControlData,
     window_tab::{Focus, WindowTabData},
 };
@@ -171,11 +170,27 @@ pub fn source_control_panel(
             },
         ))
         .style(|s| s.flex_col().width_pct(100.0).padding(10.0)),
+        {
+            let staged_diffs = source_control.staged_diffs;
+            stack((
+                panel_header("Staged Changes".to_string(), config),
+                file_diffs_view(source_control.clone(), true),
+            ))
+            .style(move |s| {
+                s.flex_col()
+                    .width_pct(100.0)
+                    .flex_grow(1.0)
+                    .flex_basis(0.0)
+                    .apply_if(staged_diffs.with(|diffs| diffs.is_empty()), |s| {
+                        s.hide()
+                    })
+            })
+        },
         stack((
             panel_header("Changes".to_string(), config),
-            file_diffs_view(source_control),
+            file_diffs_view(source_control, false),
         ))
-        .style(|s| s.flex_col().size_pct(100.0, 100.0)),
+        .style(|s| s.flex_col().width_pct(100.0).flex_grow(1.0).flex_basis(0.0)),
     ))
     .on_event(EventListener::PointerDown, move |_| {
         if focus.get_untracked() != Focus::Panel(PanelKind::SourceControl) {
@@ -186,8 +201,12 @@ pub fn source_control_panel(
     .style(|s| s.flex_col().size_pct(100.0, 100.0))
 }
 
-fn file_diffs_view(source_control: SourceControlData) -> impl View {
-    let file_diffs = source_control.file_diffs;
+fn file_diffs_view(source_control: SourceControlData, staged: bool) -> impl View {
+    let file_diffs = if staged {
+        source_control.staged_diffs
+    } else {
+        source_control.file_diffs
+    };
     let config = source_control.common.config;
     let workspace = source_control.common.workspace.clone();
     let panel_rect = create_rw_signal(Rect::ZERO);
@@ -195,7 +214,7 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
     let lapce_command = source_control.common.lapce_command;
     let internal_command = source_control.common.internal_command;
 
-    let view_fn = move |(path, (diff, checked)): (PathBuf, (FileDiff, bool))| {
+    let view_fn = move |(path, diff): (PathBuf, FileDiff)| {
         let diff_for_style = diff.clone();
         let full_path = path.clone();
         let diff_for_menu = diff.clone();
@@ -220,16 +239,6 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
             .to_string();
         let style_path = path.clone();
         stack((
-            checkbox(move || checked, config)
-                .on_click(move |_| {
-                    file_diffs.update(|diffs| {
-                        if let Some((_, checked)) = diffs.get_mut(&full_path) {
-                            *checked = !*checked;
-                        }
-                    });
-                    true
-                })
-                .hover_style(|s| s.cursor(CursorStyle::Pointer)),
             svg(move || config.get().file_svg(&path).0).style(move |s| {
                 let config = config.get();
                 let size = config.ui.icon_size() as f32;
@@ -246,8 +255,6 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
                     - 10.0
                     - size
                     - 6.0
-                    - size
-                    - 6.0
                     - 10.0
                     - size
                     - 6.0;
@@ -302,19 +309,35 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
         .on_event(EventListener::PointerDown, move |event| {
             let diff_for_menu = diff_for_menu.clone();
 
-            let discard = move || {
-                lapce_command.send(LapceCommand {
-                    kind: CommandKind::Workbench(
-                        LapceWorkbenchCommand::SourceControlDiscardTargetFileChanges,
-                    ),
-                    data: Some(serde_json::json!(diff_for_menu.clone())),
-                });
+            let command = move |cmd: LapceWorkbenchCommand| {
+                let diff_for_menu = diff_for_menu.clone();
+                move || {
+                    lapce_command.send(LapceCommand {
+                        kind: CommandKind::Workbench(cmd.clone()),
+                        data: Some(serde_json::json!(diff_for_menu.clone())),
+                    });
+                }
             };
 
             if let Event::PointerDown(pointer_event) = event {
                 if pointer_event.button.is_secondary() {
-                    let menu = Menu::new("")
-                        .entry(MenuItem::new("Discard Changes").action(discard));
+                    let menu = if staged {
+                        let unstage = command(
+                            LapceWorkbenchCommand::SourceControlUnstageTargetFile,
+                        );
+                        Menu::new("")
+                            .entry(MenuItem::new("Unstage Changes").action(unstage))
+                    } else {
+                        let stage = command(
+                            LapceWorkbenchCommand::SourceControlStageTargetFile,
+                        );
+                        let discard = command(
+                            LapceWorkbenchCommand::SourceControlDiscardTargetFileChanges,
+                        );
+                        Menu::new("")
+                            .entry(MenuItem::new("Stage Changes").action(stage))
+                            .entry(MenuItem::new("Discard Changes").action(discard))
+                    };
                     show_context_menu(menu, None);
                 }
             }
@@ -339,9 +362,7 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
         scroll({
             list(
                 move || file_diffs.get(),
-                |(path, (diff, checked))| {
-                    (path.to_path_buf(), diff.clone(), *checked)
-                },
+                |(path, diff)| (path.to_path_buf(), diff.clone()),
                 view_fn,
             )
             .style(|s| s.line_height(1.6).flex_col().width_pct(100.0))
@@ -0,0 +1,197 @@
+use std::rc::Rc;
+
+use floem::{
+    event::EventListener,
+    reactive::create_memo,
+    view::View,
+    views::{container, label, list, scroll, stack, Decorators},
+};
+use lapce_core::buffer::undo_tree::UndoTreeNode;
+
+use super::{kind::PanelKind, position::PanelPosition};
+use crate::{
+    app::clickable_icon,
+    config::{color::LapceColor, icon::LapceIcons},
+    text_input::text_input,
+    undo_tree::{undo_group_time, UndoTreeData},
+    window_tab::{Focus, WindowTabData},
+};
+
+pub fn undo_tree_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let undo_tree = window_tab_data.undo_tree.clone();
+    let config = undo_tree.common.config;
+    let focus = undo_tree.common.focus;
+    let error = undo_tree.error;
+    let doc = undo_tree.doc;
+
+    stack((
+        label(|| "Open a file to see the history of its changes".to_string()).style(
+            move |s| {
+                s.padding(10.0)
+                    .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                    .apply_if(doc.with(|doc| doc.is_some()), |s| s.hide())
+            },
+        ),
+        stack((
+            time_travel_input(undo_tree.clone()),
+            label(move || error.get().unwrap_or_default()).style(move |s| {
+                s.padding_horiz(10.0)
+                    .color(*config.get().get_color(LapceColor::LAPCE_ERROR))
+                    .apply_if(error.with(|e| e.is_none()), |s| s.hide())
+            }),
+            node_list(undo_tree),
+        ))
+        .on_event(EventListener::PointerDown, move |_| {
+            focus.set(Focus::Panel(PanelKind::UndoTree));
+            false
+        })
+        .style(move |s| {
+            s.size_pct(100.0, 100.0)
+                .flex_col()
+                .apply_if(doc.with(|doc| doc.is_none()), |s| s.hide())
+        }),
+    ))
+    .style(|s| s.absolute().size_pct(100.0, 100.0).flex_col())
+}
+
+fn time_travel_input(undo_tree: UndoTreeData) -> impl View {
+    let config = undo_tree.common.config;
+    let focus = undo_tree.common.focus;
+    let doc = undo_tree.editor.view.doc;
+    let is_empty = create_memo(move |_| {
+        let doc = doc.get();
+        doc.buffer.with(|b| b.len() == 0)
+    });
+    let is_focused = move || focus.get() == Focus::Panel(PanelKind::UndoTree);
+
+    container(
+        stack((
+            text_input(undo_tree.editor.clone(), is_focused)
+                .style(|s| s.width_pct(100.0)),
+            label(|| "Go earlier or later, e.g. 10m or later 3".to_string()).style(
+                move |s| {
+                    s.absolute()
+                        .padding_left(6.0)
+                        .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                        .apply_if(!is_empty.get(), |s| s.hide())
+                },
+            ),
+        ))
+        .style(move |s| {
+            s.width_pct(100.0)
+                .items_center()
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(*config.get().get_color(LapceColor::LAPCE_BORDER))
+        }),
+    )
+    .style(|s| s.width_pct(100.0).padding(10.0))
+}
+
+fn node_list(undo_tree: UndoTreeData) -> impl View {
+    let doc = undo_tree.doc;
+    let nodes = create_memo(move |_| {
+        doc.with(|doc| {
+            doc.as_ref()
+                .map(|doc| doc.buffer.with(|buffer| buffer.undo_tree()))
+                .unwrap_or_default()
+        })
+    });
+    let current = create_memo(move |_| {
+        doc.with(|doc| {
+            doc.as_ref()
+                .map(|doc| doc.buffer.with(|buffer| buffer.current_undo_group()))
+        })
+    });
+
+    container({
+        scroll({
+            list(
+                // Newest first, like the other history lists
+                move || nodes.get().into_iter().rev(),
+                |node| (node.group, node.depth, node.applied),
+                move |node| {
+                    node_view(undo_tree.clone(), node, move || current.get())
+                },
+            )
+            .style(|s| s.flex_col().width_pct(100.0).line_height(1.6))
+        })
+        .style(|s| s.absolute().size_pct(100.0, 100.0))
+    })
+    .style(|s| s.size_pct(100.0, 100.0))
+}
+
+fn node_view(
+    undo_tree: UndoTreeData,
+    node: UndoTreeNode,
+    current: impl Fn() -> Option<usize> + 'static,
+) -> impl View {
+    let config = undo_tree.common.config;
+    let group = node.group;
+    let applied = node.applied;
+    let description = match node.time {
+        _ if group == 0 => "initial content".to_string(),
+        Some(time) => format!("{group}  {}", undo_group_time(time)),
+        None => group.to_string(),
+    };
+
+    stack((
+        label(move || description.clone()).style(move |s| {
+            s.flex_grow(1.0)
+                .flex_basis(0.0)
+                .min_width(0.0)
+                .padding_left(node.depth as f32 * 12.0)
+                .apply_if(!applied, |s| {
+                    s.color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                })
+        }),
+        stack((
+            {
+                let undo_tree = undo_tree.clone();
+                clickable_icon(
+                    || LapceIcons::UNDO_TREE_PREVIEW,
+                    move || {
+                        undo_tree.preview(group);
+                    },
+                    || false,
+                    || false,
+                    config,
+                )
+                .style(|s| s.margin_right(4.0))
+            },
+            {
+                let undo_tree = undo_tree.clone();
+                clickable_icon(
+                    || LapceIcons::UNDO_TREE_RESTORE,
+                    move || {
+                        undo_tree.restore(group);
+                    },
+                    || false,
+                    || false,
+                    config,
+                )
+            },
+        ))
+        .style(|s| s.margin_left(10.0)),
+    ))
+    .on_click(move |_| {
+        undo_tree.go_to(group);
+        true
+    })
+    .style(move |s| {
+        s.width_pct(100.0)
+            .items_center()
+            .padding_horiz(10.0)
+            .apply_if(current() == Some(group), |s| {
+                s.background(
+                    *config.get().get_color(LapceColor::PANEL_CURRENT_BACKGROUND),
+                )
+            })
+    })
+    .hover_style(move |s| {
+        s.background(*config.get().get_color(LapceColor::PANEL_HOVERED_BACKGROUND))
+    })
+}
@@ -11,13 +11,16 @@
 
 use super::{
     debug_view::debug_panel,
+    git_log_view::git_log_panel,
     global_search_view::global_search_panel,
     kind::PanelKind,
     plugin_view::plugin_panel,
+    port_forward_view::port_forward_panel,
     position::{PanelContainerPosition, PanelPosition},
     problem_view::problem_panel,
     source_control_view::source_control_panel,
     terminal_view::terminal_panel,
+    undo_tree_view::undo_tree_panel,
 };
 use crate::{
     app::clickable_icon,
@@ -311,6 +314,16 @@ fn panel_view(
                 PanelKind::Debug => {
                     container_box(debug_panel(window_tab_data.clone(), position))
                 }
+                PanelKind::GitLog => {
+                    container_box(git_log_panel(window_tab_data.clone(), position))
+                }
+                PanelKind::Ports => container_box(port_forward_panel(
+                    window_tab_data.clone(),
+                    position,
+                )),
+                PanelKind::UndoTree => {
+                    container_box(undo_tree_panel(window_tab_data.clone(), position))
+                }
             };
             view.style(|s| s.size_pct(100.0, 100.0))
         },
@@ -363,6 +376,9 @@ fn panel_picker(
                 PanelKind::Search => LapceIcons::SEARCH,
                 PanelKind::Problem => LapceIcons::PROBLEM,
                 PanelKind::Debug => LapceIcons::DEBUG_ALT,
+                PanelKind::GitLog => LapceIcons::SCM_LOG,
+                PanelKind::Ports => LapceIcons::PORTS,
+                PanelKind::UndoTree => LapceIcons::UNDO_TREE,
             };
             let is_active = {
                 let window_tab_data = window_tab_data.clone();
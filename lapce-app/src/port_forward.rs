@@ -0,0 +1,285 @@
+use std::rc::Rc;
+
+use floem::{
+    ext_event::create_ext_action,
+    keyboard::ModifiersState,
+    reactive::{RwSignal, Scope},
+};
+use lapce_core::{command::FocusCommand, mode::Mode};
+use lapce_xi_rope::Rope;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{
+    command::{CommandExecuted, CommandKind, InternalCommand},
+    editor::EditorData,
+    id::EditorId,
+    keypress::{condition::Condition, KeyPressFocus},
+    proxy::ssh::SshRemote,
+    window_tab::CommonData,
+    workspace::LapceWorkspaceType,
+};
+
+/// A local port that's forwarded to a port on the remote host
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ForwardedPort {
+    pub local: u16,
+    pub remote: u16,
+    /// If the port was found in terminal output instead of added by hand
+    pub detected: bool,
+}
+
+/// Parse a port to forward, given either as `remote` or as `local:remote`
+pub fn parse_port_spec(spec: &str) -> Option<(u16, u16)> {
+    let spec = spec.trim();
+    match spec.split_once(':') {
+        Some((local, remote)) => {
+            Some((local.trim().parse().ok()?, remote.trim().parse().ok()?))
+        }
+        None => {
+            let port = spec.parse().ok()?;
+            Some((port, port))
+        }
+    }
+}
+
+/// Find the ports that a program says it's listening on, e.g. in
+/// `Listening on http://localhost:3000`
+pub fn detect_ports(text: &str) -> Vec<u16> {
+    static REGEX: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?:localhost|127\.0\.0\.1|0\.0\.0\.0|\[::1?\]):(\d{2,5})\b")
+            .unwrap()
+    });
+    let mut ports = Vec::new();
+    for captures in REGEX.captures_iter(text) {
+        if let Some(port) = captures.get(1).and_then(|m| m.as_str().parse().ok()) {
+            if port != 0 && !ports.contains(&port) {
+                ports.push(port);
+            }
+        }
+    }
+    ports
+}
+
+#[derive(Clone)]
+pub struct PortForwardData {
+    pub ports: RwSignal<im::Vector<ForwardedPort>>,
+    /// The local ports that are being forwarded, but aren't yet
+    pending: RwSignal<im::HashSet<u16>>,
+    /// The remote ports the user stopped forwarding, which aren't forwarded
+    /// again when they're detected
+    dismissed: RwSignal<im::HashSet<u16>>,
+    /// The input for adding a port by hand
+    pub editor: EditorData,
+    /// The error of the last forward that failed
+    pub error: RwSignal<Option<String>>,
+    pub common: Rc<CommonData>,
+}
+
+impl KeyPressFocus for PortForwardData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::PanelFocus | Condition::ListFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &crate::command::LapceCommand,
+        count: Option<usize>,
+        mods: ModifiersState,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Focus(FocusCommand::ListSelect) => {
+                self.add_from_input();
+                CommandExecuted::Yes
+            }
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                self.editor.run_command(command, count, mods)
+            }
+            _ => CommandExecuted::No,
+        }
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.editor.receive_char(c);
+    }
+}
+
+impl PortForwardData {
+    pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
+        Self {
+            ports: cx.create_rw_signal(im::Vector::new()),
+            pending: cx.create_rw_signal(im::HashSet::new()),
+            dismissed: cx.create_rw_signal(im::HashSet::new()),
+            editor: EditorData::new_local(cx, EditorId::next(), common.clone()),
+            error: cx.create_rw_signal(None),
+            common,
+        }
+    }
+
+    fn ssh(&self) -> Option<SshRemote> {
+        match &self.common.workspace.kind {
+            LapceWorkspaceType::RemoteSSH(ssh) => {
+                Some(SshRemote { ssh: ssh.clone() })
+            }
+            _ => None,
+        }
+    }
+
+    /// If ports can be forwarded in the workspace
+    pub fn is_available(&self) -> bool {
+        self.ssh().is_some()
+    }
+
+    fn add_from_input(&self) {
+        let doc = self.editor.view.doc.get_untracked();
+        let spec = doc.buffer.with_untracked(|buffer| buffer.to_string());
+        match parse_port_spec(&spec) {
+            Some((local, remote)) => {
+                doc.reload(Rope::from(""), true);
+                self.forward(local, remote, false);
+            }
+            None => {
+                self.error.set(Some(format!(
+                    "\"{}\" isn't a port, use either `remote` or `local:remote`",
+                    spec.trim()
+                )));
+            }
+        }
+    }
+
+    /// Forward a local port to a port on the remote host
+    pub fn forward(&self, local: u16, remote: u16, detected: bool) {
+        let ssh = if let Some(ssh) = self.ssh() {
+            ssh
+        } else {
+            return;
+        };
+        if self
+            .ports
+            .with_untracked(|ports| ports.iter().any(|p| p.local == local))
+            || self
+                .pending
+                .with_untracked(|pending| pending.contains(&local))
+        {
+            return;
+        }
+        self.pending.update(|pending| {
+            pending.insert(local);
+        });
+        if !detected {
+            self.dismissed.update(|dismissed| {
+                dismissed.remove(&remote);
+            });
+        }
+
+        let ports = self.ports;
+        let pending = self.pending;
+        let error = self.error;
+        let send = create_ext_action(self.common.scope, move |result| {
+            pending.update(|pending| {
+                pending.remove(&local);
+            });
+            match result {
+                Ok(()) => {
+                    error.set(None);
+                    ports.update(|ports| {
+                        if !ports.iter().any(|p| p.local == local) {
+                            ports.push_back(ForwardedPort {
+                                local,
+                                remote,
+                                detected,
+                            });
+                        }
+                    });
+                }
+                Err(err) => {
+                    error.set(Some(format!(
+                        "Failed to forward port {remote}: {err}"
+                    )));
+                }
+            }
+        });
+        std::thread::spawn(move || {
+            send(ssh.forward_port(local, remote).map_err(|e| e.to_string()));
+        });
+    }
+
+    /// Forward a port that a program in the terminal said it's listening on
+    pub fn port_detected(&self, port: u16) {
+        if self
+            .ports
+            .with_untracked(|ports| ports.iter().any(|p| p.remote == port))
+            || self
+                .dismissed
+                .with_untracked(|dismissed| dismissed.contains(&port))
+        {
+            return;
+        }
+        self.forward(port, port, true);
+    }
+
+    /// Stop forwarding the port, which is then no longer forwarded when it's
+    /// detected
+    pub fn remove(&self, port: &ForwardedPort) {
+        self.ports.update(|ports| ports.retain(|p| p != port));
+        self.dismissed.update(|dismissed| {
+            dismissed.insert(port.remote);
+        });
+        if let Some(ssh) = self.ssh() {
+            let port = port.clone();
+            std::thread::spawn(move || {
+                if let Err(err) = ssh.cancel_port(port.local, port.remote) {
+                    tracing::error!("{:?}", err);
+                }
+            });
+        }
+    }
+
+    /// Set up the forwards again, e.g. after the ssh connection was dropped
+    pub fn restore(&self) {
+        let ports = self.ports.get_untracked();
+        self.ports.set(im::Vector::new());
+        for port in ports {
+            self.forward(port.local, port.remote, port.detected);
+        }
+    }
+
+    pub fn open_in_browser(&self, port: &ForwardedPort) {
+        self.common
+            .internal_command
+            .send(InternalCommand::OpenWebUri {
+                uri: format!("http://localhost:{}", port.local),
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_port_spec() {
+        assert_eq!(parse_port_spec("3000"), Some((3000, 3000)));
+        assert_eq!(parse_port_spec(" 8080:3000 "), Some((8080, 3000)));
+        assert_eq!(parse_port_spec("abc"), None);
+        assert_eq!(parse_port_spec("70000"), None);
+        assert_eq!(parse_port_spec("8080:"), None);
+    }
+
+    #[test]
+    fn test_detect_ports() {
+        assert_eq!(
+            detect_ports("Listening on http://localhost:3000/ and 0.0.0.0:8080"),
+            vec![3000, 8080]
+        );
+        assert_eq!(detect_ports("Serving on [::]:5173"), vec![5173]);
+        assert_eq!(detect_ports("127.0.0.1:4000 127.0.0.1:4000"), vec![4000]);
+        assert_eq!(detect_ports("took 12:30 minutes"), Vec::<u16>::new());
+    }
+}
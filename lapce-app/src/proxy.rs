@@ -1,4 +1,6 @@
-use std::{collections::HashMap, path::PathBuf, process::Command, sync::Arc};
+use std::{
+    collections::HashMap, path::PathBuf, process::Command, sync::Arc, time::Duration,
+};
 
 use crossbeam_channel::Sender;
 use floem::{ext_event::create_signal_from_channel, reactive::ReadSignal};
@@ -6,23 +8,51 @@
 use lapce_rpc::{
     core::{CoreHandler, CoreNotification, CoreRpcHandler},
     plugin::VoltID,
-    proxy::{ProxyRpcHandler, ProxyStatus},
+    proxy::{ProxyRpc, ProxyRpcHandler, ProxyStatus},
     terminal::TermId,
+    RpcError,
 };
 use lsp_types::Url;
 use tracing::error;
 
-use self::{remote::start_remote, ssh::SshRemote};
+use self::{
+    command::CommandRemote,
+    remote::{start_remote, RemoteExit},
+    socket::connect_socket,
+    ssh::SshRemote,
+};
 use crate::{
+    config::LapceConfig,
     terminal::event::TermEvent,
     workspace::{LapceWorkspace, LapceWorkspaceType},
 };
 
+mod command;
 mod remote;
-mod ssh;
+mod socket;
+pub mod ssh;
 #[cfg(windows)]
 mod wsl;
 
+/// How the proxy gets installed on a remote host when it's missing or outdated
+#[derive(Clone, Debug, Default)]
+pub struct ProxyInstall {
+    /// Upload a local binary instead of downloading a release
+    pub upload: bool,
+    /// Where to find `lapce-proxy-<os>-<arch>` binaries to upload
+    pub directory: Option<PathBuf>,
+}
+
+impl ProxyInstall {
+    pub fn from_config(config: &LapceConfig) -> Self {
+        let directory = config.core.remote_proxy_directory.trim();
+        Self {
+            upload: config.core.upload_remote_proxy,
+            directory: (!directory.is_empty()).then(|| PathBuf::from(directory)),
+        }
+    }
+}
+
 pub struct Proxy {
     pub tx: Sender<CoreNotification>,
     pub term_tx: Sender<(TermId, TermEvent)>,
@@ -46,6 +76,7 @@ pub fn new_proxy(
     workspace: Arc<LapceWorkspace>,
     disabled_volts: Vec<VoltID>,
     plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
+    proxy_install: ProxyInstall,
     term_tx: Sender<(TermId, TermEvent)>,
 ) -> ProxyData {
     let proxy_rpc = ProxyRpcHandler::new();
@@ -58,15 +89,19 @@ pub fn new_proxy(
             core_rpc.notification(CoreNotification::ProxyStatus {
                 status: ProxyStatus::Connecting,
             });
-            proxy_rpc.initialize(
-                workspace.path.clone(),
-                disabled_volts,
-                plugin_configurations,
-                1,
-                1,
-            );
-
-            match &workspace.kind {
+            let initialize = || {
+                proxy_rpc.initialize(
+                    workspace.path.clone(),
+                    workspace.folders(),
+                    disabled_volts.clone(),
+                    plugin_configurations.clone(),
+                    1,
+                    1,
+                );
+            };
+            initialize();
+
+            let result = match &workspace.kind {
                 LapceWorkspaceType::Local => {
                     let core_rpc = core_rpc.clone();
                     let proxy_rpc = proxy_rpc.clone();
@@ -75,15 +110,37 @@ pub fn new_proxy(
                         let proxy_rpc = dispatcher.proxy_rpc.clone();
                         proxy_rpc.mainloop(&mut dispatcher);
                     });
+                    Ok(())
                 }
                 LapceWorkspaceType::RemoteSSH(ssh) => {
-                    if let Err(e) = start_remote(
-                        SshRemote { ssh: ssh.clone() },
-                        core_rpc.clone(),
-                        proxy_rpc.clone(),
-                    ) {
-                        error!("Failed to start SSH remote: {e}");
-                    }
+                    keep_remote(&core_rpc, &proxy_rpc, initialize, || {
+                        start_remote(
+                            SshRemote { ssh: ssh.clone() },
+                            &proxy_install,
+                            core_rpc.clone(),
+                            proxy_rpc.clone(),
+                        )
+                    })
+                    .map_err(|e| format!("Failed to start SSH remote: {e}"))
+                }
+                LapceWorkspaceType::RemoteCommand(command) => {
+                    keep_remote(&core_rpc, &proxy_rpc, initialize, || {
+                        start_remote(
+                            CommandRemote {
+                                command: command.clone(),
+                            },
+                            &proxy_install,
+                            core_rpc.clone(),
+                            proxy_rpc.clone(),
+                        )
+                    })
+                    .map_err(|e| format!("Failed to start command remote: {e}"))
+                }
+                LapceWorkspaceType::RemoteProxy(host) => {
+                    keep_remote(&core_rpc, &proxy_rpc, initialize, || {
+                        connect_socket(host, core_rpc.clone(), proxy_rpc.clone())
+                    })
+                    .map_err(|e| format!("Failed to connect to remote proxy: {e:#}"))
                 }
                 #[cfg(windows)]
                 LapceWorkspaceType::RemoteWSL => {
@@ -92,16 +149,28 @@ pub fn new_proxy(
                         .ok()
                         .and_then(|d| d.into_iter().find(|distro| distro.default))
                         .map(|d| d.name);
-                    if let Some(distro) = distro {
-                        if let Err(e) = start_remote(
-                            WslRemote { distro },
-                            core_rpc.clone(),
-                            proxy_rpc.clone(),
-                        ) {
-                            error!("Failed to start SSH remote: {e}");
+                    match distro {
+                        Some(distro) => {
+                            keep_remote(&core_rpc, &proxy_rpc, initialize, || {
+                                start_remote(
+                                    WslRemote {
+                                        distro: distro.clone(),
+                                    },
+                                    &proxy_install,
+                                    core_rpc.clone(),
+                                    proxy_rpc.clone(),
+                                )
+                            })
+                            .map_err(|e| format!("Failed to start WSL remote: {e}"))
                         }
+                        None => Err("Can't find the default WSL distro".to_string()),
                     }
                 }
+            };
+            if let Err(message) = result {
+                core_rpc.notification(CoreNotification::ProxyStatus {
+                    status: ProxyStatus::Error(message),
+                });
             }
         });
     }
@@ -127,6 +196,65 @@ pub fn new_proxy(
     }
 }
 
+const MAX_RECONNECT_ATTEMPTS: usize = 10;
+
+/// Keeps a remote proxy connected, reconnecting with a backoff when the
+/// connection drops, until the proxy is shut down.
+fn keep_remote(
+    core_rpc: &CoreRpcHandler,
+    proxy_rpc: &ProxyRpcHandler,
+    initialize: impl Fn(),
+    connect: impl Fn() -> anyhow::Result<RemoteExit>,
+) -> anyhow::Result<()> {
+    let mut has_connected = false;
+    let mut attempt = 0;
+    loop {
+        let exit = connect();
+        // The requests in flight on the lost connection are never answered
+        if !matches!(exit, Ok(RemoteExit::Shutdown)) {
+            proxy_rpc.fail_pending_requests("Lost the connection to the remote");
+        }
+        match exit {
+            Ok(RemoteExit::Shutdown) => return Ok(()),
+            Ok(RemoteExit::Dropped) => {
+                has_connected = true;
+                attempt = 0;
+            }
+            // there's nothing to reconnect to if it never connected
+            Err(e) if !has_connected => return Err(e),
+            Err(e) if attempt >= MAX_RECONNECT_ATTEMPTS => {
+                return Err(e.context("Lost the connection to the remote"));
+            }
+            Err(e) => {
+                error!("failed to reconnect to the remote: {e}");
+            }
+        }
+
+        attempt += 1;
+        core_rpc.notification(CoreNotification::ProxyStatus {
+            status: ProxyStatus::Reconnecting(attempt),
+        });
+        std::thread::sleep(Duration::from_secs(1 << attempt.min(5)));
+
+        // What was sent in the meantime was meant for the lost proxy, the
+        // documents are opened again once reconnected
+        while let Ok(msg) = proxy_rpc.rx().try_recv() {
+            match msg {
+                ProxyRpc::Shutdown => return Ok(()),
+                ProxyRpc::Request(id, _) => proxy_rpc.handle_response(
+                    id,
+                    Err(RpcError {
+                        code: 0,
+                        message: "Lost the connection to the remote".to_string(),
+                    }),
+                ),
+                ProxyRpc::Notification(_) => {}
+            }
+        }
+        initialize();
+    }
+}
+
 impl CoreHandler for Proxy {
     fn handle_notification(&mut self, rpc: lapce_rpc::core::CoreNotification) {
         if let CoreNotification::UpdateTerminal { term_id, content } = &rpc {
@@ -0,0 +1,62 @@
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use anyhow::{anyhow, Result};
+use tracing::debug;
+
+use super::{new_command, remote::Remote};
+
+/// A remote that runs everything through a user provided command prefix,
+/// such as `docker exec -i container` or `kubectl exec -i pod --`.
+///
+/// The prefix is split on whitespace and has to keep stdin open, since the
+/// proxy talks over it. Arguments are passed to `sh -c 'eval "$*"'` so that
+/// they are interpreted by the remote shell like they would be over ssh.
+/// When the prefix already ends with `-c`, e.g. plain `sh -c`, it is used
+/// as that shell instead.
+pub struct CommandRemote {
+    pub command: String,
+}
+
+impl Remote for CommandRemote {
+    fn upload_file(&self, local: impl AsRef<Path>, remote: &str) -> Result<()> {
+        // there's no scp equivalent for an arbitrary command,
+        // so stream the file to the remote's stdin instead
+        let mut child = self
+            .command_builder()
+            .args(["cat", ">", remote])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("can't find stdin"))?;
+        std::io::copy(&mut std::fs::File::open(local)?, &mut stdin)?;
+        drop(stdin);
+
+        let output = child.wait_with_output()?;
+        debug!("{}", String::from_utf8_lossy(&output.stderr));
+        debug!("{}", String::from_utf8_lossy(&output.stdout));
+
+        if !output.status.success() {
+            return Err(anyhow!("failed to upload file to {remote}"));
+        }
+
+        Ok(())
+    }
+
+    fn command_builder(&self) -> Command {
+        let args = self.command.split_whitespace().collect::<Vec<_>>();
+        let mut cmd = new_command(args.first().copied().unwrap_or("sh"));
+        cmd.args(args.iter().skip(1));
+        if args.last() != Some(&"-c") {
+            cmd.args(["sh", "-c"]);
+        }
+        cmd.args(["eval \"$*\"", "sh"]);
+        cmd
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
-    io::{BufReader, Write},
-    path::Path,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
     process::{Command, Stdio},
 };
 
@@ -12,8 +12,11 @@
     proxy::{ProxyRpc, ProxyRpcHandler},
     stdio_transport, RpcMessage,
 };
+use sha2::{Digest, Sha256};
 use thiserror::Error;
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
+
+use super::ProxyInstall;
 
 const UNIX_PROXY_SCRIPT: &[u8] = include_bytes!("../../../extra/proxy.sh");
 const WINDOWS_PROXY_SCRIPT: &[u8] = include_bytes!("../../../extra/proxy.ps1");
@@ -68,11 +71,22 @@ fn home_dir(&self) -> Result<String> {
     fn command_builder(&self) -> Command;
 }
 
+/// How a connection to a remote proxy has ended
+pub enum RemoteExit {
+    /// The proxy was shut down from our side
+    Shutdown,
+    /// The connection was lost
+    Dropped,
+}
+
+/// Connects to the proxy on the remote host, installing it first if needed,
+/// and returns once the connection has ended.
 pub fn start_remote(
     remote: impl Remote,
+    install: &ProxyInstall,
     core_rpc: CoreRpcHandler,
     proxy_rpc: ProxyRpcHandler,
-) -> Result<()> {
+) -> Result<RemoteExit> {
     // Note about platforms:
     // Windows can use either cmd.exe, powershell.exe or pwsh.exe as
     // SSH shell, syntax logic varies significantly that's why we bet on
@@ -84,11 +98,13 @@ pub fn start_remote(
     // shells retain similar syntax, although shells like Nushell might not
     // work (hopefully no one uses it as login shell)
     use HostPlatform::*;
-    let (platform, architecture) = host_specification(&remote).unwrap();
+    let (platform, architecture) = host_specification(&remote)?;
 
     if platform == UnknownOS || architecture == HostArchitecture::UnknownArch {
         error!("detected remote host: {platform}/{architecture}");
-        return Err(anyhow!("Unknown OS and/or architecture"));
+        return Err(anyhow!(
+            "Unknown OS and/or architecture of the remote host: {platform}/{architecture}"
+        ));
     }
 
     // ! Below paths have to be synced with what is
@@ -112,28 +128,61 @@ pub fn start_remote(
         _ => format!("{remote_proxy_path}/lapce"),
     };
 
-    if !remote
-        .command_builder()
-        .args([&remote_proxy_file, "--version"])
-        .output()
-        .map(|output| {
-            if meta::VERSION == "debug" {
-                String::from_utf8_lossy(&output.stdout).starts_with("Lapce-proxy")
-            } else {
-                String::from_utf8_lossy(&output.stdout)
-                    == format!("Lapce-proxy {}", meta::VERSION)
+    if install.upload {
+        // Pin the remote proxy to the exact binary we have locally
+        let local_proxy_file = local_proxy_file(install, &platform, &architecture)?;
+        let checksum = file_checksum(&local_proxy_file)?;
+        let installed = remote_proxy_matches(&remote, &remote_proxy_file)
+            && remote_checksum(&remote, &platform, &remote_proxy_file).as_deref()
+                == Some(checksum.as_str());
+        if !installed {
+            upload_proxy(
+                &remote,
+                &platform,
+                &local_proxy_file,
+                &remote_proxy_path,
+                &remote_proxy_file,
+            )?;
+            if remote_checksum(&remote, &platform, &remote_proxy_file).as_deref()
+                != Some(checksum.as_str())
+            {
+                return Err(anyhow!(
+                    "The checksum of the proxy uploaded to {remote_proxy_file} doesn't match {}",
+                    local_proxy_file.display()
+                ));
             }
-        })
-        .unwrap_or(false)
-    {
-        download_remote(
+        }
+    } else if !remote_proxy_matches(&remote, &remote_proxy_file) {
+        let checksum = download_remote(
             &remote,
             &platform,
             &architecture,
             &remote_proxy_path,
             &remote_proxy_file,
         )?;
-    };
+        match checksum {
+            Some(checksum) => {
+                if remote_checksum(&remote, &platform, &remote_proxy_file)
+                    .as_deref()
+                    != Some(checksum.as_str())
+                {
+                    return Err(anyhow!(
+                        "The checksum of the proxy installed to {remote_proxy_file} doesn't match the release"
+                    ));
+                }
+            }
+            None => warn!(
+                "no published checksum for Lapce proxy {}, the proxy installed to {remote_proxy_file} isn't verified",
+                proxy_release_version()
+            ),
+        }
+        if !remote_proxy_matches(&remote, &remote_proxy_file) {
+            return Err(anyhow!(
+                "Failed to install Lapce proxy {} on the remote host",
+                meta::VERSION
+            ));
+        }
+    }
 
     debug!("remote proxy path: {remote_proxy_path}");
 
@@ -167,68 +216,96 @@ pub fn start_remote(
     );
     debug!("process id: {}", child.id());
 
+    connect_transport(stdin, stdout, core_rpc, proxy_rpc, move || {
+        let _ = child.kill();
+        let _ = child.wait();
+    })
+}
+
+/// Exchanges messages with a proxy until the connection ends. `close` is
+/// called to end the connection from our side.
+pub fn connect_transport(
+    writer: impl Write + Send + 'static,
+    reader: impl BufRead + Send + 'static,
+    core_rpc: CoreRpcHandler,
+    proxy_rpc: ProxyRpcHandler,
+    close: impl FnOnce() + Send + 'static,
+) -> Result<RemoteExit> {
     let (writer_tx, writer_rx) = crossbeam_channel::unbounded();
     let (reader_tx, reader_rx) = crossbeam_channel::unbounded();
-    stdio_transport(stdin, writer_rx, stdout, reader_tx);
+    stdio_transport(writer, writer_rx, reader, reader_tx);
 
+    let (dropped_tx, dropped_rx) = crossbeam_channel::bounded::<()>(1);
     let local_proxy_rpc = proxy_rpc.clone();
     let local_writer_tx = writer_tx.clone();
-    std::thread::spawn(move || {
-        for msg in local_proxy_rpc.rx() {
-            match msg {
-                ProxyRpc::Request(id, rpc) => {
-                    let _ = local_writer_tx.send(RpcMessage::Request(id, rpc));
-                }
-                ProxyRpc::Notification(rpc) => {
-                    let _ = local_writer_tx.send(RpcMessage::Notification(rpc));
-                }
-                ProxyRpc::Shutdown => {
-                    let _ = child.kill();
-                    let _ = child.wait();
-                    return;
+    let writer = std::thread::spawn(move || -> RemoteExit {
+        let exit = loop {
+            crossbeam_channel::select! {
+                recv(local_proxy_rpc.rx()) -> msg => match msg {
+                    Ok(ProxyRpc::Request(id, rpc)) => {
+                        let _ = local_writer_tx.send(RpcMessage::Request(id, rpc));
+                    }
+                    Ok(ProxyRpc::Notification(rpc)) => {
+                        let _ =
+                            local_writer_tx.send(RpcMessage::Notification(rpc));
+                    }
+                    Ok(ProxyRpc::Shutdown) | Err(_) => {
+                        break RemoteExit::Shutdown;
+                    }
+                },
+                // stop taking messages that are meant for the next connection
+                recv(dropped_rx) -> _ => {
+                    break RemoteExit::Dropped;
                 }
             }
-        }
+        };
+        close();
+        exit
     });
 
-    std::thread::spawn(move || {
-        for msg in reader_rx {
-            match msg {
-                RpcMessage::Request(id, req) => {
-                    let writer_tx = writer_tx.clone();
-                    let core_rpc = core_rpc.clone();
-                    std::thread::spawn(move || match core_rpc.request(req) {
-                        Ok(resp) => {
-                            let _ = writer_tx.send(RpcMessage::Response(id, resp));
-                        }
-                        Err(e) => {
-                            let _ = writer_tx.send(RpcMessage::Error(id, e));
-                        }
-                    });
-                }
-                RpcMessage::Notification(n) => {
-                    core_rpc.notification(n);
-                }
-                RpcMessage::Response(id, resp) => {
-                    proxy_rpc.handle_response(id, Ok(resp));
-                }
-                RpcMessage::Error(id, err) => {
-                    proxy_rpc.handle_response(id, Err(err));
-                }
+    for msg in reader_rx {
+        match msg {
+            RpcMessage::Request(id, req) => {
+                let writer_tx = writer_tx.clone();
+                let core_rpc = core_rpc.clone();
+                std::thread::spawn(move || match core_rpc.request(req) {
+                    Ok(resp) => {
+                        let _ = writer_tx.send(RpcMessage::Response(id, resp));
+                    }
+                    Err(e) => {
+                        let _ = writer_tx.send(RpcMessage::Error(id, e));
+                    }
+                });
+            }
+            RpcMessage::Notification(n) => {
+                core_rpc.notification(n);
+            }
+            RpcMessage::Response(id, resp) => {
+                proxy_rpc.handle_response(id, Ok(resp));
+            }
+            RpcMessage::Error(id, err) => {
+                proxy_rpc.handle_response(id, Err(err));
             }
         }
-    });
+    }
 
-    Ok(())
+    // The proxy's output has ended, either because it was shut down or
+    // because the connection was lost
+    let _ = dropped_tx.send(());
+    writer
+        .join()
+        .map_err(|_| anyhow!("the proxy writer thread panicked"))
 }
 
+/// Installs the proxy release on the remote host, and returns the checksum
+/// the installed proxy should have, if it's known
 fn download_remote(
     remote: &impl Remote,
     platform: &HostPlatform,
     architecture: &HostArchitecture,
     remote_proxy_path: &str,
     remote_proxy_file: &str,
-) -> Result<()> {
+) -> Result<Option<String>> {
     use base64::{engine::general_purpose, Engine as _};
 
     let script_install = match platform {
@@ -265,11 +342,7 @@ fn download_remote(
         _ => {
             let proxy_script = general_purpose::STANDARD.encode(UNIX_PROXY_SCRIPT);
 
-            let version = if meta::VERSION == "debug" {
-                "nightly"
-            } else {
-                meta::VERSION
-            };
+            let version = proxy_release_version();
             let cmd = remote
                 .command_builder()
                 .args([
@@ -317,48 +390,231 @@ fn download_remote(
                 // when needed
                 std::fs::remove_file(&local_proxy_file)?;
             }
-            let proxy_version = meta::VERSION;
-            let url = format!("https://github.com/lapce/lapce/releases/download/{proxy_version}/{proxy_filename}.gz");
-            debug!("proxy download URI: {url}");
-            let mut resp = reqwest::blocking::get(url).expect("request failed");
-            if resp.status().is_success() {
-                let mut out = std::fs::File::create(&local_proxy_file)
-                    .expect("failed to create file");
-                let mut gz = GzDecoder::new(&mut resp);
-                std::io::copy(&mut gz, &mut out).expect("failed to copy content");
+            let mut out = std::fs::File::create(&local_proxy_file)?;
+            download_release(platform, architecture, &mut out)?;
+
+            upload_proxy(
+                remote,
+                platform,
+                &local_proxy_file,
+                remote_proxy_path,
+                remote_proxy_file,
+            )?;
+            return file_checksum(&local_proxy_file).map(Some);
+        }
+    }
+
+    // The remote host downloaded the release itself, so check what it got
+    // against the checksum published with the release
+    Ok(published_checksum(platform, architecture))
+}
+
+/// The release the proxy is downloaded from, debug builds using the nightly
+fn proxy_release_version() -> &'static str {
+    if meta::VERSION == "debug" {
+        "nightly"
+    } else {
+        meta::VERSION
+    }
+}
+
+/// Downloads the proxy release for the platform, and writes it to `out`
+/// decompressed
+fn download_release(
+    platform: &HostPlatform,
+    architecture: &HostArchitecture,
+    out: &mut impl Write,
+) -> Result<()> {
+    let url =
+        release_asset_url(&format!("lapce-proxy-{platform}-{architecture}.gz"));
+    debug!("proxy download URI: {url}");
+    let mut resp = reqwest::blocking::get(url)?;
+    if !resp.status().is_success() {
+        return Err(anyhow!("Proxy download failed with: {}", resp.status()));
+    }
+    let mut gz = GzDecoder::new(&mut resp);
+    std::io::copy(&mut gz, out)?;
+    Ok(())
+}
+
+/// The lowercase hex sha256 of the decompressed proxy for the platform,
+/// published with the release as `lapce-proxy-<os>-<arch>.sha256`. The
+/// nightly release is replaced all the time, so it has none to rely on.
+fn published_checksum(
+    platform: &HostPlatform,
+    architecture: &HostArchitecture,
+) -> Option<String> {
+    if proxy_release_version() == "nightly" {
+        return None;
+    }
+    let url =
+        release_asset_url(&format!("lapce-proxy-{platform}-{architecture}.sha256"));
+    let resp = reqwest::blocking::get(url).ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+    let text = resp.text().ok()?;
+    let hash = text.split_whitespace().next()?;
+    (hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| hash.to_lowercase())
+}
+
+fn release_asset_url(file_name: &str) -> String {
+    format!(
+        "https://github.com/lapce/lapce/releases/download/{}/{file_name}",
+        proxy_release_version()
+    )
+}
+
+fn upload_proxy(
+    remote: &impl Remote,
+    platform: &HostPlatform,
+    local_proxy_file: &Path,
+    remote_proxy_path: &str,
+    remote_proxy_file: &str,
+) -> Result<()> {
+    match platform {
+        // Windows creates all dirs in provided path
+        HostPlatform::Windows => remote
+            .command_builder()
+            .arg("mkdir")
+            .arg(remote_proxy_path)
+            .status()?,
+        // Unix needs -p to do same
+        _ => remote
+            .command_builder()
+            .arg("mkdir")
+            .arg("-p")
+            .arg(remote_proxy_path)
+            .status()?,
+    };
+
+    remote.upload_file(local_proxy_file, remote_proxy_file)?;
+    if platform != &HostPlatform::Windows {
+        remote
+            .command_builder()
+            .arg("chmod")
+            .arg("+x")
+            .arg(remote_proxy_file)
+            .status()?;
+    }
+
+    Ok(())
+}
+
+/// Whether the proxy on the remote host has the same version as Lapce
+fn remote_proxy_matches(remote: &impl Remote, remote_proxy_file: &str) -> bool {
+    remote
+        .command_builder()
+        .args([remote_proxy_file, "--version"])
+        .output()
+        .map(|output| {
+            if meta::VERSION == "debug" {
+                String::from_utf8_lossy(&output.stdout).starts_with("Lapce-proxy")
             } else {
-                error!("proxy download failed with: {}", resp.status());
+                String::from_utf8_lossy(&output.stdout)
+                    == format!("Lapce-proxy {}", meta::VERSION)
             }
+        })
+        .unwrap_or(false)
+}
 
-            match platform {
-                // Windows creates all dirs in provided path
-                HostPlatform::Windows => remote
-                    .command_builder()
-                    .arg("mkdir")
-                    .arg(remote_proxy_path)
-                    .status()?,
-                // Unix needs -p to do same
-                _ => remote
-                    .command_builder()
-                    .arg("mkdir")
-                    .arg("-p")
-                    .arg(remote_proxy_path)
-                    .status()?,
-            };
-
-            remote.upload_file(&local_proxy_file, remote_proxy_file)?;
-            if platform != &HostPlatform::Windows {
-                remote
-                    .command_builder()
-                    .arg("chmod")
-                    .arg("+x")
-                    .arg(remote_proxy_file)
-                    .status()?;
-            }
+/// Finds the proxy binary to upload for the remote platform, either in the
+/// configured directory, or the `lapce-proxy` next to Lapce when the remote
+/// host has the same platform as this one
+fn local_proxy_file(
+    install: &ProxyInstall,
+    platform: &HostPlatform,
+    architecture: &HostArchitecture,
+) -> Result<PathBuf> {
+    let proxy_filename = format!("lapce-proxy-{platform}-{architecture}");
+
+    if let Some(directory) = install.directory.as_ref() {
+        let file = directory.join(&proxy_filename);
+        if file.exists() {
+            return Ok(file);
+        }
+
+        // the release assets are gzipped
+        let gz_file = directory.join(format!("{proxy_filename}.gz"));
+        if gz_file.exists() {
+            let file = Directory::proxy_directory()
+                .ok_or_else(|| anyhow!("can't find proxy directory"))?
+                .join(&proxy_filename);
+            let mut out = std::fs::File::create(&file)?;
+            let mut gz = GzDecoder::new(std::fs::File::open(&gz_file)?);
+            std::io::copy(&mut gz, &mut out)?;
+            return Ok(file);
         }
+
+        return Err(anyhow!(
+            "Can't find {proxy_filename} in {}",
+            directory.display()
+        ));
     }
 
-    Ok(())
+    let local_platform = parse_os(match std::env::consts::OS {
+        "macos" => "darwin",
+        "windows" => "windows_nt",
+        os => os,
+    });
+    let local_architecture = parse_arch(std::env::consts::ARCH);
+    if &local_platform != platform || &local_architecture != architecture {
+        return Err(anyhow!(
+            "The remote host is {platform}/{architecture}, set a proxy directory to upload {proxy_filename} from"
+        ));
+    }
+
+    let file = std::env::current_exe()?
+        .with_file_name("lapce-proxy")
+        .with_extension(std::env::consts::EXE_EXTENSION);
+    if !file.exists() {
+        return Err(anyhow!("Can't find {}", file.display()));
+    }
+    Ok(file)
+}
+
+fn file_checksum(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// The lowercase hex sha256 of the file on the remote host
+fn remote_checksum(
+    remote: &impl Remote,
+    platform: &HostPlatform,
+    remote_proxy_file: &str,
+) -> Option<String> {
+    let hash_of = |args: &[&str]| -> Option<String> {
+        let output = remote.command_builder().args(args).output().ok()?;
+        // certutil puts the hash on its own line, possibly with spaces in it
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find_map(|line| {
+                let hash = match platform {
+                    HostPlatform::Windows => line.replace(' ', ""),
+                    _ => line.split_whitespace().next()?.to_string(),
+                };
+                (hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+                    .then(|| hash.to_lowercase())
+            })
+    };
+
+    match platform {
+        HostPlatform::Windows => hash_of(&[
+            "cmd",
+            "/c",
+            "certutil",
+            "-hashfile",
+            remote_proxy_file,
+            "SHA256",
+        ]),
+        // macOS only has shasum. The commands are tried one after the other,
+        // as WSL runs them without a shell that'd understand `||`.
+        _ => hash_of(&["sha256sum", remote_proxy_file])
+            .or_else(|| hash_of(&["shasum", "-a", "256", remote_proxy_file])),
+    }
 }
 
 fn host_specification(
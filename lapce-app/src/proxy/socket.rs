@@ -0,0 +1,33 @@
+use std::io::BufReader;
+
+use anyhow::{anyhow, Context, Result};
+use lapce_rpc::{
+    core::CoreRpcHandler,
+    proxy::ProxyRpcHandler,
+    socket::{send_token, SocketStream},
+};
+
+use super::remote::{connect_transport, RemoteExit};
+use crate::workspace::ProxyHost;
+
+/// Connects to a proxy that was started with `lapce-proxy --listen`, and
+/// returns once the connection has ended.
+pub fn connect_socket(
+    host: &ProxyHost,
+    core_rpc: CoreRpcHandler,
+    proxy_rpc: ProxyRpcHandler,
+) -> Result<RemoteExit> {
+    if host.token.is_empty() {
+        return Err(anyhow!("No token for {}", host.address));
+    }
+    let stream = SocketStream::connect(&host.address)
+        .with_context(|| format!("Can't connect to {}", host.address))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream.try_clone()?;
+    send_token(&mut reader, &mut writer, &host.token)
+        .with_context(|| format!("{} refused the connection", host.address))?;
+
+    connect_transport(writer, reader, core_rpc, proxy_rpc, move || {
+        stream.shutdown();
+    })
+}
@@ -1,6 +1,6 @@
 use std::{path::Path, process::Command};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use tracing::debug;
 
 use super::remote::Remote;
@@ -25,6 +25,51 @@ impl SshRemote {
         "-o",
         "ConnectTimeout=15",
     ];
+
+    /// Forward a local port to a port on the remote host, through the
+    /// connection that's shared with the proxy
+    pub fn forward_port(&self, local: u16, remote: u16) -> Result<()> {
+        self.control("forward", local, remote)
+    }
+
+    /// Stop forwarding a port that was set up with [`Self::forward_port`]
+    pub fn cancel_port(&self, local: u16, remote: u16) -> Result<()> {
+        self.control("cancel", local, remote)
+    }
+
+    #[cfg(windows)]
+    fn control(&self, _command: &str, _local: u16, _remote: u16) -> Result<()> {
+        Err(anyhow!(
+            "port forwarding needs ssh connection sharing, which isn't available on Windows"
+        ))
+    }
+
+    #[cfg(unix)]
+    fn control(&self, command: &str, local: u16, remote: u16) -> Result<()> {
+        let mut cmd = new_command("ssh");
+        cmd.args(Self::SSH_ARGS);
+
+        if let Some(port) = self.ssh.port {
+            cmd.arg("-p").arg(port.to_string());
+        }
+
+        let output = cmd
+            .arg("-O")
+            .arg(command)
+            .arg("-L")
+            .arg(format!("{local}:localhost:{remote}"))
+            .arg(self.ssh.user_host())
+            .output()?;
+
+        debug!("{}", String::from_utf8_lossy(&output.stderr));
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow!("{}", stderr.trim()));
+        }
+
+        Ok(())
+    }
 }
 
 impl Remote for SshRemote {
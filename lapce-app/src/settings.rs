@@ -28,8 +28,9 @@
 use crate::{
     command::CommandExecuted,
     config::{
-        color::LapceColor, core::CoreConfig, editor::EditorConfig, icon::LapceIcons,
-        terminal::TerminalConfig, ui::UIConfig, DropdownInfo, LapceConfig,
+        color::LapceColor, core::CoreConfig, editor::EditorConfig,
+        files::FilesConfig, icon::LapceIcons, terminal::TerminalConfig,
+        ui::UIConfig, DropdownInfo, LapceConfig,
     },
     editor::EditorData,
     id::EditorId,
@@ -66,6 +67,36 @@ fn from(v: serde_json::Value) -> Self {
     }
 }
 
+/// Which settings file the edits in the settings view are written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsScope {
+    /// The user's global `settings.toml`
+    User,
+    /// The `.lapce/settings.toml` of the workspace, which is applied on top
+    /// of the user's settings
+    Workspace,
+}
+
+impl SettingsScope {
+    fn update_file(
+        &self,
+        config: &LapceConfig,
+        parent: &str,
+        key: &str,
+        value: toml_edit::Value,
+    ) -> Option<()> {
+        match self {
+            SettingsScope::User => LapceConfig::update_file(parent, key, value),
+            SettingsScope::Workspace => LapceConfig::update_workspace_file(
+                config.workspace_config_dir.as_ref()?,
+                parent,
+                key,
+                value,
+            ),
+        }
+    }
+}
+
 #[derive(Clone)]
 struct SettingsItem {
     kind: String,
@@ -87,6 +118,7 @@ struct SettingsData {
     plugin_items: RwSignal<im::Vector<SettingsItem>>,
     plugin_kinds: RwSignal<im::Vector<(String, RwSignal<Point>)>>,
     filtered_items: RwSignal<im::Vector<SettingsItem>>,
+    scope: RwSignal<SettingsScope>,
     common: Rc<CommonData>,
 }
 
@@ -171,6 +203,12 @@ fn into_settings_map(
                 &TerminalConfig::DESCS[..],
                 into_settings_map(&config.terminal),
             ),
+            (
+                "Files",
+                &FilesConfig::FIELDS[..],
+                &FilesConfig::DESCS[..],
+                into_settings_map(&config.files),
+            ),
         ] {
             let pos = cx.create_rw_signal(Point::new(0.0, item_height_accum));
             items.push_back(SettingsItem {
@@ -298,6 +336,7 @@ fn into_settings_map(
             plugin_kinds,
             items,
             kinds,
+            scope: cx.create_rw_signal(SettingsScope::User),
             common,
         }
     }
@@ -319,6 +358,7 @@ pub fn settings_view(
 
     let items = settings_data.items.clone();
     let kinds = settings_data.kinds.clone();
+    let scope = settings_data.scope;
     let filtered_items_signal = settings_data.filtered_items;
     create_effect(move |_| {
         let doc = doc.get();
@@ -473,6 +513,7 @@ pub fn settings_view(
                 .border_color(*config.get().get_color(LapceColor::LAPCE_BORDER))
         }),
         stack((
+            scope_switcher(scope, config),
             container({
                 text_input(search_editor, || false)
                     .placeholder(|| "Search Settings".to_string())
@@ -523,8 +564,50 @@ pub fn settings_view(
     .style(|s| s.absolute().size_pct(100.0, 100.0))
 }
 
+/// The tabs for choosing between the user's and the workspace's settings,
+/// which are only shown when the workspace's own settings are applied
+fn scope_switcher(
+    scope: RwSignal<SettingsScope>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let scope_tab = move |tab: SettingsScope, text: &'static str| {
+        label(move || text.to_string())
+            .on_click(move |_| {
+                scope.set(tab);
+                true
+            })
+            .style(move |s| {
+                let config = config.get();
+                s.padding_horiz(10.0)
+                    .cursor(CursorStyle::Pointer)
+                    .border_bottom(2.0)
+                    .border_color(Color::TRANSPARENT)
+                    .apply_if(scope.get() == tab, |s| {
+                        s.border_color(
+                            *config
+                                .get_color(LapceColor::LAPCE_TAB_ACTIVE_UNDERLINE),
+                        )
+                    })
+            })
+    };
+
+    stack((
+        scope_tab(SettingsScope::User, "User"),
+        scope_tab(SettingsScope::Workspace, "Workspace"),
+    ))
+    .style(move |s| {
+        let config = config.get();
+        s.padding_horiz(50.0)
+            .padding_top(20.0)
+            .line_height(1.8)
+            .font_size(config.ui.font_size() as f32 + 1.0)
+            .apply_if(config.workspace_config_dir.is_none(), |s| s.hide())
+    })
+}
+
 fn settings_item_view(settings_data: SettingsData, item: SettingsItem) -> impl View {
     let config = settings_data.common.config;
+    let scope = settings_data.scope;
 
     let is_ticked = if let SettingsValue::Bool(is_ticked) = &item.value {
         Some(*is_ticked)
@@ -602,8 +685,11 @@ fn settings_item_view(settings_data: SettingsData, item: SettingsItem) -> impl V
                                     };
 
                                     if let Some(value) = value {
-                                        LapceConfig::update_file(
-                                            &kind, &field, value,
+                                        scope.get_untracked().update_file(
+                                            &config.get_untracked(),
+                                            &kind,
+                                            &field,
+                                            value,
                                         );
                                     }
                                 }
@@ -651,7 +737,12 @@ fn settings_item_view(settings_data: SettingsData, item: SettingsItem) -> impl V
                                 &item_string,
                                 toml_edit::ser::ValueSerializer::new(),
                             ) {
-                                LapceConfig::update_file(&kind, &field, value);
+                                scope.get_untracked().update_file(
+                                    &config.get_untracked(),
+                                    &kind,
+                                    &field,
+                                    value,
+                                );
                             }
                             expanded.set(false);
                             true
@@ -814,7 +905,12 @@ fn settings_item_view(settings_data: SettingsData, item: SettingsItem) -> impl V
                         &checked,
                         toml_edit::ser::ValueSerializer::new(),
                     ) {
-                        LapceConfig::update_file(&kind, &field, value);
+                        scope.get_untracked().update_file(
+                            &config.get_untracked(),
+                            &kind,
+                            &field,
+                            value,
+                        );
                     }
                 });
 
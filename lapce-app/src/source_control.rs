@@ -1,12 +1,14 @@
-use std::{path::PathBuf, rc::Rc};
+use std::{ops::Range, path::PathBuf, rc::Rc};
 
+use chrono::{Local, TimeZone};
 use floem::{
     keyboard::ModifiersState,
     reactive::{RwSignal, Scope},
 };
 use indexmap::IndexMap;
 use lapce_core::mode::Mode;
-use lapce_rpc::source_control::FileDiff;
+use lapce_rpc::{proxy::ProxyResponse, source_control::FileDiff, RpcError};
+use tracing::error;
 
 use crate::{
     command::{CommandExecuted, CommandKind},
@@ -18,8 +20,10 @@
 
 #[derive(Clone)]
 pub struct SourceControlData {
-    // VCS modified files & whether they should be included in the next commit
-    pub file_diffs: RwSignal<IndexMap<PathBuf, (FileDiff, bool)>>,
+    // VCS modified files that aren't staged
+    pub file_diffs: RwSignal<IndexMap<PathBuf, FileDiff>>,
+    // VCS modified files that are staged for the next commit
+    pub staged_diffs: RwSignal<IndexMap<PathBuf, FileDiff>>,
     pub branch: RwSignal<String>,
     pub branches: RwSignal<im::Vector<String>>,
     pub tags: RwSignal<im::Vector<String>>,
@@ -64,6 +68,7 @@ impl SourceControlData {
     pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
         Self {
             file_diffs: cx.create_rw_signal(IndexMap::new()),
+            staged_diffs: cx.create_rw_signal(IndexMap::new()),
             branch: cx.create_rw_signal("".to_string()),
             branches: cx.create_rw_signal(im::Vector::new()),
             tags: cx.create_rw_signal(im::Vector::new()),
@@ -77,22 +82,17 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
     }
 
     pub fn commit(&self) {
-        let diffs: Vec<FileDiff> = self.file_diffs.with_untracked(|file_diffs| {
-            file_diffs
-                .iter()
-                .filter_map(
-                    |(_, (diff, checked))| {
-                        if *checked {
-                            Some(diff)
-                        } else {
-                            None
-                        }
-                    },
-                )
-                .cloned()
-                .collect()
-        });
-        if diffs.is_empty() {
+        // Commit what's staged, or all the changes if nothing is staged
+        let nothing_staged = self
+            .staged_diffs
+            .with_untracked(|staged_diffs| staged_diffs.is_empty());
+        let diffs: Vec<FileDiff> = if nothing_staged {
+            self.file_diffs
+                .with_untracked(|file_diffs| file_diffs.values().cloned().collect())
+        } else {
+            Vec::new()
+        };
+        if nothing_staged && diffs.is_empty() {
             return;
         }
 
@@ -111,4 +111,65 @@ pub fn commit(&self) {
         self.editor.reset();
         self.common.proxy.git_commit(message.to_string(), diffs);
     }
+
+    pub fn stage(&self, diff: &FileDiff) {
+        self.common.proxy.git_stage(diff_paths(diff), log_git_error);
+    }
+
+    pub fn unstage(&self, diff: &FileDiff) {
+        self.common
+            .proxy
+            .git_unstage(diff_paths(diff), log_git_error);
+    }
+
+    pub fn stage_all(&self) {
+        let paths = self.file_diffs.with_untracked(|file_diffs| {
+            file_diffs.values().flat_map(diff_paths).collect()
+        });
+        self.common.proxy.git_stage(paths, log_git_error);
+    }
+
+    pub fn unstage_all(&self) {
+        let paths = self.staged_diffs.with_untracked(|staged_diffs| {
+            staged_diffs.values().flat_map(diff_paths).collect()
+        });
+        self.common.proxy.git_unstage(paths, log_git_error);
+    }
+
+    pub fn stage_lines(&self, path: PathBuf, lines: Range<usize>) {
+        self.common
+            .proxy
+            .git_stage_lines(path, lines, log_git_error);
+    }
+
+    pub fn unstage_lines(&self, path: PathBuf, lines: Range<usize>) {
+        self.common
+            .proxy
+            .git_unstage_lines(path, lines, log_git_error);
+    }
+}
+
+/// The paths of the index a file diff touches
+fn diff_paths(diff: &FileDiff) -> Vec<PathBuf> {
+    match diff {
+        FileDiff::Modified(p) | FileDiff::Added(p) | FileDiff::Deleted(p) => {
+            vec![p.clone()]
+        }
+        FileDiff::Renamed(new, old) => vec![new.clone(), old.clone()],
+    }
+}
+
+fn log_git_error(result: Result<ProxyResponse, RpcError>) {
+    if let Err(err) = result {
+        error!("git: {}", err.message);
+    }
+}
+
+/// The local date of a commit time in seconds since the unix epoch
+pub fn commit_date(time: i64) -> String {
+    Local
+        .timestamp_opt(time, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
 }
@@ -11,6 +11,7 @@
 };
 use indexmap::IndexMap;
 use lapce_core::mode::Mode;
+use lapce_rpc::proxy::ProxyStatus;
 use lsp_types::{DiagnosticSeverity, ProgressToken};
 
 use crate::{
@@ -54,11 +55,14 @@ pub fn status(
     });
     let branch = source_control.branch;
     let file_diffs = source_control.file_diffs;
+    let staged_diffs = source_control.staged_diffs;
     let branch = move || {
         format!(
             "{}{}",
             branch.get(),
-            if file_diffs.with(|diffs| diffs.is_empty()) {
+            if file_diffs.with(|diffs| diffs.is_empty())
+                && staged_diffs.with(|diffs| diffs.is_empty())
+            {
                 ""
             } else {
                 "*"
@@ -67,6 +71,7 @@ pub fn status(
     };
 
     let progresses = window_tab_data.progresses;
+    let proxy_status = window_tab_data.common.proxy_status;
     let mode = create_memo(move |_| window_tab_data.mode());
 
     stack((
@@ -199,6 +204,7 @@ pub fn status(
                     )
                 })
             },
+            reconnecting_view(config, proxy_status),
             progress_view(config, progresses),
         ))
         .style(|s| {
@@ -350,6 +356,98 @@ pub fn status(
                     *config.get().get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
                 )
             });
+            let large_file_info =
+                label(|| "Large File Mode".to_string()).style(move |s| {
+                    s.display(
+                        if editor
+                            .get()
+                            .map(|editor| editor.view.doc.get().large_file.get())
+                            .unwrap_or(false)
+                        {
+                            Display::Flex
+                        } else {
+                            Display::None
+                        },
+                    )
+                    .height_pct(100.0)
+                    .padding_horiz(10.0)
+                    .items_center()
+                    .color(*config.get().get_color(LapceColor::STATUS_FOREGROUND))
+                });
+            let palette_clone = palette.clone();
+            let encoding_info = label(move || {
+                if let Some(editor) = editor.get() {
+                    let doc = editor.view.doc.get_untracked();
+                    doc.encoding.get().to_string()
+                } else {
+                    String::new()
+                }
+            })
+            .on_click(move |_| {
+                palette_clone.run(PaletteKind::ReopenWithEncoding);
+                true
+            })
+            .style(move |s| {
+                s.display(
+                    if editor
+                        .get()
+                        .map(|editor| {
+                            editor.view.doc.get().content.with(|c| c.is_file())
+                        })
+                        .unwrap_or(false)
+                    {
+                        Display::Flex
+                    } else {
+                        Display::None
+                    },
+                )
+                .height_pct(100.0)
+                .padding_horiz(10.0)
+                .items_center()
+                .color(*config.get().get_color(LapceColor::STATUS_FOREGROUND))
+            })
+            .hover_style(move |s| {
+                s.cursor(CursorStyle::Pointer).background(
+                    *config.get().get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                )
+            });
+            let palette_clone = palette.clone();
+            let line_ending_info = label(move || {
+                if let Some(editor) = editor.get() {
+                    let doc = editor.view.doc.get_untracked();
+                    doc.buffer.with(|b| b.line_ending().to_string())
+                } else {
+                    String::new()
+                }
+            })
+            .on_click(move |_| {
+                palette_clone.run(PaletteKind::LineEnding);
+                true
+            })
+            .style(move |s| {
+                s.display(
+                    if editor
+                        .get()
+                        .map(|editor| {
+                            editor.view.doc.get().content.with(|c| c.is_file())
+                        })
+                        .unwrap_or(false)
+                    {
+                        Display::Flex
+                    } else {
+                        Display::None
+                    },
+                )
+                .height_pct(100.0)
+                .padding_horiz(10.0)
+                .items_center()
+                .color(*config.get().get_color(LapceColor::STATUS_FOREGROUND))
+            })
+            .hover_style(move |s| {
+                s.cursor(CursorStyle::Pointer).background(
+                    *config.get().get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                )
+            });
             let palette_clone = palette.clone();
             let language_info = label(move || {
                 if let Some(editor) = editor.get() {
@@ -387,7 +485,13 @@ pub fn status(
                     *config.get().get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
                 )
             });
-            (cursor_info, language_info)
+            (
+                large_file_info,
+                cursor_info,
+                encoding_info,
+                line_ending_info,
+                language_info,
+            )
         })
         .style(|s| {
             s.height_pct(100.0)
@@ -412,6 +516,27 @@ pub fn status(
     })
 }
 
+fn reconnecting_view(
+    config: ReadSignal<Arc<LapceConfig>>,
+    proxy_status: RwSignal<Option<ProxyStatus>>,
+) -> impl View {
+    let attempt = create_memo(move |_| match proxy_status.get() {
+        Some(ProxyStatus::Reconnecting(attempt)) => Some(attempt),
+        _ => None,
+    });
+    label(move || {
+        attempt
+            .get()
+            .map(|attempt| format!("Reconnecting to remote (attempt {attempt})"))
+            .unwrap_or_default()
+    })
+    .style(move |s| {
+        s.margin_left(10.0)
+            .color(*config.get().get_color(LapceColor::STATUS_FOREGROUND))
+            .apply_if(attempt.get().is_none(), |s| s.hide())
+    })
+}
+
 fn progress_view(
     config: ReadSignal<Arc<LapceConfig>>,
     progresses: RwSignal<IndexMap<ProgressToken, WorkProgress>>,
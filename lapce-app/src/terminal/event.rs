@@ -5,11 +5,13 @@
 use parking_lot::RwLock;
 
 use super::raw::RawTerminal;
+use crate::port_forward::detect_ports;
 
 /// The notifications for terminals to send back to main thread
 pub enum TermNotification {
     SetTitle { term_id: TermId, title: String },
     RequestPaint,
+    ListeningPort { port: u16 },
 }
 
 pub enum TermEvent {
@@ -18,9 +20,12 @@ pub enum TermEvent {
     CloseTerminal,
 }
 
+/// Applies the output of terminals to their state. When `scan_ports` is
+/// set, the output is also scanned for ports that programs are listening on.
 pub fn terminal_update_process(
     receiver: Receiver<(TermId, TermEvent)>,
     term_notification_tx: Sender<TermNotification>,
+    scan_ports: bool,
 ) {
     let mut terminals = HashMap::new();
     let mut last_redraw = Instant::now();
@@ -43,6 +48,13 @@ pub fn terminal_update_process(
             }
             TermEvent::UpdateContent(content) => {
                 if let Some(raw) = terminals.get(&term_id) {
+                    if scan_ports {
+                        for port in detect_ports(&String::from_utf8_lossy(&content))
+                        {
+                            let _ = term_notification_tx
+                                .send(TermNotification::ListeningPort { port });
+                        }
+                    }
                     {
                         raw.write().update_content(content);
                     }
@@ -73,11 +73,20 @@ fn left(
         ))
         .popout_menu(move || {
             #[allow(unused_mut)]
-            let mut menu = Menu::new("").entry(
-                MenuItem::new("Connect to SSH Host").action(move || {
+            let mut menu = Menu::new("")
+                .entry(MenuItem::new("Connect to SSH Host").action(move || {
                     workbench_command.send(LapceWorkbenchCommand::ConnectSshHost);
-                }),
-            );
+                }))
+                .entry(MenuItem::new("Connect to Remote with Command").action(
+                    move || {
+                        workbench_command
+                            .send(LapceWorkbenchCommand::ConnectRemoteCommand);
+                    },
+                ))
+                .entry(MenuItem::new("Connect to Remote Proxy").action(move || {
+                    workbench_command
+                        .send(LapceWorkbenchCommand::ConnectRemoteProxy);
+                }));
             #[cfg(windows)]
             {
                 menu =
@@ -108,10 +117,10 @@ fn left(
                     Some(ProxyStatus::Connected) => {
                         *config.get_color(LapceColor::LAPCE_REMOTE_CONNECTED)
                     }
-                    Some(ProxyStatus::Connecting) => {
+                    Some(ProxyStatus::Connecting | ProxyStatus::Reconnecting(_)) => {
                         *config.get_color(LapceColor::LAPCE_REMOTE_CONNECTING)
                     }
-                    Some(ProxyStatus::Disconnected) => {
+                    Some(ProxyStatus::Disconnected | ProxyStatus::Error(_)) => {
                         *config.get_color(LapceColor::LAPCE_REMOTE_DISCONNECTED)
                     }
                     None => Color::TRANSPARENT,
@@ -0,0 +1,234 @@
+use std::rc::Rc;
+
+use chrono::{Local, TimeZone};
+use floem::{
+    keyboard::ModifiersState,
+    reactive::{create_effect, RwSignal, Scope},
+};
+use lapce_core::{command::FocusCommand, mode::Mode};
+use lapce_xi_rope::Rope;
+
+use crate::{
+    command::{CommandExecuted, CommandKind},
+    doc::{DocContent, Document},
+    editor::EditorData,
+    id::EditorId,
+    keypress::{condition::Condition, KeyPressFocus},
+    main_split::MainSplitData,
+    window_tab::CommonData,
+};
+
+/// How far to move through the states of a document, in the order they were
+/// made in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UndoTimeTravel {
+    /// A number of states, negative to go back
+    Steps(isize),
+    /// A number of seconds, negative to go back
+    Seconds(i64),
+}
+
+/// Parse a move through the undo tree like vim's `:earlier` and `:later`,
+/// e.g. `earlier 10m`, `later 3` or just `30s`, which goes back
+pub fn parse_undo_time_travel(input: &str) -> Option<UndoTimeTravel> {
+    let input = input.trim().trim_start_matches(':').trim();
+    let (sign, amount) = if let Some(rest) = input.strip_prefix("earlier") {
+        (-1, rest.trim())
+    } else if let Some(rest) = input.strip_prefix("later") {
+        (1, rest.trim())
+    } else {
+        (-1, input)
+    };
+    if amount.is_empty() {
+        return Some(UndoTimeTravel::Steps(sign as isize));
+    }
+
+    let unit_start = amount
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(amount.len());
+    let (number, unit) = amount.split_at(unit_start);
+    let number: i64 = number.parse().ok()?;
+    let seconds = match unit.trim() {
+        "" => return Some(UndoTimeTravel::Steps(sign as isize * number as isize)),
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(UndoTimeTravel::Seconds(sign * number.checked_mul(seconds)?))
+}
+
+/// When an undo group was made, with the day left out for today's
+pub fn undo_group_time(time: u64) -> String {
+    let Some(time) = Local.timestamp_opt(time as i64, 0).single() else {
+        return String::new();
+    };
+    if time.date_naive() == Local::now().date_naive() {
+        time.format("%H:%M:%S").to_string()
+    } else {
+        time.format("%Y-%m-%d %H:%M").to_string()
+    }
+}
+
+#[derive(Clone)]
+pub struct UndoTreeData {
+    /// The document of the editor that was active last
+    pub doc: RwSignal<Option<Rc<Document>>>,
+    /// The input for moving by a number of states or an amount of time
+    pub editor: EditorData,
+    pub error: RwSignal<Option<String>>,
+    pub main_split: MainSplitData,
+    pub common: Rc<CommonData>,
+}
+
+impl KeyPressFocus for UndoTreeData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::PanelFocus | Condition::ListFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &crate::command::LapceCommand,
+        count: Option<usize>,
+        mods: ModifiersState,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Focus(FocusCommand::ListSelect) => {
+                self.time_travel_from_input();
+                CommandExecuted::Yes
+            }
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                self.editor.run_command(command, count, mods)
+            }
+            _ => CommandExecuted::No,
+        }
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.editor.receive_char(c);
+    }
+}
+
+impl UndoTreeData {
+    pub fn new(
+        cx: Scope,
+        main_split: MainSplitData,
+        common: Rc<CommonData>,
+    ) -> Self {
+        let doc = cx.create_rw_signal(None);
+
+        let active_editor = main_split.active_editor;
+        create_effect(move |_| {
+            let Some(editor) = active_editor.get() else {
+                return;
+            };
+            let editor_doc = editor.view.doc.get();
+            let has_history = editor_doc.content.with(|content| {
+                matches!(
+                    content,
+                    DocContent::File { .. } | DocContent::Scratch { .. }
+                )
+            });
+            if has_history {
+                doc.set(Some(editor_doc));
+            }
+        });
+
+        Self {
+            doc,
+            editor: EditorData::new_local(cx, EditorId::next(), common.clone()),
+            error: cx.create_rw_signal(None),
+            main_split,
+            common,
+        }
+    }
+
+    /// Move the document to the state after the undo group
+    pub fn go_to(&self, group: usize) {
+        if let Some(doc) = self.doc.get_untracked() {
+            doc.undo_tree_edit(|buffer| {
+                buffer.go_to_undo_group(group).map(
+                    |(delta, inval_lines, edits, _)| (delta, inval_lines, edits),
+                )
+            });
+        }
+    }
+
+    /// Bring back the text of the state after the undo group as a new edit
+    pub fn restore(&self, group: usize) {
+        if let Some(doc) = self.doc.get_untracked() {
+            doc.undo_tree_edit(|buffer| buffer.restore_undo_group(group));
+        }
+    }
+
+    /// Compare the document with the state after the undo group
+    pub fn preview(&self, group: usize) {
+        if let Some(doc) = self.doc.get_untracked() {
+            self.main_split.preview_undo_group(doc, group);
+        }
+    }
+
+    pub fn time_travel(&self, travel: UndoTimeTravel) {
+        let Some(doc) = self.doc.get_untracked() else {
+            return;
+        };
+        doc.undo_tree_edit(|buffer| {
+            let result = match travel {
+                UndoTimeTravel::Steps(steps) if steps < 0 => {
+                    buffer.undo_earlier(steps.unsigned_abs())
+                }
+                UndoTimeTravel::Steps(steps) => buffer.undo_later(steps as usize),
+                UndoTimeTravel::Seconds(seconds) => {
+                    let time =
+                        (buffer.current_undo_time() as i64 + seconds).max(0) as u64;
+                    buffer.go_to_undo_group(buffer.undo_group_at_time(time))
+                }
+            };
+            result.map(|(delta, inval_lines, edits, _)| (delta, inval_lines, edits))
+        });
+    }
+
+    fn time_travel_from_input(&self) {
+        let doc = self.editor.view.doc.get_untracked();
+        let input = doc.buffer.with_untracked(|buffer| buffer.to_string());
+        match parse_undo_time_travel(&input) {
+            Some(travel) => {
+                doc.reload(Rope::from(""), true);
+                self.error.set(None);
+                self.time_travel(travel);
+            }
+            None => {
+                self.error.set(Some(format!(
+                    "\"{}\" isn't a number of changes or an amount of time like `10m`",
+                    input.trim()
+                )));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_undo_time_travel() {
+        use UndoTimeTravel::*;
+        assert_eq!(parse_undo_time_travel("earlier"), Some(Steps(-1)));
+        assert_eq!(parse_undo_time_travel("later 3"), Some(Steps(3)));
+        assert_eq!(parse_undo_time_travel("5"), Some(Steps(-5)));
+        assert_eq!(parse_undo_time_travel(":earlier 10m"), Some(Seconds(-600)));
+        assert_eq!(parse_undo_time_travel("later 2h"), Some(Seconds(7200)));
+        assert_eq!(parse_undo_time_travel(" 30s "), Some(Seconds(-30)));
+        assert_eq!(parse_undo_time_travel("later 1d"), Some(Seconds(86400)));
+        assert_eq!(parse_undo_time_travel("10y"), None);
+        assert_eq!(parse_undo_time_travel("soon"), None);
+    }
+}
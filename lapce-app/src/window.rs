@@ -69,7 +69,7 @@ pub fn new(
         app_command: Listener<AppCommand>,
     ) -> Self {
         let cx = Scope::new();
-        let config = LapceConfig::load(&LapceWorkspace::default(), &[]);
+        let config = LapceConfig::load(&LapceWorkspace::default(), &[], false);
         let config = cx.create_rw_signal(Arc::new(config));
         let root_view_id = cx.create_rw_signal(floem::id::Id::next());
 
@@ -153,7 +153,7 @@ pub fn new(
     }
 
     pub fn reload_config(&self) {
-        let config = LapceConfig::load(&LapceWorkspace::default(), &[]);
+        let config = LapceConfig::load(&LapceWorkspace::default(), &[], false);
         self.config.set(Arc::new(config));
         let window_tabs = self.window_tabs.get_untracked();
         for (_, window_tab) in window_tabs {
@@ -251,7 +251,7 @@ pub fn run_window_command(&self, cmd: WindowCommand) {
                         let (_, old_window_tab) = window_tabs.remove(index);
                         old_window_tab.proxy.shutdown();
                         let db: Arc<LapceDb> = use_context().unwrap();
-                        let _ = db.save_window_tab(old_window_tab);
+                        let _ = db.close_window_tab(old_window_tab);
                     }
                 });
 
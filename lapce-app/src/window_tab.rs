@@ -1,8 +1,17 @@
-use std::{collections::HashSet, env, path::Path, rc::Rc, sync::Arc, time::Instant};
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    env,
+    path::{Path, PathBuf},
+    rc::Rc,
+    str::FromStr,
+    sync::Arc,
+    time::Instant,
+};
 
 use crossbeam_channel::Sender;
 use floem::{
-    action::{open_file, TimerToken},
+    action::{open_file, save_as, TimerToken},
     cosmic_text::{Attrs, AttrsList, FamilyOwned, LineHeightValue, TextLayout},
     ext_event::{create_ext_action, create_signal_from_channel},
     file::FileDialogOptions,
@@ -18,14 +27,17 @@
     register::Register,
 };
 use lapce_rpc::{
+    control::{ControlRequest, ControlResponse, DocumentInfo, FileDiagnostics},
     core::CoreNotification,
-    dap_types::RunDebugConfig,
-    file::PathObject,
+    dap_types::{DapId, RunDebugConfig},
+    file::{LineCol, PathObject},
     proxy::{ProxyRpcHandler, ProxyStatus},
     source_control::FileDiff,
     terminal::TermId,
+    RpcError,
 };
-use lsp_types::{ProgressParams, ProgressToken, ShowMessageParams};
+use lsp_types::{MessageType, ProgressParams, ProgressToken, ShowMessageParams};
+use notify::Watcher;
 use serde_json::Value;
 use tracing::{debug, error};
 
@@ -38,9 +50,9 @@
         LapceWorkbenchCommand, WindowCommand,
     },
     completion::{CompletionData, CompletionStatus},
-    config::LapceConfig,
+    config::{watcher::ConfigWatcher, LapceConfig},
     db::LapceDb,
-    debug::{DapData, RunDebugMode, RunDebugProcess},
+    debug::{DapData, RunDebugMode, RunDebugProcess, StoppedFrame},
     doc::{DocContent, EditorDiagnostic},
     editor::{
         location::{EditorLocation, EditorPosition},
@@ -49,6 +61,7 @@
     editor_tab::EditorTabChild,
     file_explorer::data::FileExplorerData,
     find::Find,
+    git_log::GitLogData,
     global_search::GlobalSearchData,
     hover::HoverData,
     id::WindowTabId,
@@ -62,15 +75,20 @@
         position::PanelContainerPosition,
     },
     plugin::PluginData,
-    proxy::{new_proxy, path_from_url, ProxyData},
+    port_forward::PortForwardData,
+    proxy::{new_proxy, path_from_url, ProxyData, ProxyInstall},
     rename::RenameData,
     source_control::SourceControlData,
     terminal::{
         event::{terminal_update_process, TermEvent, TermNotification},
         panel::TerminalPanelData,
     },
+    undo_tree::UndoTreeData,
     update::ReleaseInfo,
-    workspace::{LapceWorkspace, LapceWorkspaceType, WorkspaceInfo},
+    workspace::{
+        is_workspace_file, save_workspace_file, LapceWorkspace, LapceWorkspaceType,
+        WorkspaceInfo, WORKSPACE_FILE_EXTENSION,
+    },
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -105,6 +123,7 @@ pub struct WorkProgress {
 
 #[derive(Clone)]
 pub struct CommonData {
+    pub window_tab_id: WindowTabId,
     pub workspace: Arc<LapceWorkspace>,
     pub scope: Scope,
     pub focus: RwSignal<Focus>,
@@ -132,6 +151,7 @@ pub struct CommonData {
     pub mouse_hover_timer: RwSignal<TimerToken>,
     pub hide_cursor: RwSignal<bool>,
     pub ime_allowed: RwSignal<bool>,
+    pub stopped_frame: RwSignal<Option<StoppedFrame>>,
 }
 
 #[derive(Clone)]
@@ -147,6 +167,9 @@ pub struct WindowTabData {
     pub plugin: PluginData,
     pub code_action: RwSignal<CodeActionData>,
     pub source_control: SourceControlData,
+    pub git_log: GitLogData,
+    pub port_forward: PortForwardData,
+    pub undo_tree: UndoTreeData,
     pub rename: RenameData,
     pub global_search: GlobalSearchData,
     pub about_data: AboutData,
@@ -162,6 +185,9 @@ pub struct WindowTabData {
     pub num_window_tabs: Memo<usize>,
     pub progresses: RwSignal<IndexMap<ProgressToken, WorkProgress>>,
     pub messages: RwSignal<Vec<(String, ShowMessageParams)>>,
+    /// Watches the `.lapce` directory of a local workspace for changes to its
+    /// settings and keymaps
+    pub workspace_config_watcher: Option<Rc<RefCell<notify::RecommendedWatcher>>>,
     pub common: Rc<CommonData>,
 }
 
@@ -258,6 +284,21 @@ pub fn new(
         let cx = cx.create_child();
         let db: Arc<LapceDb> = use_context().unwrap();
 
+        // The folders of a workspace file may have been edited since it was
+        // last opened
+        let workspace = match workspace
+            .multi_root
+            .as_ref()
+            .map(|multi_root| LapceWorkspace::from_file(&multi_root.file))
+        {
+            Some(Ok(from_file)) => Arc::new(LapceWorkspace {
+                kind: workspace.kind.clone(),
+                last_open: workspace.last_open,
+                ..from_file
+            }),
+            _ => workspace,
+        };
+
         let disabled_volts = db.get_disabled_volts().unwrap_or_default();
         let workspace_disabled_volts = db
             .get_workspace_disabled_volts(&workspace)
@@ -275,7 +316,11 @@ pub fn new(
             info
         };
 
-        let config = LapceConfig::load(&workspace, &all_disabled_volts);
+        let config = LapceConfig::load(
+            &workspace,
+            &all_disabled_volts,
+            db.is_workspace_trusted(&workspace),
+        );
         let lapce_command = Listener::new_empty(cx);
         let workbench_command = Listener::new_empty(cx);
         let internal_command = Listener::new_empty(cx);
@@ -287,8 +332,10 @@ pub fn new(
             crossbeam_channel::unbounded();
         {
             let term_notification_tx = term_notification_tx.clone();
+            let scan_ports =
+                matches!(workspace.kind, LapceWorkspaceType::RemoteSSH(_));
             std::thread::spawn(move || {
-                terminal_update_process(term_rx, term_notification_tx);
+                terminal_update_process(term_rx, term_notification_tx, scan_ports);
             });
         }
 
@@ -296,6 +343,7 @@ pub fn new(
             workspace.clone(),
             all_disabled_volts,
             config.plugins.clone(),
+            ProxyInstall::from_config(&config),
             term_tx.clone(),
         );
         let (config, set_config) = cx.create_signal(Arc::new(config));
@@ -323,7 +371,9 @@ pub fn new(
             text_layout.size().height
         });
 
+        let window_tab_id = WindowTabId::next();
         let common = Rc::new(CommonData {
+            window_tab_id,
             workspace: workspace.clone(),
             scope: cx,
             keypress,
@@ -351,6 +401,7 @@ pub fn new(
             hide_cursor: cx.create_rw_signal(false),
             window_origin: cx.create_rw_signal(Point::ZERO),
             ime_allowed,
+            stopped_frame: cx.create_rw_signal(None),
         });
 
         let main_split = MainSplitData::new(cx, common.clone());
@@ -436,6 +487,9 @@ pub fn new(
 
         let rename = RenameData::new(cx, common.clone());
         let global_search = GlobalSearchData::new(cx, main_split.clone());
+        let git_log = GitLogData::new(cx, main_split.clone());
+        let port_forward = PortForwardData::new(cx, common.clone());
+        let undo_tree = UndoTreeData::new(cx, main_split.clone(), common.clone());
 
         let plugin = PluginData::new(
             cx,
@@ -447,6 +501,7 @@ pub fn new(
         {
             let notification = create_signal_from_channel(term_notification_rx);
             let terminal = terminal.clone();
+            let port_forward = port_forward.clone();
             cx.create_effect(move |_| {
                 notification.with(|notification| {
                     if let Some(notification) = notification.as_ref() {
@@ -457,6 +512,9 @@ pub fn new(
                             TermNotification::RequestPaint => {
                                 view_id.get_untracked().request_paint();
                             }
+                            TermNotification::ListeningPort { port } => {
+                                port_forward.port_detected(*port);
+                            }
                         }
                     }
                 });
@@ -466,9 +524,23 @@ pub fn new(
         let about_data = AboutData::new(cx, common.focus);
         let alert_data = AlertBoxData::new(cx, common.clone());
 
+        let (config_tx, config_rx) = crossbeam_channel::bounded(1);
+        let workspace_config_watcher = LapceConfig::workspace_config_dir(&workspace)
+            .and_then(|dir| {
+                // The workspace root is watched as well, to pick up a `.lapce`
+                // directory that's created later on
+                let root = dir.parent()?.to_path_buf();
+                let mut watcher = notify::recommended_watcher(
+                    ConfigWatcher::for_dir(config_tx, dir),
+                )
+                .ok()?;
+                let _ = watcher.watch(&root, notify::RecursiveMode::NonRecursive);
+                Some(Rc::new(RefCell::new(watcher)))
+            });
+
         let window_tab_data = Self {
             scope: cx,
-            window_tab_id: WindowTabId::next(),
+            window_tab_id,
             workspace,
             palette,
             main_split,
@@ -477,6 +549,9 @@ pub fn new(
             file_explorer,
             code_action,
             source_control,
+            git_log,
+            port_forward,
+            undo_tree,
             plugin,
             rename,
             global_search,
@@ -493,9 +568,27 @@ pub fn new(
             latest_release,
             progresses: cx.create_rw_signal(IndexMap::new()),
             messages: cx.create_rw_signal(Vec::new()),
+            workspace_config_watcher,
             common,
         };
 
+        window_tab_data.watch_workspace_config();
+        {
+            let window_tab_data = window_tab_data.clone();
+            let notification = create_signal_from_channel(config_rx);
+            cx.create_effect(move |_| {
+                if notification.get().is_some() {
+                    window_tab_data.reload_config();
+                }
+            });
+        }
+        if LapceConfig::has_workspace_config(&window_tab_data.workspace)
+            && !db.is_workspace_trusted(&window_tab_data.workspace)
+        {
+            window_tab_data.ask_to_trust_workspace();
+        }
+        window_tab_data.palette.ask_proxy_token();
+
         {
             let focus = window_tab_data.common.focus;
             let cursor_blink_timer = window_tab_data.common.cursor_blink_timer;
@@ -560,11 +653,69 @@ pub fn reload_config(&self) {
         let mut all_disabled_volts = disabled_volts;
         all_disabled_volts.extend(workspace_disabled_volts);
 
-        let config = LapceConfig::load(&self.workspace, &all_disabled_volts);
+        let config = LapceConfig::load(
+            &self.workspace,
+            &all_disabled_volts,
+            db.is_workspace_trusted(&self.workspace),
+        );
+        self.watch_workspace_config();
         self.common.keypress.update(|keypress| {
             keypress.update_keymaps(&config);
         });
+        let show_blame = |config: &LapceConfig| {
+            config.editor.show_git_blame_gutter
+                || config.editor.show_git_blame_inline
+        };
+        let retrieve_blame =
+            show_blame(&config) && !show_blame(&self.common.config.get_untracked());
         self.set_config.set(Arc::new(config));
+
+        if retrieve_blame {
+            let docs = self.main_split.docs.get_untracked();
+            for (_, doc) in docs {
+                doc.retrieve_blame();
+            }
+        }
+    }
+
+    fn watch_workspace_config(&self) {
+        if let (Some(watcher), Some(dir)) = (
+            self.workspace_config_watcher.as_ref(),
+            LapceConfig::workspace_config_dir(&self.workspace),
+        ) {
+            if dir.is_dir() {
+                let _ = watcher
+                    .borrow_mut()
+                    .watch(&dir, notify::RecursiveMode::Recursive);
+            }
+        }
+    }
+
+    /// The settings and keymaps in a workspace's `.lapce` directory can
+    /// come from anyone who commits to it, so they're only applied once the
+    /// user says they trust the workspace
+    fn ask_to_trust_workspace(&self) {
+        let window_tab_data = self.clone();
+        self.show_alert(
+            "Do you trust the authors of this workspace?".to_string(),
+            format!(
+                "{} has its own settings or keymaps in `.lapce`, \
+                 which are only applied if you trust it.",
+                self.workspace.display().unwrap_or_default()
+            ),
+            vec![AlertButton {
+                text: "Trust Workspace".to_string(),
+                action: Rc::new(move || {
+                    let db: Arc<LapceDb> = use_context().unwrap();
+                    if let Err(err) = db.trust_workspace(&window_tab_data.workspace)
+                    {
+                        error!("{:?}", err);
+                    }
+                    window_tab_data.alert_data.active.set(false);
+                    window_tab_data.reload_config();
+                }),
+            }],
+        );
     }
 
     pub fn run_lapce_command(&self, cmd: LapceCommand) {
@@ -616,6 +767,7 @@ pub fn run_workbench_command(
                             let workspace = LapceWorkspace {
                                 kind: LapceWorkspaceType::Local,
                                 path: Some(file.path),
+                                multi_root: None,
                                 last_open: std::time::SystemTime::now()
                                     .duration_since(std::time::UNIX_EPOCH)
                                     .unwrap()
@@ -633,11 +785,38 @@ pub fn run_workbench_command(
                     let workspace = LapceWorkspace {
                         kind: LapceWorkspaceType::Local,
                         path: None,
+                        multi_root: None,
                         last_open: 0,
                     };
                     window_command.send(WindowCommand::SetWorkspace { workspace });
                 }
             }
+            OpenWorkspaceFile => {
+                if !self.workspace.kind.is_remote() {
+                    let window_command = self.common.window_command;
+                    let internal_command = self.common.internal_command;
+                    open_file(FileDialogOptions::new(), move |file| {
+                        if let Some(file) = file {
+                            open_workspace_file(
+                                &file.path,
+                                window_command,
+                                internal_command,
+                            );
+                        }
+                    });
+                }
+            }
+            AddFolderToWorkspace => {
+                if !self.workspace.kind.is_remote() {
+                    let window_tab_data = self.clone();
+                    let options = FileDialogOptions::new().select_directories();
+                    open_file(options, move |file| {
+                        if let Some(file) = file {
+                            window_tab_data.add_workspace_folder(file.path);
+                        }
+                    });
+                }
+            }
             OpenFile => {
                 if !self.workspace.kind.is_remote() {
                     let internal_command = self.common.internal_command;
@@ -725,6 +904,19 @@ pub fn run_workbench_command(
             OpenKeyboardShortcuts => {
                 self.main_split.open_keymap();
             }
+            OpenInHexEditor => {
+                if let Some(editor_data) = self.main_split.active_editor.get() {
+                    let path = editor_data
+                        .view
+                        .doc
+                        .with_untracked(|doc| doc.content.get_untracked())
+                        .path()
+                        .cloned();
+                    if let Some(path) = path {
+                        self.main_split.open_hex_editor(path);
+                    }
+                }
+            }
             OpenKeyboardShortcutsFile => {
                 if let Some(path) = LapceConfig::keymaps_file() {
                     self.main_split.jump_to_location(
@@ -919,6 +1111,12 @@ pub fn run_workbench_command(
             ConnectSshHost => {
                 self.palette.run(PaletteKind::SshHost);
             }
+            ConnectRemoteCommand => {
+                self.palette.run(PaletteKind::RemoteCommand);
+            }
+            ConnectRemoteProxy => {
+                self.palette.run(PaletteKind::RemoteProxy);
+            }
             ConnectWsl => {
                 // TODO:
             }
@@ -929,6 +1127,7 @@ pub fn run_workbench_command(
                         workspace: LapceWorkspace {
                             kind: LapceWorkspaceType::Local,
                             path: None,
+                            multi_root: None,
                             last_open: 0,
                         },
                     });
@@ -967,6 +1166,15 @@ pub fn run_workbench_command(
             ChangeFileLanguage => {
                 self.palette.run(PaletteKind::Language);
             }
+            ReopenWithEncoding => {
+                self.palette.run(PaletteKind::ReopenWithEncoding);
+            }
+            SaveWithEncoding => {
+                self.palette.run(PaletteKind::SaveWithEncoding);
+            }
+            ChangeLineEndings => {
+                self.palette.run(PaletteKind::LineEnding);
+            }
 
             // ==== Running / Debugging ====
             RunAndDebugRestart => {
@@ -1070,6 +1278,12 @@ pub fn run_workbench_command(
             ToggleSearchFocus => {
                 self.toggle_panel_focus(PanelKind::Search);
             }
+            TogglePortsFocus => {
+                self.toggle_panel_focus(PanelKind::Ports);
+            }
+            ToggleUndoTreeFocus => {
+                self.toggle_panel_focus(PanelKind::UndoTree);
+            }
             ToggleTerminalVisual => {
                 self.toggle_panel_visual(PanelKind::Terminal);
             }
@@ -1142,6 +1356,63 @@ pub fn run_workbench_command(
             SourceControlDiscardWorkspaceChanges => {
                 // TODO:
             }
+            SourceControlStageTargetFile => {
+                if let Some(diff) = data
+                    .and_then(|data| serde_json::from_value::<FileDiff>(data).ok())
+                {
+                    self.source_control.stage(&diff);
+                }
+            }
+            SourceControlUnstageTargetFile => {
+                if let Some(diff) = data
+                    .and_then(|data| serde_json::from_value::<FileDiff>(data).ok())
+                {
+                    self.source_control.unstage(&diff);
+                }
+            }
+            SourceControlStageAll => {
+                self.source_control.stage_all();
+            }
+            SourceControlUnstageAll => {
+                self.source_control.unstage_all();
+            }
+            SourceControlStageSelectedLines => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    let source_control = self.source_control.clone();
+                    editor.selected_change_lines(move |path, lines, _, _| {
+                        source_control.stage_lines(path, lines);
+                    });
+                }
+            }
+            SourceControlUnstageSelectedLines => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    let source_control = self.source_control.clone();
+                    editor.selected_change_lines(move |path, lines, _, _| {
+                        source_control.unstage_lines(path, lines);
+                    });
+                }
+            }
+            SourceControlRevertSelectedLines => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    let doc = editor.view.doc.get_untracked();
+                    editor.selected_change_lines(move |_, lines, index, changes| {
+                        doc.revert_lines(&index, &changes, &lines);
+                    });
+                }
+            }
+            SourceControlShowLog => {
+                self.git_log.show_path(None);
+                self.show_panel(PanelKind::GitLog);
+            }
+            SourceControlShowLineCommit => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    let doc = editor.view.doc.get_untracked();
+                    let offset = editor.cursor.with_untracked(|c| c.offset());
+                    let line =
+                        doc.buffer.with_untracked(|b| b.line_of_offset(offset));
+                    self.main_split.open_line_commit(doc, line);
+                }
+            }
 
             // ==== UI ====
             ShowAbout => {
@@ -1208,11 +1479,53 @@ pub fn run_workbench_command(
             }
             PreviousError => {}
             Quit => {
-                floem::quit_app();
+                self.quit();
             }
         }
     }
 
+    /// Quit right away when hot exit keeps the unsaved documents, which
+    /// happens as the app terminates, and ask first otherwise
+    fn quit(&self) {
+        if self.common.config.get_untracked().files.hot_exit {
+            floem::quit_app();
+            return;
+        }
+
+        let unsaved = self.main_split.editors.with_untracked(|editors| {
+            editors
+                .values()
+                .filter_map(|editor| {
+                    let doc = editor.view.doc.get_untracked();
+                    let key = doc.content.with_untracked(|c| c.backup_key())?;
+                    (!doc.is_pristine()).then_some(key)
+                })
+                .collect::<HashSet<_>>()
+                .len()
+        });
+        if unsaved == 0 {
+            floem::quit_app();
+            return;
+        }
+
+        let internal_command = self.common.internal_command;
+        self.show_alert(
+            "Do you want to quit without saving?".to_string(),
+            format!(
+                "{unsaved} document{} {} unsaved changes, which will be lost.",
+                if unsaved == 1 { "" } else { "s" },
+                if unsaved == 1 { "has" } else { "have" },
+            ),
+            vec![AlertButton {
+                text: "Quit Without Saving".to_string(),
+                action: Rc::new(move || {
+                    internal_command.send(InternalCommand::HideAlert);
+                    floem::quit_app();
+                }),
+            }],
+        );
+    }
+
     pub fn run_internal_command(&self, cmd: InternalCommand) {
         let cx = self.scope;
         match cmd {
@@ -1255,6 +1568,20 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             InternalCommand::OpenFileChanges { path } => {
                 self.main_split.open_file_changes(path);
             }
+            InternalCommand::CompareWithDisk { path } => {
+                self.main_split.compare_with_disk(path);
+            }
+            InternalCommand::ShowFileHistory { path } => {
+                self.git_log.show_path(Some(path));
+                self.show_panel(PanelKind::GitLog);
+            }
+            InternalCommand::RemoveWorkspaceFolder { path } => {
+                let mut roots = self.file_explorer.root_paths();
+                if roots.iter().skip(1).any(|root| root == &path) {
+                    roots.retain(|root| root != &path);
+                    self.update_workspace_folders(roots);
+                }
+            }
             InternalCommand::GoToLocation { location } => {
                 self.main_split.go_to_location(location, None);
             }
@@ -1379,8 +1706,7 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                     );
                 } else {
                     let mut new_config = self.common.config.get_untracked();
-                    Arc::make_mut(&mut new_config)
-                        .set_color_theme(&self.workspace, &name);
+                    Arc::make_mut(&mut new_config).set_color_theme(&name);
                     self.set_config.set(new_config);
                 }
             }
@@ -1394,8 +1720,7 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                     );
                 } else {
                     let mut new_config = self.common.config.get_untracked();
-                    Arc::make_mut(&mut new_config)
-                        .set_icon_theme(&self.workspace, &name);
+                    Arc::make_mut(&mut new_config).set_icon_theme(&name);
                     self.set_config.set(new_config);
                 }
             }
@@ -1441,9 +1766,44 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
         let cx = self.scope;
         match rpc {
             CoreNotification::ProxyStatus { status } => {
+                match status {
+                    ProxyStatus::Error(message) => {
+                        self.show_message(
+                            "Remote",
+                            &ShowMessageParams {
+                                typ: MessageType::ERROR,
+                                message: message.clone(),
+                            },
+                        );
+                    }
+                    ProxyStatus::Connected => {
+                        if let Some(ProxyStatus::Reconnecting(_)) =
+                            self.common.proxy_status.get_untracked()
+                        {
+                            self.main_split.reopen_docs();
+                            self.port_forward.restore();
+                            if self.workspace.multi_root.is_some() {
+                                let folders = self
+                                    .file_explorer
+                                    .root_paths()
+                                    .into_iter()
+                                    .skip(1)
+                                    .collect();
+                                self.common.proxy.update_workspace_folders(folders);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
                 self.common.proxy_status.set(Some(status.to_owned()));
             }
             CoreNotification::DiffInfo { diff } => {
+                // A commit or a checkout changes the history, but other changes
+                // of the working tree don't
+                if self.git_log.head.get_untracked() != diff.head_commit {
+                    self.git_log.head.set(diff.head_commit.clone());
+                    self.git_log.reload();
+                }
                 self.source_control.branch.set(diff.head.clone());
                 self.source_control
                     .branches
@@ -1451,23 +1811,23 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                 self.source_control
                     .tags
                     .set(diff.tags.iter().cloned().collect());
-                self.source_control.file_diffs.update(|file_diffs| {
-                    *file_diffs = diff
-                        .diffs
+                self.source_control.file_diffs.set(
+                    diff.diffs
                         .iter()
-                        .cloned()
-                        .map(|diff| {
-                            let checked = file_diffs
-                                .get(diff.path())
-                                .map_or(true, |(_, c)| *c);
-                            (diff.path().clone(), (diff, checked))
-                        })
-                        .collect();
-                });
+                        .map(|diff| (diff.path().clone(), diff.clone()))
+                        .collect(),
+                );
+                self.source_control.staged_diffs.set(
+                    diff.staged
+                        .iter()
+                        .map(|diff| (diff.path().clone(), diff.clone()))
+                        .collect(),
+                );
 
                 let docs = self.main_split.docs.get_untracked();
                 for (_, doc) in docs {
                     doc.retrieve_head();
+                    doc.retrieve_blame();
                 }
             }
             CoreNotification::CompletionResponse {
@@ -1526,6 +1886,15 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                     .common
                     .term_tx
                     .send((*term_id, TermEvent::CloseTerminal));
+                if let Some(dap_id) =
+                    self.terminal.get_terminal(term_id).and_then(|terminal| {
+                        terminal
+                            .run_debug
+                            .with_untracked(|r| r.as_ref().map(|r| r.config.dap_id))
+                    })
+                {
+                    self.clear_stopped_frame(&dap_id);
+                }
                 self.terminal.terminal_stopped(term_id);
                 if self
                     .terminal
@@ -1551,18 +1920,29 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                 dap_id,
                 stopped,
                 stack_frames,
+                variables,
             } => {
                 self.terminal.dap_stopped(dap_id, stopped, stack_frames);
-            }
-            CoreNotification::OpenPaths { paths } => {
+                self.set_stopped_frame(StoppedFrame::new(
+                    *dap_id,
+                    stopped,
+                    stack_frames,
+                    variables,
+                ));
+            }
+            CoreNotification::OpenPaths { paths, .. } => {
                 self.open_paths(paths);
             }
             CoreNotification::DapContinued { dap_id } => {
                 self.terminal.dap_continued(dap_id);
+                self.clear_stopped_frame(dap_id);
             }
             CoreNotification::OpenFileChanged { path, content } => {
                 self.main_split.open_file_changed(path, content);
             }
+            CoreNotification::OpenFileDeleted { path } => {
+                self.main_split.open_file_deleted(path);
+            }
             CoreNotification::VoltInstalled { volt, icon } => {
                 self.plugin.volt_installed(volt, icon);
             }
@@ -1633,6 +2013,15 @@ pub fn key_down<'a>(&self, event: impl Into<EventRef<'a>> + Copy) -> bool {
             Focus::Panel(PanelKind::SourceControl) => {
                 keypress.key_down(event, &self.source_control)
             }
+            Focus::Panel(PanelKind::GitLog) => {
+                keypress.key_down(event, &self.git_log)
+            }
+            Focus::Panel(PanelKind::Ports) => {
+                keypress.key_down(event, &self.port_forward)
+            }
+            Focus::Panel(PanelKind::UndoTree) => {
+                keypress.key_down(event, &self.undo_tree)
+            }
             _ => false,
         };
 
@@ -1875,9 +2264,12 @@ fn toggle_panel_focus(&self, kind: PanelKind) {
                 // in those cases.
                 self.panel.is_panel_visible(&kind)
             }
-            PanelKind::Terminal | PanelKind::SourceControl | PanelKind::Search => {
-                self.is_panel_focused(kind)
-            }
+            PanelKind::Terminal
+            | PanelKind::SourceControl
+            | PanelKind::Search
+            | PanelKind::GitLog
+            | PanelKind::Ports
+            | PanelKind::UndoTree => self.is_panel_focused(kind),
         };
         if should_hide {
             self.hide_panel(kind);
@@ -1970,10 +2362,36 @@ fn run_and_debug(
                 self.run_in_terminal(cx, mode, config);
             }
             RunDebugMode::Debug => {
-                self.common.proxy.dap_start(
-                    config.clone(),
-                    self.terminal.debug.source_breakpoints(),
-                );
+                let language =
+                    self.main_split.active_editor.get_untracked().map(|editor| {
+                        let doc = editor.view.doc.get_untracked();
+                        doc.syntax.with_untracked(|s| s.language.to_string())
+                    });
+                let dap_server = self
+                    .common
+                    .config
+                    .get_untracked()
+                    .debug
+                    .find_adapter(config.adapter.as_deref(), language.as_deref())
+                    .map(|adapter| adapter.dap_server());
+                if let Some(dap_server) = dap_server {
+                    self.common.proxy.dap_start(
+                        config.clone(),
+                        dap_server,
+                        self.terminal.debug.source_breakpoints(),
+                    );
+                } else {
+                    self.show_message(
+                        "Debug",
+                        &ShowMessageParams {
+                            typ: MessageType::ERROR,
+                            message: format!(
+                                "No debug adapter found for \"{}\", add one in the `debug.adapters` settings",
+                                config.name
+                            ),
+                        },
+                    );
+                }
             }
         }
     }
@@ -2023,6 +2441,29 @@ fn run_in_terminal(
     pub fn open_paths(&self, paths: &[PathObject]) {
         let (folders, files): (Vec<&PathObject>, Vec<&PathObject>) =
             paths.iter().partition(|p| p.is_dir);
+        // Workspace files are only read locally
+        let (workspace_files, files): (Vec<&PathObject>, Vec<&PathObject>) =
+            files.into_iter().partition(|p| {
+                !self.workspace.kind.is_remote() && is_workspace_file(&p.path)
+            });
+
+        for file in workspace_files {
+            match LapceWorkspace::from_file(&file.path) {
+                Ok(workspace) => {
+                    self.common.window_command.send(
+                        WindowCommand::NewWorkspaceTab {
+                            workspace,
+                            end: false,
+                        },
+                    );
+                }
+                Err(err) => self.show_alert(
+                    "Failed to open the workspace file".to_string(),
+                    err.to_string(),
+                    Vec::new(),
+                ),
+            }
+        }
 
         for folder in folders {
             self.common
@@ -2031,6 +2472,7 @@ pub fn open_paths(&self, paths: &[PathObject]) {
                     workspace: LapceWorkspace {
                         kind: self.workspace.kind.clone(),
                         path: Some(folder.path.clone()),
+                        multi_root: None,
                         last_open: 0,
                     },
                     end: false,
@@ -2061,6 +2503,178 @@ pub fn open_paths(&self, paths: &[PathObject]) {
         }
     }
 
+    pub fn handle_control_request(
+        &self,
+        request: ControlRequest,
+    ) -> Result<ControlResponse, RpcError> {
+        match request {
+            ControlRequest::OpenFile { path, line, column } => {
+                self.open_paths(&[PathObject {
+                    path,
+                    linecol: line.map(|line| LineCol {
+                        line,
+                        column: column.unwrap_or(1),
+                    }),
+                    is_dir: false,
+                }]);
+                Ok(ControlResponse::OpenFileResponse {})
+            }
+            ControlRequest::RunCommand { command } => {
+                let command =
+                    LapceWorkbenchCommand::from_str(&command).map_err(|_| {
+                        RpcError {
+                            code: 0,
+                            message: format!("unknown command {command}"),
+                        }
+                    })?;
+                self.common.workbench_command.send(command);
+                Ok(ControlResponse::RunCommandResponse {})
+            }
+            ControlRequest::ListDocuments {} => {
+                let mut documents = self.main_split.docs.with_untracked(|docs| {
+                    docs.iter()
+                        .map(|(path, doc)| DocumentInfo {
+                            path: path.clone(),
+                            is_dirty: !doc.is_pristine(),
+                        })
+                        .collect::<Vec<_>>()
+                });
+                documents.sort_by(|a, b| a.path.cmp(&b.path));
+                Ok(ControlResponse::ListDocumentsResponse { documents })
+            }
+            ControlRequest::GetDiagnostics { path } => {
+                let mut diagnostics =
+                    self.main_split.diagnostics.with_untracked(|diagnostics| {
+                        diagnostics
+                            .iter()
+                            .filter(|(p, _)| {
+                                path.as_ref().map(|path| path == *p).unwrap_or(true)
+                            })
+                            .map(|(p, data)| FileDiagnostics {
+                                path: p.clone(),
+                                diagnostics: data.diagnostics.with_untracked(
+                                    |diagnostics| {
+                                        diagnostics
+                                            .iter()
+                                            .map(|d| d.diagnostic.clone())
+                                            .collect()
+                                    },
+                                ),
+                            })
+                            .filter(|d| !d.diagnostics.is_empty())
+                            .collect::<Vec<_>>()
+                    });
+                diagnostics.sort_by(|a, b| a.path.cmp(&b.path));
+                Ok(ControlResponse::GetDiagnosticsResponse { diagnostics })
+            }
+            ControlRequest::InsertText { text } => {
+                let editor =
+                    self.main_split.active_editor.get_untracked().ok_or_else(
+                        || RpcError {
+                            code: 0,
+                            message: "no active editor".to_string(),
+                        },
+                    )?;
+                editor.insert_text(&text);
+                Ok(ControlResponse::InsertTextResponse {})
+            }
+        }
+    }
+
+    /// Calls `f` once the files have been opened and all their editors are closed
+    /// again, which is what `lapce --wait` blocks on.
+    pub fn on_files_closed(&self, paths: Vec<PathBuf>, f: impl FnOnce() + 'static) {
+        if paths.is_empty() {
+            f();
+            return;
+        }
+
+        let editors = self.main_split.editors;
+        let f = RefCell::new(Some(f));
+        self.scope.create_effect(move |opened: Option<bool>| {
+            let is_open = editors.with(|editors| {
+                editors.values().any(|editor| {
+                    editor.view.doc.with(|doc| {
+                        doc.content.with(|content| {
+                            content
+                                .path()
+                                .map(|p| paths.contains(p))
+                                .unwrap_or(false)
+                        })
+                    })
+                })
+            });
+            let opened = opened.unwrap_or(false) || is_open;
+            if opened && !is_open {
+                if let Some(f) = f.borrow_mut().take() {
+                    f();
+                }
+            }
+            opened
+        });
+    }
+
+    /// Add a root to the workspace. A workspace with a single folder is
+    /// saved to a new workspace file first, which is then opened instead.
+    fn add_workspace_folder(&self, path: PathBuf) {
+        let mut roots = self.file_explorer.root_paths();
+        if roots.contains(&path) {
+            return;
+        }
+        roots.push(path);
+
+        if self.workspace.multi_root.is_some() {
+            self.update_workspace_folders(roots);
+            return;
+        }
+
+        let window_command = self.common.window_command;
+        let internal_command = self.common.internal_command;
+        let options = FileDialogOptions::new()
+            .default_name(format!("workspace.{WORKSPACE_FILE_EXTENSION}"));
+        save_as(options, move |file| {
+            if let Some(file) = file {
+                let mut file = file.path;
+                if !is_workspace_file(&file) {
+                    file.set_extension(WORKSPACE_FILE_EXTENSION);
+                }
+                match save_workspace_file(&file, &roots) {
+                    Ok(()) => {
+                        open_workspace_file(&file, window_command, internal_command)
+                    }
+                    Err(err) => internal_command.send(InternalCommand::ShowAlert {
+                        title: "Failed to save the workspace file".to_string(),
+                        msg: err.to_string(),
+                        buttons: Vec::new(),
+                    }),
+                }
+            }
+        });
+    }
+
+    /// Save the roots of a multi-root workspace to its file, and show them
+    /// and tell the proxy about them
+    fn update_workspace_folders(&self, roots: Vec<PathBuf>) {
+        let multi_root = if let Some(multi_root) = self.workspace.multi_root.as_ref()
+        {
+            multi_root
+        } else {
+            return;
+        };
+        if let Err(err) = save_workspace_file(&multi_root.file, &roots) {
+            self.show_alert(
+                "Failed to save the workspace file".to_string(),
+                err.to_string(),
+                Vec::new(),
+            );
+            return;
+        }
+        self.file_explorer.set_roots(roots.clone());
+        self.common
+            .proxy
+            .update_workspace_folders(roots.into_iter().skip(1).collect());
+    }
+
     pub fn show_alert(&self, title: String, msg: String, buttons: Vec<AlertButton>) {
         self.alert_data.title.set(title);
         self.alert_data.msg.set(msg);
@@ -2100,6 +2714,34 @@ fn update_progress(&self, progress: &ProgressParams) {
         }
     }
 
+    /// Set the frame the debugger stopped at, and refresh the documents that
+    /// show or showed its decorations.
+    fn set_stopped_frame(&self, frame: Option<StoppedFrame>) {
+        let old = self.common.stopped_frame.get_untracked();
+        if old == frame {
+            return;
+        }
+        self.common.stopped_frame.set(frame.clone());
+        for path in old.iter().chain(frame.iter()).map(|f| &f.path) {
+            let doc = self
+                .main_split
+                .docs
+                .with_untracked(|docs| docs.get(path).cloned());
+            if let Some(doc) = doc {
+                doc.clear_text_cache();
+            }
+        }
+    }
+
+    fn clear_stopped_frame(&self, dap_id: &DapId) {
+        let is_stopped = self.common.stopped_frame.with_untracked(|frame| {
+            frame.as_ref().map(|f| &f.dap_id == dap_id).unwrap_or(false)
+        });
+        if is_stopped {
+            self.set_stopped_frame(None);
+        }
+    }
+
     fn show_message(&self, title: &str, message: &ShowMessageParams) {
         self.messages.update(|messages| {
             messages.push((title.to_string(), message.clone()));
@@ -2118,3 +2760,21 @@ fn open_uri(path: &Path) {
         }
     }
 }
+
+/// Open the workspace of a workspace file in the window tab
+fn open_workspace_file(
+    file: &Path,
+    window_command: Listener<WindowCommand>,
+    internal_command: Listener<InternalCommand>,
+) {
+    match LapceWorkspace::from_file(file) {
+        Ok(workspace) => {
+            window_command.send(WindowCommand::SetWorkspace { workspace });
+        }
+        Err(err) => internal_command.send(InternalCommand::ShowAlert {
+            title: "Failed to open the workspace file".to_string(),
+            msg: err.to_string(),
+            buttons: Vec::new(),
+        }),
+    }
+}
@@ -1,5 +1,10 @@
-use std::{fmt::Display, path::PathBuf};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
+use anyhow::Result;
+use lapce_rpc::socket::ProxyAddress;
 use serde::{Deserialize, Serialize};
 
 use crate::{main_split::SplitInfo, panel::data::PanelInfo};
@@ -48,10 +53,59 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     }
 }
 
+/// A headless proxy that was started with `lapce-proxy --listen`. Hosts are
+/// the same if their addresses are, whether or not the token is known.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProxyHost {
+    pub address: ProxyAddress,
+    /// The proxy's secret, which isn't stored with the workspace, so it's
+    /// asked for again when reconnecting
+    #[serde(skip)]
+    pub token: String,
+}
+
+impl ProxyHost {
+    /// Parses `token@address`, e.g. `secret@devbox:9000`
+    pub fn from_string(s: &str) -> Result<Self, String> {
+        let (token, address) = s
+            .trim()
+            .split_once('@')
+            .ok_or_else(|| "expected `token@address`".to_string())?;
+        Ok(Self {
+            address: address.parse()?,
+            token: token.to_string(),
+        })
+    }
+}
+
+impl PartialEq for ProxyHost {
+    fn eq(&self, other: &Self) -> bool {
+        self.address == other.address
+    }
+}
+
+impl Eq for ProxyHost {}
+
+impl std::hash::Hash for ProxyHost {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.address.hash(state);
+    }
+}
+
+impl Display for ProxyHost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.address)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LapceWorkspaceType {
     Local,
     RemoteSSH(SshHost),
+    /// A remote reached by prefixing commands with a user provided one,
+    /// e.g. `docker exec -i container`
+    RemoteCommand(String),
+    RemoteProxy(ProxyHost),
     #[cfg(windows)]
     RemoteWSL,
 }
@@ -65,13 +119,21 @@ pub fn is_local(&self) -> bool {
     pub fn is_remote(&self) -> bool {
         matches!(
             self,
-            LapceWorkspaceType::RemoteSSH(_) | LapceWorkspaceType::RemoteWSL
+            LapceWorkspaceType::RemoteSSH(_)
+                | LapceWorkspaceType::RemoteCommand(_)
+                | LapceWorkspaceType::RemoteProxy(_)
+                | LapceWorkspaceType::RemoteWSL
         )
     }
 
     #[cfg(not(windows))]
     pub fn is_remote(&self) -> bool {
-        matches!(self, LapceWorkspaceType::RemoteSSH(_))
+        matches!(
+            self,
+            LapceWorkspaceType::RemoteSSH(_)
+                | LapceWorkspaceType::RemoteCommand(_)
+                | LapceWorkspaceType::RemoteProxy(_)
+        )
     }
 }
 
@@ -82,22 +144,151 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             LapceWorkspaceType::RemoteSSH(ssh) => {
                 write!(f, "ssh://{ssh}")
             }
+            LapceWorkspaceType::RemoteCommand(command) => {
+                write!(f, "cmd://{command}")
+            }
+            LapceWorkspaceType::RemoteProxy(host) => {
+                write!(f, "proxy://{host}")
+            }
             #[cfg(windows)]
             LapceWorkspaceType::RemoteWSL => f.write_str("WSL"),
         }
     }
 }
 
+pub const WORKSPACE_FILE_EXTENSION: &str = "lapce-workspace";
+
+/// A workspace with several roots, whose folders are listed in a workspace
+/// file
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultiRoot {
+    /// The workspace file, e.g. `project.lapce-workspace`
+    pub file: PathBuf,
+    /// The roots other than the workspace's `path`
+    pub folders: Vec<PathBuf>,
+}
+
+/// The content of a workspace file
+///
+/// ```toml
+/// [[folders]]
+/// path = "lapce"
+///
+/// [[folders]]
+/// path = "/home/user/floem"
+/// ```
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WorkspaceFile {
+    #[serde(default)]
+    folders: Vec<WorkspaceFileFolder>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WorkspaceFileFolder {
+    path: PathBuf,
+}
+
+/// Parse the folders of a workspace file, where relative paths are relative
+/// to the directory of the file
+fn parse_workspace_file(content: &str, dir: &Path) -> Result<Vec<PathBuf>> {
+    let file: WorkspaceFile = toml::from_str(content)?;
+    let mut folders: Vec<PathBuf> = Vec::new();
+    for folder in file.folders {
+        // Paths like `../other` have to be resolved, as files are matched to
+        // the folder that they start with
+        let path = dir.join(folder.path);
+        let path = canonicalize(&path).unwrap_or(path);
+        if !folders.contains(&path) {
+            folders.push(path);
+        }
+    }
+    Ok(folders)
+}
+
+/// [`Path::canonicalize`], but without the `\\?\` prefix it gives paths on
+/// Windows, which would keep them from matching the paths of opened files
+fn canonicalize(path: &Path) -> std::io::Result<PathBuf> {
+    let path = path.canonicalize()?;
+    #[cfg(windows)]
+    if let Some(s) = path.to_str() {
+        if let Some(share) = s.strip_prefix(r"\\?\UNC\") {
+            return Ok(PathBuf::from(format!(r"\\{share}")));
+        }
+        if let Some(disk) = s.strip_prefix(r"\\?\") {
+            if disk.as_bytes().get(1) == Some(&b':') {
+                return Ok(PathBuf::from(disk));
+            }
+        }
+    }
+    Ok(path)
+}
+
+fn format_workspace_file(folders: &[PathBuf], dir: &Path) -> Result<String> {
+    let file = WorkspaceFile {
+        folders: folders
+            .iter()
+            .map(|path| WorkspaceFileFolder {
+                path: path.strip_prefix(dir).unwrap_or(path).to_path_buf(),
+            })
+            .collect(),
+    };
+    Ok(toml::to_string_pretty(&file)?)
+}
+
+/// If the path is a workspace file, which is opened as a workspace instead
+/// of as a document
+pub fn is_workspace_file(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()) == Some(WORKSPACE_FILE_EXTENSION)
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LapceWorkspace {
     pub kind: LapceWorkspaceType,
     pub path: Option<PathBuf>,
+    #[serde(default)]
+    pub multi_root: Option<MultiRoot>,
     pub last_open: u64,
 }
 
 impl LapceWorkspace {
+    /// Open the workspace of a workspace file, whose first folder is the
+    /// main root
+    pub fn from_file(file: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(file)?;
+        let dir = file.parent().unwrap_or(Path::new(""));
+        let mut folders = parse_workspace_file(&content, dir)?.into_iter();
+        Ok(Self {
+            kind: LapceWorkspaceType::Local,
+            path: folders.next(),
+            multi_root: Some(MultiRoot {
+                file: file.to_path_buf(),
+                folders: folders.collect(),
+            }),
+            last_open: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        })
+    }
+
+    /// The other roots of a multi-root workspace
+    pub fn folders(&self) -> Vec<PathBuf> {
+        self.multi_root
+            .as_ref()
+            .map(|m| m.folders.clone())
+            .unwrap_or_default()
+    }
+
+    /// All the roots of the workspace, with the main one first
+    pub fn roots(&self) -> Vec<PathBuf> {
+        self.path.iter().cloned().chain(self.folders()).collect()
+    }
+
     pub fn display(&self) -> Option<String> {
-        let path = self.path.as_ref()?;
+        let path = match self.multi_root.as_ref() {
+            Some(multi_root) => multi_root.file.with_extension(""),
+            None => self.path.clone()?,
+        };
         let path = path
             .file_name()
             .unwrap_or(path.as_os_str())
@@ -108,6 +299,12 @@ pub fn display(&self) -> Option<String> {
             LapceWorkspaceType::RemoteSSH(ssh) => {
                 format!(" [SSH: {}]", ssh.host)
             }
+            LapceWorkspaceType::RemoteCommand(command) => {
+                format!(" [{command}]")
+            }
+            LapceWorkspaceType::RemoteProxy(host) => {
+                format!(" [Proxy: {host}]")
+            }
             #[cfg(windows)]
             LapceWorkspaceType::RemoteWSL => " [WSL]".to_string(),
         };
@@ -120,18 +317,33 @@ fn default() -> Self {
         Self {
             kind: LapceWorkspaceType::Local,
             path: None,
+            multi_root: None,
             last_open: 0,
         }
     }
 }
 
+/// Write the roots to the workspace file, relative to its directory if
+/// they're inside it
+pub fn save_workspace_file(file: &Path, roots: &[PathBuf]) -> Result<()> {
+    let dir = file.parent().unwrap_or(Path::new(""));
+    std::fs::write(file, format_workspace_file(roots, dir)?)?;
+    Ok(())
+}
+
 impl std::fmt::Display for LapceWorkspace {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // A multi-root workspace is the workspace file rather than its
+        // first folder
+        let path = match self.multi_root.as_ref() {
+            Some(multi_root) => Some(&multi_root.file),
+            None => self.path.as_ref(),
+        };
         write!(
             f,
             "{}:{}",
             self.kind,
-            self.path.as_ref().and_then(|p| p.to_str()).unwrap_or("")
+            path.and_then(|p| p.to_str()).unwrap_or("")
         )
     }
 }
@@ -141,3 +353,59 @@ pub struct WorkspaceInfo {
     pub split: SplitInfo,
     pub panel: PanelInfo,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_workspace_file() {
+        let content = r#"
+            [[folders]]
+            path = "lapce"
+
+            [[folders]]
+            path = "/src/floem"
+
+            [[folders]]
+            path = "lapce"
+        "#;
+        assert_eq!(
+            parse_workspace_file(content, Path::new("/src")).unwrap(),
+            vec![PathBuf::from("/src/lapce"), PathBuf::from("/src/floem")]
+        );
+        assert!(parse_workspace_file("", Path::new("/src"))
+            .unwrap()
+            .is_empty());
+        assert!(parse_workspace_file("folders = 1", Path::new("/src")).is_err());
+    }
+
+    #[test]
+    fn test_parse_workspace_file_parent_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("lapce")).unwrap();
+        std::fs::create_dir_all(dir.path().join("floem")).unwrap();
+        let content = r#"
+            [[folders]]
+            path = "../floem"
+        "#;
+        let folders =
+            parse_workspace_file(content, &dir.path().join("lapce")).unwrap();
+        assert_eq!(
+            folders,
+            vec![canonicalize(&dir.path().join("floem")).unwrap()]
+        );
+        assert!(!folders[0].to_string_lossy().starts_with(r"\\?\"));
+    }
+
+    #[test]
+    fn test_workspace_file_roundtrip() {
+        let folders = vec![PathBuf::from("/src/lapce"), PathBuf::from("/opt/floem")];
+        let content = format_workspace_file(&folders, Path::new("/src")).unwrap();
+        assert!(content.contains("path = \"lapce\""));
+        assert_eq!(
+            parse_workspace_file(&content, Path::new("/src")).unwrap(),
+            folders
+        );
+    }
+}
@@ -7,7 +7,7 @@
     },
 };
 
-use lapce_xi_rope::Rope;
+use lapce_xi_rope::{Delta, DeltaBuilder, Interval, Rope, RopeDelta};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum DiffResult<T> {
@@ -244,3 +244,213 @@ pub fn rope_diff(
 
     Some(changes)
 }
+
+/// Whether the lines a change covers are in `selection`, both being ranges of
+/// lines in the right text. A change that only removes lines covers an empty
+/// range at the line it's before, and is selected when it touches `selection`.
+pub fn diff_lines_selected(lines: &Range<usize>, selection: &Range<usize>) -> bool {
+    if lines.is_empty() {
+        selection.start <= lines.start && lines.start <= selection.end
+    } else {
+        lines.start < selection.end && selection.start < lines.end
+    }
+}
+
+/// Map a line of the right text to the corresponding line of the left text.
+/// Lines that only exist in the right text map to where they'd be in the left.
+pub fn right_line_to_left(changes: &[DiffLines], line: usize) -> usize {
+    let mut left_line = 0;
+    for change in changes {
+        match change {
+            DiffLines::Left(range) => {
+                left_line = range.end;
+            }
+            DiffLines::Both(info) => {
+                if line < info.right.end {
+                    return info.left.start + line.saturating_sub(info.right.start);
+                }
+                left_line = info.left.end;
+            }
+            DiffLines::Right(range) => {
+                if line < range.end {
+                    return left_line;
+                }
+            }
+        }
+    }
+    left_line
+}
+
+/// Build a text from `left` with only the changes towards `right` that
+/// `include` selects applied to it. `include` is given the range of lines in
+/// `right` each change covers, added lines being given one by one, so that
+/// changes can be picked line by line.
+pub fn merge_diff_lines(
+    left: &Rope,
+    right: &Rope,
+    changes: &[DiffLines],
+    include: impl Fn(&Range<usize>) -> bool,
+) -> String {
+    fn push_lines(result: &mut String, rope: &Rope, lines: Range<usize>) {
+        if lines.is_empty() {
+            return;
+        }
+        if !result.is_empty() && !result.ends_with('\n') {
+            result.push('\n');
+        }
+        let start = rope.offset_of_line(lines.start);
+        let end = rope.offset_of_line(lines.end);
+        result.push_str(&rope.slice_to_cow(start..end));
+    }
+
+    let mut result = String::new();
+    let mut right_line = 0;
+    for change in changes {
+        match change {
+            DiffLines::Left(range) => {
+                if !include(&(right_line..right_line)) {
+                    push_lines(&mut result, left, range.clone());
+                }
+            }
+            DiffLines::Both(info) => {
+                push_lines(&mut result, left, info.left.clone());
+                right_line = info.right.end;
+            }
+            DiffLines::Right(range) => {
+                for line in range.clone() {
+                    if include(&(line..line + 1)) {
+                        push_lines(&mut result, right, line..line + 1);
+                    }
+                }
+                right_line = range.end;
+            }
+        }
+    }
+    result
+}
+
+/// Build the delta that turns `left` into `right`, only touching the lines
+/// that differ so that positions in the unchanged lines are kept.
+pub fn rope_diff_delta(left: &Rope, right: &Rope) -> RopeDelta {
+    let changes = rope_diff(
+        left.clone(),
+        right.clone(),
+        0,
+        Arc::new(AtomicU64::new(0)),
+        None,
+    )
+    .unwrap_or_default();
+
+    let mut builder = DeltaBuilder::new(left.len());
+    let mut left_line = 0;
+    for change in &changes {
+        match change {
+            DiffLines::Left(range) => {
+                builder.delete(
+                    left.offset_of_line(range.start)..left.offset_of_line(range.end),
+                );
+                left_line = range.end;
+            }
+            DiffLines::Both(info) => {
+                left_line = info.left.end;
+            }
+            DiffLines::Right(range) => {
+                let offset = left.offset_of_line(left_line);
+                let start = right.offset_of_line(range.start);
+                let end = right.offset_of_line(range.end);
+                builder.replace(offset..offset, right.slice(start..end));
+            }
+        }
+    }
+    let delta = builder.build();
+
+    // the lines don't tell about a missing newline at the end of the text
+    if delta.apply(left).to_string() != right.to_string() {
+        return Delta::simple_edit(
+            Interval::new(0, left.len()),
+            right.clone(),
+            left.len(),
+        );
+    }
+    delta
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::AtomicU64, Arc};
+
+    use lapce_xi_rope::Rope;
+
+    use super::*;
+
+    fn diff(left: &Rope, right: &Rope) -> Vec<DiffLines> {
+        rope_diff(
+            left.clone(),
+            right.clone(),
+            0,
+            Arc::new(AtomicU64::new(0)),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_merge_diff_lines() {
+        let left = Rope::from("a\nb\nc\nd\n");
+        let right = Rope::from("a\nB\nc\nd\ne\n");
+        let changes = diff(&left, &right);
+
+        let all = merge_diff_lines(&left, &right, &changes, |_| true);
+        assert_eq!(all, right.to_string());
+        let none = merge_diff_lines(&left, &right, &changes, |_| false);
+        assert_eq!(none, left.to_string());
+
+        // only the change of the second line
+        let merged = merge_diff_lines(&left, &right, &changes, |lines| {
+            diff_lines_selected(lines, &(1..2))
+        });
+        assert_eq!(merged, "a\nB\nc\nd\n");
+        // only the added last line
+        let merged = merge_diff_lines(&left, &right, &changes, |lines| {
+            diff_lines_selected(lines, &(4..5))
+        });
+        assert_eq!(merged, "a\nb\nc\nd\ne\n");
+    }
+
+    #[test]
+    fn test_merge_diff_lines_no_trailing_newline() {
+        let left = Rope::from("a");
+        let right = Rope::from("a\nb");
+        let changes = diff(&left, &right);
+        let merged = merge_diff_lines(&left, &right, &changes, |_| true);
+        assert_eq!(merged, "a\nb");
+    }
+
+    #[test]
+    fn test_rope_diff_delta() {
+        let left = Rope::from("a\nb\nc\nd\n");
+        let right = Rope::from("x\na\nB\nc\n");
+        let delta = rope_diff_delta(&left, &right);
+        assert_eq!(delta.apply(&left).to_string(), right.to_string());
+        // the unchanged lines are kept rather than the whole text replaced
+        assert!(delta
+            .els
+            .iter()
+            .any(|el| matches!(el, lapce_xi_rope::DeltaElement::Copy(..))));
+
+        let left = Rope::from("a");
+        let right = Rope::from("a\n");
+        let delta = rope_diff_delta(&left, &right);
+        assert_eq!(delta.apply(&left).to_string(), right.to_string());
+    }
+
+    #[test]
+    fn test_right_line_to_left() {
+        let left = Rope::from("a\nb\nc\n");
+        let right = Rope::from("x\na\nc\n");
+        let changes = diff(&left, &right);
+        assert_eq!(right_line_to_left(&changes, 0), 0);
+        assert_eq!(right_line_to_left(&changes, 1), 0);
+        assert_eq!(right_line_to_left(&changes, 2), 2);
+    }
+}
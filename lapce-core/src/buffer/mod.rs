@@ -1,7 +1,7 @@
 use std::{
     borrow::{Borrow, Cow},
     cmp::Ordering,
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     sync::{
         atomic::{self, AtomicU64},
         Arc,
@@ -14,12 +14,14 @@
     tree::{Node, NodeInfo},
     Delta, DeltaBuilder, DeltaElement, Interval, Rope, RopeDelta, RopeInfo,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{
     char_buffer::CharBuffer,
     cursor::CursorMode,
     editor::EditType,
     indent::{auto_detect_indent_style, IndentStyle},
+    line_ending::{auto_detect_line_ending, LineEnding},
     mode::Mode,
     selection::Selection,
     syntax::{self, edit::SyntaxEdit, Syntax},
@@ -28,10 +30,13 @@
 
 pub mod diff;
 pub mod rope_text;
+pub mod undo_history;
+pub mod undo_tree;
 
 use rope_text::*;
+use undo_tree::UndoGroupInfo;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 enum Contents {
     Edit {
         /// Groups related edits together so that they are undone and re-done
@@ -55,7 +60,7 @@ enum Contents {
     },
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct Revision {
     num: u64,
     max_undo_so_far: usize,
@@ -85,11 +90,14 @@ pub struct Buffer {
     live_undos: Vec<usize>,
     deletes_from_union: Subset,
     undone_groups: BTreeSet<usize>,
+    /// Which undo group each one was started from, and when
+    undo_tree: BTreeMap<usize, UndoGroupInfo>,
     tombstones: Rope,
     this_edit_type: EditType,
     last_edit_type: EditType,
 
     indent_style: IndentStyle,
+    line_ending: LineEnding,
 
     max_len: usize,
     max_len_line: usize,
@@ -126,11 +134,13 @@ pub fn new(text: &str) -> Self {
             live_undos: vec![0],
             deletes_from_union: Subset::new(text.len()),
             undone_groups: BTreeSet::new(),
+            undo_tree: BTreeMap::new(),
             tombstones: Rope::default(),
 
             this_edit_type: EditType::Other,
             last_edit_type: EditType::Other,
             indent_style: IndentStyle::DEFAULT_INDENT,
+            line_ending: LineEnding::Lf,
 
             max_len: 0,
             max_len_line: 0,
@@ -264,6 +274,9 @@ pub fn reload(
         if set_pristine {
             self.set_pristine();
         }
+        if let Some(line_ending) = auto_detect_line_ending(&self.text) {
+            self.line_ending = line_ending;
+        }
         (delta, inval_lines, edits)
     }
 
@@ -276,6 +289,45 @@ pub fn indent_unit(&self) -> &'static str {
         self.indent_style.as_str()
     }
 
+    /// Detect the line ending of the content, falling back to `default` when
+    /// there's no line break to go by.
+    pub fn detect_line_ending(&mut self, default: LineEnding) {
+        self.line_ending = auto_detect_line_ending(&self.text).unwrap_or(default);
+    }
+
+    /// The line ending new lines are inserted with
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+    }
+
+    /// Rewrite every line break in the buffer to `line_ending`, as a single
+    /// edit so that it's undone in one go.
+    pub fn convert_line_ending(
+        &mut self,
+        line_ending: LineEnding,
+    ) -> (RopeDelta, InvalLines, SyntaxEdit) {
+        self.line_ending = line_ending;
+
+        let mut builder = DeltaBuilder::new(self.len());
+        let mut offset = 0;
+        for line in self.text.lines_raw(..) {
+            let end = offset + line.len();
+            let is_crlf = line.ends_with("\r\n");
+            if line.ends_with('\n') && is_crlf != (line_ending == LineEnding::CrLf) {
+                let start = if is_crlf { end - 2 } else { end - 1 };
+                builder.replace(start..end, Rope::from(line_ending.as_str()));
+            }
+            offset = end;
+        }
+        let delta = builder.build();
+        self.this_edit_type = EditType::Other;
+        self.add_delta(delta)
+    }
+
     pub fn reset_edit_type(&mut self) {
         self.last_edit_type = EditType::Other;
     }
@@ -379,6 +431,8 @@ fn calculate_undo_group(&mut self) -> usize {
             *self.live_undos.last().unwrap()
         } else {
             let undo_group = self.undo_group_id;
+            self.undo_tree
+                .insert(undo_group, UndoGroupInfo::new(self.current_undo_group()));
             self.live_undos.truncate(self.cur_undo);
             self.live_undos.push(undo_group);
             self.cur_undo += 1;
@@ -679,6 +733,10 @@ fn undo(
             new_tombstones,
             new_deletes_from_union,
         );
+        // The undone edit might have been a line ending conversion
+        if let Some(line_ending) = auto_detect_line_ending(&self.text) {
+            self.line_ending = line_ending;
+        }
 
         (delta, inval_lines, edits, cursor_before, cursor_after)
     }
@@ -4,7 +4,7 @@ mod editing {
     use lapce_xi_rope::Rope;
 
     use super::*;
-    use crate::{editor::EditType, selection::Selection};
+    use crate::{editor::EditType, line_ending::LineEnding, selection::Selection};
 
     #[test]
     fn is_pristine() {
@@ -16,6 +16,96 @@ fn is_pristine() {
         buffer.do_undo();
         assert!(buffer.is_pristine());
     }
+
+    #[test]
+    fn convert_line_ending() {
+        let mut buffer = Buffer::new("");
+        buffer.init_content(Rope::from("a\r\nb\nc\r\n"));
+        buffer.detect_line_ending(LineEnding::Lf);
+        assert_eq!(buffer.line_ending(), LineEnding::CrLf);
+
+        buffer.convert_line_ending(LineEnding::Lf);
+        assert_eq!(buffer.to_string(), "a\nb\nc\n");
+        assert_eq!(buffer.line_ending(), LineEnding::Lf);
+
+        buffer.do_undo();
+        assert_eq!(buffer.to_string(), "a\r\nb\nc\r\n");
+        assert_eq!(buffer.line_ending(), LineEnding::CrLf);
+    }
+
+    #[test]
+    fn restore_undo_history() {
+        let mut buffer = Buffer::new("");
+        buffer.init_content(Rope::from("abc"));
+        buffer.edit(&[(Selection::caret(3), "d")], EditType::InsertChars);
+        buffer.edit(&[(Selection::caret(0), " ")], EditType::Paste);
+        let history = buffer.undo_history();
+
+        let mut other = Buffer::new("");
+        other.init_content(Rope::from("abc"));
+        assert!(!other.restore_undo_history(history.clone()));
+
+        let mut reopened = Buffer::new("");
+        reopened.init_content(Rope::from(" abcd"));
+        assert!(reopened.restore_undo_history(history));
+        assert!(reopened.is_pristine());
+        reopened.do_undo();
+        assert_eq!(reopened.to_string(), "abcd");
+        reopened.do_undo();
+        assert_eq!(reopened.to_string(), "abc");
+        assert!(reopened.do_undo().is_none());
+        reopened.do_redo();
+        assert_eq!(reopened.to_string(), "abcd");
+    }
+
+    #[test]
+    fn undo_tree() {
+        let mut buffer = Buffer::new("");
+        buffer.init_content(Rope::from(""));
+        buffer.edit(&[(Selection::caret(0), "a")], EditType::InsertChars);
+        buffer.edit(&[(Selection::caret(1), "b")], EditType::Paste);
+        buffer.do_undo();
+        // Branches off from "a"
+        buffer.edit(&[(Selection::caret(1), "c")], EditType::Paste);
+        assert_eq!(buffer.to_string(), "ac");
+
+        let tree: Vec<_> = buffer
+            .undo_tree()
+            .into_iter()
+            .map(|node| (node.group, node.parent, node.depth, node.applied))
+            .collect();
+        assert_eq!(
+            tree,
+            vec![
+                (0, None, 0, true),
+                (1, Some(0), 0, true),
+                (2, Some(1), 0, false),
+                (3, Some(1), 1, true),
+            ]
+        );
+
+        assert_eq!(buffer.text_at_undo_group(2).unwrap().to_string(), "ab");
+        assert_eq!(buffer.to_string(), "ac");
+
+        buffer.go_to_undo_group(2);
+        assert_eq!(buffer.to_string(), "ab");
+        assert!(buffer.do_redo().is_none());
+        buffer.do_undo();
+        assert_eq!(buffer.to_string(), "a");
+
+        buffer.undo_later(1);
+        assert_eq!(buffer.to_string(), "ab");
+        buffer.undo_later(1);
+        assert_eq!(buffer.to_string(), "ac");
+        buffer.undo_earlier(3);
+        assert_eq!(buffer.to_string(), "");
+
+        buffer.restore_undo_group(2);
+        assert_eq!(buffer.to_string(), "ab");
+        assert_eq!(buffer.current_undo_group(), 4);
+        buffer.do_undo();
+        assert_eq!(buffer.to_string(), "");
+    }
 }
 
 mod motion {
@@ -0,0 +1,86 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::atomic,
+};
+
+use lapce_xi_rope::{multiset::Subset, Rope};
+use serde::{Deserialize, Serialize};
+
+use super::{undo_tree::UndoGroupInfo, Buffer, Revision};
+use crate::{editor::EditType, stable_hash::stable_hash};
+
+/// The undo history of a buffer, which can be written to disk and given back
+/// to a buffer with the same text when the file is opened again.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UndoHistory {
+    /// See [`text_hash`], of the text the history ends in
+    pub text_hash: u64,
+    rev_counter: u64,
+    revs: Vec<Revision>,
+    cur_undo: usize,
+    undos: BTreeSet<usize>,
+    undo_group_id: usize,
+    live_undos: Vec<usize>,
+    deletes_from_union: Subset,
+    undone_groups: BTreeSet<usize>,
+    #[serde(default)]
+    undo_tree: BTreeMap<usize, UndoGroupInfo>,
+    tombstones: Rope,
+}
+
+impl Buffer {
+    pub fn undo_history(&self) -> UndoHistory {
+        UndoHistory {
+            text_hash: text_hash(&self.text),
+            rev_counter: self.rev_counter,
+            revs: self.revs.clone(),
+            cur_undo: self.cur_undo,
+            undos: self.undos.clone(),
+            undo_group_id: self.undo_group_id,
+            live_undos: self.live_undos.clone(),
+            deletes_from_union: self.deletes_from_union.clone(),
+            undone_groups: self.undone_groups.clone(),
+            undo_tree: self.undo_tree.clone(),
+            tombstones: self.tombstones.clone(),
+        }
+    }
+
+    /// Take over the undo history of an earlier buffer, if it ended in the
+    /// same text as this buffer has. The revision moves on to the one the
+    /// history ends in.
+    ///
+    /// Returns whether the history was restored.
+    pub fn restore_undo_history(&mut self, history: UndoHistory) -> bool {
+        if history.text_hash != text_hash(&self.text)
+            || history.deletes_from_union.len_after_delete() != self.text.len()
+        {
+            return false;
+        }
+
+        let pristine = self.is_pristine();
+        self.rev_counter = history.rev_counter;
+        self.revs = history.revs;
+        self.cur_undo = history.cur_undo;
+        self.undos = history.undos;
+        self.undo_group_id = history.undo_group_id;
+        self.live_undos = history.live_undos;
+        self.deletes_from_union = history.deletes_from_union;
+        self.undone_groups = history.undone_groups;
+        self.undo_tree = history.undo_tree;
+        self.tombstones = history.tombstones;
+        // The next edit mustn't be merged into the last one of the history
+        self.last_edit_type = EditType::Other;
+        self.atomic_rev
+            .store(self.rev_counter, atomic::Ordering::Release);
+        if pristine {
+            self.set_pristine();
+        }
+        true
+    }
+}
+
+/// A [`stable_hash`] of the text, since it's compared against the one written
+/// to disk.
+pub fn text_hash(text: &Rope) -> u64 {
+    stable_hash(text.iter_chunks(..).map(str::as_bytes))
+}
@@ -0,0 +1,243 @@
+use std::{
+    collections::BTreeSet,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use lapce_xi_rope::{Delta, Rope, RopeDelta};
+use serde::{Deserialize, Serialize};
+
+use super::{Buffer, InvalLines};
+use crate::{cursor::CursorMode, editor::EditType, syntax::edit::SyntaxEdit};
+
+/// Where an undo group sits in the undo tree
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UndoGroupInfo {
+    /// The group that was the last one applied when this one was started
+    pub parent: usize,
+    /// When the group was started, in seconds since the unix epoch
+    pub time: u64,
+}
+
+impl UndoGroupInfo {
+    pub fn new(parent: usize) -> Self {
+        Self {
+            parent,
+            time: now(),
+        }
+    }
+}
+
+/// An undo group as listed in the undo tree, where the state of the buffer
+/// after a group is the group and all its ancestors applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UndoTreeNode {
+    pub group: usize,
+    pub parent: Option<usize>,
+    /// When the group was started, in seconds since the unix epoch. The root
+    /// is the content the buffer was loaded with, which has no time.
+    pub time: Option<u64>,
+    /// How many branches off the first one the node is, for indenting it
+    pub depth: usize,
+    /// If the group is applied in the current state
+    pub applied: bool,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl Buffer {
+    /// The undo group the buffer is at, which is `0` before any edit
+    pub fn current_undo_group(&self) -> usize {
+        self.cur_undo
+            .checked_sub(1)
+            .and_then(|i| self.live_undos.get(i))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// When the undo group was started, if known
+    pub fn undo_group_time(&self, group: usize) -> Option<u64> {
+        self.undo_tree.get(&group).map(|info| info.time)
+    }
+
+    fn undo_group_parent(&self, group: usize) -> Option<usize> {
+        if group == 0 {
+            return None;
+        }
+        // Groups of histories from before the tree was kept follow each other
+        Some(
+            self.undo_tree
+                .get(&group)
+                .map(|info| info.parent)
+                .unwrap_or(group - 1),
+        )
+    }
+
+    /// The groups started from each group, oldest first
+    fn undo_group_children(&self) -> Vec<Vec<usize>> {
+        let mut children = vec![Vec::new(); self.undo_group_id.max(1)];
+        for group in 1..self.undo_group_id {
+            if let Some(parent) = self.undo_group_parent(group) {
+                children[parent].push(group);
+            }
+        }
+        children
+    }
+
+    /// The groups from the root down to `group`
+    fn undo_path(&self, group: usize) -> Vec<usize> {
+        let mut path = vec![group];
+        let mut group = group;
+        while let Some(parent) = self.undo_group_parent(group) {
+            path.push(parent);
+            group = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    /// The groups which are undone in the state after `group`
+    fn undone_at(&self, group: usize) -> BTreeSet<usize> {
+        let path: BTreeSet<usize> = self.undo_path(group).into_iter().collect();
+        (0..self.undo_group_id)
+            .filter(|g| !path.contains(g))
+            .collect()
+    }
+
+    /// Every undo group in depth first order, with the branches started
+    /// later after the earlier ones.
+    pub fn undo_tree(&self) -> Vec<UndoTreeNode> {
+        let applied: BTreeSet<usize> = self
+            .undo_path(self.current_undo_group())
+            .into_iter()
+            .collect();
+        let children = self.undo_group_children();
+        let mut nodes = Vec::new();
+        let mut stack = vec![(0, 0)];
+        while let Some((group, depth)) = stack.pop() {
+            nodes.push(UndoTreeNode {
+                group,
+                parent: self.undo_group_parent(group),
+                time: self.undo_group_time(group),
+                depth,
+                applied: applied.contains(&group),
+            });
+            for (i, child) in children[group].iter().enumerate().rev() {
+                stack.push((*child, if i == 0 { depth } else { depth + 1 }));
+            }
+        }
+        nodes
+    }
+
+    /// The delta from the current text to the text in the state after
+    /// `group`
+    fn delta_to_undo_group(&self, group: usize) -> Option<RopeDelta> {
+        if group >= self.undo_group_id {
+            return None;
+        }
+        let (_, deletes_from_union) = self.compute_undo(&self.undone_at(group));
+        Some(Delta::synthesize(
+            &self.tombstones,
+            &self.deletes_from_union,
+            &deletes_from_union,
+        ))
+    }
+
+    /// The text in the state after `group`, without changing the buffer
+    pub fn text_at_undo_group(&self, group: usize) -> Option<Rope> {
+        self.delta_to_undo_group(group)
+            .map(|delta| delta.apply(&self.text))
+    }
+
+    /// Change the text to what it was after `group` as a new edit, so the
+    /// states in between stay where they are in the tree
+    pub fn restore_undo_group(
+        &mut self,
+        group: usize,
+    ) -> Option<(RopeDelta, InvalLines, SyntaxEdit)> {
+        let delta = self.delta_to_undo_group(group)?;
+        if delta.is_identity() {
+            return None;
+        }
+        self.this_edit_type = EditType::Other;
+        Some(self.add_delta(delta))
+    }
+
+    /// Move to the state after `group`, undoing every group that isn't on
+    /// its path. Redo carries on along the branch started last from there.
+    pub fn go_to_undo_group(
+        &mut self,
+        group: usize,
+    ) -> Option<(RopeDelta, InvalLines, SyntaxEdit, Option<CursorMode>)> {
+        let current = self.current_undo_group();
+        if group >= self.undo_group_id || group == current {
+            return None;
+        }
+
+        let children = self.undo_group_children();
+        let mut live_undos = self.undo_path(group);
+        let cur_undo = live_undos.len();
+        let mut last = group;
+        while let Some(child) = children[last].last() {
+            live_undos.push(*child);
+            last = *child;
+        }
+        let undone = self.undone_at(group);
+        self.live_undos = live_undos;
+        self.cur_undo = cur_undo;
+        self.undos = undone.clone();
+        self.last_edit_type = EditType::Undo;
+
+        let (delta, inval_lines, edits, cursor_before, cursor_after) =
+            self.undo(undone);
+        let cursor = if group > current {
+            cursor_after
+        } else {
+            cursor_before
+        };
+        Some((delta, inval_lines, edits, cursor))
+    }
+
+    /// Move `count` states back in the order they were made in, whichever
+    /// branch they're on, like vim's `g-`
+    pub fn undo_earlier(
+        &mut self,
+        count: usize,
+    ) -> Option<(RopeDelta, InvalLines, SyntaxEdit, Option<CursorMode>)> {
+        self.go_to_undo_group(self.current_undo_group().saturating_sub(count))
+    }
+
+    /// Move `count` states forward in the order they were made in, like
+    /// vim's `g+`
+    pub fn undo_later(
+        &mut self,
+        count: usize,
+    ) -> Option<(RopeDelta, InvalLines, SyntaxEdit, Option<CursorMode>)> {
+        let last = self.undo_group_id.saturating_sub(1);
+        self.go_to_undo_group((self.current_undo_group() + count).min(last))
+    }
+
+    /// The last state that was made at or before `time`, or the root if
+    /// there's none
+    pub fn undo_group_at_time(&self, time: u64) -> usize {
+        self.undo_tree
+            .iter()
+            .filter(|(_, info)| info.time <= time)
+            .map(|(group, _)| *group)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The time to go back or forward from for time based navigation, which
+    /// is when the current state was made
+    pub fn current_undo_time(&self) -> u64 {
+        let current = self.current_undo_group();
+        self.undo_group_time(current)
+            .or_else(|| self.undo_tree.values().map(|info| info.time).min())
+            .unwrap_or_else(now)
+    }
+}
@@ -65,6 +65,12 @@ pub enum EditCommand {
     Undo,
     #[strum(serialize = "redo")]
     Redo,
+    #[strum(message = "Go to Earlier Text State")]
+    #[strum(serialize = "undo_earlier")]
+    UndoEarlier,
+    #[strum(message = "Go to Later Text State")]
+    #[strum(serialize = "undo_later")]
+    UndoLater,
     #[strum(message = "Copy")]
     #[strum(serialize = "clipboard_copy")]
     ClipboardCopy,
@@ -86,6 +86,34 @@ pub fn proxy_directory() -> Option<PathBuf> {
             None
         }
     }
+
+    /// Directory to store the unsaved content of documents in, so it can be
+    /// restored the next time Lapce starts
+    pub fn backups_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::data_local_directory() {
+            let dir = dir.join("backups");
+            if !dir.exists() {
+                let _ = std::fs::create_dir(&dir);
+            }
+            Some(dir)
+        } else {
+            None
+        }
+    }
+
+    /// Directory to store the undo histories of files in, when they're kept
+    /// across restarts
+    pub fn undo_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::data_local_directory() {
+            let dir = dir.join("undo");
+            if !dir.exists() {
+                let _ = std::fs::create_dir(&dir);
+            }
+            Some(dir)
+        } else {
+            None
+        }
+    }
     /// Get the path to the themes folder
     /// Themes are stored within as individual toml files
     pub fn themes_directory() -> Option<PathBuf> {
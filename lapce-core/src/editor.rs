@@ -89,6 +89,9 @@ pub fn insert(
     ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
         let mut deltas = Vec::new();
         if let CursorMode::Insert(selection) = &cursor.mode {
+            // Line breaks are inserted in the style the document uses
+            let s = buffer.line_ending().normalize(s);
+            let s = s.as_ref();
             if s.chars().count() != 1 {
                 let (delta, inval_lines, edits) =
                     buffer.edit([(selection, s)], EditType::InsertChars);
@@ -373,7 +376,7 @@ fn insert_new_line(
                 } else {
                     &line_indent
                 };
-                format!("\n{indent}")
+                format!("{}{indent}", buffer.line_ending().as_str())
             };
 
             let selection = Selection::region(region.min(), region.max());
@@ -390,7 +393,10 @@ fn insert_new_line(
                             let selection = Selection::caret(
                                 (region.max() as i32 + shift) as usize,
                             );
-                            let content = format!("\n{line_indent}");
+                            let content = format!(
+                                "{}{line_indent}",
+                                buffer.line_ending().as_str()
+                            );
                             extra_edits.push((selection, content));
                         }
                     }
@@ -794,6 +800,31 @@ fn duplicate_line(
         vec![(delta, inval_lines, edits)]
     }
 
+    /// Move the cursor to where the undone or redone edit was
+    fn cursor_after_undo(
+        cursor: &mut Cursor,
+        buffer: &Buffer,
+        delta: &RopeDelta,
+        cursor_mode: Option<CursorMode>,
+        modal: bool,
+    ) {
+        if let Some(cursor_mode) = cursor_mode {
+            cursor.mode = if modal {
+                CursorMode::Normal(cursor_mode.offset())
+            } else if cursor.is_insert() {
+                cursor_mode
+            } else {
+                CursorMode::Insert(Selection::caret(cursor_mode.offset()))
+            };
+        } else if let Some(new_cursor) =
+            get_first_selection_after(cursor, buffer, delta)
+        {
+            *cursor = new_cursor
+        } else {
+            cursor.apply_delta(delta);
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn do_edit<T: Clipboard>(
         cursor: &mut Cursor,
@@ -1043,23 +1074,13 @@ pub fn do_edit<T: Clipboard>(
                 if let Some((delta, inval_lines, edits, cursor_mode)) =
                     buffer.do_undo()
                 {
-                    if let Some(cursor_mode) = cursor_mode {
-                        cursor.mode = if modal {
-                            CursorMode::Normal(cursor_mode.offset())
-                        } else if cursor.is_insert() {
-                            cursor_mode
-                        } else {
-                            CursorMode::Insert(Selection::caret(
-                                cursor_mode.offset(),
-                            ))
-                        };
-                    } else if let Some(new_cursor) =
-                        get_first_selection_after(cursor, buffer, &delta)
-                    {
-                        *cursor = new_cursor
-                    } else {
-                        cursor.apply_delta(&delta);
-                    }
+                    Self::cursor_after_undo(
+                        cursor,
+                        buffer,
+                        &delta,
+                        cursor_mode,
+                        modal,
+                    );
                     vec![(delta, inval_lines, edits)]
                 } else {
                     vec![]
@@ -1069,23 +1090,32 @@ pub fn do_edit<T: Clipboard>(
                 if let Some((delta, inval_lines, edits, cursor_mode)) =
                     buffer.do_redo()
                 {
-                    if let Some(cursor_mode) = cursor_mode {
-                        cursor.mode = if modal {
-                            CursorMode::Normal(cursor_mode.offset())
-                        } else if cursor.is_insert() {
-                            cursor_mode
-                        } else {
-                            CursorMode::Insert(Selection::caret(
-                                cursor_mode.offset(),
-                            ))
-                        };
-                    } else if let Some(new_cursor) =
-                        get_first_selection_after(cursor, buffer, &delta)
-                    {
-                        *cursor = new_cursor
-                    } else {
-                        cursor.apply_delta(&delta);
-                    }
+                    Self::cursor_after_undo(
+                        cursor,
+                        buffer,
+                        &delta,
+                        cursor_mode,
+                        modal,
+                    );
+                    vec![(delta, inval_lines, edits)]
+                } else {
+                    vec![]
+                }
+            }
+            UndoEarlier | UndoLater => {
+                let result = if matches!(cmd, UndoEarlier) {
+                    buffer.undo_earlier(1)
+                } else {
+                    buffer.undo_later(1)
+                };
+                if let Some((delta, inval_lines, edits, cursor_mode)) = result {
+                    Self::cursor_after_undo(
+                        cursor,
+                        buffer,
+                        &delta,
+                        cursor_mode,
+                        modal,
+                    );
                     vec![(delta, inval_lines, edits)]
                 } else {
                     vec![]
@@ -1642,6 +1672,7 @@ mod test {
         buffer::{rope_text::RopeText, Buffer},
         cursor::{Cursor, CursorMode},
         editor::{DuplicateDirection, Editor},
+        line_ending::LineEnding,
         selection::{SelRegion, Selection},
         syntax::Syntax,
     };
@@ -1656,6 +1687,18 @@ fn test_insert_simple() {
         assert_eq!("aebc", buffer.slice_to_cow(0..buffer.len()));
     }
 
+    #[test]
+    fn test_insert_line_ending() {
+        let mut buffer = Buffer::new("ab\r\n");
+        buffer.detect_line_ending(LineEnding::Lf);
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(1)), None, None);
+
+        Editor::insert(&mut cursor, &mut buffer, "\n", &Syntax::plaintext(), true);
+        assert_eq!("a\r\nb\r\n", buffer.slice_to_cow(0..buffer.len()));
+        assert_eq!(cursor.offset(), 3);
+    }
+
     #[test]
     fn test_insert_multiple_cursor() {
         let mut buffer = Buffer::new("abc\nefg\n");
@@ -1,7 +1,18 @@
-use std::{collections::HashSet, path::Path, str::FromStr};
+use std::{
+    collections::HashSet,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{self, AtomicBool},
+        Arc, Mutex, RwLock,
+    },
+    time::SystemTime,
+};
 
-use once_cell::sync::Lazy;
-use strum_macros::{AsRefStr, Display, EnumMessage, EnumString};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Deserialize;
+use strum_macros::{AsRefStr, EnumMessage, EnumString};
 use tracing::{debug, error};
 use tree_sitter::TreeCursor;
 
@@ -10,7 +21,24 @@
     syntax::highlight::{HighlightConfiguration, HighlightIssue},
 };
 
-pub static RUNTIME_LANGUAGES: Lazy<Vec<SyntaxProperties>> = Lazy::new(Vec::new);
+/// Languages declared in `languages.toml` files, indexed by
+/// `LapceLanguage::Runtime`. Entries are never removed, only replaced or
+/// marked as removed, so that the indices held by open documents stay valid.
+static RUNTIME_LANGUAGES: Lazy<RwLock<Vec<&'static RuntimeLanguage>>> =
+    Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Every runtime language that was created, which has to live on as long as
+/// the program does. A declaration that's changed back to how it was reuses
+/// the language it had then.
+static CREATED_RUNTIME_LANGUAGES: Lazy<Mutex<Vec<&'static RuntimeLanguage>>> =
+    Lazy::new(|| Mutex::new(Vec::new()));
+
+/// The strings of runtime languages, leaked only once however often the
+/// languages are reloaded
+static INTERNED_STRS: Lazy<Mutex<HashSet<&'static str>>> =
+    Lazy::new(|| Mutex::new(HashSet::new()));
+static INTERNED_STR_LISTS: Lazy<Mutex<HashSet<&'static [&'static str]>>> =
+    Lazy::new(|| Mutex::new(HashSet::new()));
 
 #[allow(dead_code)]
 const DEFAULT_CODE_LENS_LIST: &[&str] = &["source_file"];
@@ -122,8 +150,9 @@ struct CommentProperties {
     multi_line_prefix: Option<&'static str>,
 }
 
-/// NOTE: Keep the enum variants "fieldless" so they can cast to usize as array
-/// indices into the LANGUAGES array.  See method `LapceLanguage::properties`.
+/// NOTE: Keep the enum variants "fieldless", apart from `Runtime` which must
+/// stay the last one, as the LANGUAGES array is searched by their order.  See
+/// method `LapceLanguage::properties`.
 ///
 /// Do not assign values to the variants because the number of variants and
 /// number of elements in the LANGUAGES array change as different features
@@ -137,7 +166,6 @@ struct CommentProperties {
     Clone,
     Copy,
     Debug,
-    Display,
     AsRefStr,
     EnumString,
     EnumMessage,
@@ -262,6 +290,17 @@ pub enum LapceLanguage {
     Yaml,
     #[strum(message = "Zig")]
     Zig,
+
+    /// A language declared in a `languages.toml` file, see
+    /// `register_runtime_languages`
+    #[strum(disabled)]
+    Runtime(usize),
+}
+
+impl fmt::Display for LapceLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 /// NOTE: Elements in the array must be in the same order as the enum variants of
@@ -1543,7 +1582,33 @@ impl LapceLanguage {
     const SYSTEM_QUERIES_DIRECTORY: &str = "/usr/share/tree-sitter/grammars";
 
     pub fn from_path(path: &Path) -> LapceLanguage {
-        Self::from_path_raw(path).unwrap_or(LapceLanguage::Plaintext)
+        Self::from_runtime_path(path)
+            .or_else(|| Self::from_path_raw(path))
+            .unwrap_or(LapceLanguage::Plaintext)
+    }
+
+    /// Runtime languages are checked before the builtin ones so that users
+    /// can take over files of a builtin language.
+    fn from_runtime_path(path: &Path) -> Option<LapceLanguage> {
+        let languages = RUNTIME_LANGUAGES.read().ok()?;
+        languages
+            .iter()
+            .find(|l| !l.is_removed() && l.matches_path(path))
+            .map(|l| l.properties.id)
+    }
+
+    /// Find the language of a script from the interpreter in its first line,
+    /// like `#!/usr/bin/env python3`.
+    pub fn from_shebang(line: &str) -> Option<LapceLanguage> {
+        let interpreter = shebang_interpreter(line)?;
+        let languages = RUNTIME_LANGUAGES.read().ok()?;
+        languages
+            .iter()
+            .find(|l| {
+                !l.is_removed()
+                    && l.shebangs.iter().any(|s| s.as_str() == interpreter)
+            })
+            .map(|l| l.properties.id)
     }
 
     fn from_path_raw(path: &Path) -> Option<LapceLanguage> {
@@ -1563,9 +1628,26 @@ fn from_path_raw(path: &Path) -> Option<LapceLanguage> {
     }
 
     pub fn from_name(name: &str) -> Option<LapceLanguage> {
+        if let Some(language) = RUNTIME_LANGUAGES.read().ok().and_then(|languages| {
+            languages
+                .iter()
+                .find(|l| !l.is_removed() && l.name.eq_ignore_ascii_case(name))
+                .map(|l| l.properties.id)
+        }) {
+            return Some(language);
+        }
+
         match LapceLanguage::from_str(name.to_lowercase().as_str()) {
             Ok(v) => Some(v),
             Err(e) => {
+                // The names listed by `languages` are the display names
+                if let Some(l) = LANGUAGES.iter().find(|l| {
+                    strum::EnumMessage::get_message(&l.id)
+                        .map(|m| m.eq_ignore_ascii_case(name))
+                        .unwrap_or(false)
+                }) {
+                    return Some(l.id);
+                }
                 eprintln!("failed parsing {name} LapceLanguage: {e}");
                 None
             }
@@ -1579,14 +1661,54 @@ pub fn languages() -> Vec<&'static str> {
                 langs.push(lang)
             }
         }
+        if let Ok(languages) = RUNTIME_LANGUAGES.read() {
+            langs.extend(
+                languages.iter().filter(|l| !l.is_removed()).map(|l| l.name),
+            );
+        }
         langs
     }
 
-    // NOTE: Instead of using `&LANGUAGES[*self as usize]` directly, the
-    // `debug_assertion` gives better feedback should something has gone wrong
-    // badly.
-    fn properties(&self) -> &SyntaxProperties {
-        let i = *self as usize;
+    /// The name used to refer to the language, in settings and queries
+    /// directories for example.
+    pub fn name(&self) -> &'static str {
+        match self.runtime() {
+            Some(runtime) => runtime.name,
+            None => self.as_ref(),
+        }
+    }
+
+    /// The name shown to the user
+    pub fn display_name(&self) -> &'static str {
+        match self.runtime() {
+            Some(runtime) => runtime.name,
+            None => strum::EnumMessage::get_message(self)
+                .unwrap_or_else(|| self.as_ref()),
+        }
+    }
+
+    fn runtime(&self) -> Option<&'static RuntimeLanguage> {
+        match self {
+            LapceLanguage::Runtime(i) => {
+                RUNTIME_LANGUAGES.read().ok()?.get(*i).copied()
+            }
+            _ => None,
+        }
+    }
+
+    // NOTE: The `debug_assertion` gives better feedback should something has
+    // gone wrong badly with the order of the LANGUAGES array.
+    fn properties(&self) -> &'static SyntaxProperties {
+        if let LapceLanguage::Runtime(_) = self {
+            return match self.runtime() {
+                Some(runtime) => &runtime.properties,
+                None => &LANGUAGES[0],
+            };
+        }
+
+        let i = LANGUAGES
+            .partition_point(|l| l.id < *self)
+            .min(LANGUAGES.len() - 1);
         let l = &LANGUAGES[i];
         debug_assert!(
             l.id == *self,
@@ -1602,7 +1724,9 @@ fn tree_sitter(&self) -> Option<TreeSitterProperties> {
     }
 
     pub fn sticky_header_tags(&self) -> &[&'static str] {
-        if let Some(ts) = self.properties().tree_sitter {
+        if let Some(runtime) = self.runtime() {
+            runtime.sticky_headers
+        } else if let Some(ts) = self.properties().tree_sitter {
             ts.sticky_headers
         } else {
             &[]
@@ -1620,6 +1744,19 @@ pub fn indent_unit(&self) -> &str {
         self.properties().indent
     }
 
+    /// The highlight configuration of a runtime language, which is loaded
+    /// once per registration of the language. Loading it again after it
+    /// failed picks up a grammar or queries that were fixed since.
+    pub(crate) fn runtime_highlight_config(
+        &self,
+    ) -> Result<Arc<HighlightConfiguration>, HighlightIssue> {
+        let runtime = self.runtime().ok_or(HighlightIssue::NotAvailable)?;
+        runtime
+            .highlight_config
+            .get_or_try_init(|| runtime.new_highlight_config().map(Arc::new))
+            .cloned()
+    }
+
     pub(crate) fn new_highlight_config(
         &self,
     ) -> Result<HighlightConfiguration, HighlightIssue> {
@@ -1710,9 +1847,11 @@ pub(crate) fn walk_tree(
         cursor: &mut TreeCursor,
         normal_lines: &mut HashSet<usize>,
     ) {
-        if let Some((list, ignore_list)) =
-            self.tree_sitter().as_ref().map(|p| p.code_lens)
-        {
+        let code_lens = match self.runtime() {
+            Some(runtime) => Some(runtime.code_lens),
+            None => self.tree_sitter().as_ref().map(|p| p.code_lens),
+        };
+        if let Some((list, ignore_list)) = code_lens {
             walk_tree(cursor, normal_lines, list, ignore_list);
         }
     }
@@ -1724,7 +1863,15 @@ fn load_grammar(
 ) -> Result<tree_sitter::Language, HighlightIssue> {
     let mut library_path = path.join(format!("tree-sitter-{}", grammar_name));
     library_path.set_extension(std::env::consts::DLL_EXTENSION);
+    let language_fn_name = format!("tree_sitter_{}", grammar_name.replace('-', "_"));
 
+    load_grammar_library(&library_path, &language_fn_name)
+}
+
+fn load_grammar_library(
+    library_path: &Path,
+    language_fn_name: &str,
+) -> Result<tree_sitter::Language, HighlightIssue> {
     debug!("Grammars dir: {library_path:?}");
     if !library_path.exists() {
         return Err(HighlightIssue::Error(String::from(
@@ -1733,7 +1880,7 @@ fn load_grammar(
     }
 
     debug!("Loading grammar from user grammar dir");
-    let library = match unsafe { libloading::Library::new(&library_path) } {
+    let library = match unsafe { libloading::Library::new(library_path) } {
         Ok(v) => v,
         Err(e) => {
             return Err(HighlightIssue::Error(format!(
@@ -1742,7 +1889,6 @@ fn load_grammar(
             )));
         }
     };
-    let language_fn_name = format!("tree_sitter_{}", grammar_name.replace('-', "_"));
     debug!("Loading grammar with address: '{language_fn_name}'");
     let language = unsafe {
         let language_fn: libloading::Symbol<
@@ -1791,3 +1937,441 @@ fn walk_tree(
         cursor.goto_parent();
     }
 }
+
+/// A language declared in a `languages.toml` file, as a `[[language]]` table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct LanguageDeclaration {
+    pub name: String,
+    /// Globs of the files belonging to the language, like `*.nim`. Globs
+    /// containing a `/` are matched against the whole path.
+    pub file_globs: Vec<String>,
+    /// Interpreters of the scripts belonging to the language, like `python3`
+    pub shebangs: Vec<String>,
+    /// Line comment token, like `//`
+    pub comment: Option<String>,
+    /// Block comment start and end tokens, like `["/*", "*/"]`
+    pub block_comment: Option<(String, String)>,
+    pub indent: Option<String>,
+    /// Path of the tree-sitter grammar library, relative to the file the
+    /// language is declared in
+    pub grammar: Option<PathBuf>,
+    /// The function creating the grammar's language, `tree_sitter_{name}` with
+    /// the name in snake case by default
+    pub grammar_symbol: Option<String>,
+    /// Directory of the `highlights.scm` and `injections.scm` queries,
+    /// relative to the file the language is declared in
+    pub queries: Option<PathBuf>,
+    pub sticky_headers: Vec<String>,
+    pub code_lens: Vec<String>,
+    pub code_lens_ignore: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LanguagesFile {
+    #[serde(default)]
+    pub language: Vec<LanguageDeclaration>,
+}
+
+struct RuntimeLanguage {
+    name: &'static str,
+    properties: SyntaxProperties,
+    file_globs: Vec<String>,
+    shebangs: Vec<String>,
+    /// The grammar library and the name of its language function
+    grammar: Option<(PathBuf, String)>,
+    queries: Option<PathBuf>,
+    code_lens: (&'static [&'static str], &'static [&'static str]),
+    sticky_headers: &'static [&'static str],
+    highlight_config: OnceCell<Arc<HighlightConfiguration>>,
+    /// What the language was created from, to tell if it changed
+    source: (PathBuf, LanguageDeclaration),
+    /// When the grammar and query files were modified as the language was
+    /// created, to tell if they were rebuilt or edited since
+    files_modified: Vec<Option<SystemTime>>,
+    removed: AtomicBool,
+}
+
+impl RuntimeLanguage {
+    fn new(index: usize, dir: PathBuf, declaration: LanguageDeclaration) -> Self {
+        let d = &declaration;
+        let name = intern_str(d.name.trim().to_string());
+        let properties = SyntaxProperties {
+            id: LapceLanguage::Runtime(index),
+            comment: CommentProperties {
+                single_line_start: d.comment.clone().map(intern_str),
+                single_line_end: None,
+                multi_line_start: d
+                    .block_comment
+                    .as_ref()
+                    .map(|(start, _)| intern_str(start.clone())),
+                multi_line_end: d
+                    .block_comment
+                    .as_ref()
+                    .map(|(_, end)| intern_str(end.clone())),
+                multi_line_prefix: None,
+            },
+            indent: intern_str(
+                d.indent.clone().unwrap_or_else(|| "    ".to_string()),
+            ),
+            files: &[],
+            extensions: &[],
+            tree_sitter: None,
+        };
+        let grammar = d.grammar.as_ref().map(|grammar| {
+            let symbol = d.grammar_symbol.clone().unwrap_or_else(|| {
+                format!(
+                    "tree_sitter_{}",
+                    name.to_lowercase().replace(['-', ' '], "_")
+                )
+            });
+            (dir.join(grammar), symbol)
+        });
+        let code_lens = if d.code_lens.is_empty() {
+            (DEFAULT_CODE_LENS_LIST, DEFAULT_CODE_LENS_IGNORE_LIST)
+        } else {
+            (
+                intern_strs(&d.code_lens),
+                if d.code_lens_ignore.is_empty() {
+                    DEFAULT_CODE_LENS_IGNORE_LIST
+                } else {
+                    intern_strs(&d.code_lens_ignore)
+                },
+            )
+        };
+
+        let queries = d.queries.as_ref().map(|queries| dir.join(queries));
+        let files_modified = Self::files_modified(&grammar, &queries);
+        Self {
+            name,
+            properties,
+            file_globs: d.file_globs.clone(),
+            shebangs: d.shebangs.clone(),
+            grammar,
+            queries,
+            code_lens,
+            sticky_headers: intern_strs(&d.sticky_headers),
+            highlight_config: OnceCell::new(),
+            source: (dir, declaration),
+            files_modified,
+            removed: AtomicBool::new(false),
+        }
+    }
+
+    fn files_modified(
+        grammar: &Option<(PathBuf, String)>,
+        queries: &Option<PathBuf>,
+    ) -> Vec<Option<SystemTime>> {
+        let mut files = Vec::new();
+        files.extend(grammar.as_ref().map(|(path, _)| path.clone()));
+        if let Some(queries) = queries {
+            files.push(queries.join(LapceLanguage::HIGHLIGHTS_QUERIES_FILE_NAME));
+            files.push(queries.join("injections.scm"));
+        }
+        files
+            .iter()
+            .map(|file| file.metadata().and_then(|m| m.modified()).ok())
+            .collect()
+    }
+
+    /// If the language is still what `source` declares, with the same grammar
+    /// and query files
+    fn is_up_to_date(&self, source: &(PathBuf, LanguageDeclaration)) -> bool {
+        self.source == *source
+            && self.files_modified
+                == Self::files_modified(&self.grammar, &self.queries)
+    }
+
+    fn is_removed(&self) -> bool {
+        self.removed.load(atomic::Ordering::Relaxed)
+    }
+
+    fn matches_path(&self, path: &Path) -> bool {
+        let Some(file_name) = path.file_name().and_then(|f| f.to_str()) else {
+            return false;
+        };
+        let full_path = path.to_string_lossy().replace('\\', "/");
+        self.file_globs.iter().any(|glob| {
+            let text = if glob.contains('/') {
+                full_path.as_str()
+            } else {
+                file_name
+            };
+            glob_match(glob.as_bytes(), text.as_bytes())
+        })
+    }
+
+    fn new_highlight_config(
+        &self,
+    ) -> Result<HighlightConfiguration, HighlightIssue> {
+        let (library_path, language_fn_name) =
+            self.grammar.as_ref().ok_or(HighlightIssue::NotAvailable)?;
+        let language = load_grammar_library(library_path, language_fn_name)?;
+
+        let read_query = |file_name: &str| {
+            self.queries
+                .as_ref()
+                .and_then(|dir| std::fs::read_to_string(dir.join(file_name)).ok())
+                .unwrap_or_default()
+        };
+        let highlight = read_query(LapceLanguage::HIGHLIGHTS_QUERIES_FILE_NAME);
+        let injection = read_query("injections.scm");
+
+        match HighlightConfiguration::new(language, &highlight, &injection, "") {
+            Ok(x) => Ok(x),
+            Err(x) => {
+                let str = format!("Encountered {x:?} while trying to construct HighlightConfiguration for {}", self.name);
+                error!("{str}");
+                Err(HighlightIssue::Error(str))
+            }
+        }
+    }
+}
+
+/// Make the languages declared in `languages.toml` files available, replacing
+/// the ones registered before. Each declaration comes with the directory of
+/// its file, which its paths are relative to. A later declaration with the
+/// same name as an earlier one takes its place.
+pub fn register_runtime_languages(
+    declarations: Vec<(PathBuf, LanguageDeclaration)>,
+) {
+    let mut unique: Vec<(PathBuf, LanguageDeclaration)> = Vec::new();
+    for (dir, declaration) in declarations {
+        if declaration.name.trim().is_empty() {
+            error!("Language declared in {dir:?} has no name");
+            continue;
+        }
+        unique.retain(|(_, d)| {
+            !d.name.trim().eq_ignore_ascii_case(declaration.name.trim())
+        });
+        unique.push((dir, declaration));
+    }
+
+    let mut languages = RUNTIME_LANGUAGES.write().unwrap_or_else(|e| e.into_inner());
+    let mut declared = vec![false; languages.len()];
+    for source in unique {
+        let index = languages
+            .iter()
+            .position(|l| l.name.eq_ignore_ascii_case(source.1.name.trim()))
+            .unwrap_or(languages.len());
+        if index < languages.len() {
+            declared[index] = true;
+            // Keep the loaded grammar if nothing changed
+            if languages[index].is_up_to_date(&source) {
+                languages[index]
+                    .removed
+                    .store(false, atomic::Ordering::Relaxed);
+                continue;
+            }
+        }
+
+        let mut created = CREATED_RUNTIME_LANGUAGES
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let language = match created
+            .iter()
+            .find(|l| {
+                l.properties.id == LapceLanguage::Runtime(index)
+                    && l.is_up_to_date(&source)
+            })
+            .copied()
+        {
+            Some(language) => {
+                language.removed.store(false, atomic::Ordering::Relaxed);
+                language
+            }
+            None => {
+                let (dir, declaration) = source;
+                let language: &'static RuntimeLanguage = Box::leak(Box::new(
+                    RuntimeLanguage::new(index, dir, declaration),
+                ));
+                created.push(language);
+                language
+            }
+        };
+        if index < languages.len() {
+            languages[index] = language;
+        } else {
+            languages.push(language);
+            declared.push(true);
+        }
+    }
+
+    for (language, declared) in languages.iter().zip(declared) {
+        if !declared {
+            language.removed.store(true, atomic::Ordering::Relaxed);
+        }
+    }
+}
+
+fn intern_str(s: String) -> &'static str {
+    let mut interned = INTERNED_STRS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(&s) = interned.get(s.as_str()) {
+        return s;
+    }
+    let s: &'static str = Box::leak(s.into_boxed_str());
+    interned.insert(s);
+    s
+}
+
+fn intern_strs(strs: &[String]) -> &'static [&'static str] {
+    let strs = strs
+        .iter()
+        .map(|s| intern_str(s.clone()))
+        .collect::<Vec<_>>();
+    let mut interned = INTERNED_STR_LISTS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(&strs) = interned.get(strs.as_slice()) {
+        return strs;
+    }
+    let strs: &'static [&'static str] = Box::leak(strs.into_boxed_slice());
+    interned.insert(strs);
+    strs
+}
+
+/// Match a file glob, where `*` matches anything but `/`, `**` matches
+/// anything, and `?` matches a single character other than `/`. Letters are
+/// matched case insensitively like the extensions of the builtin languages.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', b'/', rest @ ..] => {
+            glob_match(rest, text)
+                || (0..text.len())
+                    .any(|i| text[i] == b'/' && glob_match(rest, &text[i + 1..]))
+        }
+        [b'*', b'*', rest @ ..] => {
+            (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+        }
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| glob_match(rest, &text[i..])),
+        [b'?', rest @ ..] => match text {
+            [c, text @ ..] if *c != b'/' => glob_match(rest, text),
+            _ => false,
+        },
+        [p, rest @ ..] => match text {
+            [c, text @ ..] if c.eq_ignore_ascii_case(p) => glob_match(rest, text),
+            _ => false,
+        },
+    }
+}
+
+/// The program a `#!` line runs, looking through `env`
+fn shebang_interpreter(line: &str) -> Option<&str> {
+    let mut words = line.strip_prefix("#!")?.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|w| !w.starts_with('-'))?;
+    }
+    Some(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        let matches =
+            |glob: &str, text: &str| glob_match(glob.as_bytes(), text.as_bytes());
+        assert!(matches("*.nim", "main.nim"));
+        assert!(matches("*.nim", "main.NIM"));
+        assert!(!matches("*.nim", "main.nims"));
+        assert!(matches("Tiltfile", "Tiltfile"));
+        assert!(matches("?akefile", "Makefile"));
+        assert!(!matches("*.conf", "dir/a.conf"));
+        assert!(matches("**/nginx/*.conf", "/etc/nginx/a.conf"));
+        assert!(matches("**/nginx/*.conf", "nginx/a.conf"));
+        assert!(!matches("**/nginx/*.conf", "/etc/nginx/sites/a.conf"));
+        assert!(matches("/etc/**.conf", "/etc/nginx/sites/a.conf"));
+    }
+
+    #[test]
+    fn test_shebang_interpreter() {
+        assert_eq!(shebang_interpreter("#!/bin/sh"), Some("sh"));
+        assert_eq!(
+            shebang_interpreter("#!/usr/bin/env python3 -u"),
+            Some("python3")
+        );
+        assert_eq!(
+            shebang_interpreter("#!/usr/bin/env -S nu --stdin"),
+            Some("nu")
+        );
+        assert_eq!(shebang_interpreter("#! /usr/bin/tclsh"), Some("tclsh"));
+        assert_eq!(shebang_interpreter("# comment"), None);
+        assert_eq!(shebang_interpreter("#!"), None);
+    }
+
+    #[test]
+    fn test_languages_file() {
+        let file: LanguagesFile = toml::from_str(
+            r##"
+            [[language]]
+            name = "Nim"
+            file-globs = ["*.nim", "*.nims"]
+            comment = "#"
+            block-comment = ["#[", "]#"]
+            grammar = "grammars/nim.so"
+            grammar-symbol = "tree_sitter_nim"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(file.language.len(), 1);
+        let nim = &file.language[0];
+        assert_eq!(nim.file_globs, ["*.nim", "*.nims"]);
+        assert_eq!(
+            nim.block_comment,
+            Some(("#[".to_string(), "]#".to_string()))
+        );
+        assert_eq!(nim.grammar_symbol.as_deref(), Some("tree_sitter_nim"));
+        assert!(nim.shebangs.is_empty());
+    }
+
+    #[test]
+    fn test_register_runtime_languages() {
+        let declaration = |name: &str, comment: &str| {
+            (
+                PathBuf::from("/languages"),
+                LanguageDeclaration {
+                    name: name.to_string(),
+                    comment: Some(comment.to_string()),
+                    ..Default::default()
+                },
+            )
+        };
+
+        register_runtime_languages(vec![
+            declaration("Registered A", "#"),
+            declaration("Registered B", "//"),
+        ]);
+        let a = LapceLanguage::from_name("Registered A").unwrap();
+        let b = LapceLanguage::from_name("Registered B").unwrap();
+        assert!(matches!(a, LapceLanguage::Runtime(_)));
+        assert!(matches!(b, LapceLanguage::Runtime(_)));
+        assert_ne!(a, b);
+
+        // Replacing B and removing A
+        register_runtime_languages(vec![declaration("Registered B", "--")]);
+        assert_eq!(LapceLanguage::from_name("Registered A"), None);
+        assert_eq!(LapceLanguage::from_name("Registered B"), Some(b));
+        assert_eq!(b.comment_token(), "--");
+
+        // Adding A back
+        register_runtime_languages(vec![
+            declaration("Registered B", "--"),
+            declaration("Registered A", ";"),
+        ]);
+        assert_eq!(LapceLanguage::from_name("Registered A"), Some(a));
+        assert_eq!(LapceLanguage::from_name("Registered B"), Some(b));
+        assert_eq!(a.comment_token(), ";");
+
+        // Changing B back to how it was
+        register_runtime_languages(vec![
+            declaration("Registered A", ";"),
+            declaration("Registered B", "//"),
+        ]);
+        assert_eq!(LapceLanguage::from_name("Registered B"), Some(b));
+        assert_eq!(b.comment_token(), "//");
+    }
+}
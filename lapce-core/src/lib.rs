@@ -11,6 +11,7 @@
 pub mod indent;
 pub mod language;
 pub mod lens;
+pub mod line_ending;
 pub mod meta;
 pub mod mode;
 pub mod movement;
@@ -18,6 +19,7 @@
 pub mod register;
 pub mod selection;
 pub mod soft_tab;
+pub mod stable_hash;
 pub mod style;
 pub mod syntax;
 pub mod word;
@@ -0,0 +1,136 @@
+use std::borrow::Cow;
+
+use lapce_xi_rope::Rope;
+
+/// The line ending new lines are inserted with, and which a document can be
+/// converted to as a whole.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+impl LineEnding {
+    /// The line ending of the platform Lapce is running on
+    pub const NATIVE: LineEnding = if cfg!(windows) {
+        LineEnding::CrLf
+    } else {
+        LineEnding::Lf
+    };
+
+    pub fn all() -> [LineEnding; 2] {
+        [LineEnding::Lf, LineEnding::CrLf]
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+
+    /// The short name of the line ending, as shown in the status bar
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::CrLf => "CRLF",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<LineEnding> {
+        match name.to_lowercase().as_str() {
+            "lf" => Some(LineEnding::Lf),
+            "crlf" => Some(LineEnding::CrLf),
+            _ => None,
+        }
+    }
+
+    /// Replace every line ending in `s` with this one.
+    pub fn normalize<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        let needs_change = match self {
+            LineEnding::Lf => s.contains('\r'),
+            LineEnding::CrLf => {
+                s.match_indices('\n').any(|(i, _)| !s[..i].ends_with('\r'))
+            }
+        };
+        if !needs_change {
+            return Cow::Borrowed(s);
+        }
+
+        let mut result = String::with_capacity(s.len());
+        let mut lines = s.split('\n').peekable();
+        while let Some(line) = lines.next() {
+            if lines.peek().is_some() {
+                result.push_str(line.strip_suffix('\r').unwrap_or(line));
+                result.push_str(self.as_str());
+            } else {
+                result.push_str(line);
+            }
+        }
+        Cow::Owned(result)
+    }
+}
+
+impl std::fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Attempts to detect the line ending used in a document, going by which one
+/// the most lines end in.
+///
+/// Returns `None` if the document doesn't have any line breaks yet.
+pub fn auto_detect_line_ending(text: &Rope) -> Option<LineEnding> {
+    let mut lf = 0usize;
+    let mut crlf = 0usize;
+
+    let end = text.offset_of_line(text.line_of_offset(text.len()).min(1000));
+    for line in text.lines_raw(..end) {
+        if line.ends_with("\r\n") {
+            crlf += 1;
+        } else if line.ends_with('\n') {
+            lf += 1;
+        }
+    }
+
+    if lf == 0 && crlf == 0 {
+        None
+    } else if crlf > lf {
+        Some(LineEnding::CrLf)
+    } else {
+        Some(LineEnding::Lf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_detect() {
+        assert_eq!(auto_detect_line_ending(&Rope::from("abc")), None);
+        assert_eq!(
+            auto_detect_line_ending(&Rope::from("a\nb\nc")),
+            Some(LineEnding::Lf)
+        );
+        assert_eq!(
+            auto_detect_line_ending(&Rope::from("a\r\nb\r\nc\n")),
+            Some(LineEnding::CrLf)
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(LineEnding::Lf.normalize("a\r\nb\nc"), "a\nb\nc");
+        assert_eq!(LineEnding::CrLf.normalize("a\r\nb\nc\n"), "a\r\nb\r\nc\r\n");
+        assert!(matches!(LineEnding::Lf.normalize("a\nb"), Cow::Borrowed(_)));
+        assert!(matches!(
+            LineEnding::CrLf.normalize("a\r\nb"),
+            Cow::Borrowed(_)
+        ));
+    }
+}
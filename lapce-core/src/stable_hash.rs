@@ -0,0 +1,29 @@
+/// FNV-1a of the bytes of the chunks, which is the same as the hash of the
+/// chunks joined together. Unlike the std hasher, it stays the same across
+/// runs and builds, for what's written to disk and has to be found or
+/// compared again later.
+pub fn stable_hash<'a>(chunks: impl IntoIterator<Item = &'a [u8]>) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for chunk in chunks {
+        for byte in chunk {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_hash() {
+        assert_eq!(stable_hash(std::iter::empty()), 0xcbf29ce484222325);
+        assert_eq!(stable_hash(["a".as_bytes()]), 0xaf63dc4c8601ec8c);
+        assert_eq!(
+            stable_hash(["foo".as_bytes(), "bar".as_bytes()]),
+            stable_hash(["foobar".as_bytes()])
+        );
+    }
+}
@@ -49,6 +49,7 @@ pub(crate) fn get_highlight_config(lang: LapceLanguage) -> Result<Arc<HighlightC
                     #[cfg(feature = $feature_name)]
                     LapceLanguage::$name => highlights::$name.clone()
                 ),*,
+                LapceLanguage::Runtime(_) => lang.runtime_highlight_config(),
                 _ => Err(HighlightIssue::NotAvailable),
             }
         }
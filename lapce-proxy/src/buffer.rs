@@ -1,21 +1,27 @@
 use std::{
     borrow::Cow,
-    ffi::OsString,
+    collections::hash_map::DefaultHasher,
     fs,
-    fs::File,
-    io::{Read, Write},
+    hash::{Hash, Hasher},
+    io::{self, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
     time::SystemTime,
 };
 
 use anyhow::{anyhow, Result};
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
 use lapce_core::{
     buffer::rope_text::CharIndicesJoin, encoding::offset_utf8_to_utf16,
 };
-use lapce_rpc::buffer::BufferId;
+use lapce_rpc::buffer::{BufferId, FileEncoding};
 use lapce_xi_rope::{interval::IntervalBounds, rope::Rope, RopeDelta};
 use lsp_types::*;
 
+/// How much of a large file is read from disk, or sent to the editor, at once
+const LARGE_FILE_CHUNK_SIZE: usize = 1024 * 1024;
+
 #[derive(Clone)]
 pub struct Buffer {
     pub language_id: &'static str,
@@ -25,14 +31,38 @@ pub struct Buffer {
     pub path: PathBuf,
     pub rev: u64,
     pub mod_time: Option<SystemTime>,
+    /// The encoding the file was read in, which it's saved in as well
+    pub encoding: FileEncoding,
+    /// Hash of the file's content when it was last loaded or saved
+    pub disk_hash: u64,
+    /// If the file is too large to be synced with language servers
+    pub large_file: bool,
 }
 
 impl Buffer {
-    pub fn new(id: BufferId, path: PathBuf) -> Buffer {
-        let (s, read_only) = match load_file(&path) {
-            Ok(s) => (s, false),
-            Err(_) => ("Not Supported".to_string(), true),
+    /// Open the file, in the given encoding or else in the one that's
+    /// detected. Files of at least `large_file_threshold` bytes are opened in
+    /// large file mode.
+    pub fn new(
+        id: BufferId,
+        path: PathBuf,
+        encoding: Option<&FileEncoding>,
+        large_file_threshold: Option<u64>,
+    ) -> Buffer {
+        let large_file = large_file_threshold
+            .zip(fs::metadata(&path).ok())
+            .map(|(threshold, metadata)| metadata.len() >= threshold)
+            .unwrap_or(false);
+        let loaded = match encoding {
+            Some(encoding) => load_file_with_encoding(&path, encoding),
+            None if large_file => load_large_file(&path),
+            None => load_file(&path),
+        };
+        let (s, encoding, read_only) = match loaded {
+            Ok((s, encoding)) => (s, encoding, false),
+            Err(_) => ("Not Supported".to_string(), FileEncoding::default(), true),
         };
+        let disk_hash = content_hash(&s);
         let rope = Rope::from(s);
         let rev = u64::from(!rope.is_empty());
         let language_id = language_id_from_path(&path).unwrap_or("");
@@ -45,10 +75,67 @@ pub fn new(id: BufferId, path: PathBuf) -> Buffer {
             language_id,
             rev,
             mod_time,
+            encoding,
+            disk_hash,
+            large_file,
+        }
+    }
+
+    /// Read the file again in another encoding, returning its content. The
+    /// buffer keeps its text until the edit that replaces it arrives.
+    pub fn reopen_with_encoding(
+        &mut self,
+        encoding: &FileEncoding,
+    ) -> Result<String> {
+        let (content, encoding) = load_file_with_encoding(&self.path, encoding)?;
+        self.encoding = encoding;
+        self.read_only = false;
+        self.mod_time = get_mod_time(&self.path);
+        self.disk_hash = content_hash(&content);
+        Ok(content)
+    }
+
+    /// Whether the file was changed on disk since it was loaded or last
+    /// saved, going by its modification time and then by its content. A
+    /// file that was deleted isn't, as saving just creates it again.
+    pub fn changed_on_disk(&self) -> bool {
+        let mod_time = get_mod_time(&self.path);
+        if mod_time.is_none() || mod_time == self.mod_time {
+            return false;
+        }
+        match load_file_with_encoding(&self.path, &self.encoding) {
+            Ok((content, _)) => content_hash(&content) != self.disk_hash,
+            Err(_) => true,
         }
     }
 
+    /// Whether the buffer has changes that aren't on disk
+    pub fn is_modified(&self) -> bool {
+        content_hash(&self.rope.slice_to_cow(..)) != self.disk_hash
+    }
+
     pub fn save(&mut self, rev: u64) -> Result<()> {
+        let (path, contents, disk_hash) = self.prepare_save(rev)?;
+
+        write_file(&path, &contents).map_err(|err| {
+            if is_permission_denied(&err) {
+                err.context(format!(
+                    "{} or the folder it's in isn't writable",
+                    path.display()
+                ))
+            } else {
+                err
+            }
+        })?;
+
+        self.mark_saved(disk_hash);
+
+        Ok(())
+    }
+
+    /// The path to write, the encoded content and its hash, for saving the
+    /// buffer at revision `rev`
+    pub fn prepare_save(&self, rev: u64) -> Result<(PathBuf, Vec<u8>, u64)> {
         if self.read_only {
             return Err(anyhow!("can't save to read only file"));
         }
@@ -56,41 +143,32 @@ pub fn save(&mut self, rev: u64) -> Result<()> {
         if self.rev != rev {
             return Err(anyhow!("not the right rev"));
         }
-        let bak_extension = self.path.extension().map_or_else(
-            || OsString::from("bak"),
-            |ext| {
-                let mut ext = ext.to_os_string();
-                ext.push(".bak");
-                ext
-            },
-        );
+
+        // Saving through a symlink writes the file it points to, and leaves
+        // the link itself alone
         let path = if self.path.is_symlink() {
             self.path.canonicalize()?
         } else {
             self.path.clone()
         };
-        let new_file = !path.exists();
-
-        let bak_file_path = &path.with_extension(bak_extension);
-        if !new_file {
-            fs::copy(&path, bak_file_path)?;
-        }
 
-        let mut f = fs::OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&path)?;
-        for chunk in self.rope.iter_chunks(..self.rope.len()) {
-            f.write_all(chunk.as_bytes())?;
-        }
+        // Encoded before the file is touched, as not every character can be
+        // written in every encoding
+        let text = self.rope.to_string();
+        let disk_hash = content_hash(&text);
+        let contents = if self.encoding.is_utf8() {
+            text.into_bytes()
+        } else {
+            encode(&text, &self.encoding)?
+        };
 
-        self.mod_time = get_mod_time(&path);
-        if !new_file {
-            fs::remove_file(bak_file_path)?;
-        }
+        Ok((path, contents, disk_hash))
+    }
 
-        Ok(())
+    /// Take note that content with `disk_hash` was written to the file
+    pub fn mark_saved(&mut self, disk_hash: u64) {
+        self.mod_time = get_mod_time(&self.path);
+        self.disk_hash = disk_hash;
     }
 
     pub fn update(
@@ -113,6 +191,12 @@ pub fn update(
         )
     }
 
+    /// Applies the delta and takes over the revision, whatever the current one
+    pub fn sync(&mut self, delta: &RopeDelta, rev: u64) {
+        self.rope = delta.apply(&self.rope);
+        self.rev = rev;
+    }
+
     pub fn get_document(&self) -> String {
         self.rope.to_string()
     }
@@ -165,24 +249,327 @@ pub fn len(&self) -> usize {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// The content from `offset` on, of about `LARGE_FILE_CHUNK_SIZE` bytes,
+    /// and whether it goes to the end. Large files are sent to the editor a
+    /// chunk at a time, so the whole file is never copied into one message.
+    pub fn content_chunk(&self, offset: usize) -> (String, bool) {
+        let len = self.rope.len();
+        let start = offset.min(len);
+        let end = (start + LARGE_FILE_CHUNK_SIZE).min(len);
+        let end = self.rope.at_or_next_codepoint_boundary(end).unwrap_or(len);
+        (self.rope.slice_to_cow(start..end).into_owned(), end == len)
+    }
+}
+
+/// Read the file, detecting its encoding
+pub fn load_file(path: &Path) -> Result<(String, FileEncoding)> {
+    decode(&fs::read(path)?)
+}
+
+/// Read a large file a chunk at a time, so the bytes of the whole file are
+/// never in memory next to its text. Files which aren't plain UTF-8 are read
+/// in one go like any other, to detect their encoding.
+pub fn load_large_file(path: &Path) -> Result<(String, FileEncoding)> {
+    let mut file = fs::File::open(path)?;
+    let len = file.metadata()?.len() as usize;
+    let mut content = String::with_capacity(len);
+    let mut chunk = vec![0; LARGE_FILE_CHUNK_SIZE];
+    // The bytes at the end of the last chunk that are only the start of a
+    // character
+    let mut carry = 0;
+    loop {
+        let read = file.read(&mut chunk[carry..])?;
+        if read == 0 {
+            break;
+        }
+        let filled = carry + read;
+        if content.is_empty() && Encoding::for_bom(&chunk[..filled]).is_some() {
+            return load_file(path);
+        }
+        let valid = match std::str::from_utf8(&chunk[..filled]) {
+            Ok(s) => {
+                content.push_str(s);
+                s.len()
+            }
+            // An error without a length is a character cut off at the end
+            Err(e) if e.error_len().is_none() => {
+                let valid = e.valid_up_to();
+                content.push_str(std::str::from_utf8(&chunk[..valid])?);
+                valid
+            }
+            Err(_) => return load_file(path),
+        };
+        chunk.copy_within(valid..filled, 0);
+        carry = filled - valid;
+    }
+    if carry > 0 {
+        return load_file(path);
+    }
+    Ok((content, FileEncoding::default()))
 }
 
-pub fn load_file(path: &Path) -> Result<String> {
-    read_path_to_string(path)
+/// Read the file in the given encoding, keeping a byte order mark out of
+/// the content
+pub fn load_file_with_encoding(
+    path: &Path,
+    encoding: &FileEncoding,
+) -> Result<(String, FileEncoding)> {
+    decode_with_encoding(&fs::read(path)?, encoding)
+}
+
+/// Whether the file looks like it isn't text, going by its first few
+/// kilobytes
+pub fn is_binary_file(path: &Path) -> bool {
+    let mut sample = Vec::new();
+    let Ok(file) = fs::File::open(path) else {
+        return false;
+    };
+    if file.take(8192).read_to_end(&mut sample).is_err()
+        || Encoding::for_bom(&sample).is_some()
+    {
+        return false;
+    }
+    let sample = &sample[..sample.len() / 2 * 2];
+    sample.contains(&0) && detect_utf16(sample).is_none()
+}
+
+/// Read up to `len` bytes of the file from `offset`, along with the size of
+/// the whole file
+pub fn read_file_bytes(
+    path: &Path,
+    offset: u64,
+    len: u64,
+) -> Result<(Vec<u8>, u64)> {
+    let mut file = fs::File::open(path)?;
+    let file_len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::new();
+    file.take(len).read_to_end(&mut bytes)?;
+    Ok((bytes, file_len))
+}
+
+/// Overwrite bytes of the file in place. Changes that go past the end of
+/// the file are refused, so it keeps its size.
+pub fn write_file_bytes(path: &Path, changes: &[(u64, Vec<u8>)]) -> Result<()> {
+    let mut file = fs::OpenOptions::new().write(true).open(path)?;
+    let file_len = file.metadata()?.len();
+    if changes
+        .iter()
+        .any(|(offset, bytes)| offset + bytes.len() as u64 > file_len)
+    {
+        return Err(anyhow!("the changes go past the end of the file"));
+    }
+    for (offset, bytes) in changes {
+        file.seek(SeekFrom::Start(*offset))?;
+        file.write_all(bytes)?;
+    }
+    file.sync_all()?;
+    Ok(())
+}
+
+/// The offsets where `pattern` is in the file, with the unsaved `changes`
+/// made to it, up to `max_matches` of them. The file is read a chunk at a
+/// time, so files of any size can be searched.
+pub fn search_file_bytes(
+    path: &Path,
+    pattern: &[u8],
+    changes: &[(u64, Vec<u8>)],
+    max_matches: usize,
+) -> Result<Vec<u64>> {
+    const CHUNK_SIZE: usize = 1024 * 1024;
+
+    let mut matches = Vec::new();
+    if pattern.is_empty() {
+        return Ok(matches);
+    }
+    let mut file = fs::File::open(path)?;
+    // Each chunk starts with the end of the one before, to find matches that
+    // cross from one to the next
+    let overlap = pattern.len() - 1;
+    let mut chunk = vec![0; CHUNK_SIZE + overlap];
+    let mut filled = 0;
+    // The offset in the file of the start of the chunk
+    let mut start = 0;
+    loop {
+        let read = file.read(&mut chunk[filled..])?;
+        let end = filled + read;
+        let at_end = read == 0;
+        if !at_end && end < chunk.len() {
+            filled = end;
+            continue;
+        }
+
+        overlay_changes(&mut chunk[..end], start, changes);
+        for (i, window) in chunk[..end].windows(pattern.len()).enumerate() {
+            if window == pattern {
+                matches.push(start + i as u64);
+                if matches.len() >= max_matches {
+                    return Ok(matches);
+                }
+            }
+        }
+        if at_end || end <= overlap {
+            break;
+        }
+
+        let keep = end - overlap;
+        chunk.copy_within(keep..end, 0);
+        filled = overlap;
+        start += keep as u64;
+    }
+    Ok(matches)
+}
+
+/// Apply the changes that fall into `chunk`, which is at `chunk_start` in
+/// the file
+fn overlay_changes(chunk: &mut [u8], chunk_start: u64, changes: &[(u64, Vec<u8>)]) {
+    let chunk_end = chunk_start + chunk.len() as u64;
+    for (offset, bytes) in changes {
+        let change_end = offset + bytes.len() as u64;
+        if change_end <= chunk_start || *offset >= chunk_end {
+            continue;
+        }
+        let from = (*offset).max(chunk_start);
+        let to = change_end.min(chunk_end);
+        chunk[(from - chunk_start) as usize..(to - chunk_start) as usize]
+            .copy_from_slice(
+                &bytes[(from - offset) as usize..(to - offset) as usize],
+            );
+    }
 }
 
 pub fn read_path_to_string<P: AsRef<Path>>(path: P) -> Result<String> {
-    let path = path.as_ref();
+    load_file(path.as_ref()).map(|(content, _)| content)
+}
+
+fn encoding_for_name(name: &str) -> Result<&'static Encoding> {
+    Encoding::for_label(name.as_bytes())
+        .ok_or_else(|| anyhow!("unknown encoding {name}"))
+}
 
-    let mut file = File::open(path)?;
-    // Read the file in as bytes
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
+/// Decode the content of a file, going by its byte order mark if it has
+/// one, and otherwise by what the bytes look like
+pub fn decode(bytes: &[u8]) -> Result<(String, FileEncoding)> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (content, had_errors) =
+            encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        if had_errors {
+            return Err(anyhow!("the file isn't valid {}", encoding.name()));
+        }
+        return Ok((
+            content.into_owned(),
+            FileEncoding::new(encoding.name(), true),
+        ));
+    }
 
-    // Parse the file contents as utf8
-    let contents = String::from_utf8(buffer)?;
+    // checked first, as the zero bytes of UTF-16 are valid UTF-8 as well
+    let encoding = match detect_utf16(bytes) {
+        Some(encoding) => encoding,
+        None => {
+            if let Ok(content) = std::str::from_utf8(bytes) {
+                return Ok((content.to_string(), FileEncoding::default()));
+            }
+            if bytes.contains(&0) {
+                return Err(anyhow!("binary files aren't supported"));
+            }
+            let mut detector = EncodingDetector::new();
+            detector.feed(bytes, true);
+            detector.guess(None, true)
+        }
+    };
+    let (content, had_errors) = encoding.decode_without_bom_handling(bytes);
+    if had_errors {
+        return Err(anyhow!("can't detect the encoding of the file"));
+    }
+    Ok((
+        content.into_owned(),
+        FileEncoding::new(encoding.name(), false),
+    ))
+}
 
-    Ok(contents.to_string())
+/// Decode the content of a file in the given encoding
+pub fn decode_with_encoding(
+    bytes: &[u8],
+    encoding: &FileEncoding,
+) -> Result<(String, FileEncoding)> {
+    let encoding = encoding_for_name(&encoding.name)?;
+    let (bytes, bom) = match Encoding::for_bom(bytes) {
+        Some((bom_encoding, bom_len)) if bom_encoding == encoding => {
+            (&bytes[bom_len..], true)
+        }
+        _ => (bytes, false),
+    };
+    let (content, had_errors) = encoding.decode_without_bom_handling(bytes);
+    if had_errors {
+        return Err(anyhow!("the file isn't valid {}", encoding.name()));
+    }
+    Ok((
+        content.into_owned(),
+        FileEncoding::new(encoding.name(), bom),
+    ))
+}
+
+/// Encode text to be written to a file in the given encoding
+pub fn encode(text: &str, encoding: &FileEncoding) -> Result<Vec<u8>> {
+    let bom = encoding.bom;
+    let encoding = encoding_for_name(&encoding.name)?;
+    let mut bytes = Vec::with_capacity(text.len() + 3);
+
+    // encoding_rs only decodes UTF-16, which is simple enough to encode here
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let le = encoding == UTF_16LE;
+        if bom {
+            bytes.extend_from_slice(if le { &[0xFF, 0xFE] } else { &[0xFE, 0xFF] });
+        }
+        for unit in text.encode_utf16() {
+            if le {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            } else {
+                bytes.extend_from_slice(&unit.to_be_bytes());
+            }
+        }
+        return Ok(bytes);
+    }
+
+    if bom && encoding == UTF_8 {
+        bytes.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+    }
+    let (encoded, _, had_unmappable) = encoding.encode(text);
+    if had_unmappable {
+        return Err(anyhow!(
+            "the file has characters that can't be saved in {}",
+            encoding.name()
+        ));
+    }
+    bytes.extend_from_slice(&encoded);
+    Ok(bytes)
+}
+
+/// Text in UTF-16 without a byte order mark is recognized by the zero
+/// bytes of its ASCII characters, which are all on the same side
+fn detect_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(1024)];
+    let pairs = sample.len() / 2;
+    if pairs == 0 || bytes.len() % 2 != 0 {
+        return None;
+    }
+    let zeros = |start: usize| {
+        sample
+            .iter()
+            .skip(start)
+            .step_by(2)
+            .filter(|b| **b == 0)
+            .count()
+    };
+    let (even, odd) = (zeros(0), zeros(1));
+    if odd > pairs / 2 && even <= pairs / 10 {
+        Some(UTF_16LE)
+    } else if even > pairs / 2 && odd <= pairs / 10 {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
 }
 
 pub fn language_id_from_path(path: &Path) -> Option<&'static str> {
@@ -322,3 +709,337 @@ pub fn get_mod_time<P: AsRef<Path>>(path: P) -> Option<SystemTime> {
         .and_then(|meta| meta.modified())
         .ok()
 }
+
+/// Hash of a file's content, to tell if it changed
+pub fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Whether an error is due to the file, or the folder it's in, not being
+/// writable
+pub fn is_permission_denied(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<io::Error>()
+        .map(|err| err.kind() == io::ErrorKind::PermissionDenied)
+        .unwrap_or(false)
+}
+
+/// Write the file by way of a temporary file in the same folder, which is
+/// renamed over it once it's fully written and synced. That way a crash
+/// never leaves the file half written. The file keeps its permissions and
+/// owner, and files with other hard links are written in place, as are
+/// files in a folder that isn't writable or whose owner can't be kept.
+fn write_file(path: &Path, contents: &[u8]) -> Result<()> {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => Some(metadata),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+    if metadata.as_ref().map(has_other_links).unwrap_or(false) {
+        return write_file_in_place(path, contents);
+    }
+
+    let tmp_path = temp_path(path);
+    let written = write_temp_file(&tmp_path, contents, metadata.as_ref());
+    match written {
+        Ok(true) => {}
+        Ok(false) => {
+            let _ = fs::remove_file(&tmp_path);
+            return write_file_in_place(path, contents);
+        }
+        Err(err) => {
+            let _ = fs::remove_file(&tmp_path);
+            if err.kind() == io::ErrorKind::PermissionDenied && metadata.is_some() {
+                return write_file_in_place(path, contents);
+            }
+            return Err(err.into());
+        }
+    }
+
+    if let Err(err) = fs::rename(&tmp_path, path) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(err.into());
+    }
+    if let Some(dir) = path.parent() {
+        sync_dir(dir);
+    }
+
+    Ok(())
+}
+
+/// Write the new content of a file to `tmp_path`, with the owner and
+/// permissions of the file, returning `false` if the owner can't be kept.
+/// The temporary file is only readable by its owner until then, so that the
+/// content of a private file is never exposed.
+fn write_temp_file(
+    tmp_path: &Path,
+    contents: &[u8],
+    metadata: Option<&fs::Metadata>,
+) -> io::Result<bool> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if metadata.is_some() {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut f = options.open(tmp_path)?;
+    if let Some(metadata) = metadata {
+        if !copy_owner(&f, metadata) {
+            return Ok(false);
+        }
+    }
+    f.write_all(contents)?;
+    // The permissions last, as changing the owner or writing can clear the
+    // setuid bits
+    if let Some(metadata) = metadata {
+        f.set_permissions(metadata.permissions())?;
+    }
+    f.sync_all()?;
+    Ok(true)
+}
+
+/// Write the file over its current content, which keeps everything about
+/// the file but isn't crash safe
+fn write_file_in_place(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut f = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path)?;
+    f.write_all(contents)?;
+    f.sync_all()?;
+    Ok(())
+}
+
+/// Write the file as root with `tee`, run through `pkexec`, or `sudo` when
+/// it isn't installed
+#[cfg(target_os = "linux")]
+pub fn write_file_as_root(path: &Path, contents: &[u8]) -> Result<()> {
+    use std::process::{Command, Stdio};
+
+    let mut cmd = if which::which("pkexec").is_ok() {
+        Command::new("pkexec")
+    } else {
+        let mut cmd = Command::new("sudo");
+        cmd.arg("--non-interactive");
+        cmd
+    };
+    let mut child = cmd
+        .arg("tee")
+        .arg("--")
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(contents)?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "couldn't save as root: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn write_file_as_root(_path: &Path, _contents: &[u8]) -> Result<()> {
+    Err(anyhow!("saving as root is only supported on Linux"))
+}
+
+/// A path next to `path` to write the new content of the file to
+fn temp_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(
+        ".{name}.{}-{}.lapce-save",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+#[cfg(unix)]
+fn has_other_links(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink() > 1
+}
+
+#[cfg(not(unix))]
+fn has_other_links(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Give the file the owner and group from `metadata`, returning whether it
+/// has them now
+#[cfg(unix)]
+fn copy_owner(file: &fs::File, metadata: &fs::Metadata) -> bool {
+    use std::os::unix::{fs::MetadataExt, io::AsRawFd};
+
+    let Ok(current) = file.metadata() else {
+        return false;
+    };
+    if current.uid() == metadata.uid() && current.gid() == metadata.gid() {
+        return true;
+    }
+    unsafe { libc::fchown(file.as_raw_fd(), metadata.uid(), metadata.gid()) == 0 }
+}
+
+#[cfg(not(unix))]
+fn copy_owner(_file: &fs::File, _metadata: &fs::Metadata) -> bool {
+    true
+}
+
+/// Sync the folder, so that a rename in it is on disk
+#[cfg(unix)]
+fn sync_dir(dir: &Path) {
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(
+            decode("héllo".as_bytes()).unwrap(),
+            ("héllo".to_string(), FileEncoding::default())
+        );
+        assert_eq!(
+            decode(b"\xEF\xBB\xBFhi").unwrap(),
+            ("hi".to_string(), FileEncoding::new("UTF-8", true))
+        );
+        assert_eq!(
+            decode(b"\xFF\xFEh\0i\0").unwrap(),
+            ("hi".to_string(), FileEncoding::new("UTF-16LE", true))
+        );
+        assert_eq!(
+            decode(b"\0h\0e\0l\0l\0o").unwrap(),
+            ("hello".to_string(), FileEncoding::new("UTF-16BE", false))
+        );
+        assert!(decode(b"\x7FELF\x02\x01\x01\0\0\0\xC3").is_err());
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        for encoding in [
+            FileEncoding::new("UTF-8", true),
+            FileEncoding::new("UTF-16LE", true),
+            FileEncoding::new("UTF-16BE", false),
+            FileEncoding::new("Shift_JIS", false),
+            FileEncoding::new("windows-1252", false),
+        ] {
+            let text = if encoding.name == "Shift_JIS" {
+                "日本語のテキスト"
+            } else {
+                "café au lait"
+            };
+            let bytes = encode(text, &encoding).unwrap();
+            assert_eq!(
+                decode_with_encoding(&bytes, &encoding).unwrap(),
+                (text.to_string(), encoding)
+            );
+        }
+        assert!(encode("😀", &FileEncoding::new("windows-1252", false)).is_err());
+    }
+
+    #[test]
+    fn test_load_large_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+
+        // The "é"s are cut in half at the end of the first chunk
+        let text = format!("{}{}\n", "a".repeat(1024 * 1024 - 1), "é".repeat(10));
+        fs::write(&path, &text).unwrap();
+        assert_eq!(
+            load_large_file(&path).unwrap(),
+            (text, FileEncoding::default())
+        );
+
+        // Anything that isn't plain UTF-8 is detected like in smaller files
+        fs::write(&path, b"\xFF\xFEh\0i\0").unwrap();
+        assert_eq!(
+            load_large_file(&path).unwrap(),
+            ("hi".to_string(), FileEncoding::new("UTF-16LE", true))
+        );
+    }
+
+    #[test]
+    fn test_file_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.bin");
+
+        fs::write(&path, b"\x7FELF\x02\x01\x01\0\0\0").unwrap();
+        assert!(is_binary_file(&path));
+        assert_eq!(read_file_bytes(&path, 1, 3).unwrap(), (b"ELF".to_vec(), 10));
+        assert_eq!(read_file_bytes(&path, 8, 100).unwrap(), (vec![0, 0], 10));
+
+        write_file_bytes(&path, &[(0, vec![0]), (8, vec![1, 2])]).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"\0ELF\x02\x01\x01\0\x01\x02");
+        assert!(write_file_bytes(&path, &[(9, vec![1, 2])]).is_err());
+
+        assert_eq!(
+            search_file_bytes(&path, b"\x01\x02", &[], 10).unwrap(),
+            vec![8]
+        );
+        assert_eq!(
+            search_file_bytes(&path, b"\x01\x02", &[(6, vec![2])], 10).unwrap(),
+            vec![5, 8]
+        );
+        assert_eq!(
+            search_file_bytes(&path, b"\x01\x02", &[(6, vec![2])], 1).unwrap(),
+            vec![5]
+        );
+        assert_eq!(search_file_bytes(&path, b"ELF", &[], 10).unwrap(), vec![1]);
+        assert!(search_file_bytes(&path, b"ELF", &[(2, vec![0])], 10)
+            .unwrap()
+            .is_empty());
+
+        fs::write(&path, "héllo\n").unwrap();
+        assert!(!is_binary_file(&path));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_file() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        let link = dir.path().join("link.txt");
+
+        write_file(&path, b"first").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        write_file(&path, b"second").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert_eq!(fs::metadata(&path).unwrap().mode() & 0o777, 0o640);
+
+        // A hard link keeps pointing to the same file
+        fs::hard_link(&path, &link).unwrap();
+        write_file(&path, b"third").unwrap();
+        assert_eq!(fs::read(&link).unwrap(), b"third");
+        assert_eq!(fs::metadata(&link).unwrap().nlink(), 2);
+
+        // No temporary file is left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+}
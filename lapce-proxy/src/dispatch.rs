@@ -18,14 +18,18 @@
 use grep_regex::RegexMatcherBuilder;
 use grep_searcher::{sinks::UTF8, SearcherBuilder};
 use indexmap::IndexMap;
+use lapce_core::buffer::diff::{
+    diff_lines_selected, merge_diff_lines, right_line_to_left, rope_diff, DiffLines,
+};
 use lapce_rpc::{
+    buffer::FileEncoding,
     core::{CoreNotification, CoreRpcHandler},
     file::FileNodeItem,
     proxy::{
         ProxyHandler, ProxyNotification, ProxyRequest, ProxyResponse,
-        ProxyRpcHandler, SearchMatch,
+        ProxyRpcHandler, SearchMatch, SAVE_CONFLICT, SAVE_PERMISSION_DENIED,
     },
-    source_control::{DiffInfo, FileDiff},
+    source_control::{BlameHunk, DiffInfo, FileDiff, GitCommit, GitLogFilter},
     style::{LineStyle, SemanticStyles},
     terminal::TermId,
     RequestId, RpcError,
@@ -35,7 +39,11 @@
 use parking_lot::Mutex;
 
 use crate::{
-    buffer::{get_mod_time, load_file, Buffer},
+    buffer::{
+        content_hash, get_mod_time, is_binary_file, is_permission_denied, load_file,
+        load_file_with_encoding, read_file_bytes, search_file_bytes,
+        write_file_as_root, write_file_bytes, Buffer,
+    },
     plugin::{catalog::PluginCatalog, remove_volt, PluginCatalogRpcHandler},
     terminal::Terminal,
     watcher::{FileWatcher, Notify, WatchToken},
@@ -44,8 +52,16 @@
 const OPEN_FILE_EVENT_TOKEN: WatchToken = WatchToken(1);
 const WORKSPACE_EVENT_TOKEN: WatchToken = WatchToken(2);
 
+/// How many matches of a search through the bytes of a file are sent back
+const MAX_FILE_BYTES_MATCHES: usize = 100_000;
+
 pub struct Dispatcher {
     workspace: Option<PathBuf>,
+    /// The other roots of a multi-root workspace
+    folders: Vec<PathBuf>,
+    /// The roots that are watched, shared with the file watcher
+    roots: Arc<Mutex<Vec<PathBuf>>>,
+    last_diff: Arc<Mutex<DiffInfo>>,
     pub proxy_rpc: ProxyRpcHandler,
     core_rpc: CoreRpcHandler,
     catalog_rpc: PluginCatalogRpcHandler,
@@ -55,6 +71,9 @@ pub struct Dispatcher {
     file_watcher: FileWatcher,
     window_id: usize,
     tab_id: usize,
+    /// If a client has initialized the proxy. A proxy that listens on a
+    /// socket outlives its clients, and the next one reattaches to it.
+    initialized: bool,
 }
 
 impl ProxyHandler for Dispatcher {
@@ -63,6 +82,7 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
         match rpc {
             Initialize {
                 workspace,
+                folders,
                 disabled_volts,
                 plugin_configurations,
                 window_id,
@@ -70,28 +90,50 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
             } => {
                 self.window_id = window_id;
                 self.tab_id = tab_id;
-                self.workspace = workspace;
-                self.file_watcher.notify(FileWatchNotifier::new(
-                    self.workspace.clone(),
-                    self.core_rpc.clone(),
-                    self.proxy_rpc.clone(),
-                ));
-                if let Some(workspace) = self.workspace.as_ref() {
-                    self.file_watcher
-                        .watch(workspace, true, WORKSPACE_EVENT_TOKEN);
+                // What an earlier client had opened went away with it
+                self.close_session();
+
+                // The plugins keep running for a client that reattaches to
+                // the same workspace
+                if !self.initialized || self.workspace != workspace {
+                    if self.initialized {
+                        // Start over for the other workspace
+                        self.catalog_rpc.shutdown();
+                        self.catalog_rpc = PluginCatalogRpcHandler::new(
+                            self.core_rpc.clone(),
+                            self.proxy_rpc.clone(),
+                        );
+                        self.file_watcher = FileWatcher::new();
+                    }
+
+                    self.workspace = workspace;
+                    self.folders.clear();
+                    self.roots = Arc::new(Mutex::new(Vec::new()));
+                    self.last_diff = Arc::new(Mutex::new(DiffInfo::default()));
+                    self.file_watcher.notify(FileWatchNotifier::new(
+                        self.roots.clone(),
+                        self.last_diff.clone(),
+                        self.core_rpc.clone(),
+                        self.proxy_rpc.clone(),
+                    ));
+                    self.update_folders(folders);
+
+                    let plugin_rpc = self.catalog_rpc.clone();
+                    let workspace = self.workspace.clone();
+                    thread::spawn(move || {
+                        let mut plugin = PluginCatalog::new(
+                            workspace,
+                            disabled_volts,
+                            plugin_configurations,
+                            plugin_rpc.clone(),
+                        );
+                        plugin_rpc.mainloop(&mut plugin);
+                    });
+                } else {
+                    self.update_folders(folders);
                 }
+                self.initialized = true;
 
-                let plugin_rpc = self.catalog_rpc.clone();
-                let workspace = self.workspace.clone();
-                thread::spawn(move || {
-                    let mut plugin = PluginCatalog::new(
-                        workspace,
-                        disabled_volts,
-                        plugin_configurations,
-                        plugin_rpc.clone(),
-                    );
-                    plugin_rpc.mainloop(&mut plugin);
-                });
                 self.core_rpc.notification(CoreNotification::ProxyStatus {
                     status: lapce_rpc::proxy::ProxyStatus::Connected,
                 });
@@ -104,19 +146,62 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 }
             }
             OpenPaths { paths } => {
-                self.core_rpc
-                    .notification(CoreNotification::OpenPaths { paths });
+                self.core_rpc.notification(CoreNotification::OpenPaths {
+                    paths,
+                    wait: false,
+                });
+            }
+            UpdateWorkspaceFolders { folders } => {
+                self.update_folders(folders);
             }
             OpenFileChanged { path } => {
-                if let Some(buffer) = self.buffers.get(&path) {
-                    if get_mod_time(&buffer.path) == buffer.mod_time {
+                if let Some(buffer) = self.buffers.get_mut(&path) {
+                    let mod_time = get_mod_time(&buffer.path);
+                    if mod_time == buffer.mod_time {
+                        return;
+                    }
+                    if mod_time.is_none() {
+                        self.core_rpc.open_file_deleted(path);
                         return;
                     }
-                    if let Ok(content) = load_file(&buffer.path) {
+                    if let Ok((content, _)) =
+                        load_file_with_encoding(&buffer.path, &buffer.encoding)
+                    {
+                        // The UI reloads a document without unsaved changes,
+                        // so the new content is what it's based on from now on
+                        if !buffer.is_modified() {
+                            buffer.mod_time = mod_time;
+                            buffer.disk_hash = content_hash(&content);
+                        }
                         self.core_rpc.open_file_changed(path, content);
                     }
                 }
             }
+            CloseBuffer { path } => {
+                if let Some(buffer) = self.buffers.remove(&path) {
+                    if !buffer.large_file {
+                        self.catalog_rpc.did_close_document(&path);
+                    }
+                    self.file_watcher.unwatch(&path, OPEN_FILE_EVENT_TOKEN);
+                }
+            }
+            RootSaveFinished { id, path, result } => {
+                let result = result
+                    .map(|disk_hash| {
+                        if let Some(buffer) = self.buffers.get_mut(&path) {
+                            buffer.mark_saved(disk_hash);
+                            if !buffer.large_file {
+                                self.catalog_rpc.did_save_text_document(
+                                    &path,
+                                    buffer.rope.clone(),
+                                );
+                            }
+                        }
+                        ProxyResponse::SaveResponse {}
+                    })
+                    .map_err(|message| RpcError { code: 0, message });
+                self.respond_rpc(id, result);
+            }
             Completion {
                 request_id,
                 path,
@@ -142,16 +227,36 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 self.proxy_rpc.shutdown();
             }
             Update { path, delta, rev } => {
-                let buffer = self.buffers.get_mut(&path).unwrap();
-                let old_text = buffer.rope.clone();
-                buffer.update(&delta, rev);
-                self.catalog_rpc.did_change_text_document(
-                    &path,
-                    rev,
-                    delta,
-                    old_text,
-                    buffer.rope.clone(),
-                );
+                // the buffer isn't there yet when it's being reopened
+                // after reconnecting
+                if let Some(buffer) = self.buffers.get_mut(&path) {
+                    let old_text = buffer.rope.clone();
+                    buffer.update(&delta, rev);
+                    if !buffer.large_file {
+                        self.catalog_rpc.did_change_text_document(
+                            &path,
+                            rev,
+                            delta,
+                            old_text,
+                            buffer.rope.clone(),
+                        );
+                    }
+                }
+            }
+            SyncBuffer { path, delta, rev } => {
+                if let Some(buffer) = self.buffers.get_mut(&path) {
+                    let old_text = buffer.rope.clone();
+                    buffer.sync(&delta, rev);
+                    if !buffer.large_file {
+                        self.catalog_rpc.did_change_text_document(
+                            &path,
+                            rev,
+                            delta,
+                            old_text,
+                            buffer.rope.clone(),
+                        );
+                    }
+                }
             }
             UpdatePluginConfigs { configs } => {
                 let _ = self.catalog_rpc.update_plugin_configs(configs);
@@ -210,9 +315,10 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
             }
             DapStart {
                 config,
+                dap_server,
                 breakpoints,
             } => {
-                let _ = self.catalog_rpc.dap_start(config, breakpoints);
+                let _ = self.catalog_rpc.dap_start(config, dap_server, breakpoints);
             }
             DapProcessId {
                 dap_id,
@@ -269,8 +375,10 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 let _ = self.catalog_rpc.enable_volt(volt);
             }
             GitCommit { message, diffs } => {
-                if let Some(workspace) = self.workspace.as_ref() {
-                    match git_commit(workspace, &message, diffs) {
+                for (root, diffs) in
+                    self.group_by_root(diffs, |diff| diff.path().as_path())
+                {
+                    match git_commit(&root, &message, diffs) {
                         Ok(()) => (),
                         Err(e) => eprintln!("{e:?}"),
                     }
@@ -285,9 +393,9 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 }
             }
             GitDiscardFilesChanges { files } => {
-                if let Some(workspace) = self.workspace.as_ref() {
+                for (root, files) in self.group_by_root(files, PathBuf::as_path) {
                     match git_discard_files_changes(
-                        workspace,
+                        &root,
                         files.iter().map(AsRef::as_ref),
                     ) {
                         Ok(()) => (),
@@ -317,26 +425,94 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
     fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
         use ProxyRequest::*;
         match rpc {
-            NewBuffer { buffer_id, path } => {
-                let buffer = Buffer::new(buffer_id, path.clone());
-                let content = buffer.rope.to_string();
-                let read_only = buffer.read_only;
-                self.catalog_rpc.did_open_document(
-                    &path,
-                    buffer.language_id.to_string(),
-                    buffer.rev as i32,
-                    content.clone(),
+            NewBuffer {
+                buffer_id,
+                path,
+                encoding,
+                large_file_threshold,
+            } => {
+                let buffer = Buffer::new(
+                    buffer_id,
+                    path.clone(),
+                    encoding.as_ref(),
+                    large_file_threshold,
                 );
+                let read_only = buffer.read_only;
+                let encoding = buffer.encoding.clone();
+                let large_file = buffer.large_file;
+                // A large file is sent a chunk at a time, instead of copying
+                // all of it into one message
+                let (content, partial) = if large_file {
+                    let (content, done) = buffer.content_chunk(0);
+                    (content, !done)
+                } else {
+                    (buffer.rope.to_string(), false)
+                };
+                // Files that couldn't be read as text are opened read only
+                let binary = read_only && is_binary_file(&path);
+                // Language servers would choke on the file, so they aren't
+                // told about it at all
+                if !large_file {
+                    self.catalog_rpc.did_open_document(
+                        &path,
+                        buffer.language_id.to_string(),
+                        buffer.rev as i32,
+                        content.clone(),
+                    );
+                }
                 self.file_watcher.watch(&path, false, OPEN_FILE_EVENT_TOKEN);
                 self.buffers.insert(path, buffer);
                 self.respond_rpc(
                     id,
-                    Ok(ProxyResponse::NewBufferResponse { content, read_only }),
+                    Ok(ProxyResponse::NewBufferResponse {
+                        content,
+                        read_only,
+                        encoding,
+                        large_file,
+                        binary,
+                        partial,
+                    }),
                 );
             }
+            BufferChunk { path, offset } => {
+                let result = match self.buffers.get(&path) {
+                    Some(buffer) => {
+                        let (content, done) = buffer.content_chunk(offset);
+                        Ok(ProxyResponse::BufferChunkResponse { content, done })
+                    }
+                    None => Err(RpcError {
+                        code: 0,
+                        message: "the file isn't open".to_string(),
+                    }),
+                };
+                self.respond_rpc(id, result);
+            }
+            ReopenWithEncoding { path, encoding } => {
+                let result = match self.buffers.get_mut(&path) {
+                    Some(buffer) => buffer
+                        .reopen_with_encoding(&encoding)
+                        .map(|content| ProxyResponse::NewBufferResponse {
+                            content,
+                            read_only: false,
+                            encoding: buffer.encoding.clone(),
+                            large_file: buffer.large_file,
+                            binary: false,
+                            partial: false,
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        }),
+                    None => Err(RpcError {
+                        code: 0,
+                        message: "the file isn't open".to_string(),
+                    }),
+                };
+                self.respond_rpc(id, result);
+            }
             BufferHead { path } => {
-                let result = if let Some(workspace) = self.workspace.as_ref() {
-                    let result = file_get_head(workspace, &path);
+                let result = if let Some(root) = self.root_of(&path) {
+                    let result = file_get_head(&root, &path);
                     if let Ok((_blob_id, content)) = result {
                         Ok(ProxyResponse::BufferHeadResponse {
                             version: "head".to_string(),
@@ -365,7 +541,7 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 static WORKER_ID: AtomicU64 = AtomicU64::new(0);
                 let our_id = WORKER_ID.fetch_add(1, Ordering::SeqCst) + 1;
 
-                let workspace = self.workspace.clone();
+                let roots = self.roots();
                 let buffers = self
                     .buffers
                     .iter()
@@ -381,7 +557,7 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                         search_in_path(
                             our_id,
                             &WORKER_ID,
-                            workspace
+                            roots
                                 .iter()
                                 .flat_map(|w| ignore::Walk::new(w).flatten())
                                 .chain(
@@ -445,8 +621,8 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 );
             }
             GitGetRemoteFileUrl { file } => {
-                if let Some(workspace) = self.workspace.as_ref() {
-                    match git_get_remote_file_url(workspace, &file) {
+                if let Some(root) = self.root_of(&file) {
+                    match git_get_remote_file_url(&root, &file) {
                         Ok(s) => self.proxy_rpc.handle_response(
                             id,
                             Ok(ProxyResponse::GitGetRemoteFileUrl { file_url: s }),
@@ -455,6 +631,202 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     }
                 }
             }
+            GitStage { paths } => {
+                let result = self
+                    .group_by_root(paths, PathBuf::as_path)
+                    .into_iter()
+                    .try_for_each(|(root, paths)| {
+                        self.git_op(&root, |root| git_stage(root, &paths))
+                    });
+                self.respond_rpc(id, result.map(|_| ProxyResponse::Success {}));
+            }
+            GitUnstage { paths } => {
+                let result = self
+                    .group_by_root(paths, PathBuf::as_path)
+                    .into_iter()
+                    .try_for_each(|(root, paths)| {
+                        self.git_op(&root, |root| git_unstage(root, &paths))
+                    });
+                self.respond_rpc(id, result.map(|_| ProxyResponse::Success {}));
+            }
+            GitStageLines { path, lines } => {
+                let result = self.working_text(&path).and_then(|working| {
+                    self.git_op(&path, |root| {
+                        git_stage_lines(root, &path, &working, lines)
+                    })
+                });
+                self.respond_rpc(id, result.map(|_| ProxyResponse::Success {}));
+            }
+            GitUnstageLines { path, lines } => {
+                let result = self.working_text(&path).and_then(|working| {
+                    self.git_op(&path, |root| {
+                        git_unstage_lines(root, &path, &working, lines)
+                    })
+                });
+                self.respond_rpc(id, result.map(|_| ProxyResponse::Success {}));
+            }
+            GitIndexContent { path } => {
+                let result = if let Some(root) = self.root_of(&path) {
+                    file_get_index(&root, &path)
+                        .map(|content| ProxyResponse::BufferHeadResponse {
+                            version: "index".to_string(),
+                            content,
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        })
+                } else {
+                    Err(RpcError {
+                        code: 0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            }
+            GitBlame { path } => {
+                let workspace = self.root_of(&path);
+                // Blame the content of the open buffer, so that the hunks match
+                // the lines that are shown in the editor
+                let content = self
+                    .buffers
+                    .get(&path)
+                    .map(|buffer| buffer.rope.to_string());
+                let proxy_rpc = self.proxy_rpc.clone();
+
+                // Blaming a file walks its history, so don't block the proxy thread
+                thread::spawn(move || {
+                    let result = workspace
+                        .ok_or_else(|| anyhow!("no workspace"))
+                        .and_then(|workspace| {
+                            git_blame(&workspace, &path, content.as_deref())
+                        })
+                        .map(|hunks| ProxyResponse::GitBlameResponse { hunks })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitLog {
+                filter,
+                skip,
+                limit,
+            } => {
+                let workspace = self.workspace.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = workspace
+                        .ok_or_else(|| anyhow!("no workspace"))
+                        .and_then(|workspace| {
+                            git_log(&workspace, &filter, skip, limit)
+                        })
+                        .map(|commits| ProxyResponse::GitLogResponse { commits })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitCommitDiffs { commit } => {
+                let result = if let Some(workspace) = self.workspace.as_ref() {
+                    git_commit_diffs(workspace, &commit)
+                        .map(|diffs| ProxyResponse::GitCommitDiffsResponse { diffs })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        })
+                } else {
+                    Err(RpcError {
+                        code: 0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            }
+            DiskContent { path } => {
+                let result = match self.buffers.get(&path) {
+                    Some(buffer) => {
+                        load_file_with_encoding(&buffer.path, &buffer.encoding)
+                    }
+                    None => load_file(&path),
+                }
+                .map(|(content, _)| ProxyResponse::BufferHeadResponse {
+                    version: "disk".to_string(),
+                    content,
+                })
+                .map_err(|e| RpcError {
+                    code: 0,
+                    message: e.to_string(),
+                });
+                self.respond_rpc(id, result);
+            }
+            ReadFileBytes { path, offset, len } => {
+                let result = read_file_bytes(&path, offset, len)
+                    .map(|(bytes, file_len)| ProxyResponse::ReadFileBytesResponse {
+                        bytes,
+                        file_len,
+                    })
+                    .map_err(|e| RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    });
+                self.respond_rpc(id, result);
+            }
+            WriteFileBytes { path, changes } => {
+                let result = write_file_bytes(&path, &changes)
+                    .map(|_| ProxyResponse::Success {})
+                    .map_err(|e| RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    });
+                self.respond_rpc(id, result);
+            }
+            SearchFileBytes {
+                path,
+                pattern,
+                changes,
+            } => {
+                // Reading through a large file takes a while
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = search_file_bytes(
+                        &path,
+                        &pattern,
+                        &changes,
+                        MAX_FILE_BYTES_MATCHES,
+                    )
+                    .map(|matches| ProxyResponse::SearchFileBytesResponse {
+                        matches,
+                    })
+                    .map_err(|e| RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitFileContent { path, revision } => {
+                let result = if let Some(root) = self.root_of(&path) {
+                    file_get_revision(&root, &path, &revision)
+                        .map(|content| ProxyResponse::BufferHeadResponse {
+                            version: revision,
+                            content,
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        })
+                } else {
+                    Err(RpcError {
+                        code: 0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            }
             GetDefinition {
                 request_id,
                 path,
@@ -633,10 +1005,11 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 );
             }
             GetFiles { .. } => {
-                let workspace = self.workspace.clone();
+                let roots = self.roots();
                 let proxy_rpc = self.proxy_rpc.clone();
                 thread::spawn(move || {
-                    let result = if let Some(workspace) = workspace {
+                    let mut items = Vec::new();
+                    for workspace in roots {
                         let git_folder =
                             ignore::overrides::OverrideBuilder::new(&workspace)
                                 .add("!.git/")
@@ -657,7 +1030,6 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                                 .build(),
                         };
 
-                        let mut items = Vec::new();
                         for path in walker.flatten() {
                             if let Some(file_type) = path.file_type() {
                                 if file_type.is_file() {
@@ -665,11 +1037,11 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                                 }
                             }
                         }
-                        Ok(ProxyResponse::GetFilesResponse { items })
-                    } else {
-                        Ok(ProxyResponse::GetFilesResponse { items: Vec::new() })
-                    };
-                    proxy_rpc.handle_response(id, result);
+                    }
+                    proxy_rpc.handle_response(
+                        id,
+                        Ok(ProxyResponse::GetFilesResponse { items }),
+                    );
                 });
             }
             GetOpenFilesContent {} => {
@@ -718,35 +1090,97 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     proxy_rpc.handle_response(id, result);
                 });
             }
-            Save { rev, path } => {
+            Save {
+                rev,
+                path,
+                encoding,
+                force,
+            } => {
                 let buffer = self.buffers.get_mut(&path).unwrap();
-                let result = buffer
-                    .save(rev)
+                if !force && buffer.changed_on_disk() {
+                    self.respond_rpc(
+                        id,
+                        Err(RpcError {
+                            code: SAVE_CONFLICT,
+                            message: format!(
+                                "{} was changed on disk since it was opened",
+                                path.display()
+                            ),
+                        }),
+                    );
+                    return;
+                }
+                let old_encoding = encoding.map(|encoding| {
+                    std::mem::replace(&mut buffer.encoding, encoding)
+                });
+                let result = buffer.save(rev);
+                if let (Err(_), Some(old_encoding)) = (&result, old_encoding) {
+                    buffer.encoding = old_encoding;
+                }
+                if result.is_ok() {
+                    // The file was replaced by renaming a new one over it,
+                    // which the watch on the old one doesn't follow
+                    self.file_watcher.unwatch(&path, OPEN_FILE_EVENT_TOKEN);
+                    self.file_watcher.watch(&path, false, OPEN_FILE_EVENT_TOKEN);
+                }
+                let result = result
                     .map(|_r| {
-                        self.catalog_rpc
-                            .did_save_text_document(&path, buffer.rope.clone());
+                        if !buffer.large_file {
+                            self.catalog_rpc
+                                .did_save_text_document(&path, buffer.rope.clone());
+                        }
                         ProxyResponse::SaveResponse {}
                     })
                     .map_err(|e| RpcError {
-                        code: 0,
+                        code: save_error_code(&e),
                         message: e.to_string(),
                     });
                 self.respond_rpc(id, result);
             }
+            SaveAsRoot { rev, path } => {
+                let buffer = self.buffers.get(&path).unwrap();
+                let (save_path, contents, disk_hash) = match buffer.prepare_save(rev)
+                {
+                    Ok(v) => v,
+                    Err(e) => {
+                        self.respond_rpc(
+                            id,
+                            Err(RpcError {
+                                code: 0,
+                                message: e.to_string(),
+                            }),
+                        );
+                        return;
+                    }
+                };
+                // The password prompt can stay open for a long time, which
+                // mustn't hold up everything else
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = write_file_as_root(&save_path, &contents)
+                        .map(|_| disk_hash)
+                        .map_err(|e| e.to_string());
+                    proxy_rpc.notification(ProxyNotification::RootSaveFinished {
+                        id,
+                        path,
+                        result,
+                    });
+                });
+            }
             SaveBufferAs {
                 buffer_id,
                 path,
                 rev,
                 content,
             } => {
-                let mut buffer = Buffer::new(buffer_id, path.clone());
+                let mut buffer = Buffer::new(buffer_id, path.clone(), None, None);
                 buffer.rope = Rope::from(content);
                 buffer.rev = rev;
                 let result = buffer
                     .save(rev)
                     .map(|_| ProxyResponse::Success {})
                     .map_err(|e| RpcError {
-                        code: 0,
+                        code: save_error_code(&e),
                         message: e.to_string(),
                     });
                 self.buffers.insert(path, buffer);
@@ -880,6 +1314,9 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
 
         Self {
             workspace: None,
+            folders: Vec::new(),
+            roots: Arc::new(Mutex::new(Vec::new())),
+            last_diff: Arc::new(Mutex::new(DiffInfo::default())),
             proxy_rpc,
             core_rpc,
             catalog_rpc: plugin_rpc,
@@ -888,18 +1325,149 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
             file_watcher,
             window_id: 1,
             tab_id: 1,
+            initialized: false,
+        }
+    }
+
+    /// Close the buffers and terminals of the client, e.g. before another
+    /// client attaches
+    fn close_session(&mut self) {
+        for (path, buffer) in self.buffers.drain() {
+            if !buffer.large_file {
+                self.catalog_rpc.did_close_document(&path);
+            }
+            self.file_watcher.unwatch(&path, OPEN_FILE_EVENT_TOKEN);
+        }
+        for (_, sender) in self.terminals.drain() {
+            #[allow(deprecated)]
+            let _ = sender.send(Msg::Shutdown);
         }
     }
 
     fn respond_rpc(&self, id: RequestId, result: Result<ProxyResponse, RpcError>) {
         self.proxy_rpc.handle_response(id, result);
     }
+
+    /// The roots of the workspace, with the main one first
+    fn roots(&self) -> Vec<PathBuf> {
+        self.workspace
+            .iter()
+            .chain(self.folders.iter())
+            .cloned()
+            .collect()
+    }
+
+    /// The root that contains the path, which is the innermost one if roots
+    /// are nested
+    fn root_of(&self, path: &Path) -> Option<PathBuf> {
+        self.roots()
+            .into_iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .or_else(|| self.workspace.clone())
+    }
+
+    /// Split the items by the root that contains their path, as each root
+    /// has its own repository
+    fn group_by_root<T>(
+        &self,
+        items: Vec<T>,
+        path: impl Fn(&T) -> &Path,
+    ) -> Vec<(PathBuf, Vec<T>)> {
+        let mut groups: Vec<(PathBuf, Vec<T>)> = Vec::new();
+        for item in items {
+            let root = match self.root_of(path(&item)) {
+                Some(root) => root,
+                None => continue,
+            };
+            match groups.iter_mut().find(|(r, _)| r == &root) {
+                Some((_, items)) => items.push(item),
+                None => groups.push((root, vec![item])),
+            }
+        }
+        groups
+    }
+
+    /// Set the other roots of the workspace, and watch and tell the
+    /// plugins about the ones that were added or removed
+    fn update_folders(&mut self, folders: Vec<PathBuf>) {
+        let old_roots = self.roots.lock().clone();
+        self.folders = folders
+            .into_iter()
+            .filter(|folder| Some(folder) != self.workspace.as_ref())
+            .fold(Vec::new(), |mut folders, folder| {
+                if !folders.contains(&folder) {
+                    folders.push(folder);
+                }
+                folders
+            });
+        let roots = self.roots();
+
+        for root in old_roots.iter().filter(|root| !roots.contains(root)) {
+            self.file_watcher.unwatch(root, WORKSPACE_EVENT_TOKEN);
+        }
+        for root in roots.iter().filter(|root| !old_roots.contains(root)) {
+            self.file_watcher.watch(root, true, WORKSPACE_EVENT_TOKEN);
+        }
+        if roots == old_roots {
+            return;
+        }
+        *self.roots.lock() = roots.clone();
+
+        let core_rpc = self.core_rpc.clone();
+        let last_diff = self.last_diff.clone();
+        thread::spawn(move || {
+            update_diff(&roots, &core_rpc, &last_diff);
+        });
+        self.catalog_rpc
+            .update_workspace_folders(self.folders.clone());
+    }
+
+    /// The text of a file as the editor has it, which includes the unsaved
+    /// changes of an open document, since those are what line ranges refer to
+    fn working_text(&self, path: &Path) -> Result<Rope, RpcError> {
+        let (text, encoding) = match self.buffers.get(path) {
+            Some(buffer) => (buffer.rope.clone(), buffer.encoding.clone()),
+            None => {
+                let (content, encoding) = load_file(path).map_err(|e| RpcError {
+                    code: 0,
+                    message: e.to_string(),
+                })?;
+                (Rope::from(content), encoding)
+            }
+        };
+        // The index is read and written as UTF-8
+        if encoding.name != FileEncoding::default().name {
+            return Err(RpcError {
+                code: 0,
+                message: format!("can't stage lines of a file in {encoding}"),
+            });
+        }
+        Ok(text)
+    }
+
+    /// Run a git operation on the repository of the root that contains the
+    /// path
+    fn git_op(
+        &self,
+        path: &Path,
+        f: impl FnOnce(&Path) -> Result<()>,
+    ) -> Result<(), RpcError> {
+        let root = self.root_of(path).ok_or_else(|| RpcError {
+            code: 0,
+            message: "no workspace".to_string(),
+        })?;
+        f(&root).map_err(|e| RpcError {
+            code: 0,
+            message: e.to_string(),
+        })
+    }
 }
 
 struct FileWatchNotifier {
     core_rpc: CoreRpcHandler,
     proxy_rpc: ProxyRpcHandler,
-    workspace: Option<PathBuf>,
+    roots: Arc<Mutex<Vec<PathBuf>>>,
     workspace_fs_change_handler: Arc<Mutex<Option<Sender<bool>>>>,
     last_diff: Arc<Mutex<DiffInfo>>,
 }
@@ -912,30 +1480,18 @@ fn notify(&self, events: Vec<(WatchToken, notify::Event)>) {
 
 impl FileWatchNotifier {
     fn new(
-        workspace: Option<PathBuf>,
+        roots: Arc<Mutex<Vec<PathBuf>>>,
+        last_diff: Arc<Mutex<DiffInfo>>,
         core_rpc: CoreRpcHandler,
         proxy_rpc: ProxyRpcHandler,
     ) -> Self {
-        let notifier = Self {
-            workspace,
+        Self {
+            roots,
             core_rpc,
             proxy_rpc,
             workspace_fs_change_handler: Arc::new(Mutex::new(None)),
-            last_diff: Arc::new(Mutex::new(DiffInfo::default())),
-        };
-
-        if let Some(workspace) = notifier.workspace.clone() {
-            let core_rpc = notifier.core_rpc.clone();
-            let last_diff = notifier.last_diff.clone();
-            thread::spawn(move || {
-                if let Some(diff) = git_diff_new(&workspace) {
-                    core_rpc.diff_info(diff.clone());
-                    *last_diff.lock() = diff;
-                }
-            });
+            last_diff,
         }
-
-        notifier
     }
 
     fn handle_fs_events(&self, events: Vec<(WatchToken, notify::Event)>) {
@@ -949,7 +1505,7 @@ fn handle_fs_events(&self, events: Vec<(WatchToken, notify::Event)>) {
     }
 
     fn handle_open_file_fs_event(&self, event: notify::Event) {
-        if event.kind.is_modify() {
+        if event.kind.is_modify() || event.kind.is_remove() {
             for path in event.paths {
                 self.proxy_rpc
                     .notification(ProxyNotification::OpenFileChanged { path });
@@ -982,7 +1538,7 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
 
         let local_handler = self.workspace_fs_change_handler.clone();
         let core_rpc = self.core_rpc.clone();
-        let workspace = self.workspace.clone().unwrap();
+        let roots = self.roots.clone();
         let last_diff = self.last_diff.clone();
         thread::spawn(move || {
             thread::sleep(Duration::from_millis(500));
@@ -1001,13 +1557,8 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
             if explorer_change {
                 core_rpc.workspace_file_change();
             }
-            if let Some(diff) = git_diff_new(&workspace) {
-                let mut last_diff = last_diff.lock();
-                if diff != *last_diff {
-                    core_rpc.diff_info(diff.clone());
-                    *last_diff = diff;
-                }
-            }
+            let roots = roots.lock().clone();
+            update_diff(&roots, &core_rpc, &last_diff);
         });
         *handler = Some(sender);
     }
@@ -1022,6 +1573,16 @@ pub struct DiffHunk {
     pub header: String,
 }
 
+/// The error code a failed save is responded with, which tells the UI if it
+/// can offer saving as root instead
+fn save_error_code(err: &anyhow::Error) -> i64 {
+    if cfg!(target_os = "linux") && is_permission_denied(err) {
+        SAVE_PERMISSION_DENIED
+    } else {
+        0
+    }
+}
+
 fn git_init(workspace_path: &Path) -> Result<()> {
     if Repository::discover(workspace_path).is_err() {
         Repository::init(workspace_path)?;
@@ -1067,6 +1628,148 @@ fn git_commit(
     Ok(())
 }
 
+fn git_stage(workspace_path: &Path, paths: &[PathBuf]) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let mut index = repo.index()?;
+    for path in paths {
+        let relative = path.strip_prefix(workspace_path)?;
+        if path.exists() {
+            index.add_path(relative)?;
+        } else {
+            index.remove_path(relative)?;
+        }
+    }
+    index.write()?;
+    Ok(())
+}
+
+fn git_unstage(workspace_path: &Path, paths: &[PathBuf]) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let paths = paths
+        .iter()
+        .map(|p| p.strip_prefix(workspace_path))
+        .collect::<Result<Vec<_>, _>>()?;
+    // Without a commit to reset to, unstaging removes the paths from the index
+    let head = repo.head().and_then(|head| head.peel_to_commit()).ok();
+    repo.reset_default(head.as_ref().map(|c| c.as_object()), paths)?;
+    Ok(())
+}
+
+/// The content of a file in the index, which is empty for a file that isn't
+/// in the index, and the index entry of it.
+fn git_index_content(
+    repo: &Repository,
+    index: &git2::Index,
+    relative: &Path,
+) -> Result<(String, Option<git2::IndexEntry>)> {
+    match index.get_path(relative, 0) {
+        Some(entry) => {
+            let blob = repo.find_blob(entry.id)?;
+            let content = std::str::from_utf8(blob.content())
+                .with_context(|| "content bytes to string")?
+                .to_string();
+            Ok((content, Some(entry)))
+        }
+        None => Ok((String::new(), None)),
+    }
+}
+
+/// Write the content of a file to the index, creating an entry for it if it
+/// isn't in the index yet.
+fn git_index_write(
+    index: &mut git2::Index,
+    relative: &Path,
+    entry: Option<git2::IndexEntry>,
+    content: &str,
+) -> Result<()> {
+    let entry = entry.unwrap_or_else(|| git2::IndexEntry {
+        ctime: git2::IndexTime::new(0, 0),
+        mtime: git2::IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode: 0o100644,
+        uid: 0,
+        gid: 0,
+        file_size: 0,
+        id: git2::Oid::zero(),
+        flags: 0,
+        flags_extended: 0,
+        path: relative.to_string_lossy().into_owned().into_bytes(),
+    });
+    index.add_frombuffer(&entry, content.as_bytes())?;
+    index.write()?;
+    Ok(())
+}
+
+fn lines_diff(left: &Rope, right: &Rope) -> Vec<DiffLines> {
+    rope_diff(
+        left.clone(),
+        right.clone(),
+        0,
+        Arc::new(AtomicU64::new(0)),
+        None,
+    )
+    .unwrap_or_default()
+}
+
+fn git_stage_lines(
+    workspace_path: &Path,
+    path: &Path,
+    working: &Rope,
+    lines: std::ops::Range<usize>,
+) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let mut index = repo.index()?;
+    let relative = path.strip_prefix(workspace_path)?;
+    let (staged, entry) = git_index_content(&repo, &index, relative)?;
+    let staged = Rope::from(staged);
+
+    let changes = lines_diff(&staged, working);
+    let content = merge_diff_lines(&staged, working, &changes, |l| {
+        diff_lines_selected(l, &lines)
+    });
+    git_index_write(&mut index, relative, entry, &content)
+}
+
+fn git_unstage_lines(
+    workspace_path: &Path,
+    path: &Path,
+    working: &Rope,
+    lines: std::ops::Range<usize>,
+) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let mut index = repo.index()?;
+    let relative = path.strip_prefix(workspace_path)?;
+    let (staged, entry) = git_index_content(&repo, &index, relative)?;
+    let staged = Rope::from(staged);
+    let head = file_get_head(workspace_path, path)
+        .map(|(_, content)| content)
+        .unwrap_or_default();
+    let head = Rope::from(head);
+
+    // The lines are the ones of the working copy, so find where they are in
+    // the index first.
+    let working_changes = lines_diff(&staged, working);
+    let lines = right_line_to_left(&working_changes, lines.start)
+        ..right_line_to_left(&working_changes, lines.end);
+
+    let changes = lines_diff(&head, &staged);
+    let content = merge_diff_lines(&head, &staged, &changes, |l| {
+        !diff_lines_selected(l, &lines)
+    });
+    git_index_write(&mut index, relative, entry, &content)
+}
+
+/// The content of a file in the index, which is empty for a file that isn't
+/// in the index
+fn file_get_index(workspace_path: &Path, path: &Path) -> Result<String> {
+    let repo = Repository::discover(workspace_path)?;
+    let index = repo.index()?;
+    let relative = path.strip_prefix(workspace_path)?;
+    let (content, _) = git_index_content(&repo, &index, relative)?;
+    Ok(content)
+}
+
 fn git_checkout(workspace_path: &Path, reference: &str) -> Result<()> {
     let repo = Repository::discover(workspace_path)?;
     let (object, reference) = repo.revparse_ext(reference)?;
@@ -1143,6 +1846,7 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
     let repo = Repository::discover(workspace_path).ok()?;
     let head = repo.head().ok()?;
     let name = head.shorthand()?.to_string();
+    let head_commit = head.target().map(|id| id.to_string()).unwrap_or_default();
 
     let mut branches = Vec::new();
     for branch in repo.branches(None).ok()? {
@@ -1156,7 +1860,6 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
         }
     }
 
-    let mut deltas = Vec::new();
     let mut diff_options = DiffOptions::new();
     let diff = repo
         .diff_index_to_workdir(
@@ -1168,11 +1871,8 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
             ),
         )
         .ok()?;
-    for delta in diff.deltas() {
-        if let Some(delta) = git_delta_format(workspace_path, &delta) {
-            deltas.push(delta);
-        }
-    }
+    let diffs = git_file_diffs(workspace_path, &diff);
+
     let cached_diff = repo
         .diff_tree_to_index(
             repo.find_tree(repo.revparse_single("HEAD^{tree}").ok()?.id())
@@ -1182,7 +1882,58 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
             None,
         )
         .ok()?;
-    for delta in cached_diff.deltas() {
+    let staged = git_file_diffs(workspace_path, &cached_diff);
+
+    Some(DiffInfo {
+        head: name,
+        head_commit,
+        branches,
+        tags,
+        diffs,
+        staged,
+    })
+}
+
+/// Send the diff of the roots to the app, if it changed since the last one
+fn update_diff(
+    roots: &[PathBuf],
+    core_rpc: &CoreRpcHandler,
+    last_diff: &Mutex<DiffInfo>,
+) {
+    if let Some(diff) = git_diff_roots(roots) {
+        let mut last_diff = last_diff.lock();
+        if diff != *last_diff {
+            core_rpc.diff_info(diff.clone());
+            *last_diff = diff;
+        }
+    }
+}
+
+/// The diff of the repositories of all roots. The branches are the ones of
+/// the main root.
+fn git_diff_roots(roots: &[PathBuf]) -> Option<DiffInfo> {
+    let mut diffs = roots.iter().filter_map(|root| git_diff_new(root));
+    let mut diff_info = diffs.next()?;
+    for diff in diffs {
+        for file_diff in diff.diffs {
+            if !diff_info.diffs.contains(&file_diff) {
+                diff_info.diffs.push(file_diff);
+            }
+        }
+        for file_diff in diff.staged {
+            if !diff_info.staged.contains(&file_diff) {
+                diff_info.staged.push(file_diff);
+            }
+        }
+    }
+    Some(diff_info)
+}
+
+/// The changed files of a diff, where an added and a deleted file with the same
+/// content are a renamed file.
+fn git_file_diffs(workspace_path: &Path, diff: &git2::Diff) -> Vec<FileDiff> {
+    let mut deltas = Vec::new();
+    for delta in diff.deltas() {
         if let Some(delta) = git_delta_format(workspace_path, &delta) {
             deltas.push(delta);
         }
@@ -1228,12 +1979,7 @@ fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
         | FileDiff::Renamed(p, _)
         | FileDiff::Deleted(p) => p.clone(),
     });
-    Some(DiffInfo {
-        head: name,
-        branches,
-        tags,
-        diffs: file_diffs,
-    })
+    file_diffs
 }
 
 fn file_get_head(workspace_path: &Path, path: &Path) -> Result<(String, String)> {
@@ -1249,6 +1995,181 @@ fn file_get_head(workspace_path: &Path, path: &Path) -> Result<(String, String)>
     Ok((id, content))
 }
 
+/// Get the content of a file at a revision, which can be anything
+/// `git rev-parse` understands, like `HEAD` or `<commit>^`
+fn file_get_revision(
+    workspace_path: &Path,
+    path: &Path,
+    revision: &str,
+) -> Result<String> {
+    let repo = Repository::discover(workspace_path)?;
+    let workdir = repo.workdir().ok_or_else(|| anyhow!("no workdir"))?;
+    let tree = repo.revparse_single(revision)?.peel_to_tree()?;
+    let tree_entry = tree.get_path(path.strip_prefix(workdir)?)?;
+    let blob = repo.find_blob(tree_entry.id())?;
+    let content = std::str::from_utf8(blob.content())
+        .with_context(|| "content bytes to string")?
+        .to_string();
+    Ok(content)
+}
+
+/// Blame a file, or the given content of it if it has unsaved changes
+fn git_blame(
+    workspace_path: &Path,
+    path: &Path,
+    content: Option<&str>,
+) -> Result<Vec<BlameHunk>> {
+    let repo = Repository::discover(workspace_path)?;
+    let workdir = repo.workdir().ok_or_else(|| anyhow!("no workdir"))?;
+    let blame = repo.blame_file(path.strip_prefix(workdir)?, None)?;
+    let blame = match content {
+        Some(content) => blame.blame_buffer(content.as_bytes())?,
+        None => blame,
+    };
+
+    let mut summaries: HashMap<git2::Oid, String> = HashMap::new();
+    let mut hunks = Vec::new();
+    for hunk in blame.iter() {
+        let commit_id = hunk.final_commit_id();
+        let summary = if commit_id.is_zero() {
+            String::new()
+        } else {
+            summaries
+                .entry(commit_id)
+                .or_insert_with(|| {
+                    repo.find_commit(commit_id)
+                        .ok()
+                        .and_then(|commit| commit.summary().map(|s| s.to_string()))
+                        .unwrap_or_default()
+                })
+                .clone()
+        };
+        let signature = hunk.final_signature();
+        hunks.push(BlameHunk {
+            start_line: hunk.final_start_line().saturating_sub(1),
+            lines: hunk.lines_in_hunk(),
+            commit_id: commit_id.to_string(),
+            author: signature.name().unwrap_or_default().to_string(),
+            time: signature.when().seconds(),
+            summary,
+        });
+    }
+    Ok(hunks)
+}
+
+/// List the commits reachable from `HEAD` that pass the filter, in topological
+/// order with the newest first
+fn git_log(
+    workspace_path: &Path,
+    filter: &GitLogFilter,
+    skip: usize,
+    limit: usize,
+) -> Result<Vec<GitCommit>> {
+    let repo = Repository::discover(workspace_path)?;
+    let workdir = repo.workdir().ok_or_else(|| anyhow!("no workdir"))?;
+    let path = match filter.path.as_ref() {
+        Some(path) => Some(path.strip_prefix(workdir)?),
+        None => None,
+    }
+    // The root of the repository is changed by every commit
+    .filter(|path| !path.as_os_str().is_empty());
+    let author = filter.author.to_lowercase();
+    let message = filter.message.to_lowercase();
+
+    let mut refs: HashMap<git2::Oid, Vec<String>> = HashMap::new();
+    for reference in repo.references()?.flatten() {
+        if !reference.is_branch() && !reference.is_tag() {
+            continue;
+        }
+        if let (Some(name), Ok(commit)) =
+            (reference.shorthand(), reference.peel_to_commit())
+        {
+            refs.entry(commit.id()).or_default().push(name.to_string());
+        }
+    }
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+
+    let mut commits = Vec::new();
+    let mut skipped = 0;
+    for id in revwalk {
+        let commit = repo.find_commit(id?)?;
+        let signature = commit.author();
+
+        if !author.is_empty() {
+            let name = signature.name().unwrap_or_default().to_lowercase();
+            let email = signature.email().unwrap_or_default().to_lowercase();
+            if !name.contains(&author) && !email.contains(&author) {
+                continue;
+            }
+        }
+        if !message.is_empty()
+            && !commit
+                .message()
+                .unwrap_or_default()
+                .to_lowercase()
+                .contains(&message)
+        {
+            continue;
+        }
+        if let Some(path) = path {
+            if !git_commit_touches(&commit, path)? {
+                continue;
+            }
+        }
+
+        if skipped < skip {
+            skipped += 1;
+            continue;
+        }
+        commits.push(GitCommit {
+            id: commit.id().to_string(),
+            parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+            author: signature.name().unwrap_or_default().to_string(),
+            time: commit.time().seconds(),
+            summary: commit.summary().unwrap_or_default().to_string(),
+            refs: refs.remove(&commit.id()).unwrap_or_default(),
+        });
+        if commits.len() >= limit {
+            break;
+        }
+    }
+    Ok(commits)
+}
+
+/// If the commit changed the file or directory at the relative path. Like
+/// `git log`, a merge commit only counts if it differs from all its parents.
+fn git_commit_touches(commit: &git2::Commit, path: &Path) -> Result<bool> {
+    let entry_id = |commit: &git2::Commit| -> Result<Option<git2::Oid>> {
+        Ok(commit.tree()?.get_path(path).ok().map(|entry| entry.id()))
+    };
+    let id = entry_id(commit)?;
+    if commit.parent_count() == 0 {
+        return Ok(id.is_some());
+    }
+    for parent in commit.parents() {
+        if entry_id(&parent)? == id {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// The files a commit changed compared to its first parent
+fn git_commit_diffs(workspace_path: &Path, commit: &str) -> Result<Vec<FileDiff>> {
+    let repo = Repository::discover(workspace_path)?;
+    let commit = repo.revparse_single(commit)?.peel_to_commit()?;
+    let parent_tree = match commit.parents().next() {
+        Some(parent) => Some(parent.tree()?),
+        None => None,
+    };
+    let diff =
+        repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    Ok(git_file_diffs(workspace_path, &diff))
+}
+
 fn git_get_remote_file_url(workspace_path: &Path, file: &Path) -> Result<String> {
     let repo = Repository::discover(workspace_path)?;
     let head = repo.head()?;
@@ -17,15 +17,18 @@
 
 use anyhow::{anyhow, Result};
 use clap::Parser;
+use crossbeam_channel::Sender;
 use dispatch::Dispatcher;
 use lapce_core::{directory::Directory, meta};
 use lapce_rpc::{
-    core::{CoreRpc, CoreRpcHandler},
+    core::{CoreNotification, CoreRequest, CoreRpc, CoreRpcHandler},
     file::PathObject,
-    proxy::{ProxyMessage, ProxyNotification, ProxyRpcHandler},
+    proxy::{ProxyMessage, ProxyNotification, ProxyResponse, ProxyRpcHandler},
+    socket::{check_token, ProxyAddress, SocketListener, SocketStream},
     stdio::stdio_transport,
-    RpcMessage,
+    RpcError, RpcMessage,
 };
+use parking_lot::Mutex;
 use tracing::error;
 
 #[derive(Parser)]
@@ -35,6 +38,17 @@ struct Cli {
     #[clap(short, long, action, hide = true)]
     proxy: bool,
 
+    /// Run headless and wait for Lapce to connect on the address,
+    /// either `host:port` or `unix:/path/to/socket`
+    #[clap(long, value_name = "ADDRESS", value_parser)]
+    listen: Option<ProxyAddress>,
+
+    /// The token Lapce has to send when it connects to `--listen`.
+    /// Taken from `LAPCE_PROXY_TOKEN` when not given, and generated
+    /// when that isn't set either
+    #[clap(long, requires = "listen")]
+    token: Option<String>,
+
     /// Paths to file(s) and/or folder(s) to open.
     /// When path is a file (that exists or not),
     /// it accepts `path:line:column` syntax
@@ -44,8 +58,23 @@ struct Cli {
     paths: Vec<PathObject>,
 }
 
+/// What the proxy sends to the app
+type ClientMessage = RpcMessage<CoreRequest, CoreNotification, ProxyResponse>;
+
 pub fn mainloop() {
     let cli = Cli::parse();
+    if let Some(address) = cli.listen {
+        let token = cli
+            .token
+            .or_else(|| std::env::var("LAPCE_PROXY_TOKEN").ok())
+            .filter(|token| !token.is_empty());
+        if let Err(e) = listen(&address, token) {
+            error!("failed to listen on {address}: {e}");
+            eprintln!("failed to listen on {address}: {e}");
+            exit(1);
+        }
+        return;
+    }
     if !cli.proxy {
         if let Err(e) = cli::try_open_in_existing_process(&cli.paths) {
             error!("failed to open path(s): {e}");
@@ -82,31 +111,143 @@ pub fn mainloop() {
     let writer_tx = Arc::new(writer_tx);
     thread::spawn(move || {
         for msg in reader_rx {
-            match msg {
-                RpcMessage::Request(id, req) => {
-                    let writer_tx = writer_tx.clone();
-                    local_proxy_rpc.request_async(req, move |result| match result {
-                        Ok(resp) => {
-                            let _ = writer_tx.send(RpcMessage::Response(id, resp));
-                        }
-                        Err(e) => {
-                            let _ = writer_tx.send(RpcMessage::Error(id, e));
-                        }
-                    });
-                }
-                RpcMessage::Notification(n) => {
-                    local_proxy_rpc.notification(n);
+            handle_client_message(msg, &writer_tx, &local_proxy_rpc, &core_rpc);
+        }
+        local_proxy_rpc.shutdown();
+    });
+
+    let local_proxy_rpc = proxy_rpc.clone();
+    std::thread::spawn(move || {
+        let _ = listen_local_socket(local_proxy_rpc);
+    });
+    let _ = register_lapce_path();
+
+    proxy_rpc.mainloop(&mut dispatcher);
+}
+
+fn handle_client_message(
+    msg: ProxyMessage,
+    writer_tx: &Arc<Sender<ClientMessage>>,
+    proxy_rpc: &ProxyRpcHandler,
+    core_rpc: &CoreRpcHandler,
+) {
+    match msg {
+        RpcMessage::Request(id, req) => {
+            let writer_tx = writer_tx.clone();
+            proxy_rpc.request_async(req, move |result| match result {
+                Ok(resp) => {
+                    let _ = writer_tx.send(RpcMessage::Response(id, resp));
                 }
-                RpcMessage::Response(id, resp) => {
-                    core_rpc.handle_response(id, Ok(resp));
+                Err(e) => {
+                    let _ = writer_tx.send(RpcMessage::Error(id, e));
                 }
-                RpcMessage::Error(id, err) => {
-                    core_rpc.handle_response(id, Err(err));
+            });
+        }
+        RpcMessage::Notification(n) => {
+            proxy_rpc.notification(n);
+        }
+        RpcMessage::Response(id, resp) => {
+            core_rpc.handle_response(id, Ok(resp));
+        }
+        RpcMessage::Error(id, err) => {
+            core_rpc.handle_response(id, Err(err));
+        }
+    }
+}
+
+/// The app that's connected to a headless proxy
+struct Client {
+    id: u64,
+    writer_tx: Sender<ClientMessage>,
+    stream: SocketStream,
+}
+
+/// Runs a headless proxy that serves one app at a time over a socket. The
+/// proxy keeps running when the app disconnects, so that the next one can
+/// reattach to it with plugins that are already warmed up.
+fn listen(address: &ProxyAddress, token: Option<String>) -> Result<()> {
+    let listener = SocketListener::bind(address)?;
+    println!("Lapce proxy listening on {address}");
+    let token = match token {
+        Some(token) => token,
+        None => {
+            let token = generate_token()?;
+            println!("token: {token}");
+            token
+        }
+    };
+
+    let core_rpc = CoreRpcHandler::new();
+    let proxy_rpc = ProxyRpcHandler::new();
+    let mut dispatcher = Dispatcher::new(core_rpc.clone(), proxy_rpc.clone());
+
+    let client: Arc<Mutex<Option<Client>>> = Arc::new(Mutex::new(None));
+
+    {
+        let core_rpc = core_rpc.clone();
+        let client = client.clone();
+        thread::spawn(move || {
+            for msg in core_rpc.rx() {
+                let msg = match msg {
+                    CoreRpc::Request(id, rpc) => RpcMessage::Request(id, rpc),
+                    CoreRpc::Notification(rpc) => RpcMessage::Notification(rpc),
+                    CoreRpc::Shutdown => return,
+                };
+                let request_id = match &msg {
+                    RpcMessage::Request(id, _) => Some(*id),
+                    _ => None,
+                };
+                // Notifications while no app is connected are dropped, the
+                // next one gets the state when it initializes. Requests are
+                // answered with an error so that their callers don't wait
+                // forever.
+                let sent = client
+                    .lock()
+                    .as_ref()
+                    .map(|client| client.writer_tx.send(msg).is_ok())
+                    .unwrap_or(false);
+                if let (false, Some(id)) = (sent, request_id) {
+                    core_rpc.handle_response(
+                        id,
+                        Err(RpcError {
+                            code: 0,
+                            message: "no app is connected".to_string(),
+                        }),
+                    );
                 }
             }
-        }
-        local_proxy_rpc.shutdown();
-    });
+        });
+    }
+
+    {
+        let proxy_rpc = proxy_rpc.clone();
+        thread::spawn(move || {
+            let token = Arc::new(token);
+            let mut next_id = 0;
+            loop {
+                let stream = match listener.accept() {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        error!("failed to accept a connection: {e}");
+                        continue;
+                    }
+                };
+                next_id += 1;
+                let id = next_id;
+                let token = token.clone();
+                let proxy_rpc = proxy_rpc.clone();
+                let core_rpc = core_rpc.clone();
+                let client = client.clone();
+                thread::spawn(move || {
+                    if let Err(e) =
+                        serve_client(id, stream, &token, proxy_rpc, core_rpc, client)
+                    {
+                        error!("connection to the app failed: {e}");
+                    }
+                });
+            }
+        });
+    }
 
     let local_proxy_rpc = proxy_rpc.clone();
     std::thread::spawn(move || {
@@ -115,6 +256,62 @@ pub fn mainloop() {
     let _ = register_lapce_path();
 
     proxy_rpc.mainloop(&mut dispatcher);
+    Ok(())
+}
+
+fn serve_client(
+    id: u64,
+    stream: SocketStream,
+    token: &str,
+    proxy_rpc: ProxyRpcHandler,
+    core_rpc: CoreRpcHandler,
+    client: Arc<Mutex<Option<Client>>>,
+) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream.try_clone()?;
+    if !check_token(&mut reader, &mut writer, token)? {
+        stream.shutdown();
+        return Err(anyhow!("the app sent an invalid token"));
+    }
+
+    let (writer_tx, writer_rx) = crossbeam_channel::unbounded();
+    let (reader_tx, reader_rx) = crossbeam_channel::unbounded();
+    stdio_transport(writer, writer_rx, reader, reader_tx);
+
+    // Only one app is served at a time, the one that connected last
+    let previous = client.lock().replace(Client {
+        id,
+        writer_tx: writer_tx.clone(),
+        stream,
+    });
+    if let Some(previous) = previous {
+        previous.stream.shutdown();
+    }
+
+    let writer_tx = Arc::new(writer_tx);
+    for msg in reader_rx {
+        // The app shuts down its side, but the proxy outlives it
+        if let RpcMessage::Notification(ProxyNotification::Shutdown {}) = msg {
+            break;
+        }
+        handle_client_message(msg, &writer_tx, &proxy_rpc, &core_rpc);
+    }
+
+    let mut client = client.lock();
+    if client.as_ref().map(|c| c.id) == Some(id) {
+        if let Some(client) = client.take() {
+            client.stream.shutdown();
+        }
+    }
+    Ok(())
+}
+
+/// A random token for apps to authenticate with
+fn generate_token() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| anyhow!("failed to generate a token: {e}"))?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
 }
 
 pub fn register_lapce_path() -> Result<()> {
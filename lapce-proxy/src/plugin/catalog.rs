@@ -10,7 +10,7 @@
 };
 
 use lapce_rpc::{
-    dap_types::{DapId, DapServer, SetBreakpointsResponse},
+    dap_types::{DapId, SetBreakpointsResponse},
     plugin::{PluginId, VoltID, VoltMetadata},
     proxy::ProxyResponse,
     style::LineStyle,
@@ -18,8 +18,9 @@
 };
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
-    notification::DidOpenTextDocument, DidOpenTextDocumentParams, SemanticTokens,
-    TextDocumentIdentifier, TextDocumentItem, VersionedTextDocumentIdentifier,
+    notification::{DidCloseTextDocument, DidOpenTextDocument},
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, SemanticTokens,
+    TextDocumentIdentifier, TextDocumentItem, Url, VersionedTextDocumentIdentifier,
 };
 use parking_lot::Mutex;
 use psp_types::Notification;
@@ -52,35 +53,6 @@ pub fn new(
         plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
         plugin_rpc: PluginCatalogRpcHandler,
     ) -> Self {
-        {
-            thread::spawn(move || {
-                // let mut dap = DapClient::new(
-                //     workspace,
-                //     Url::parse("file:///opt/homebrew/opt/llvm@14/bin/lldb-vscode")
-                //         .unwrap(),
-                //     Vec::new(),
-                //     core_rpc,
-                // )
-                // .unwrap();
-                // let dap_rpc = dap.dap_rpc.clone();
-                // // let _ = dap.initialize();
-
-                // {
-                //     let dap_rpc = dap_rpc.clone();
-                //     thread::spawn(move || {
-                //         dap_rpc.mainloop(&mut dap);
-                //     });
-                // }
-
-                // std::thread::sleep(std::time::Duration::from_secs(1));
-                // let _ = dap_rpc.launch(serde_json::json!({
-                //     "program": "/Users/dz/lapce/target/debug/lapce",
-                //     "args": vec!["--wait", "--new"],
-                //     "runInTerminal": true,
-                // }));
-            });
-        }
-
         let plugin = Self {
             workspace,
             plugin_rpc: plugin_rpc.clone(),
@@ -304,6 +276,28 @@ pub fn handle_did_open_text_document(&mut self, document: TextDocumentItem) {
         }
     }
 
+    pub fn handle_did_close_text_document(&mut self, path: PathBuf) {
+        let language_id = match self.open_files.remove(&path) {
+            Some(language_id) => language_id,
+            None => return,
+        };
+        let uri = match Url::from_file_path(&path) {
+            Ok(uri) => uri,
+            Err(_) => return,
+        };
+        for (_, plugin) in self.plugins.iter() {
+            plugin.server_notification(
+                DidCloseTextDocument::METHOD,
+                DidCloseTextDocumentParams {
+                    text_document: TextDocumentIdentifier { uri: uri.clone() },
+                },
+                Some(language_id.clone()),
+                Some(path.clone()),
+                true,
+            );
+        }
+    }
+
     pub fn handle_did_save_text_document(
         &mut self,
         language_id: String,
@@ -463,25 +457,32 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
             }
             DapStart {
                 config,
+                mut dap_server,
                 breakpoints,
             } => {
-                let workspace = self.workspace.clone();
+                if dap_server.cwd.is_none() {
+                    dap_server.cwd = self.workspace.clone();
+                }
                 let plugin_rpc = self.plugin_rpc.clone();
                 thread::spawn(move || {
-                    if let Ok(dap_rpc) = DapClient::start(
-                        DapServer {
-                            program: "/opt/homebrew/opt/llvm@14/bin/lldb-vscode"
-                                .to_string(),
-                            args: Vec::new(),
-                            cwd: workspace,
-                        },
+                    let program = dap_server.program.clone();
+                    match DapClient::start(
+                        dap_server,
                         config.clone(),
                         breakpoints,
                         plugin_rpc.clone(),
                     ) {
-                        let _ = plugin_rpc.dap_loaded(dap_rpc.clone());
+                        Ok(dap_rpc) => {
+                            let _ = plugin_rpc.dap_loaded(dap_rpc.clone());
 
-                        let _ = dap_rpc.launch(&config);
+                            let _ = dap_rpc.launch(&config);
+                        }
+                        Err(err) => {
+                            plugin_rpc.core_rpc.log(
+                                tracing::Level::ERROR,
+                                format!("can't start dap server {program}: {err}"),
+                            );
+                        }
                     }
                 });
             }
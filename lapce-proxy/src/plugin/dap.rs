@@ -1,6 +1,7 @@
 use std::{
     collections::HashMap,
-    io::{BufReader, BufWriter, Write},
+    io::{BufRead, BufReader, BufWriter, Write},
+    net::{TcpListener, TcpStream},
     path::PathBuf,
     process::{Child, Command, Stdio},
     sync::{
@@ -8,6 +9,7 @@
         Arc,
     },
     thread,
+    time::Duration,
 };
 
 use anyhow::{anyhow, Result};
@@ -16,12 +18,13 @@
     dap_types::{
         self, ConfigurationDone, Continue, ContinueArguments, ContinueResponse,
         DapEvent, DapId, DapPayload, DapRequest, DapResponse, DapServer,
-        DebuggerCapabilities, Disconnect, Initialize, Launch, Pause, PauseArguments,
-        Request, RunDebugConfig, RunInTerminal, RunInTerminalArguments,
-        RunInTerminalResponse, SetBreakpoints, SetBreakpointsArguments,
+        DapTransport, DebuggerCapabilities, Disconnect, Initialize, Launch, Pause,
+        PauseArguments, Request, RunDebugConfig, RunInTerminal,
+        RunInTerminalArguments, RunInTerminalResponse, Scope, Scopes,
+        ScopesArguments, ScopesResponse, SetBreakpoints, SetBreakpointsArguments,
         SetBreakpointsResponse, Source, SourceBreakpoint, StackTrace,
         StackTraceArguments, StackTraceResponse, Terminate, ThreadId, Threads,
-        ThreadsResponse,
+        ThreadsResponse, Variable, Variables, VariablesArguments, VariablesResponse,
     },
     terminal::TermId,
     RpcError,
@@ -94,19 +97,71 @@ pub fn start(
 
     fn start_process(&self) -> Result<()> {
         let program = self.dap_server.program.clone();
-        let mut process = Self::process(
-            &program,
-            &self.dap_server.args,
-            self.dap_server.cwd.as_ref(),
-        )?;
-        let stdin = process.stdin.take().unwrap();
-        let stdout = process.stdout.take().unwrap();
-        // let stderr = process.stderr.take().unwrap();
+        match &self.dap_server.transport {
+            DapTransport::Stdio => {
+                let mut process = Self::process(
+                    &program,
+                    &self.dap_server.args,
+                    self.dap_server.cwd.as_ref(),
+                    true,
+                )?;
+                let stdin = process.stdin.take().unwrap();
+                let stdout = process.stdout.take().unwrap();
+                // let stderr = process.stderr.take().unwrap();
+
+                self.start_io(
+                    program,
+                    Box::new(BufReader::new(stdout)),
+                    Box::new(BufWriter::new(stdin)),
+                );
+            }
+            DapTransport::Tcp { host, port } => {
+                let port = match port {
+                    Some(port) => *port,
+                    None => free_port(host)?,
+                };
+                if !program.is_empty() {
+                    let args = self
+                        .dap_server
+                        .args
+                        .iter()
+                        .map(|arg| arg.replace("${port}", &port.to_string()))
+                        .collect::<Vec<_>>();
+                    Self::process(
+                        &program,
+                        &args,
+                        self.dap_server.cwd.as_ref(),
+                        false,
+                    )?;
+                }
+                let stream = connect(host, port)?;
+                let reader = stream.try_clone()?;
+                let name = if program.is_empty() {
+                    format!("{host}:{port}")
+                } else {
+                    program
+                };
+
+                self.start_io(
+                    name,
+                    Box::new(BufReader::new(reader)),
+                    Box::new(BufWriter::new(stream)),
+                );
+            }
+        }
 
+        Ok(())
+    }
+
+    fn start_io(
+        &self,
+        name: String,
+        mut reader: Box<dyn BufRead + Send>,
+        mut writer: Box<dyn Write + Send>,
+    ) {
         let dap_rpc = self.dap_rpc.clone();
         let io_rx = self.dap_rpc.io_rx.clone();
         let io_tx = self.dap_rpc.io_tx.clone();
-        let mut writer = Box::new(BufWriter::new(stdin));
         thread::spawn(move || -> Result<()> {
             for msg in io_rx {
                 if let Ok(msg) = serde_json::to_string(&msg) {
@@ -119,39 +174,32 @@ fn start_process(&self) -> Result<()> {
             Ok(())
         });
 
-        {
-            let plugin_rpc = self.plugin_rpc.clone();
-            thread::spawn(move || {
-                let mut reader = Box::new(BufReader::new(stdout));
-                loop {
-                    match crate::plugin::lsp::read_message(&mut reader) {
-                        Ok(message_str) => {
-                            dap_rpc.handle_server_message(&message_str);
-                        }
-                        Err(_err) => {
-                            let _ = io_tx.send(DapPayload::Event(
-                                DapEvent::Initialized(None),
-                            ));
-                            plugin_rpc.core_rpc.log(
-                                tracing::Level::ERROR,
-                                format!("dap server {program} stopped!"),
-                            );
-
-                            dap_rpc.disconnected();
-                            return;
-                        }
-                    };
+        let plugin_rpc = self.plugin_rpc.clone();
+        thread::spawn(move || loop {
+            match crate::plugin::lsp::read_message(&mut reader) {
+                Ok(message_str) => {
+                    dap_rpc.handle_server_message(&message_str);
                 }
-            });
-        }
-
-        Ok(())
+                Err(_err) => {
+                    let _ =
+                        io_tx.send(DapPayload::Event(DapEvent::Initialized(None)));
+                    plugin_rpc.core_rpc.log(
+                        tracing::Level::ERROR,
+                        format!("dap server {name} stopped!"),
+                    );
+
+                    dap_rpc.disconnected();
+                    return;
+                }
+            };
+        });
     }
 
     fn process(
         server: &str,
         args: &[String],
         cwd: Option<&PathBuf>,
+        piped: bool,
     ) -> Result<Child> {
         let mut process = Command::new(server);
         if let Some(cwd) = cwd {
@@ -168,10 +216,13 @@ fn process(
             &mut process,
             0x08000000,
         );
+        // An adapter that talks over a socket doesn't need its stdio, and
+        // leaving it piped without a reader could block it once the pipe is full.
+        let stdio = || if piped { Stdio::piped() } else { Stdio::null() };
         let child = process
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
+            .stdin(stdio())
+            .stdout(stdio())
+            .stderr(stdio())
             .spawn()?;
         Ok(child)
     }
@@ -203,6 +254,27 @@ fn handle_host_request(&mut self, req: &DapRequest) -> Result<Value> {
         }
     }
 
+    /// Get the variables of the cheap scopes of a stack frame, like the locals
+    /// and the arguments, which are shown inline in the editor.
+    fn frame_variables(&self, frame_id: usize) -> Vec<(Scope, Vec<Variable>)> {
+        let scopes = match self.dap_rpc.scopes(frame_id) {
+            Ok(response) => response.scopes,
+            Err(_) => return Vec::new(),
+        };
+        scopes
+            .into_iter()
+            .filter(|scope| {
+                !scope.expensive
+                    && scope.presentation_hint.as_deref() != Some("registers")
+            })
+            .filter_map(|scope| {
+                let variables =
+                    self.dap_rpc.variables(scope.variables_reference).ok()?;
+                Some((scope, variables.variables))
+            })
+            .collect()
+    }
+
     fn handle_host_event(&mut self, event: &DapEvent) -> Result<()> {
         match event {
             DapEvent::Initialized(_) => {
@@ -232,12 +304,23 @@ fn handle_host_event(&mut self, event: &DapEvent) -> Result<()> {
                             }
                         }
                     }
+                } else if let Some(thread_id) = stopped.thread_id {
+                    if let Ok(frames) = self.dap_rpc.stack_trace(thread_id) {
+                        stack_frames.insert(thread_id, frames.stack_frames);
+                    }
                 }
 
+                let variables = stack_frames
+                    .get(&stopped.thread_id.unwrap_or_default())
+                    .and_then(|frames| frames.first())
+                    .map(|frame| self.frame_variables(frame.id))
+                    .unwrap_or_default();
+
                 self.plugin_rpc.core_rpc.dap_stopped(
                     self.config.dap_id,
                     stopped.clone(),
                     stack_frames,
+                    variables,
                 );
 
                 // if all_threads_stopped {
@@ -673,4 +756,52 @@ pub fn stack_trace(&self, thread_id: ThreadId) -> Result<StackTraceResponse> {
             .map_err(|e| anyhow!(e.message))?;
         Ok(resp)
     }
+
+    pub fn scopes(&self, frame_id: usize) -> Result<ScopesResponse> {
+        let params = ScopesArguments { frame_id };
+        let resp = self
+            .request::<Scopes>(params)
+            .map_err(|e| anyhow!(e.message))?;
+        Ok(resp)
+    }
+
+    pub fn variables(
+        &self,
+        variables_reference: usize,
+    ) -> Result<VariablesResponse> {
+        let params = VariablesArguments {
+            variables_reference,
+            ..Default::default()
+        };
+        let resp = self
+            .request::<Variables>(params)
+            .map_err(|e| anyhow!(e.message))?;
+        Ok(resp)
+    }
+}
+
+/// Ask the OS for a port that's currently free on `host`
+fn free_port(host: &str) -> Result<u16> {
+    let listener = TcpListener::bind((host, 0))?;
+    Ok(listener.local_addr()?.port())
+}
+
+/// Connect to a debug adapter listening on `host:port`, giving a freshly
+/// spawned adapter some time to start listening.
+fn connect(host: &str, port: u16) -> Result<TcpStream> {
+    let mut retries = 50;
+    loop {
+        match TcpStream::connect((host, port)) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => {
+                if retries == 0 {
+                    return Err(anyhow!(
+                        "can't connect to dap server at {host}:{port}: {err}"
+                    ));
+                }
+                retries -= 1;
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
+    }
 }
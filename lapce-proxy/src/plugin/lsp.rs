@@ -31,6 +31,7 @@
         handle_plugin_server_message, PluginHandlerNotification, PluginHostHandler,
         PluginServerHandler, PluginServerRpcHandler, ResponseSender, RpcCallback,
     },
+    workspace_folder,
 };
 use crate::{buffer::Buffer, plugin::PluginCatalogRpcHandler};
 
@@ -326,15 +327,15 @@ fn initialize(&mut self) {
         #[allow(deprecated)]
         let params = InitializeParams {
             process_id: Some(process::id()),
-            root_uri: root_uri.clone(),
+            root_uri,
             initialization_options: self.options.clone(),
             capabilities: client_capabilities(),
             trace: Some(TraceValue::Verbose),
-            workspace_folders: root_uri.map(|uri| {
-                vec![WorkspaceFolder {
-                    name: uri.as_str().to_string(),
-                    uri,
-                }]
+            workspace_folders: self.workspace.as_ref().map(|workspace| {
+                std::iter::once(workspace.clone())
+                    .chain(self.plugin_rpc.workspace_folders())
+                    .filter_map(|folder| workspace_folder(&folder))
+                    .collect()
             }),
             client_info: Some(ClientInfo {
                 name: meta::NAME.to_owned(),
@@ -23,7 +23,7 @@
 use lapce_core::directory::Directory;
 use lapce_rpc::{
     core::CoreRpcHandler,
-    dap_types::{DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
+    dap_types::{DapId, DapServer, RunDebugConfig, SourceBreakpoint, ThreadId},
     plugin::{PluginId, VoltInfo, VoltMetadata},
     proxy::ProxyRpcHandler,
     style::LineStyle,
@@ -32,6 +32,7 @@
 };
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
+    notification::{DidChangeWorkspaceFolders, Notification},
     request::{
         CodeActionRequest, CodeActionResolveRequest, Completion,
         DocumentSymbolRequest, Formatting, GotoDefinition, GotoTypeDefinition,
@@ -45,11 +46,12 @@
     CodeActionKindLiteralSupport, CodeActionLiteralSupport, CodeActionParams,
     CodeActionResponse, CompletionClientCapabilities, CompletionItem,
     CompletionItemCapability, CompletionItemCapabilityResolveSupport,
-    CompletionParams, CompletionResponse, Diagnostic, DocumentFormattingParams,
-    DocumentSymbolParams, DocumentSymbolResponse, FormattingOptions, GotoCapability,
-    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverClientCapabilities,
-    HoverParams, InlayHint, InlayHintClientCapabilities, InlayHintParams, Location,
-    MarkupKind, MessageActionItemCapabilities, ParameterInformationSettings,
+    CompletionParams, CompletionResponse, Diagnostic,
+    DidChangeWorkspaceFoldersParams, DocumentFormattingParams, DocumentSymbolParams,
+    DocumentSymbolResponse, FormattingOptions, GotoCapability, GotoDefinitionParams,
+    GotoDefinitionResponse, Hover, HoverClientCapabilities, HoverParams, InlayHint,
+    InlayHintClientCapabilities, InlayHintParams, Location, MarkupKind,
+    MessageActionItemCapabilities, ParameterInformationSettings,
     PartialResultParams, Position, PrepareRenameResponse,
     PublishDiagnosticsClientCapabilities, Range, ReferenceContext, ReferenceParams,
     RenameParams, SelectionRange, SelectionRangeParams, SemanticTokens,
@@ -61,7 +63,8 @@
     TextDocumentSyncClientCapabilities, TextEdit, Url,
     VersionedTextDocumentIdentifier, WindowClientCapabilities,
     WorkDoneProgressParams, WorkspaceClientCapabilities, WorkspaceEdit,
-    WorkspaceSymbolClientCapabilities, WorkspaceSymbolParams,
+    WorkspaceFolder, WorkspaceFoldersChangeEvent, WorkspaceSymbolClientCapabilities,
+    WorkspaceSymbolParams,
 };
 use parking_lot::Mutex;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
@@ -107,6 +110,9 @@ pub enum PluginCatalogRpc {
     DidOpenTextDocument {
         document: TextDocumentItem,
     },
+    DidCloseTextDocument {
+        path: PathBuf,
+    },
     DidChangeTextDocument {
         language_id: String,
         document: VersionedTextDocumentIdentifier,
@@ -137,6 +143,7 @@ pub enum PluginCatalogNotification {
     DapDisconnected(DapId),
     DapStart {
         config: RunDebugConfig,
+        dap_server: DapServer,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
     },
     DapProcessId {
@@ -180,6 +187,9 @@ pub struct PluginCatalogRpcHandler {
     id: Arc<AtomicU64>,
     #[allow(dead_code, clippy::type_complexity)]
     pending: Arc<Mutex<HashMap<u64, Sender<Result<Value, RpcError>>>>>,
+    /// The other roots of a multi-root workspace, for the servers that
+    /// start later
+    workspace_folders: Arc<Mutex<Vec<PathBuf>>>,
 }
 
 impl PluginCatalogRpcHandler {
@@ -192,6 +202,7 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
             plugin_rx: Arc::new(Mutex::new(Some(plugin_rx))),
             id: Arc::new(AtomicU64::new(0)),
             pending: Arc::new(Mutex::new(HashMap::new())),
+            workspace_folders: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
@@ -258,6 +269,9 @@ pub fn mainloop(&self, plugin: &mut PluginCatalog) {
                 PluginCatalogRpc::DidOpenTextDocument { document } => {
                     plugin.handle_did_open_text_document(document);
                 }
+                PluginCatalogRpc::DidCloseTextDocument { path } => {
+                    plugin.handle_did_close_text_document(path);
+                }
                 PluginCatalogRpc::DidSaveTextDocument {
                     language_id,
                     path,
@@ -1001,6 +1015,47 @@ pub fn did_open_document(
         });
     }
 
+    pub fn workspace_folders(&self) -> Vec<PathBuf> {
+        self.workspace_folders.lock().clone()
+    }
+
+    /// Set the other roots of the workspace, and tell the servers that
+    /// support it about the change
+    pub fn update_workspace_folders(&self, folders: Vec<PathBuf>) {
+        let old_folders =
+            std::mem::replace(&mut *self.workspace_folders.lock(), folders.clone());
+        let added: Vec<WorkspaceFolder> = folders
+            .iter()
+            .filter(|f| !old_folders.contains(f))
+            .filter_map(|f| workspace_folder(f))
+            .collect();
+        let removed: Vec<WorkspaceFolder> = old_folders
+            .iter()
+            .filter(|f| !folders.contains(f))
+            .filter_map(|f| workspace_folder(f))
+            .collect();
+        if added.is_empty() && removed.is_empty() {
+            return;
+        }
+
+        self.send_notification(
+            None,
+            DidChangeWorkspaceFolders::METHOD,
+            DidChangeWorkspaceFoldersParams {
+                event: WorkspaceFoldersChangeEvent { added, removed },
+            },
+            None,
+            None,
+            true,
+        );
+    }
+
+    pub fn did_close_document(&self, path: &Path) {
+        let _ = self.plugin_tx.send(PluginCatalogRpc::DidCloseTextDocument {
+            path: path.to_path_buf(),
+        });
+    }
+
     pub fn unactivated_volts(&self, volts: Vec<VoltMetadata>) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::UnactivatedVolts(volts))
     }
@@ -1050,10 +1105,12 @@ pub fn dap_loaded(&self, dap_rpc: DapRpcHandler) -> Result<()> {
     pub fn dap_start(
         &self,
         config: RunDebugConfig,
+        dap_server: DapServer,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
     ) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::DapStart {
             config,
+            dap_server,
             breakpoints,
         })
     }
@@ -1194,6 +1251,14 @@ pub fn download_volt(volt: &VoltInfo) -> Result<VoltMetadata> {
     Ok(meta)
 }
 
+pub(crate) fn workspace_folder(path: &Path) -> Option<WorkspaceFolder> {
+    let uri = Url::from_directory_path(path).ok()?;
+    Some(WorkspaceFolder {
+        name: uri.as_str().to_string(),
+        uri,
+    })
+}
+
 pub fn install_volt(
     catalog_rpc: PluginCatalogRpcHandler,
     workspace: Option<PathBuf>,
@@ -1339,6 +1404,7 @@ fn client_capabilities() -> ClientCapabilities {
                 ..Default::default()
             }),
             configuration: Some(false),
+            workspace_folders: Some(true),
             ..Default::default()
         }),
         ..Default::default()
@@ -26,9 +26,9 @@
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
     notification::{
-        DidChangeTextDocument, DidOpenTextDocument, DidSaveTextDocument,
-        Initialized, LogMessage, Notification, Progress, PublishDiagnostics,
-        ShowMessage,
+        DidChangeTextDocument, DidChangeWorkspaceFolders, DidCloseTextDocument,
+        DidOpenTextDocument, DidSaveTextDocument, Initialized, LogMessage,
+        Notification, Progress, PublishDiagnostics, ShowMessage,
     },
     request::{
         CodeActionRequest, CodeActionResolveRequest, Completion,
@@ -674,7 +674,7 @@ pub fn method_registered(&mut self, method: &str) -> bool {
                 .as_ref()
                 .and_then(|c| c.resolve_provider)
                 .unwrap_or(false),
-            DidOpenTextDocument::METHOD => {
+            DidOpenTextDocument::METHOD | DidCloseTextDocument::METHOD => {
                 match &self.server_capabilities.text_document_sync {
                     Some(TextDocumentSyncCapability::Kind(kind)) => {
                         kind != &TextDocumentSyncKind::NONE
@@ -775,6 +775,17 @@ pub fn method_registered(&mut self, method: &str) -> bool {
             CodeActionResolveRequest::METHOD => {
                 self.server_capabilities.code_action_provider.is_some()
             }
+            DidChangeWorkspaceFolders::METHOD => self
+                .server_capabilities
+                .workspace
+                .as_ref()
+                .and_then(|w| w.workspace_folders.as_ref())
+                .and_then(|f| f.change_notifications.as_ref())
+                .map(|c| match c {
+                    OneOf::Left(is_capable) => *is_capable,
+                    OneOf::Right(_) => true,
+                })
+                .unwrap_or(false),
             _ => false,
         }
     }
@@ -22,3 +22,80 @@ pub struct BufferHeadResponse {
     pub version: String,
     pub content: String,
 }
+
+/// The encoding a file is read in, and written back in when it's saved
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FileEncoding {
+    /// The name of the encoding, as in the Encoding Standard, e.g. `UTF-8`,
+    /// `UTF-16LE` or `Shift_JIS`
+    pub name: String,
+    /// If the file starts with a byte order mark
+    pub bom: bool,
+}
+
+impl Default for FileEncoding {
+    fn default() -> Self {
+        Self::new("UTF-8", false)
+    }
+}
+
+impl std::fmt::Display for FileEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.bom {
+            write!(f, "{} with BOM", self.name)
+        } else {
+            f.write_str(&self.name)
+        }
+    }
+}
+
+impl FileEncoding {
+    pub fn new(name: &str, bom: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            bom,
+        }
+    }
+
+    /// If the encoding is plain UTF-8, which isn't worth pointing out
+    pub fn is_utf8(&self) -> bool {
+        self.name == "UTF-8" && !self.bom
+    }
+
+    /// The encodings that files can be reopened or saved with
+    pub fn all() -> Vec<FileEncoding> {
+        let mut encodings = vec![
+            FileEncoding::new("UTF-8", false),
+            FileEncoding::new("UTF-8", true),
+            FileEncoding::new("UTF-16LE", true),
+            FileEncoding::new("UTF-16BE", true),
+        ];
+        encodings.extend(
+            [
+                "windows-1252",
+                "ISO-8859-2",
+                "ISO-8859-15",
+                "windows-1250",
+                "windows-1251",
+                "KOI8-R",
+                "windows-1253",
+                "windows-1254",
+                "windows-1255",
+                "windows-1256",
+                "windows-1257",
+                "windows-1258",
+                "windows-874",
+                "Shift_JIS",
+                "EUC-JP",
+                "ISO-2022-JP",
+                "GBK",
+                "gb18030",
+                "Big5",
+                "EUC-KR",
+            ]
+            .into_iter()
+            .map(|name| FileEncoding::new(name, false)),
+        );
+        encodings
+    }
+}
@@ -0,0 +1,69 @@
+//! Requests that scripts can send to a running Lapce over its local socket.
+//!
+//! Every message is a single line of JSON. A request carries an `id`, a
+//! `method` and its `params`, e.g.
+//!
+//! ```json
+//! {"id":1,"method":"open_file","params":{"path":"/tmp/a.rs","line":3,"column":1}}
+//! ```
+//!
+//! and is answered on the same connection with either
+//! `{"id":1,"result":{"method":"open_file_response","params":{}}}` or
+//! `{"id":1,"error":{"code":0,"message":"..."}}`.
+//!
+//! The socket also accepts the `open_paths` notification, which is
+//! acknowledged with the raw bytes `received` instead of a JSON response.
+
+use std::path::PathBuf;
+
+use lsp_types::Diagnostic;
+use serde::{Deserialize, Serialize};
+
+use crate::{core::CoreNotification, RpcMessage};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(tag = "method", content = "params")]
+pub enum ControlRequest {
+    /// Open a file in the active window, optionally at a 1-based line and column
+    OpenFile {
+        path: PathBuf,
+        line: Option<usize>,
+        column: Option<usize>,
+    },
+    /// Run a workbench command by the name it has in the keymaps,
+    /// e.g. `open_settings`
+    RunCommand { command: String },
+    /// List the documents that are open in the active window
+    ListDocuments {},
+    /// Get the diagnostics of a file, or of all files when `path` is not set
+    GetDiagnostics { path: Option<PathBuf> },
+    /// Insert text at the cursors of the active editor
+    InsertText { text: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(tag = "method", content = "params")]
+pub enum ControlResponse {
+    OpenFileResponse {},
+    RunCommandResponse {},
+    ListDocumentsResponse { documents: Vec<DocumentInfo> },
+    GetDiagnosticsResponse { diagnostics: Vec<FileDiagnostics> },
+    InsertTextResponse {},
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentInfo {
+    pub path: PathBuf,
+    pub is_dirty: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiagnostics {
+    pub path: PathBuf,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+pub type ControlMessage =
+    RpcMessage<ControlRequest, CoreNotification, ControlResponse>;
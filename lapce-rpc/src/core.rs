@@ -16,7 +16,9 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    dap_types::{self, DapId, RunDebugConfig, StackFrame, Stopped, ThreadId},
+    dap_types::{
+        self, DapId, RunDebugConfig, Scope, StackFrame, Stopped, ThreadId, Variable,
+    },
     file::PathObject,
     plugin::{PluginId, VoltInfo, VoltMetadata},
     proxy::ProxyStatus,
@@ -42,6 +44,9 @@ pub enum CoreNotification {
         path: PathBuf,
         content: String,
     },
+    OpenFileDeleted {
+        path: PathBuf,
+    },
     CompletionResponse {
         request_id: usize,
         input: String,
@@ -55,6 +60,9 @@ pub enum CoreNotification {
     },
     OpenPaths {
         paths: Vec<PathObject>,
+        /// The sender waits for the opened files to be closed
+        #[serde(default)]
+        wait: bool,
     },
     WorkspaceFileChange,
     PublishDiagnostics {
@@ -114,6 +122,8 @@ pub enum CoreNotification {
         dap_id: DapId,
         stopped: Stopped,
         stack_frames: HashMap<ThreadId, Vec<StackFrame>>,
+        /// The variables of the scopes of the stopped thread's top frame
+        variables: Vec<(Scope, Vec<Variable>)>,
     },
     DapContinued {
         dap_id: DapId,
@@ -230,6 +240,10 @@ pub fn open_file_changed(&self, path: PathBuf, content: String) {
         self.notification(CoreNotification::OpenFileChanged { path, content });
     }
 
+    pub fn open_file_deleted(&self, path: PathBuf) {
+        self.notification(CoreNotification::OpenFileDeleted { path });
+    }
+
     pub fn completion_response(
         &self,
         request_id: usize,
@@ -324,11 +338,13 @@ pub fn dap_stopped(
         dap_id: DapId,
         stopped: Stopped,
         stack_frames: HashMap<ThreadId, Vec<StackFrame>>,
+        variables: Vec<(Scope, Vec<Variable>)>,
     ) {
         self.notification(CoreNotification::DapStopped {
             dap_id,
             stopped,
             stack_frames,
+            variables,
         });
     }
 
@@ -21,10 +21,26 @@ pub fn next() -> Self {
     }
 }
 
+/// How Lapce talks to a debug adapter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DapTransport {
+    /// Messages are exchanged over the stdin and stdout of the adapter process
+    Stdio,
+    /// Messages are exchanged over a TCP socket the adapter listens on.
+    /// If `port` is `None`, a free port is picked and substituted for
+    /// `${port}` in the adapter arguments.
+    Tcp { host: String, port: Option<u16> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DapServer {
+    /// The adapter executable. It can be empty for a TCP adapter that is
+    /// already running, in which case Lapce only connects to it.
     pub program: String,
     pub args: Vec<String>,
     pub cwd: Option<PathBuf>,
+    pub transport: DapTransport,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
@@ -34,6 +50,10 @@ pub struct RunDebugConfig {
     pub args: Vec<String>,
     pub cwd: Option<String>,
     pub env: Option<HashMap<String, String>>,
+    /// The name of the debug adapter in the `debug.adapters` settings.
+    /// If not set, the adapter is picked based on the language.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adapter: Option<String>,
     #[serde(skip)]
     pub debug_command: Option<String>,
     #[serde(skip)]
@@ -639,6 +659,98 @@ impl Request for StackTrace {
     const COMMAND: &'static str = "stackTrace";
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopesArguments {
+    pub frame_id: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scope {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presentation_hint: Option<String>,
+    pub variables_reference: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub named_variables: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexed_variables: Option<usize>,
+    #[serde(default)]
+    pub expensive: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<Source>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_column: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopesResponse {
+    pub scopes: Vec<Scope>,
+}
+
+#[derive(Debug)]
+pub enum Scopes {}
+
+impl Request for Scopes {
+    type Arguments = ScopesArguments;
+    type Result = ScopesResponse;
+    const COMMAND: &'static str = "scopes";
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariablesArguments {
+    pub variables_reference: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Variable {
+    pub name: String,
+    pub value: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ty: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evaluate_name: Option<String>,
+    #[serde(default)]
+    pub variables_reference: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub named_variables: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexed_variables: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_reference: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariablesResponse {
+    pub variables: Vec<Variable>,
+}
+
+#[derive(Debug)]
+pub enum Variables {}
+
+impl Request for Variables {
+    type Arguments = VariablesArguments;
+    type Result = VariablesResponse;
+    const COMMAND: &'static str = "variables";
+}
+
 #[derive(Debug)]
 pub enum Disconnect {}
 
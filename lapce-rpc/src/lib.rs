@@ -1,6 +1,7 @@
 #![allow(clippy::manual_clamp)]
 
 pub mod buffer;
+pub mod control;
 pub mod core;
 pub mod counter;
 pub mod dap_types;
@@ -8,6 +9,7 @@
 mod parse;
 pub mod plugin;
 pub mod proxy;
+pub mod socket;
 pub mod source_control;
 pub mod stdio;
 pub mod style;
@@ -1,5 +1,6 @@
 use std::{
     collections::HashMap,
+    ops::Range,
     path::PathBuf,
     sync::{
         atomic::{AtomicU64, Ordering},
@@ -21,16 +22,24 @@
 
 use super::plugin::VoltID;
 use crate::{
-    buffer::BufferId,
-    dap_types::{DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
+    buffer::{BufferId, FileEncoding},
+    dap_types::{DapId, DapServer, RunDebugConfig, SourceBreakpoint, ThreadId},
     file::{FileNodeItem, PathObject},
     plugin::{PluginId, VoltInfo, VoltMetadata},
-    source_control::FileDiff,
+    source_control::{BlameHunk, FileDiff, GitCommit, GitLogFilter},
     style::SemanticStyles,
     terminal::{TermId, TerminalProfile},
     RequestId, RpcError, RpcMessage,
 };
 
+/// The error code of a save that was refused because the file was changed on
+/// disk since it was loaded, and which can be forced with `force`
+pub const SAVE_CONFLICT: i64 = 17;
+
+/// The error code of a save that failed because the file, or the folder it's
+/// in, isn't writable, and which can be retried with [`ProxyRequest::SaveAsRoot`]
+pub const SAVE_PERMISSION_DENIED: i64 = 13;
+
 #[allow(clippy::large_enum_variant)]
 pub enum ProxyRpc {
     Request(RequestId, ProxyRequest),
@@ -38,11 +47,15 @@ pub enum ProxyRpc {
     Shutdown,
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ProxyStatus {
     Connecting,
     Connected,
     Disconnected,
+    /// The connection was lost and this is the attempt to get it back
+    Reconnecting(usize),
+    /// The proxy couldn't be started, with the reason why
+    Error(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,6 +73,23 @@ pub enum ProxyRequest {
     NewBuffer {
         buffer_id: BufferId,
         path: PathBuf,
+        /// The encoding to read the file in, instead of detecting it
+        #[serde(default)]
+        encoding: Option<FileEncoding>,
+        /// The size in bytes from which the file is opened in large file
+        /// mode, which isn't synced with language servers
+        #[serde(default)]
+        large_file_threshold: Option<u64>,
+    },
+    /// The content of an open buffer from the byte `offset` on, for fetching
+    /// the rest of a large file a chunk at a time
+    BufferChunk {
+        path: PathBuf,
+        offset: usize,
+    },
+    ReopenWithEncoding {
+        path: PathBuf,
+        encoding: FileEncoding,
     },
     BufferHead {
         path: PathBuf,
@@ -94,6 +124,71 @@ pub enum ProxyRequest {
     GitGetRemoteFileUrl {
         file: PathBuf,
     },
+    GitStage {
+        paths: Vec<PathBuf>,
+    },
+    GitUnstage {
+        paths: Vec<PathBuf>,
+    },
+    /// Stage the changes of a file that are in the lines of its working copy
+    GitStageLines {
+        path: PathBuf,
+        lines: Range<usize>,
+    },
+    /// Unstage the staged changes of a file that are in the lines of its
+    /// working copy
+    GitUnstageLines {
+        path: PathBuf,
+        lines: Range<usize>,
+    },
+    /// The content of a file in the index, which the changes of its lines
+    /// are staged to, or reverted to
+    GitIndexContent {
+        path: PathBuf,
+    },
+    GitBlame {
+        path: PathBuf,
+    },
+    /// List the commits reachable from `HEAD`, newest first
+    GitLog {
+        filter: GitLogFilter,
+        skip: usize,
+        limit: usize,
+    },
+    /// The files a commit changed compared to its first parent
+    GitCommitDiffs {
+        commit: String,
+    },
+    /// Get the content of a file at a revision, e.g. a commit id or `<commit>^`
+    GitFileContent {
+        path: PathBuf,
+        revision: String,
+    },
+    /// The content of the file on disk, rather than of its buffer, e.g. to
+    /// compare the unsaved changes against
+    DiskContent {
+        path: PathBuf,
+    },
+    /// Read `len` bytes of the file from `offset`, for viewing files that
+    /// aren't text
+    ReadFileBytes {
+        path: PathBuf,
+        offset: u64,
+        len: u64,
+    },
+    /// Overwrite the bytes at the offsets in place, leaving the size of the
+    /// file as it is
+    WriteFileBytes {
+        path: PathBuf,
+        changes: Vec<(u64, Vec<u8>)>,
+    },
+    /// Find the offsets of `pattern` in the file, as it would be with the
+    /// unsaved `changes` written to it
+    SearchFileBytes {
+        path: PathBuf,
+        pattern: Vec<u8>,
+        changes: Vec<(u64, Vec<u8>)>,
+    },
     GetReferences {
         path: PathBuf,
         position: Position,
@@ -148,6 +243,16 @@ pub enum ProxyRequest {
     Save {
         rev: u64,
         path: PathBuf,
+        /// The encoding to save the file in from now on
+        #[serde(default)]
+        encoding: Option<FileEncoding>,
+        /// Save even if the file was changed on disk
+        #[serde(default)]
+        force: bool,
+    },
+    SaveAsRoot {
+        rev: u64,
+        path: PathBuf,
     },
     SaveBufferAs {
         buffer_id: BufferId,
@@ -180,6 +285,9 @@ pub enum ProxyRequest {
 pub enum ProxyNotification {
     Initialize {
         workspace: Option<PathBuf>,
+        /// The other roots of a multi-root workspace
+        #[serde(default)]
+        folders: Vec<PathBuf>,
         disabled_volts: Vec<VoltID>,
         plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
         window_id: usize,
@@ -188,9 +296,26 @@ pub enum ProxyNotification {
     OpenFileChanged {
         path: PathBuf,
     },
+    /// The editor is done with the buffer of the file, e.g. as it's shown
+    /// in a hex editor instead
+    CloseBuffer {
+        path: PathBuf,
+    },
+    /// Sent by the proxy to itself once the write of a
+    /// [`ProxyRequest::SaveAsRoot`], which runs on its own thread, is done,
+    /// with the hash of the saved content or the error
+    RootSaveFinished {
+        id: RequestId,
+        path: PathBuf,
+        result: Result<u64, String>,
+    },
     OpenPaths {
         paths: Vec<PathObject>,
     },
+    /// The folders of a multi-root workspace were edited
+    UpdateWorkspaceFolders {
+        folders: Vec<PathBuf>,
+    },
     Shutdown {},
     Completion {
         request_id: usize,
@@ -208,6 +333,13 @@ pub enum ProxyNotification {
         delta: RopeDelta,
         rev: u64,
     },
+    /// Bring a reopened buffer in line with the content and revision of the
+    /// document in the editor
+    SyncBuffer {
+        path: PathBuf,
+        delta: RopeDelta,
+        rev: u64,
+    },
     UpdatePluginConfigs {
         configs: HashMap<String, HashMap<String, serde_json::Value>>,
     },
@@ -256,6 +388,7 @@ pub enum ProxyNotification {
     },
     DapStart {
         config: RunDebugConfig,
+        dap_server: DapServer,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
     },
     DapProcessId {
@@ -298,11 +431,44 @@ pub enum ProxyResponse {
     NewBufferResponse {
         content: String,
         read_only: bool,
+        #[serde(default)]
+        encoding: FileEncoding,
+        #[serde(default)]
+        large_file: bool,
+        /// If the file isn't text, so it's better viewed as bytes
+        #[serde(default)]
+        binary: bool,
+        /// If `content` is only the start of a large file, whose rest is
+        /// fetched with [`ProxyRequest::BufferChunk`]
+        #[serde(default)]
+        partial: bool,
+    },
+    BufferChunkResponse {
+        content: String,
+        /// Whether the chunk goes to the end of the file
+        done: bool,
+    },
+    ReadFileBytesResponse {
+        bytes: Vec<u8>,
+        /// The size of the whole file
+        file_len: u64,
+    },
+    SearchFileBytesResponse {
+        matches: Vec<u64>,
     },
     BufferHeadResponse {
         version: String,
         content: String,
     },
+    GitBlameResponse {
+        hunks: Vec<BlameHunk>,
+    },
+    GitLogResponse {
+        commits: Vec<GitCommit>,
+    },
+    GitCommitDiffsResponse {
+        diffs: Vec<FileDiff>,
+    },
     ReadDirResponse {
         items: Vec<FileNodeItem>,
     },
@@ -481,10 +647,26 @@ pub fn handle_response(
         }
     }
 
+    /// Fail all the requests that are still waiting for a response, e.g.
+    /// because the connection they were sent on is lost
+    pub fn fail_pending_requests(&self, message: &str) {
+        let pending = std::mem::take(&mut *self.pending.lock());
+        for (_, handler) in pending {
+            handler.invoke(Err(RpcError {
+                code: 0,
+                message: message.to_string(),
+            }));
+        }
+    }
+
     pub fn notification(&self, notification: ProxyNotification) {
         let _ = self.tx.send(ProxyRpc::Notification(notification));
     }
 
+    pub fn close_buffer(&self, path: PathBuf) {
+        self.notification(ProxyNotification::CloseBuffer { path });
+    }
+
     pub fn git_init(&self) {
         self.notification(ProxyNotification::GitInit {});
     }
@@ -525,6 +707,7 @@ pub fn shutdown(&self) {
     pub fn initialize(
         &self,
         workspace: Option<PathBuf>,
+        folders: Vec<PathBuf>,
         disabled_volts: Vec<VoltID>,
         plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
         window_id: usize,
@@ -532,6 +715,7 @@ pub fn initialize(
     ) {
         self.notification(ProxyNotification::Initialize {
             workspace,
+            folders,
             disabled_volts,
             plugin_configurations,
             window_id,
@@ -539,6 +723,10 @@ pub fn initialize(
         });
     }
 
+    pub fn update_workspace_folders(&self, folders: Vec<PathBuf>) {
+        self.notification(ProxyNotification::UpdateWorkspaceFolders { folders });
+    }
+
     pub fn completion(
         &self,
         request_id: usize,
@@ -591,9 +779,37 @@ pub fn new_buffer(
         &self,
         buffer_id: BufferId,
         path: PathBuf,
+        encoding: Option<FileEncoding>,
+        large_file_threshold: Option<u64>,
         f: impl ProxyCallback + 'static,
     ) {
-        self.request_async(ProxyRequest::NewBuffer { buffer_id, path }, f);
+        self.request_async(
+            ProxyRequest::NewBuffer {
+                buffer_id,
+                path,
+                encoding,
+                large_file_threshold,
+            },
+            f,
+        );
+    }
+
+    pub fn buffer_chunk(
+        &self,
+        path: PathBuf,
+        offset: usize,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::BufferChunk { path, offset }, f);
+    }
+
+    pub fn reopen_with_encoding(
+        &self,
+        path: PathBuf,
+        encoding: FileEncoding,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::ReopenWithEncoding { path, encoding }, f);
     }
 
     pub fn get_buffer_head(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
@@ -674,8 +890,32 @@ pub fn global_search(
         );
     }
 
-    pub fn save(&self, rev: u64, path: PathBuf, f: impl ProxyCallback + 'static) {
-        self.request_async(ProxyRequest::Save { rev, path }, f);
+    pub fn save(
+        &self,
+        rev: u64,
+        path: PathBuf,
+        encoding: Option<FileEncoding>,
+        force: bool,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::Save {
+                rev,
+                path,
+                encoding,
+                force,
+            },
+            f,
+        );
+    }
+
+    pub fn save_as_root(
+        &self,
+        rev: u64,
+        path: PathBuf,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::SaveAsRoot { rev, path }, f);
     }
 
     pub fn get_files(&self, f: impl ProxyCallback + 'static) {
@@ -851,6 +1091,110 @@ pub fn git_get_remote_file_url(
         self.request_async(ProxyRequest::GitGetRemoteFileUrl { file }, f);
     }
 
+    pub fn git_stage(&self, paths: Vec<PathBuf>, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitStage { paths }, f);
+    }
+
+    pub fn git_unstage(&self, paths: Vec<PathBuf>, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitUnstage { paths }, f);
+    }
+
+    pub fn git_stage_lines(
+        &self,
+        path: PathBuf,
+        lines: Range<usize>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitStageLines { path, lines }, f);
+    }
+
+    pub fn git_unstage_lines(
+        &self,
+        path: PathBuf,
+        lines: Range<usize>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitUnstageLines { path, lines }, f);
+    }
+
+    pub fn git_index_content(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitIndexContent { path }, f);
+    }
+
+    pub fn git_blame(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitBlame { path }, f);
+    }
+
+    pub fn git_log(
+        &self,
+        filter: GitLogFilter,
+        skip: usize,
+        limit: usize,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GitLog {
+                filter,
+                skip,
+                limit,
+            },
+            f,
+        );
+    }
+
+    pub fn git_commit_diffs(&self, commit: String, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitCommitDiffs { commit }, f);
+    }
+
+    pub fn disk_content(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::DiskContent { path }, f);
+    }
+
+    pub fn read_file_bytes(
+        &self,
+        path: PathBuf,
+        offset: u64,
+        len: u64,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::ReadFileBytes { path, offset, len }, f);
+    }
+
+    pub fn write_file_bytes(
+        &self,
+        path: PathBuf,
+        changes: Vec<(u64, Vec<u8>)>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::WriteFileBytes { path, changes }, f);
+    }
+
+    pub fn search_file_bytes(
+        &self,
+        path: PathBuf,
+        pattern: Vec<u8>,
+        changes: Vec<(u64, Vec<u8>)>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::SearchFileBytes {
+                path,
+                pattern,
+                changes,
+            },
+            f,
+        );
+    }
+
+    pub fn git_file_content(
+        &self,
+        path: PathBuf,
+        revision: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitFileContent { path, revision }, f);
+    }
+
     pub fn rename(
         &self,
         path: PathBuf,
@@ -876,6 +1220,10 @@ pub fn update(&self, path: PathBuf, delta: RopeDelta, rev: u64) {
         self.notification(ProxyNotification::Update { path, delta, rev });
     }
 
+    pub fn sync_buffer(&self, path: PathBuf, delta: RopeDelta, rev: u64) {
+        self.notification(ProxyNotification::SyncBuffer { path, delta, rev });
+    }
+
     pub fn update_plugin_configs(
         &self,
         configs: HashMap<String, HashMap<String, serde_json::Value>>,
@@ -903,10 +1251,12 @@ pub fn get_selection_range(
     pub fn dap_start(
         &self,
         config: RunDebugConfig,
+        dap_server: DapServer,
         breakpoints: HashMap<PathBuf, Vec<SourceBreakpoint>>,
     ) {
         self.notification(ProxyNotification::DapStart {
             config,
+            dap_server,
             breakpoints,
         })
     }
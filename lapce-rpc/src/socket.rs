@@ -0,0 +1,282 @@
+//! A transport for a proxy that's started with `--listen` and waits for the
+//! app to connect, instead of talking over its stdio.
+//!
+//! The first line a client sends is the token the proxy was started with.
+//! The proxy answers with a line that's either `ok`, after which the usual
+//! messages of [`crate::stdio`] follow, or an error before it closes the
+//! connection.
+
+use std::{
+    fmt::Display,
+    io::{self, BufRead, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+const TOKEN_ACCEPTED: &str = "ok";
+/// The longest token line that's read from a client, so that one that never
+/// sends a newline can't make the proxy buffer without bounds
+const MAX_TOKEN_LINE: u64 = 1024;
+
+/// Where a proxy listens, written as `tcp:host:port`, `unix:/path` or just
+/// `host:port`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ProxyAddress {
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+impl FromStr for ProxyAddress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(path) = s.strip_prefix("unix:") {
+            if path.is_empty() {
+                return Err("missing the path of the unix socket".to_string());
+            }
+            return Ok(ProxyAddress::Unix(PathBuf::from(path)));
+        }
+        let addr = s.strip_prefix("tcp:").unwrap_or(s);
+        match addr.rsplit_once(':') {
+            Some((host, port))
+                if !host.is_empty() && port.parse::<u16>().is_ok() =>
+            {
+                Ok(ProxyAddress::Tcp(addr.to_string()))
+            }
+            _ => Err(format!(
+                "\"{s}\" isn't a `host:port` or `unix:/path` address"
+            )),
+        }
+    }
+}
+
+impl Display for ProxyAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProxyAddress::Tcp(addr) => write!(f, "tcp:{addr}"),
+            ProxyAddress::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+pub enum SocketStream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixStream),
+}
+
+impl SocketStream {
+    pub fn connect(address: &ProxyAddress) -> io::Result<Self> {
+        match address {
+            ProxyAddress::Tcp(addr) => {
+                Ok(SocketStream::Tcp(TcpStream::connect(addr)?))
+            }
+            #[cfg(unix)]
+            ProxyAddress::Unix(path) => Ok(SocketStream::Unix(
+                std::os::unix::net::UnixStream::connect(path)?,
+            )),
+            #[cfg(not(unix))]
+            ProxyAddress::Unix(_) => Err(unix_unsupported()),
+        }
+    }
+
+    pub fn try_clone(&self) -> io::Result<Self> {
+        match self {
+            SocketStream::Tcp(stream) => Ok(SocketStream::Tcp(stream.try_clone()?)),
+            #[cfg(unix)]
+            SocketStream::Unix(stream) => {
+                Ok(SocketStream::Unix(stream.try_clone()?))
+            }
+        }
+    }
+
+    /// Close the connection, which also ends reads that are blocked on it
+    /// in other threads
+    pub fn shutdown(&self) {
+        match self {
+            SocketStream::Tcp(stream) => {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+            #[cfg(unix)]
+            SocketStream::Unix(stream) => {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+}
+
+/// Send the token to the proxy and wait for it to be accepted
+pub fn send_token(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write,
+    token: &str,
+) -> io::Result<()> {
+    writer.write_all(format!("{token}\n").as_bytes())?;
+    writer.flush()?;
+
+    let mut answer = String::new();
+    reader.read_line(&mut answer)?;
+    match answer.trim() {
+        TOKEN_ACCEPTED => Ok(()),
+        "" => Err(io::Error::new(
+            io::ErrorKind::ConnectionAborted,
+            "the proxy closed the connection",
+        )),
+        answer => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            answer.to_string(),
+        )),
+    }
+}
+
+/// Read the token a client sends, and answer whether it's the expected one
+pub fn check_token(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write,
+    token: &str,
+) -> io::Result<bool> {
+    let mut line = String::new();
+    reader.take(MAX_TOKEN_LINE).read_line(&mut line)?;
+    let accepted = token_eq(line.trim_end_matches(['\r', '\n']), token);
+    let answer = if accepted {
+        TOKEN_ACCEPTED
+    } else {
+        "invalid token"
+    };
+    writer.write_all(format!("{answer}\n").as_bytes())?;
+    writer.flush()?;
+    Ok(accepted)
+}
+
+impl Read for SocketStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            SocketStream::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            SocketStream::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for SocketStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            SocketStream::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            SocketStream::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            SocketStream::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            SocketStream::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+pub enum SocketListener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixListener),
+}
+
+impl SocketListener {
+    pub fn bind(address: &ProxyAddress) -> io::Result<Self> {
+        match address {
+            ProxyAddress::Tcp(addr) => {
+                Ok(SocketListener::Tcp(TcpListener::bind(addr)?))
+            }
+            #[cfg(unix)]
+            ProxyAddress::Unix(path) => {
+                // a socket file that's left over from an earlier run
+                // can't be bound again
+                let _ = std::fs::remove_file(path);
+                Ok(SocketListener::Unix(
+                    std::os::unix::net::UnixListener::bind(path)?,
+                ))
+            }
+            #[cfg(not(unix))]
+            ProxyAddress::Unix(_) => Err(unix_unsupported()),
+        }
+    }
+
+    pub fn accept(&self) -> io::Result<SocketStream> {
+        match self {
+            SocketListener::Tcp(listener) => {
+                let (stream, _) = listener.accept()?;
+                Ok(SocketStream::Tcp(stream))
+            }
+            #[cfg(unix)]
+            SocketListener::Unix(listener) => {
+                let (stream, _) = listener.accept()?;
+                Ok(SocketStream::Unix(stream))
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn unix_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "unix sockets aren't supported on this platform",
+    )
+}
+
+/// Compares tokens in a time that doesn't depend on where they differ
+fn token_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proxy_address() {
+        assert_eq!(
+            "devbox:9000".parse(),
+            Ok(ProxyAddress::Tcp("devbox:9000".to_string()))
+        );
+        assert_eq!(
+            "tcp:[::1]:9000".parse(),
+            Ok(ProxyAddress::Tcp("[::1]:9000".to_string()))
+        );
+        assert_eq!(
+            "unix:/tmp/lapce.sock".parse(),
+            Ok(ProxyAddress::Unix(PathBuf::from("/tmp/lapce.sock")))
+        );
+        assert!("devbox".parse::<ProxyAddress>().is_err());
+        assert!("devbox:port".parse::<ProxyAddress>().is_err());
+        assert!("unix:".parse::<ProxyAddress>().is_err());
+    }
+
+    #[test]
+    fn test_token_eq() {
+        assert!(token_eq("secret", "secret"));
+        assert!(!token_eq("secret", "secreT"));
+        assert!(!token_eq("secret", "secret2"));
+    }
+
+    #[test]
+    fn test_check_token() {
+        let mut answer = Vec::new();
+        let mut reader = io::Cursor::new("secret\n");
+        assert!(check_token(&mut reader, &mut answer, "secret").unwrap());
+        assert_eq!(answer, b"ok\n");
+
+        let long = "a".repeat(MAX_TOKEN_LINE as usize * 2);
+        let mut reader = io::Cursor::new(long.clone());
+        assert!(!check_token(&mut reader, &mut Vec::new(), &long).unwrap());
+    }
+}
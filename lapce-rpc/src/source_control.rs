@@ -5,9 +5,16 @@
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 pub struct DiffInfo {
     pub head: String,
+    /// The id of the commit HEAD points to
+    #[serde(default)]
+    pub head_commit: String,
     pub branches: Vec<String>,
     pub tags: Vec<String>,
+    /// Changes of the working tree that aren't staged
     pub diffs: Vec<FileDiff>,
+    /// Changes staged in the index
+    #[serde(default)]
+    pub staged: Vec<FileDiff>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -28,3 +35,47 @@ pub fn path(&self) -> &PathBuf {
         }
     }
 }
+
+/// The commit that last changed a range of lines of a file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlameHunk {
+    /// The first line of the hunk, zero based
+    pub start_line: usize,
+    pub lines: usize,
+    /// The commit id, which is all zeros for lines that aren't committed yet
+    pub commit_id: String,
+    pub author: String,
+    /// The commit time in seconds since the unix epoch
+    pub time: i64,
+    pub summary: String,
+}
+
+impl BlameHunk {
+    pub fn is_committed(&self) -> bool {
+        self.commit_id.chars().any(|c| c != '0')
+    }
+}
+
+/// A commit in the log of the repository
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GitCommit {
+    pub id: String,
+    pub parents: Vec<String>,
+    pub author: String,
+    /// The commit time in seconds since the unix epoch
+    pub time: i64,
+    pub summary: String,
+    /// The branches and tags that point at the commit
+    pub refs: Vec<String>,
+}
+
+/// Which commits of the log to list. Empty fields don't filter anything.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct GitLogFilter {
+    /// Only commits that changed the file or directory
+    pub path: Option<PathBuf>,
+    /// Only commits whose author name or email contains it, ignoring case
+    pub author: String,
+    /// Only commits whose message contains it, ignoring case
+    pub message: String,
+}